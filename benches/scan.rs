@@ -0,0 +1,177 @@
+//! Compares the lookup table used by `Stream` for names and spaces scanning
+//! with the range checks used before it and with checking 8 bytes at a time,
+//! on an attribute-dense SVG.
+//!
+//! Run with `cargo bench --bench scan`.
+
+#[macro_use]
+extern crate bencher;
+extern crate xmlparser;
+
+use bencher::{black_box, Bencher};
+
+fn svg() -> String {
+    let mut text = String::from(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\">\n",
+    );
+    for i in 0..20_000 {
+        text.push_str(&format!(
+            "    <path id=\"path{}\" class=\"shape-{}\" fill=\"#ff0000\" stroke-width=\"1.5\" \
+             xlink:title=\"p\" d=\"M 10 20 L 30 40\"/>\n",
+            i,
+            i % 7
+        ));
+    }
+    text.push_str("</svg>\n");
+    text
+}
+
+const SPACE: u8 = 1;
+const NAME: u8 = 2;
+const COLON: u8 = 4;
+
+// A copy of the `Stream` table, without the non-XML char bits.
+static BYTE_CLASS: [u8; 256] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 2, 0, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 4, 0, 0, 0, 0, 0,
+    0, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 0, 0, 0, 0, 2,
+    0, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+fn count_table(bytes: &[u8], class: u8) -> usize {
+    let mut i = 0;
+    while i < bytes.len() && BYTE_CLASS[bytes[i] as usize] & class != 0 {
+        i += 1;
+    }
+
+    i
+}
+
+// The range checks used before the table.
+fn count_ranges(bytes: &[u8], class: u8) -> usize {
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        let found = match b {
+            b' ' | b'\t' | b'\n' | b'\r' => class & SPACE != 0,
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'_' | b'-' | b'.' => class & NAME != 0,
+            b':' => class & COLON != 0,
+            _ => false,
+        };
+
+        if !found {
+            break;
+        }
+
+        i += 1;
+    }
+
+    i
+}
+
+const ONES: u64 = 0x0101_0101_0101_0101;
+const HIGH: u64 = 0x8080_8080_8080_8080;
+
+// Sets the high bit of each byte in `lo..=hi`. Bytes must be ASCII.
+fn in_range(x: u64, lo: u8, hi: u8) -> u64 {
+    let ge_lo = x + ONES * u64::from(0x80 - lo);
+    let gt_hi = x + ONES * u64::from(0x7F - hi);
+    ge_lo & !gt_hi & HIGH
+}
+
+// Sets the high bit of each byte equal to `c`. Bytes must be ASCII.
+fn equal(x: u64, c: u8) -> u64 {
+    let t = x ^ (ONES * u64::from(c));
+    !((t + !HIGH) | t) & HIGH
+}
+
+fn word_mask(x: u64, class: u8) -> u64 {
+    // Non-ASCII bytes never match. Clear high bits to avoid carries between bytes.
+    let ascii = !x & HIGH;
+    let x = x & !HIGH;
+    let mut mask = 0;
+    if class & SPACE != 0 {
+        mask |= equal(x, b' ') | equal(x, b'\t') | equal(x, b'\n') | equal(x, b'\r');
+    }
+    if class & NAME != 0 {
+        mask |= in_range(x, b'a', b'z')
+            | in_range(x, b'A', b'Z')
+            | in_range(x, b'0', b'9')
+            | equal(x, b'-')
+            | equal(x, b'.')
+            | equal(x, b'_');
+    }
+    if class & COLON != 0 {
+        mask |= equal(x, b':');
+    }
+
+    mask & ascii
+}
+
+fn count_words(bytes: &[u8], class: u8) -> usize {
+    let mut i = 0;
+    while i + 8 <= bytes.len() {
+        let mut x = 0u64;
+        for (n, b) in bytes[i..i + 8].iter().enumerate() {
+            x |= u64::from(*b) << (n * 8);
+        }
+
+        let stop = !word_mask(x, class) & HIGH;
+        if stop != 0 {
+            return i + stop.trailing_zeros() as usize / 8;
+        }
+
+        i += 8;
+    }
+
+    i + count_table(&bytes[i..], class)
+}
+
+// Scans names and spaces at each position, like the tokenizer does between markup.
+fn scan(text: &[u8], count: fn(&[u8], u8) -> usize) -> usize {
+    let mut total = 0;
+    let mut i = 0;
+    while i < text.len() {
+        let n = count(&text[i..], NAME | COLON);
+        let s = count(&text[i + n..], SPACE);
+        total += n + s;
+        i += n + s + 1;
+    }
+
+    total
+}
+
+fn scan_ranges(bencher: &mut Bencher) {
+    let text = svg();
+    assert_eq!(
+        scan(text.as_bytes(), count_ranges),
+        scan(text.as_bytes(), count_table)
+    );
+    bencher.iter(|| scan(black_box(text.as_bytes()), count_ranges));
+}
+
+fn scan_table(bencher: &mut Bencher) {
+    let text = svg();
+    bencher.iter(|| scan(black_box(text.as_bytes()), count_table));
+}
+
+fn scan_words(bencher: &mut Bencher) {
+    let text = svg();
+    assert_eq!(
+        scan(text.as_bytes(), count_words),
+        scan(text.as_bytes(), count_table)
+    );
+    bencher.iter(|| scan(black_box(text.as_bytes()), count_words));
+}
+
+fn tokenize(bencher: &mut Bencher) {
+    let text = svg();
+    bencher.iter(|| xmlparser::Tokenizer::from(black_box(text.as_str())).count());
+}
+
+benchmark_group!(benches, scan_ranges, scan_table, scan_words, tokenize);
+benchmark_main!(benches);
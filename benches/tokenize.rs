@@ -0,0 +1,88 @@
+//! Tokenizes whole documents: a mixed SVG-like one and ones dominated
+//! by text, comments or CDATA.
+//!
+//! Run with `cargo bench --bench tokenize`, with and without `--features memchr`,
+//! and compare with the same benchmark on the 0.13.6 release.
+
+#[macro_use]
+extern crate bencher;
+extern crate xmlparser;
+
+use bencher::{black_box, Bencher};
+
+const SIZE: usize = 1_000_000;
+
+fn mixed() -> String {
+    let mut text = String::from(
+        "<?xml version=\"1.0\"?>\n\
+         <svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\">\n",
+    );
+    let mut i = 0;
+    while text.len() < SIZE {
+        text.push_str(&format!(
+            "  <g id=\"g{}\" transform=\"translate(10 20)\">\n\
+             \x20   <!-- group {} comment text -->\n\
+             \x20   <path id=\"path{}\" class=\"shape-{}\" fill=\"#ff0000\" d=\"M 10 20 L 30 40\"/>\n\
+             \x20   <text x=\"1\" y=\"2\">Some text content &amp; more text, number {}</text>\n\
+             \x20   <style><![CDATA[ .a {{ fill: red; }} ]]></style>\n\
+             \x20 </g>\n",
+            i,
+            i,
+            i,
+            i % 7,
+            i
+        ));
+        i += 1;
+    }
+    text.push_str("</svg>\n");
+    text
+}
+
+fn repeated(item: &str) -> String {
+    let mut text = String::from("<svg>\n");
+    while text.len() < SIZE {
+        text.push_str(item);
+    }
+    text.push_str("</svg>\n");
+    text
+}
+
+fn tokenize(bencher: &mut Bencher, text: &str) {
+    assert!(xmlparser::Tokenizer::from(text).all(|t| t.is_ok()));
+    bencher.bytes = text.len() as u64;
+    bencher.iter(|| xmlparser::Tokenizer::from(black_box(text)).count());
+}
+
+fn tokenize_mixed(bencher: &mut Bencher) {
+    tokenize(bencher, &mixed());
+}
+
+fn tokenize_text(bencher: &mut Bencher) {
+    tokenize(
+        bencher,
+        &repeated("<t>Some text content and more text, with even more text here</t>"),
+    );
+}
+
+fn tokenize_comments(bencher: &mut Bencher) {
+    tokenize(
+        bencher,
+        &repeated("<!-- a group comment text and some more comment text here -->"),
+    );
+}
+
+fn tokenize_cdata(bencher: &mut Bencher) {
+    tokenize(
+        bencher,
+        &repeated("<![CDATA[ .a { fill: red; } some more cdata text here ]]>"),
+    );
+}
+
+benchmark_group!(
+    benches,
+    tokenize_mixed,
+    tokenize_text,
+    tokenize_comments,
+    tokenize_cdata
+);
+benchmark_main!(benches);
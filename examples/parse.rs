@@ -13,17 +13,9 @@ fn main() {
 
     let text = load_file(&args[1]);
 
-    if let Err(e) = parse(&text) {
-        println!("Error: {}.", e);
-    }
-}
-
-fn parse(text: &str) -> Result<(), xml::Error> {
-    for token in xml::Tokenizer::from(text) {
-        println!("{:?}", token?);
-    }
-
-    Ok(())
+    let mut out = String::new();
+    xml::dump(&text, &mut out).unwrap();
+    print!("{}", out);
 }
 
 fn load_file(path: &str) -> String {
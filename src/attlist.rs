@@ -0,0 +1,191 @@
+use crate::{StrSpan, Stream, StreamError, XmlStreamExt};
+
+type Result<T> = core::result::Result<T, StreamError>;
+
+/// A list of attribute definitions from the `ATTLIST` declaration.
+///
+/// The list is already validated by the `Tokenizer`, so iteration is infallible.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::{AttributeDefault, Token, Tokenizer};
+///
+/// let text = "<!DOCTYPE a [<!ATTLIST a b CDATA #REQUIRED c (x|y) 'x'>]>";
+/// let token = Tokenizer::from(text).nth(1).unwrap().unwrap();
+/// if let Token::AttlistDeclaration { definitions, .. } = token {
+///     let defs: Vec<_> = definitions.iter().collect();
+///     assert_eq!(defs[0].name.as_str(), "b");
+///     assert_eq!(defs[0].default, AttributeDefault::Required);
+///     assert_eq!(defs[1].att_type.as_str(), "(x|y)");
+/// }
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct AttributeDefinitions<'a> {
+    span: StrSpan<'a>,
+}
+
+impl<'a> AttributeDefinitions<'a> {
+    pub(crate) fn new(span: StrSpan<'a>) -> Self {
+        AttributeDefinitions { span }
+    }
+
+    /// Returns the raw definitions text.
+    pub fn span(&self) -> StrSpan<'a> {
+        self.span
+    }
+
+    /// Returns an iterator over attribute definitions.
+    pub fn iter(&self) -> AttributeDefinitionsIter<'a> {
+        AttributeDefinitionsIter {
+            stream: Stream::from(self.span),
+            offset: self.span.start(),
+        }
+    }
+}
+
+impl<'a> IntoIterator for AttributeDefinitions<'a> {
+    type Item = AttributeDefinition<'a>;
+    type IntoIter = AttributeDefinitionsIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Representation of the [AttDef](https://www.w3.org/TR/xml/#NT-AttDef) value.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct AttributeDefinition<'a> {
+    /// Attribute name.
+    pub name: StrSpan<'a>,
+    /// Attribute type, like `CDATA`, `(a|b)` or `NOTATION (a|b)`.
+    ///
+    /// Types are not validated.
+    pub att_type: StrSpan<'a>,
+    /// Attribute default value.
+    pub default: AttributeDefault<'a>,
+}
+
+/// Representation of the [DefaultDecl](https://www.w3.org/TR/xml/#NT-DefaultDecl) value.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum AttributeDefault<'a> {
+    /// `#REQUIRED`
+    Required,
+    /// `#IMPLIED`
+    Implied,
+    /// `#FIXED 'value'`
+    Fixed(StrSpan<'a>),
+    /// `'value'`
+    Value(StrSpan<'a>),
+}
+
+/// An iterator over attribute definitions.
+#[derive(Clone, Copy, Debug)]
+pub struct AttributeDefinitionsIter<'a> {
+    stream: Stream<'a>,
+    offset: usize,
+}
+
+impl<'a> Iterator for AttributeDefinitionsIter<'a> {
+    type Item = AttributeDefinition<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Spans are relative to the definitions span, so we have to shift them.
+        let offset = self.offset;
+        let shift = |span: StrSpan<'a>| span.shift(offset);
+
+        match parse_attribute_definition(&mut self.stream) {
+            Ok(Some(def)) => Some(AttributeDefinition {
+                name: shift(def.name),
+                att_type: shift(def.att_type),
+                default: match def.default {
+                    AttributeDefault::Fixed(v) => AttributeDefault::Fixed(shift(v)),
+                    AttributeDefault::Value(v) => AttributeDefault::Value(shift(v)),
+                    d => d,
+                },
+            }),
+            _ => None,
+        }
+    }
+}
+
+// AttDef ::= S Name S AttType S DefaultDecl
+//
+// Returns `None` on `>` or at the end of the stream.
+pub(crate) fn parse_attribute_definition<'a>(
+    s: &mut Stream<'a>,
+) -> Result<Option<AttributeDefinition<'a>>> {
+    let has_space = s.starts_with_space();
+    s.skip_spaces();
+    if s.at_end() || s.curr_byte_unchecked() == b'>' {
+        return Ok(None);
+    }
+
+    if !has_space {
+        return Err(StreamError::InvalidSpace(
+            s.curr_byte_unchecked(),
+            s.gen_text_pos(),
+            s.pos(),
+        ));
+    }
+
+    let name = s.consume_name()?;
+    s.consume_spaces()?;
+    let att_type = parse_attribute_type(s)?;
+    s.consume_spaces()?;
+    let default = parse_default_decl(s)?;
+
+    Ok(Some(AttributeDefinition {
+        name,
+        att_type,
+        default,
+    }))
+}
+
+// AttType        ::= StringType | TokenizedType | EnumeratedType
+// NotationType   ::= 'NOTATION' S '(' S? Name (S? '|' S? Name)* S? ')'
+// Enumeration    ::= '(' S? Nmtoken (S? '|' S? Nmtoken)* S? ')'
+fn parse_attribute_type<'a>(s: &mut Stream<'a>) -> Result<StrSpan<'a>> {
+    let start = s.pos();
+    if s.starts_with(b"NOTATION") {
+        s.advance(8);
+        s.consume_spaces()?;
+        consume_enumeration(s)?;
+    } else if s.starts_with(b"(") {
+        consume_enumeration(s)?;
+    } else {
+        s.skip_name()?;
+    }
+
+    Ok(s.slice_back(start))
+}
+
+fn consume_enumeration(s: &mut Stream) -> Result<()> {
+    s.consume_byte(b'(')?;
+    s.skip_bytes(|_, c| c != b')' && c != b'>');
+    s.consume_byte(b')')
+}
+
+// DefaultDecl ::= '#REQUIRED' | '#IMPLIED' | (('#FIXED' S)? AttValue)
+fn parse_default_decl<'a>(s: &mut Stream<'a>) -> Result<AttributeDefault<'a>> {
+    if s.starts_with(b"#REQUIRED") {
+        s.advance(9);
+        Ok(AttributeDefault::Required)
+    } else if s.starts_with(b"#IMPLIED") {
+        s.advance(8);
+        Ok(AttributeDefault::Implied)
+    } else if s.starts_with(b"#FIXED") {
+        s.advance(6);
+        s.consume_spaces()?;
+        Ok(AttributeDefault::Fixed(consume_att_value(s)?))
+    } else {
+        Ok(AttributeDefault::Value(consume_att_value(s)?))
+    }
+}
+
+fn consume_att_value<'a>(s: &mut Stream<'a>) -> Result<StrSpan<'a>> {
+    let quote = s.consume_quote()?;
+    let value = s.consume_bytes_until(quote);
+    s.consume_byte(quote)?;
+    Ok(value)
+}
@@ -0,0 +1,134 @@
+use crate::{ElementEnd, Error, StrSpan, Token, Tokenizer};
+
+/// An element attribute.
+///
+/// See [`Tokenizer::attributes`](struct.Tokenizer.html#method.attributes).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Attribute<'a> {
+    /// Attribute prefix.
+    pub prefix: StrSpan<'a>,
+    /// Attribute local name.
+    pub local: StrSpan<'a>,
+    /// Attribute value. Not normalized.
+    pub value: StrSpan<'a>,
+    /// Attribute span, like `ns:attr="value"`.
+    pub span: StrSpan<'a>,
+}
+
+impl<'a> Attribute<'a> {
+    /// Returns spans of the attribute parts.
+    pub fn spans(&self) -> AttributeSpans<'a> {
+        AttributeSpans::new(self.local, self.value, self.span)
+    }
+}
+
+/// Spans of the attribute parts.
+///
+/// See [`Token::attribute_spans`](enum.Token.html#method.attribute_spans).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct AttributeSpans<'a> {
+    /// Qualified name span, like `ns:attr`.
+    pub name: StrSpan<'a>,
+    /// `=` span.
+    ///
+    /// `None` for a minimized attribute in the lenient mode, like `<input disabled>`.
+    pub eq: Option<StrSpan<'a>>,
+    /// Value span including quotes, like `"value"`.
+    ///
+    /// Equal to the value span for an unquoted value in the lenient mode.
+    pub quoted_value: StrSpan<'a>,
+}
+
+impl<'a> AttributeSpans<'a> {
+    pub(crate) fn new(local: StrSpan<'a>, value: StrSpan<'a>, span: StrSpan<'a>) -> Self {
+        // Spans are absolute, while `slice_region` is relative.
+        let sub = |start: usize, end: usize| {
+            span.slice_region(start - span.start(), end - span.start())
+                .shift(span.start())
+        };
+
+        let name = sub(span.start(), local.end());
+
+        // Only spaces can be around `=`.
+        let eq = if value.start() > local.end() {
+            sub(local.end(), value.start())
+                .as_str()
+                .find('=')
+                .map(|idx| sub(local.end() + idx, local.end() + idx + 1))
+        } else {
+            None
+        };
+
+        let bytes = span.as_str().as_bytes();
+        let is_quoted = eq.is_some()
+            && value.end() < span.end()
+            && matches!(bytes[value.start() - 1 - span.start()], b'"' | b'\'');
+        let quoted_value = if is_quoted {
+            sub(value.start() - 1, value.end() + 1)
+        } else {
+            value
+        };
+
+        AttributeSpans {
+            name,
+            eq,
+            quoted_value,
+        }
+    }
+}
+
+/// An iterator over the current start tag attributes.
+///
+/// See [`Tokenizer::attributes`](struct.Tokenizer.html#method.attributes).
+#[derive(Debug)]
+pub struct Attributes<'t, 'a> {
+    tokenizer: &'t mut Tokenizer<'a>,
+    end: Option<(ElementEnd<'a>, StrSpan<'a>)>,
+}
+
+impl<'t, 'a> Attributes<'t, 'a> {
+    pub(crate) fn new(tokenizer: &'t mut Tokenizer<'a>) -> Self {
+        Attributes {
+            tokenizer,
+            end: None,
+        }
+    }
+
+    /// Returns the start tag end and its span.
+    ///
+    /// Available only after all the attributes were consumed.
+    pub fn element_end(&self) -> Option<(ElementEnd<'a>, StrSpan<'a>)> {
+        self.end
+    }
+}
+
+impl<'t, 'a> Iterator for Attributes<'t, 'a> {
+    type Item = Result<Attribute<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.end.is_some() || !self.tokenizer.in_start_tag() {
+            return None;
+        }
+
+        match self.tokenizer.next()? {
+            Ok(Token::Attribute {
+                prefix,
+                local,
+                value,
+                span,
+            }) => Some(Ok(Attribute {
+                prefix,
+                local,
+                value,
+                span,
+            })),
+            Ok(Token::ElementEnd { end, span }) => {
+                self.end = Some((end, span));
+                None
+            }
+            // Only attributes and element ends are possible inside a start tag.
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
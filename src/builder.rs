@@ -0,0 +1,188 @@
+use core::ops::Range;
+
+use crate::{BomHandling, DtdLimitAction, Options, Tokenizer};
+
+/// A builder of tokenizers with custom options.
+///
+/// Each method corresponds to a `Tokenizer` setter with the same name,
+/// so see them for details. Use `Tokenizer::from` when the default options are enough.
+///
+/// A single builder can be used to create tokenizers for multiple texts.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::TokenizerBuilder;
+///
+/// let builder = TokenizerBuilder::new().lenient(true).max_depth(2);
+/// assert!(builder.build("<a b='<'/>").all(|t| t.is_ok()));
+/// assert!(builder.build("<a><b><c/></b></a>").any(|t| t.is_err()));
+///
+/// let text = "<a>text</a>";
+/// let mut p = TokenizerBuilder::new().fragment(3..7).build(text);
+/// assert_eq!(p.next().unwrap().unwrap().span().as_str(), "text");
+/// ```
+#[derive(Clone, Default, Debug)]
+pub struct TokenizerBuilder {
+    opt: Options,
+    fragment: Option<Range<usize>>,
+    prolog_retries: usize,
+    track_text_pos: bool,
+    defer_positions: bool,
+}
+
+impl TokenizerBuilder {
+    /// Creates a new builder with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses only a fragment of the text.
+    ///
+    /// See [`Tokenizer::from_fragment`](struct.Tokenizer.html#method.from_fragment).
+    pub fn fragment(mut self, fragment: Range<usize>) -> Self {
+        self.fragment = Some(fragment);
+        self
+    }
+
+    /// Sets the UTF-8 BOM handling.
+    pub fn bom_handling(mut self, handling: BomHandling) -> Self {
+        self.opt.bom = handling;
+        self
+    }
+
+    /// Enables recovery from unknown tokens before the root element.
+    pub fn prolog_recovery(mut self, max_retries: usize) -> Self {
+        self.prolog_retries = max_retries;
+        self
+    }
+
+    /// Enables whitespace tokens.
+    pub fn whitespace_tokens(mut self, flag: bool) -> Self {
+        self.opt.whitespace_tokens = flag;
+        self
+    }
+
+    /// Enables raw tokens.
+    pub fn raw_tokens(mut self, flag: bool) -> Self {
+        self.opt.raw_tokens = flag;
+        self
+    }
+
+    /// Splits large text and CDATA nodes into chunks.
+    pub fn text_chunk_size(mut self, size: usize) -> Self {
+        self.opt.text_chunk_size = Some(size);
+        self
+    }
+
+    /// Allows multiple root elements.
+    pub fn allow_multiple_roots(mut self, flag: bool) -> Self {
+        self.opt.multiple_roots = flag;
+        self
+    }
+
+    /// Enables lenient parsing.
+    pub fn lenient(mut self, flag: bool) -> Self {
+        self.opt.lenient = flag;
+        self
+    }
+
+    /// Makes start tag end spans cover the whole start tag.
+    pub fn full_tag_spans(mut self, flag: bool) -> Self {
+        self.opt.full_tag_spans = flag;
+        self
+    }
+
+    /// Enables HTML-style conditional comments.
+    pub fn conditional_comments(mut self, flag: bool) -> Self {
+        self.opt.conditional_comments = flag;
+        self
+    }
+
+    /// Enables errors recovery.
+    pub fn recover(mut self, flag: bool) -> Self {
+        self.opt.recover = flag;
+        self
+    }
+
+    /// Enables partial documents parsing.
+    pub fn partial(mut self, flag: bool) -> Self {
+        self.opt.partial = flag;
+        self
+    }
+
+    /// Sets the maximum size of the DTD internal subset in bytes.
+    pub fn dtd_size_limit(mut self, limit: usize, action: DtdLimitAction) -> Self {
+        self.opt.dtd_limit = Some((limit, action));
+        self
+    }
+
+    /// Enables strict qualified names validation.
+    pub fn namespaces(mut self, flag: bool) -> Self {
+        self.opt.namespaces = flag;
+        self
+    }
+
+    /// Sets the maximum number of nested elements.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.opt.max_depth = Some(depth);
+        self
+    }
+
+    /// Enables incremental text position tracking.
+    pub fn track_text_pos(mut self, flag: bool) -> Self {
+        self.track_text_pos = flag;
+        self
+    }
+
+    /// Defers error positions calculation.
+    pub fn defer_positions(mut self, flag: bool) -> Self {
+        self.defer_positions = flag;
+        self
+    }
+
+    /// Sets the maximum attribute value length in bytes.
+    pub fn max_attribute_value_len(mut self, len: usize) -> Self {
+        self.opt.max_attribute_value_len = Some(len);
+        self
+    }
+
+    /// Enables references validation inside attribute values.
+    pub fn validate_attribute_references(mut self, flag: bool) -> Self {
+        self.opt.validate_attribute_references = flag;
+        self
+    }
+
+    /// Sets the maximum comment length in bytes.
+    pub fn max_comment_len(mut self, len: usize) -> Self {
+        self.opt.max_comment_len = Some(len);
+        self
+    }
+
+    /// Sets the maximum text and CDATA length in bytes.
+    pub fn max_text_len(mut self, len: usize) -> Self {
+        self.opt.max_text_len = Some(len);
+        self
+    }
+
+    /// Enables tracking of unclosed elements.
+    #[cfg(feature = "alloc")]
+    pub fn track_tags(mut self, flag: bool) -> Self {
+        self.opt.track_tags = flag;
+        self
+    }
+
+    /// Creates a tokenizer for `text`.
+    pub fn build<'a>(&self, text: &'a str) -> Tokenizer<'a> {
+        let mut p = match self.fragment {
+            Some(ref fragment) => Tokenizer::from_fragment(text, fragment.clone()),
+            None => Tokenizer::from(text),
+        };
+
+        p.opt = self.opt;
+        p.prolog_retries = self.prolog_retries;
+        p.set_track_text_pos(self.track_text_pos);
+        p.set_defer_positions(self.defer_positions);
+        p
+    }
+}
@@ -0,0 +1,82 @@
+use core::fmt;
+
+use crate::{ElementEnd, StrSpan, Stream, TextPos, Token};
+
+/// A close tag that doesn't match the open tag.
+///
+/// See [`check_close_tag`](fn.check_close_tag.html).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct MismatchedTag<'a> {
+    /// The open tag span, like `<ns:a`.
+    pub open: StrSpan<'a>,
+    /// The open tag position.
+    pub open_pos: TextPos,
+    /// The close tag span, like `</b>`.
+    pub close: StrSpan<'a>,
+    /// The close tag position.
+    pub close_pos: TextPos,
+}
+
+impl fmt::Display for MismatchedTag<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "close tag {} at {} doesn't match the open tag {}> at {}",
+            self.close, self.close_pos, self.open, self.open_pos
+        )
+    }
+}
+
+/// Checks that a close tag matches an open tag.
+///
+/// `start` must be an `ElementStart` token and `end` must be an `ElementEnd` token,
+/// both produced from `text`. Names are compared without allocations.
+/// Other tokens and `ElementEnd` tokens that are not close tags are always accepted.
+///
+/// # Examples
+///
+/// ```
+/// let text = "<a>\n</b>";
+/// let tokens: Vec<_> = xmlparser::Tokenizer::from(text).map(|t| t.unwrap()).collect();
+/// let err = xmlparser::check_close_tag(text, tokens[0], tokens[3]).unwrap_err();
+/// assert_eq!(err.to_string(), "close tag </b> at 2:1 doesn't match the open tag <a> at 1:1");
+/// ```
+pub fn check_close_tag<'a>(
+    text: &str,
+    start: Token<'a>,
+    end: Token<'a>,
+) -> Result<(), MismatchedTag<'a>> {
+    check_close_tag_in(&Stream::from(text), start, end)
+}
+
+// Like `check_close_tag`, but positions are calculated by the tokenizer stream,
+// which can have a line index.
+pub(crate) fn check_close_tag_in<'a>(
+    s: &Stream,
+    start: Token<'a>,
+    end: Token<'a>,
+) -> Result<(), MismatchedTag<'a>> {
+    if let (
+        Token::ElementStart {
+            prefix,
+            local,
+            span: open,
+        },
+        Token::ElementEnd {
+            end: end @ ElementEnd::Close(..),
+            span: close,
+        },
+    ) = (start, end)
+    {
+        if !end.is_close_of(prefix.as_str(), local.as_str()) {
+            return Err(MismatchedTag {
+                open,
+                open_pos: s.gen_text_pos_from(open.start()),
+                close,
+                close_pos: s.gen_text_pos_from(close.start()),
+            });
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use core::fmt;
+use std::borrow::Cow;
+use std::error;
+use std::string::String;
+
+use crate::Tokenizer;
+
+/// An input encoding.
+#[allow(missing_docs)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+/// A text decoding error.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum DecodeError {
+    /// An invalid UTF-8 sequence at the specified byte offset.
+    InvalidUtf8(usize),
+    /// An unpaired surrogate or a truncated code unit at the specified byte offset.
+    InvalidUtf16(usize),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeError::InvalidUtf8(offset) => {
+                write!(f, "invalid UTF-8 sequence at offset {}", offset)
+            }
+            DecodeError::InvalidUtf16(offset) => {
+                write!(f, "invalid UTF-16 sequence at offset {}", offset)
+            }
+        }
+    }
+}
+
+impl error::Error for DecodeError {
+    fn description(&self) -> &str {
+        "a text decoding error"
+    }
+}
+
+/// A text decoded from raw bytes.
+///
+/// The encoding is detected using a BOM or, when there is no BOM,
+/// using the first `<?` bytes, as described in the
+/// [spec](https://www.w3.org/TR/xml/#sec-guessing-no-ext-info).
+/// Defaults to UTF-8. The `encoding` attribute of the XML declaration is ignored.
+///
+/// UTF-8 input is borrowed, while UTF-16 input is decoded into an internal buffer.
+/// Therefore, token spans are always relative to the decoded text.
+///
+/// Available only with the `utf16` feature.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::{DecodedText, Encoding, Token};
+///
+/// let bytes = b"\xFF\xFE<\0a\0/\0>\0";
+/// let text = DecodedText::decode(bytes).unwrap();
+/// assert_eq!(text.encoding(), Encoding::Utf16Le);
+/// match text.tokenizer().next() {
+///     Some(Ok(Token::ElementStart { local, .. })) => assert_eq!(local.as_str(), "a"),
+///     _ => unreachable!(),
+/// }
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct DecodedText<'a> {
+    text: Cow<'a, str>,
+    encoding: Encoding,
+}
+
+impl<'a> DecodedText<'a> {
+    /// Decodes raw bytes.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidUtf8` or `InvalidUtf16` on malformed input.
+    pub fn decode(bytes: &'a [u8]) -> Result<Self, DecodeError> {
+        let encoding = detect_encoding(bytes);
+        let text = match encoding {
+            Encoding::Utf8 => match std::str::from_utf8(bytes) {
+                Ok(text) => Cow::Borrowed(text),
+                Err(e) => return Err(DecodeError::InvalidUtf8(e.valid_up_to())),
+            },
+            Encoding::Utf16Le => Cow::Owned(decode_utf16(bytes, |b| [b[0], b[1]])?),
+            Encoding::Utf16Be => Cow::Owned(decode_utf16(bytes, |b| [b[1], b[0]])?),
+        };
+
+        Ok(DecodedText { text, encoding })
+    }
+
+    /// Returns the detected encoding.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Returns the decoded text.
+    ///
+    /// The BOM is preserved.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns a tokenizer over the decoded text.
+    pub fn tokenizer(&self) -> Tokenizer<'_> {
+        Tokenizer::from(self.as_str())
+    }
+}
+
+fn detect_encoding(bytes: &[u8]) -> Encoding {
+    if bytes.starts_with(&[0xFF, 0xFE]) || bytes.starts_with(&[0x3C, 0x00, 0x3F, 0x00]) {
+        Encoding::Utf16Le
+    } else if bytes.starts_with(&[0xFE, 0xFF]) || bytes.starts_with(&[0x00, 0x3C, 0x00, 0x3F]) {
+        Encoding::Utf16Be
+    } else {
+        Encoding::Utf8
+    }
+}
+
+// `to_le` converts a code unit to the little-endian byte order.
+fn decode_utf16<F>(bytes: &[u8], to_le: F) -> Result<String, DecodeError>
+where
+    F: Fn(&[u8]) -> [u8; 2],
+{
+    let units = bytes.chunks(2).map(|b| {
+        if b.len() == 2 {
+            let b = to_le(b);
+            u16::from(b[0]) | u16::from(b[1]) << 8
+        } else {
+            // A truncated code unit is not a valid surrogate either.
+            0xD800
+        }
+    });
+
+    let mut text = String::with_capacity(bytes.len() / 2);
+    let mut offset = 0;
+    for c in core::char::decode_utf16(units) {
+        match c {
+            Ok(c) => {
+                text.push(c);
+                offset += c.len_utf16() * 2;
+            }
+            Err(_) => return Err(DecodeError::InvalidUtf16(offset)),
+        }
+    }
+
+    Ok(text)
+}
@@ -0,0 +1,45 @@
+use core::fmt;
+
+use crate::{TextPos, Tokenizer};
+
+/// Prints all tokens of the document, one per line.
+///
+/// Each line has the `{row}:{col} {start}..{end} {token:?}` format.
+/// On error, prints `error: {error}` and stops.
+///
+/// Intended for debugging and bug reports.
+///
+/// # Examples
+///
+/// ```
+/// let mut s = String::new();
+/// xmlparser::dump("<a>\n  <b/>\n</a>", &mut s).unwrap();
+/// assert_eq!(s.lines().nth(3).unwrap(), "2:3 6..8 ElementStart { \
+///     prefix: StrSpan(\"\" 0..0), local: StrSpan(\"b\" 7..8), span: StrSpan(\"<b\" 6..8) }");
+/// ```
+pub fn dump(text: &str, out: &mut impl fmt::Write) -> fmt::Result {
+    let mut pos = TextPos::new(1, 1);
+    let mut offset = 0;
+    for token in Tokenizer::from(text) {
+        let token = match token {
+            Ok(token) => token,
+            Err(e) => return writeln!(out, "error: {}", e),
+        };
+
+        // Tokens are ordered, so we can calculate positions incrementally.
+        let span = token.span();
+        for c in text[offset..span.start()].chars() {
+            if c == '\n' {
+                pos.row += 1;
+                pos.col = 1;
+            } else {
+                pos.col += 1;
+            }
+        }
+        offset = span.start();
+
+        writeln!(out, "{} {:?} {:?}", pos, span.range(), token)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,464 @@
+use core::fmt;
+use std::collections::HashMap;
+use std::error;
+use std::string::String;
+use std::vec::Vec;
+
+use crate::{
+    EntityDefinition, EntityResolver, Error, Reference, StrSpan, Stream, TextPos, Token, Tokenizer,
+    XmlStreamExt,
+};
+
+/// The default maximum number of entities collected by [`EntityMap::collect`].
+///
+/// [`EntityMap::collect`]: struct.EntityMap.html#method.collect
+pub const DEFAULT_ENTITY_LIMIT: usize = 1024;
+
+/// The default maximum nesting depth of entity references used by [`EntityMap::expand`].
+///
+/// [`EntityMap::expand`]: struct.EntityMap.html#method.expand
+pub const DEFAULT_EXPANSION_DEPTH_LIMIT: usize = 16;
+
+/// The default maximum size of an expanded text in bytes used by [`EntityMap::expand`].
+///
+/// [`EntityMap::expand`]: struct.EntityMap.html#method.expand
+pub const DEFAULT_EXPANSION_SIZE_LIMIT: usize = 1024 * 1024;
+
+/// An entity expansion error.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ExpansionError {
+    /// An entity references itself, directly or indirectly.
+    ///
+    /// Contains the offset of the outermost reference in the input text.
+    RecursiveEntity(usize),
+    /// The nesting depth limit was exceeded.
+    ///
+    /// Contains the offset of the outermost reference in the input text.
+    DepthLimitExceeded(usize),
+    /// The expanded text size limit was exceeded.
+    SizeLimitExceeded,
+}
+
+impl fmt::Display for ExpansionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ExpansionError::RecursiveEntity(offset) => {
+                write!(f, "recursive entity reference at offset {}", offset)
+            }
+            ExpansionError::DepthLimitExceeded(offset) => {
+                write!(f, "entity nesting is too deep at offset {}", offset)
+            }
+            ExpansionError::SizeLimitExceeded => write!(f, "expanded text is too large"),
+        }
+    }
+}
+
+impl error::Error for ExpansionError {
+    fn description(&self) -> &str {
+        "an entity expansion error"
+    }
+}
+
+/// Options of [`EntityMap::collect_with_options`].
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::{EntityMap, EntityMapOptions, Tokenizer};
+///
+/// let text = "<!DOCTYPE a [<!ENTITY b 'c'><!ENTITY b 'd'>]><a/>";
+/// let opt = EntityMapOptions::new().limit(16).duplicates(true);
+/// let entities = EntityMap::collect_with_options(&mut Tokenizer::from(text), opt).unwrap();
+/// assert_eq!(entities.duplicates().len(), 1);
+/// ```
+///
+/// [`EntityMap::collect_with_options`]: struct.EntityMap.html#method.collect_with_options
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct EntityMapOptions {
+    limit: usize,
+    duplicates: bool,
+}
+
+impl Default for EntityMapOptions {
+    fn default() -> Self {
+        EntityMapOptions {
+            limit: DEFAULT_ENTITY_LIMIT,
+            duplicates: false,
+        }
+    }
+}
+
+impl EntityMapOptions {
+    /// Creates new options with default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of collected entities.
+    ///
+    /// `DEFAULT_ENTITY_LIMIT` by default.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Enables recording of duplicated entity declarations.
+    ///
+    /// General and parameter entities are checked separately.
+    /// At most `limit` duplicates are recorded.
+    ///
+    /// Disabled by default.
+    pub fn duplicates(mut self, flag: bool) -> Self {
+        self.duplicates = flag;
+        self
+    }
+}
+
+/// An entity declaration that was ignored, since the entity was already declared.
+///
+/// See [`EntityMap::duplicates`](struct.EntityMap.html#method.duplicates).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct DuplicatedEntity<'a> {
+    /// Indicates that this is a parameter entity.
+    pub parameter: bool,
+    /// The first declaration name.
+    pub first: StrSpan<'a>,
+    /// The first declaration name position.
+    pub first_pos: TextPos,
+    /// The ignored declaration name.
+    pub duplicate: StrSpan<'a>,
+    /// The ignored declaration name position.
+    pub duplicate_pos: TextPos,
+}
+
+/// A lookup table of entities declared in the DTD.
+///
+/// # Examples
+///
+/// ```
+/// let text = "<!DOCTYPE svg [<!ENTITY a 'text'>]><svg>&a;</svg>";
+/// let mut tokenizer = xmlparser::Tokenizer::from(text);
+/// let entities = xmlparser::EntityMap::collect(&mut tokenizer).unwrap();
+/// assert_eq!(entities.value("a").unwrap().as_str(), "text");
+///
+/// // The tokenizer is positioned at the root element now.
+/// for token in tokenizer {
+///     println!("{:?}", token);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct EntityMap<'a> {
+    // Definitions with their names.
+    entities: HashMap<&'a str, (StrSpan<'a>, EntityDefinition<'a>)>,
+    duplicates: Vec<DuplicatedEntity<'a>>,
+    depth_limit: usize,
+    size_limit: usize,
+}
+
+impl<'a> EntityResolver<'a> for EntityMap<'a> {
+    /// Returns a replacement text of an internal entity.
+    ///
+    /// The replacement text is not expanded. Use `expand` for this.
+    fn resolve(&self, name: &str) -> Option<&'a str> {
+        self.value(name).map(|value| value.as_str())
+    }
+}
+
+impl<'a> Default for EntityMap<'a> {
+    fn default() -> Self {
+        EntityMap {
+            entities: HashMap::new(),
+            duplicates: Vec::new(),
+            depth_limit: DEFAULT_EXPANSION_DEPTH_LIMIT,
+            size_limit: DEFAULT_EXPANSION_SIZE_LIMIT,
+        }
+    }
+}
+
+impl<'a> EntityMap<'a> {
+    /// Collects entities from the tokenizer.
+    ///
+    /// Consumes tokens up to and including the end of the DTD.
+    /// If there is no DTD, stops right before the root element.
+    /// Either way, the tokenizer can be used to parse the rest of the document afterwards.
+    ///
+    /// Only the first declaration of an entity is used, as required by the spec.
+    /// Parameter entities are skipped, since they cannot be referenced outside the DTD.
+    ///
+    /// # Errors
+    ///
+    /// - Any tokenizer error.
+    /// - `TooManyEntities` when more than `DEFAULT_ENTITY_LIMIT` entities were declared.
+    pub fn collect(tokenizer: &mut Tokenizer<'a>) -> Result<Self, Error> {
+        Self::collect_with_limit(tokenizer, DEFAULT_ENTITY_LIMIT)
+    }
+
+    /// Collects entities from the tokenizer, allowing at most `limit` entities.
+    ///
+    /// See [`collect`] for details.
+    ///
+    /// [`collect`]: #method.collect
+    pub fn collect_with_limit(tokenizer: &mut Tokenizer<'a>, limit: usize) -> Result<Self, Error> {
+        Self::collect_with_options(tokenizer, EntityMapOptions::new().limit(limit))
+    }
+
+    /// Collects entities from the tokenizer using the specified options.
+    ///
+    /// See [`collect`] for details.
+    ///
+    /// [`collect`]: #method.collect
+    pub fn collect_with_options(
+        tokenizer: &mut Tokenizer<'a>,
+        opt: EntityMapOptions,
+    ) -> Result<Self, Error> {
+        let limit = opt.limit;
+        let mut map = EntityMap::default();
+        // Parameter entities names. Used only for duplicates detection.
+        let mut parameters: HashMap<&'a str, StrSpan<'a>> = HashMap::new();
+        loop {
+            // The root element must stay in the tokenizer.
+            if tokenizer.at_prolog_end() {
+                break;
+            }
+
+            let token = match tokenizer.next() {
+                Some(token) => token,
+                None => break,
+            };
+
+            let (name, definition, span) = match token? {
+                Token::EntityDeclaration {
+                    name,
+                    definition,
+                    span,
+                } => (name, definition, span),
+                Token::DtdEnd { .. } | Token::EmptyDtd { .. } => break,
+                _ => continue,
+            };
+
+            let parameter = is_parameter_entity(span);
+
+            let first = if parameter {
+                parameters.get(name.as_str()).cloned()
+            } else {
+                map.entities.get(name.as_str()).map(|&(first, _)| first)
+            };
+
+            if let Some(first) = first {
+                // Memory is bounded by the same limit as entities.
+                if opt.duplicates && map.duplicates.len() < limit {
+                    let s = tokenizer.stream();
+                    map.duplicates.push(DuplicatedEntity {
+                        parameter,
+                        first,
+                        first_pos: s.gen_text_pos_from(first.start()),
+                        duplicate: name,
+                        duplicate_pos: s.gen_text_pos_from(name.start()),
+                    });
+                }
+
+                continue;
+            }
+
+            if parameter {
+                if opt.duplicates && parameters.len() < limit {
+                    parameters.insert(name.as_str(), name);
+                }
+
+                continue;
+            }
+
+            if map.entities.len() == limit {
+                let pos = tokenizer.stream().gen_text_pos_from(name.start());
+                return Err(Error::TooManyEntities(pos, name.start()));
+            }
+
+            map.entities.insert(name.as_str(), (name, definition));
+        }
+
+        Ok(map)
+    }
+
+    /// Returns an entity definition by name.
+    pub fn get(&self, name: &str) -> Option<EntityDefinition<'a>> {
+        self.entities.get(name).map(|&(_, definition)| definition)
+    }
+
+    /// Returns a replacement text of an internal entity by name.
+    ///
+    /// Returns `None` for unknown and external entities.
+    pub fn value(&self, name: &str) -> Option<StrSpan<'a>> {
+        match self.get(name) {
+            Some(EntityDefinition::EntityValue(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns ignored duplicated entity declarations.
+    ///
+    /// Always empty, unless enabled via [`EntityMapOptions::duplicates`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{EntityMap, EntityMapOptions, Tokenizer};
+    ///
+    /// let text = "<!DOCTYPE a [<!ENTITY b 'c'><!ENTITY b 'd'>]><a/>";
+    /// let opt = EntityMapOptions::new().duplicates(true);
+    /// let entities = EntityMap::collect_with_options(&mut Tokenizer::from(text), opt).unwrap();
+    /// assert_eq!(entities.value("b").unwrap().as_str(), "c");
+    ///
+    /// let dup = entities.duplicates()[0];
+    /// assert_eq!(dup.first.start(), 22);
+    /// assert_eq!(dup.duplicate_pos, xmlparser::TextPos::new(1, 38));
+    /// ```
+    ///
+    /// [`EntityMapOptions::duplicates`]: struct.EntityMapOptions.html#method.duplicates
+    pub fn duplicates(&self) -> &[DuplicatedEntity<'a>] {
+        &self.duplicates
+    }
+
+    /// Returns the number of collected entities.
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Checks that no entities were collected.
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// Sets the maximum nesting depth of entity references
+    /// used by `expand` and `AttrValueNormalizer`.
+    ///
+    /// `DEFAULT_EXPANSION_DEPTH_LIMIT` by default.
+    pub fn set_depth_limit(&mut self, limit: usize) {
+        self.depth_limit = limit;
+    }
+
+    /// Sets the maximum size of an expanded text in bytes
+    /// used by `expand` and `AttrValueNormalizer`.
+    ///
+    /// `DEFAULT_EXPANSION_SIZE_LIMIT` by default.
+    pub fn set_size_limit(&mut self, limit: usize) {
+        self.size_limit = limit;
+    }
+
+    pub(crate) fn depth_limit(&self) -> usize {
+        self.depth_limit
+    }
+
+    pub(crate) fn size_limit(&self) -> usize {
+        self.size_limit
+    }
+
+    /// Expands references to internal entities in a text or an attribute value.
+    ///
+    /// Nested references are expanded too.
+    /// Character references, predefined entity references and references
+    /// to unknown and external entities are preserved as is,
+    /// so the result can be passed to `TextUnescape` or `AttrValueNormalizer`.
+    ///
+    /// # Errors
+    ///
+    /// - `RecursiveEntity` when an entity references itself.
+    /// - `DepthLimitExceeded` when references are nested deeper than the depth limit.
+    /// - `SizeLimitExceeded` when the result is larger than the size limit
+    ///   or more references than the size limit were expanded.
+    ///   Protects against the "billion laughs" attack, including entities
+    ///   that expand to an empty text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let text = "<!DOCTYPE a [<!ENTITY b '&c; &amp;'><!ENTITY c 'text'>]><a/>";
+    /// let mut tokenizer = xmlparser::Tokenizer::from(text);
+    /// let entities = xmlparser::EntityMap::collect(&mut tokenizer).unwrap();
+    /// assert_eq!(entities.expand("&b; &d;").unwrap(), "text &amp; &d;");
+    /// ```
+    pub fn expand(&self, text: &str) -> Result<String, ExpansionError> {
+        let mut out = String::with_capacity(text.len());
+        let mut stack = Vec::new();
+        let mut count = 0;
+        self.expand_into(text, 0, &mut stack, &mut count, &mut out)?;
+        Ok(out)
+    }
+
+    // `stack` contains the entities that are being expanded
+    // along with the offset of the outermost reference.
+    // `count` is the number of already expanded references.
+    fn expand_into<'t>(
+        &'t self,
+        text: &'t str,
+        offset: usize,
+        stack: &mut Vec<&'t str>,
+        count: &mut usize,
+        out: &mut String,
+    ) -> Result<(), ExpansionError> {
+        let mut s = Stream::from(text);
+        while !s.at_end() {
+            if out.len() > self.size_limit {
+                return Err(ExpansionError::SizeLimitExceeded);
+            }
+
+            let start = s.pos();
+            let name = match s.try_consume_reference() {
+                Some(Reference::Entity(name)) => name,
+                Some(Reference::Char(_)) => {
+                    out.push_str(s.slice_back(start).as_str());
+                    continue;
+                }
+                None => {
+                    s.advance(1);
+                    s.skip_bytes(|_, c| c != b'&');
+                    out.push_str(s.slice_back(start).as_str());
+                    continue;
+                }
+            };
+
+            // Only the outermost reference offset is meaningful to the caller.
+            let offset = if stack.is_empty() { start } else { offset };
+
+            let value = match self.value(name) {
+                Some(value) => value.as_str(),
+                None => {
+                    out.push_str(s.slice_back(start).as_str());
+                    continue;
+                }
+            };
+
+            if stack.contains(&name) {
+                return Err(ExpansionError::RecursiveEntity(offset));
+            }
+
+            if stack.len() == self.depth_limit {
+                return Err(ExpansionError::DepthLimitExceeded(offset));
+            }
+
+            // Empty entities do not increase the output size,
+            // so the amount of work is limited separately.
+            *count += 1;
+            if *count > self.size_limit {
+                return Err(ExpansionError::SizeLimitExceeded);
+            }
+
+            stack.push(name);
+            self.expand_into(value, offset, stack, count, out)?;
+            stack.pop();
+        }
+
+        if out.len() > self.size_limit {
+            return Err(ExpansionError::SizeLimitExceeded);
+        }
+
+        Ok(())
+    }
+}
+
+// Checks that an entity declaration span is `<!ENTITY` S `%` ...
+fn is_parameter_entity(span: StrSpan) -> bool {
+    let mut s = Stream::from(span);
+    s.advance(8);
+    s.skip_spaces();
+    s.starts_with(b"%")
+}
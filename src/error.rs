@@ -1,79 +1,411 @@
+use core::cmp;
 use core::fmt;
 use core::str;
 #[cfg(feature = "std")]
 use std::error;
 
+use crate::{StrSpan, Stream};
+
 /// An XML parser errors.
+///
+/// Each error contains a text position and a byte offset in the original text.
 #[allow(missing_docs)]
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Error {
-    InvalidDeclaration(StreamError, TextPos),
-    InvalidComment(StreamError, TextPos),
-    InvalidPI(StreamError, TextPos),
-    InvalidDoctype(StreamError, TextPos),
-    InvalidEntity(StreamError, TextPos),
-    InvalidElement(StreamError, TextPos),
-    InvalidAttribute(StreamError, TextPos),
-    InvalidCdata(StreamError, TextPos),
-    InvalidCharData(StreamError, TextPos),
-    UnknownToken(TextPos),
+    InvalidDeclaration(StreamError, TextPos, usize),
+    InvalidComment(StreamError, TextPos, usize),
+    InvalidPI(StreamError, TextPos, usize),
+    InvalidDoctype(StreamError, TextPos, usize),
+    InvalidEntity(StreamError, TextPos, usize),
+    InvalidElementDecl(StreamError, TextPos, usize),
+    InvalidAttlistDecl(StreamError, TextPos, usize),
+    InvalidNotationDecl(StreamError, TextPos, usize),
+    InvalidElement(StreamError, TextPos, usize),
+    InvalidAttribute(StreamError, TextPos, usize),
+    InvalidCdata(StreamError, TextPos, usize),
+    InvalidCharData(StreamError, TextPos, usize),
+    UnknownToken(ExpectedToken, TextPos, usize),
+    TooManyEntities(TextPos, usize),
+    DtdTooLarge(TextPos, usize),
+    NestingTooDeep(TextPos, usize),
+    UnexpectedEndOfDocument(TextPos, usize),
+    UnexpectedBom(TextPos, usize),
+    MisplacedDeclaration(TextPos, usize),
 }
 
 impl Error {
     /// Returns the error position.
     pub fn pos(&self) -> TextPos {
         match *self {
-            Error::InvalidDeclaration(_, pos) => pos,
-            Error::InvalidComment(_, pos) => pos,
-            Error::InvalidPI(_, pos) => pos,
-            Error::InvalidDoctype(_, pos) => pos,
-            Error::InvalidEntity(_, pos) => pos,
-            Error::InvalidElement(_, pos) => pos,
-            Error::InvalidAttribute(_, pos) => pos,
-            Error::InvalidCdata(_, pos) => pos,
-            Error::InvalidCharData(_, pos) => pos,
-            Error::UnknownToken(pos) => pos,
+            Error::InvalidDeclaration(_, pos, _) => pos,
+            Error::InvalidComment(_, pos, _) => pos,
+            Error::InvalidPI(_, pos, _) => pos,
+            Error::InvalidDoctype(_, pos, _) => pos,
+            Error::InvalidEntity(_, pos, _) => pos,
+            Error::InvalidElementDecl(_, pos, _) => pos,
+            Error::InvalidAttlistDecl(_, pos, _) => pos,
+            Error::InvalidNotationDecl(_, pos, _) => pos,
+            Error::InvalidElement(_, pos, _) => pos,
+            Error::InvalidAttribute(_, pos, _) => pos,
+            Error::InvalidCdata(_, pos, _) => pos,
+            Error::InvalidCharData(_, pos, _) => pos,
+            Error::UnknownToken(_, pos, _) => pos,
+            Error::TooManyEntities(pos, _) => pos,
+            Error::DtdTooLarge(pos, _) => pos,
+            Error::NestingTooDeep(pos, _) => pos,
+            Error::UnexpectedEndOfDocument(pos, _) => pos,
+            Error::UnexpectedBom(pos, _) => pos,
+            Error::MisplacedDeclaration(pos, _) => pos,
+        }
+    }
+
+    /// Returns the error byte offset in the original text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let err = xmlparser::Tokenizer::from("<a>\n<b c></a>").nth(4).unwrap().unwrap_err();
+    /// assert_eq!(err.pos(), xmlparser::TextPos::new(2, 3));
+    /// assert_eq!(err.offset(), 6);
+    /// ```
+    pub fn offset(&self) -> usize {
+        match *self {
+            Error::InvalidDeclaration(_, _, offset) => offset,
+            Error::InvalidComment(_, _, offset) => offset,
+            Error::InvalidPI(_, _, offset) => offset,
+            Error::InvalidDoctype(_, _, offset) => offset,
+            Error::InvalidEntity(_, _, offset) => offset,
+            Error::InvalidElementDecl(_, _, offset) => offset,
+            Error::InvalidAttlistDecl(_, _, offset) => offset,
+            Error::InvalidNotationDecl(_, _, offset) => offset,
+            Error::InvalidElement(_, _, offset) => offset,
+            Error::InvalidAttribute(_, _, offset) => offset,
+            Error::InvalidCdata(_, _, offset) => offset,
+            Error::InvalidCharData(_, _, offset) => offset,
+            Error::UnknownToken(_, _, offset) => offset,
+            Error::TooManyEntities(_, offset) => offset,
+            Error::DtdTooLarge(_, offset) => offset,
+            Error::NestingTooDeep(_, offset) => offset,
+            Error::UnexpectedEndOfDocument(_, offset) => offset,
+            Error::UnexpectedBom(_, offset) => offset,
+            Error::MisplacedDeclaration(_, offset) => offset,
+        }
+    }
+
+    /// Calculates the error position in the original text.
+    ///
+    /// Unlike `pos`, works with deferred positions as well.
+    /// See [`Tokenizer::set_defer_positions`](struct.Tokenizer.html#method.set_defer_positions).
+    pub fn text_pos(&self, text: &str) -> TextPos {
+        Stream::from(text).gen_text_pos_from(self.offset())
+    }
+
+    /// Returns the text of a rejected comment.
+    ///
+    /// `text` must be the text that was tokenized. The comment text ends before
+    /// the first `-->` or at the end of `text`. The rejection reason is available
+    /// via `stream_error`: `InvalidCommentData` for `--` inside the comment
+    /// and `InvalidCommentEnd` for a comment ending with `--->`.
+    ///
+    /// Returns `None` for other errors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::StreamError;
+    ///
+    /// let text = "<a><!-- a -- b --></a>";
+    /// let err = xmlparser::first_error(text).unwrap();
+    /// assert_eq!(err.stream_error(), Some(StreamError::InvalidCommentData));
+    /// assert_eq!(err.comment_text(text).unwrap().as_str(), " a -- b ");
+    /// ```
+    pub fn comment_text<'a>(&self, text: &'a str) -> Option<StrSpan<'a>> {
+        let offset = match *self {
+            Error::InvalidComment(_, _, offset) => offset,
+            _ => return None,
+        };
+
+        if !text.get(offset..)?.starts_with("<!--") {
+            return None;
+        }
+
+        let start = offset + 4;
+        let end = match text[start..].find("-->") {
+            Some(idx) => start + idx,
+            None => text.len(),
+        };
+
+        Some(StrSpan::from_substr(text, start, end))
+    }
+}
+
+impl Error {
+    /// Returns a short, stable error code.
+    ///
+    /// Unlike the `Display` output, codes are never changed,
+    /// therefore they should be preferred for error matching.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let err = xmlparser::Tokenizer::from("<a b></a>").nth(1).unwrap().unwrap_err();
+    /// assert_eq!(err.code(), "invalid-attribute");
+    /// ```
+    pub fn code(&self) -> &'static str {
+        match *self {
+            Error::InvalidDeclaration(..) => "invalid-declaration",
+            Error::InvalidComment(..) => "invalid-comment",
+            Error::InvalidPI(..) => "invalid-pi",
+            Error::InvalidDoctype(..) => "invalid-doctype",
+            Error::InvalidEntity(..) => "invalid-entity",
+            Error::InvalidElementDecl(..) => "invalid-element-decl",
+            Error::InvalidAttlistDecl(..) => "invalid-attlist-decl",
+            Error::InvalidNotationDecl(..) => "invalid-notation-decl",
+            Error::InvalidElement(..) => "invalid-element",
+            Error::InvalidAttribute(..) => "invalid-attribute",
+            Error::InvalidCdata(..) => "invalid-cdata",
+            Error::InvalidCharData(..) => "invalid-char-data",
+            Error::UnknownToken(..) => "unknown-token",
+            Error::TooManyEntities(..) => "too-many-entities",
+            Error::DtdTooLarge(..) => "dtd-too-large",
+            Error::NestingTooDeep(..) => "nesting-too-deep",
+            Error::UnexpectedEndOfDocument(..) => "unexpected-end-of-document",
+            Error::UnexpectedBom(..) => "unexpected-bom",
+            Error::MisplacedDeclaration(..) => "misplaced-declaration",
+        }
+    }
+
+    /// Returns the underlying stream error, if any.
+    pub fn stream_error(&self) -> Option<StreamError> {
+        match *self {
+            Error::InvalidDeclaration(e, _, _) => Some(e),
+            Error::InvalidComment(e, _, _) => Some(e),
+            Error::InvalidPI(e, _, _) => Some(e),
+            Error::InvalidDoctype(e, _, _) => Some(e),
+            Error::InvalidEntity(e, _, _) => Some(e),
+            Error::InvalidElementDecl(e, _, _) => Some(e),
+            Error::InvalidAttlistDecl(e, _, _) => Some(e),
+            Error::InvalidNotationDecl(e, _, _) => Some(e),
+            Error::InvalidElement(e, _, _) => Some(e),
+            Error::InvalidAttribute(e, _, _) => Some(e),
+            Error::InvalidCdata(e, _, _) => Some(e),
+            Error::InvalidCharData(e, _, _) => Some(e),
+            Error::UnknownToken(..) => None,
+            Error::TooManyEntities(_, _) => None,
+            Error::DtdTooLarge(_, _) => None,
+            Error::NestingTooDeep(_, _) => None,
+            Error::UnexpectedEndOfDocument(_, _) => None,
+            Error::UnexpectedBom(_, _) => None,
+            Error::MisplacedDeclaration(_, _) => None,
+        }
+    }
+
+    /// Returns a hint about what was expected at the error position.
+    ///
+    /// Available only for `UnknownToken`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let err = xmlparser::Tokenizer::from("<a/>text").nth(2).unwrap().unwrap_err();
+    /// assert_eq!(
+    ///     err.expected().unwrap().to_string(),
+    ///     "expected a comment or a processing instruction"
+    /// );
+    /// ```
+    pub fn expected(&self) -> Option<ExpectedToken> {
+        match *self {
+            Error::UnknownToken(expected, _, _) => Some(expected),
+            _ => None,
+        }
+    }
+
+    /// Checks that the error was caused by a truncated document.
+    pub fn is_truncated(&self) -> bool {
+        match *self {
+            Error::UnexpectedEndOfDocument(..) => true,
+            _ => self.stream_error() == Some(StreamError::UnexpectedEndOfStream),
+        }
+    }
+
+    /// Renders the line containing the error with a caret pointing at the error position.
+    ///
+    /// Lines longer than `width` characters are clamped to a `width`-wide window around
+    /// the error position. Tabs are rendered as spaces to keep the caret aligned.
+    ///
+    /// `text` must be the same text that was passed to the `Tokenizer`.
+    /// Deferred positions are resolved using `text`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let text = "<root>\n  <a b='<'/>\n</root>";
+    /// let err = xmlparser::Tokenizer::from(text)
+    ///     .filter_map(|t| t.err())
+    ///     .next()
+    ///     .unwrap();
+    ///
+    /// let mut s = String::new();
+    /// err.render_snippet(text, &mut s, 80).unwrap();
+    /// assert_eq!(s, "  <a b='<'/>\n    ^\n");
+    /// ```
+    pub fn render_snippet(
+        &self,
+        text: &str,
+        out: &mut impl fmt::Write,
+        width: usize,
+    ) -> fmt::Result {
+        let mut pos = self.pos();
+        if pos.row == 0 {
+            // The position was deferred.
+            pos = self.text_pos(text);
+        }
+
+        let row = (pos.row as usize).saturating_sub(1);
+        let line = text.split('\n').nth(row).unwrap_or("");
+        let line = line.trim_end_matches('\r');
+
+        let width = cmp::max(width, 1);
+        let len = line.chars().count();
+        let col = cmp::min((pos.col as usize).saturating_sub(1), len);
+        let start = if len <= width {
+            0
+        } else {
+            cmp::min(col.saturating_sub(width / 2), len - width)
+        };
+
+        for c in line.chars().skip(start).take(width) {
+            out.write_char(if c == '\t' { ' ' } else { c })?;
+        }
+        out.write_char('\n')?;
+
+        for _ in start..col {
+            out.write_char(' ')?;
+        }
+        out.write_str("^\n")
+    }
+
+    /// Returns an error message with a frozen template.
+    ///
+    /// The message is built only from error codes and positions
+    /// and has one of the following forms:
+    ///
+    /// ```text
+    /// {code} at {row}:{col}
+    /// {code} at {row}:{col} caused by {cause code}
+    /// {code} at {row}:{col} caused by {cause code} at {row}:{col}
+    /// ```
+    ///
+    /// Unlike `Display`, this output is guaranteed to never change
+    /// and can be safely used in golden tests.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let err = xmlparser::Tokenizer::from("<a b></a>").nth(1).unwrap().unwrap_err();
+    /// assert_eq!(
+    ///     err.stable_message().to_string(),
+    ///     "invalid-attribute at 1:3 caused by invalid-char at 1:5"
+    /// );
+    /// ```
+    #[cfg(feature = "stable-messages")]
+    pub fn stable_message(&self) -> StableMessage {
+        StableMessage(*self)
+    }
+}
+
+/// An error message with a frozen template.
+///
+/// See [`Error::stable_message`] for details.
+///
+/// [`Error::stable_message`]: enum.Error.html#method.stable_message
+#[cfg(feature = "stable-messages")]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct StableMessage(Error);
+
+#[cfg(feature = "stable-messages")]
+impl fmt::Display for StableMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.0.code(), self.0.pos())?;
+        if let Some(cause) = self.0.stream_error() {
+            write!(f, " caused by {}", cause.code())?;
+            if let Some(pos) = cause.pos() {
+                write!(f, " at {}", pos)?;
+            }
         }
+
+        Ok(())
     }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            Error::InvalidDeclaration(ref cause, pos) => {
+            Error::InvalidDeclaration(ref cause, pos, _) => {
                 write!(f, "invalid XML declaration at {} cause {}", pos, cause)
             }
-            Error::InvalidComment(ref cause, pos) => {
+            Error::InvalidComment(ref cause, pos, _) => {
                 write!(f, "invalid comment at {} cause {}", pos, cause)
             }
-            Error::InvalidPI(ref cause, pos) => {
+            Error::InvalidPI(ref cause, pos, _) => {
                 write!(
                     f,
                     "invalid processing instruction at {} cause {}",
                     pos, cause
                 )
             }
-            Error::InvalidDoctype(ref cause, pos) => {
+            Error::InvalidDoctype(ref cause, pos, _) => {
                 write!(f, "invalid DTD at {} cause {}", pos, cause)
             }
-            Error::InvalidEntity(ref cause, pos) => {
+            Error::InvalidEntity(ref cause, pos, _) => {
                 write!(f, "invalid DTD entity at {} cause {}", pos, cause)
             }
-            Error::InvalidElement(ref cause, pos) => {
+            Error::InvalidElementDecl(ref cause, pos, _) => {
+                write!(f, "invalid DTD element at {} cause {}", pos, cause)
+            }
+            Error::InvalidAttlistDecl(ref cause, pos, _) => {
+                write!(f, "invalid DTD attribute list at {} cause {}", pos, cause)
+            }
+            Error::InvalidNotationDecl(ref cause, pos, _) => {
+                write!(f, "invalid DTD notation at {} cause {}", pos, cause)
+            }
+            Error::InvalidElement(ref cause, pos, _) => {
                 write!(f, "invalid element at {} cause {}", pos, cause)
             }
-            Error::InvalidAttribute(ref cause, pos) => {
+            Error::InvalidAttribute(ref cause, pos, _) => {
                 write!(f, "invalid attribute at {} cause {}", pos, cause)
             }
-            Error::InvalidCdata(ref cause, pos) => {
+            Error::InvalidCdata(ref cause, pos, _) => {
                 write!(f, "invalid CDATA at {} cause {}", pos, cause)
             }
-            Error::InvalidCharData(ref cause, pos) => {
+            Error::InvalidCharData(ref cause, pos, _) => {
                 write!(f, "invalid character data at {} cause {}", pos, cause)
             }
-            Error::UnknownToken(pos) => {
+            Error::UnknownToken(_, pos, _) => {
                 write!(f, "unknown token at {}", pos)
             }
+            Error::TooManyEntities(pos, _) => {
+                write!(f, "too many entities at {}", pos)
+            }
+            Error::DtdTooLarge(pos, _) => {
+                write!(f, "the DTD internal subset is too large at {}", pos)
+            }
+            Error::NestingTooDeep(pos, _) => {
+                write!(f, "elements are nested too deep at {}", pos)
+            }
+            Error::UnexpectedEndOfDocument(pos, _) => {
+                write!(f, "unexpected end of document at {}", pos)
+            }
+            Error::UnexpectedBom(pos, _) => {
+                write!(f, "unexpected BOM at {}", pos)
+            }
+            Error::MisplacedDeclaration(pos, _) => {
+                write!(
+                    f,
+                    "the XML declaration is allowed only at the start of the document at {}",
+                    pos
+                )
+            }
         }
     }
 }
@@ -85,7 +417,46 @@ impl error::Error for Error {
     }
 }
 
+/// A hint about tokens that were expected at the `UnknownToken` error position.
+///
+/// Depends on the document part where the error has occurred.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ExpectedToken {
+    /// A comment, a processing instruction, a DOCTYPE or the root element.
+    Prolog,
+    /// A markup declaration, a comment, a processing instruction,
+    /// a parameter entity reference or the DOCTYPE end.
+    DtdContent,
+    /// A comment, a processing instruction or the root element.
+    RootElement,
+    /// An element, a text, a comment, a CDATA or a processing instruction.
+    ElementContent,
+    /// A comment or a processing instruction.
+    Misc,
+}
+
+impl fmt::Display for ExpectedToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            ExpectedToken::Prolog => "a comment, a processing instruction, a DOCTYPE or an element",
+            ExpectedToken::DtdContent => {
+                "a markup declaration, a comment, a processing instruction, \
+                 a parameter entity reference or ']'"
+            }
+            ExpectedToken::RootElement => "a comment, a processing instruction or an element",
+            ExpectedToken::ElementContent => {
+                "an element, a text, a comment, a CDATA or a processing instruction"
+            }
+            ExpectedToken::Misc => "a comment or a processing instruction",
+        };
+
+        write!(f, "expected {}", s)
+    }
+}
+
 /// A stream parser errors.
+///
+/// Errors with a text position also contain a byte offset in the original text.
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum StreamError {
     /// The steam ended earlier than we expected.
@@ -100,32 +471,32 @@ pub enum StreamError {
     /// A non-XML character has occurred.
     ///
     /// Valid characters are: <https://www.w3.org/TR/xml/#char32>
-    NonXmlChar(char, TextPos),
+    NonXmlChar(char, TextPos, usize),
 
     /// An invalid/unexpected character.
     ///
     /// The first byte is an actual one, the second one is expected.
     ///
     /// We are using a single value to reduce the struct size.
-    InvalidChar(u8, u8, TextPos),
+    InvalidChar(u8, u8, TextPos, usize),
 
     /// An invalid/unexpected character.
     ///
     /// Just like `InvalidChar`, but specifies multiple expected characters.
-    InvalidCharMultiple(u8, &'static [u8], TextPos),
+    InvalidCharMultiple(u8, &'static [u8], TextPos, usize),
 
     /// An unexpected character instead of `"` or `'`.
-    InvalidQuote(u8, TextPos),
+    InvalidQuote(u8, TextPos, usize),
 
     /// An unexpected character instead of an XML space.
     ///
     /// Includes: `' ' \n \r \t &#x20; &#x9; &#xD; &#xA;`.
-    InvalidSpace(u8, TextPos),
+    InvalidSpace(u8, TextPos, usize),
 
     /// An unexpected string.
     ///
     /// Contains what string was expected.
-    InvalidString(&'static str, TextPos),
+    InvalidString(&'static str, TextPos, usize),
 
     /// An invalid reference.
     InvalidReference,
@@ -143,6 +514,129 @@ pub enum StreamError {
     ///
     /// Currently, only `]]>` is not allowed.
     InvalidCharacterData,
+
+    /// An invalid number.
+    InvalidNumber(TextPos, usize),
+
+    /// A space between `/` and `>` in an empty element end, like in `<a/ >`.
+    SpaceInEmptyElementEnd(TextPos, usize),
+
+    /// A token content is longer than the limit set on the tokenizer.
+    ///
+    /// Points to the content start.
+    LengthLimitExceeded(TextPos, usize),
+
+    /// A processing instruction target that matches `xml` case-insensitively,
+    /// like in `<?XML?>`.
+    ///
+    /// Points to the target start.
+    ReservedPiTarget(TextPos, usize),
+
+    /// A character that is not allowed in a public identifier literal.
+    ///
+    /// Points to the character.
+    InvalidPubidLiteral(char, TextPos, usize),
+
+    /// A malformed or an invalid reference.
+    ///
+    /// Unlike `InvalidReference`, points to the reference start.
+    MalformedReference(TextPos, usize),
+
+    /// An invalid encoding name in the XML declaration.
+    ///
+    /// Points to the name start.
+    InvalidEncodingName(TextPos, usize),
+}
+
+impl StreamError {
+    /// Returns a short, stable error code.
+    ///
+    /// Unlike the `Display` output, codes are never changed,
+    /// therefore they should be preferred for error matching.
+    pub fn code(&self) -> &'static str {
+        match *self {
+            StreamError::UnexpectedEndOfStream => "unexpected-end-of-stream",
+            StreamError::InvalidName => "invalid-name",
+            StreamError::NonXmlChar(..) => "non-xml-char",
+            StreamError::InvalidChar(..) => "invalid-char",
+            StreamError::InvalidCharMultiple(..) => "invalid-char",
+            StreamError::InvalidQuote(..) => "invalid-quote",
+            StreamError::InvalidSpace(..) => "invalid-space",
+            StreamError::InvalidString(..) => "invalid-string",
+            StreamError::InvalidReference => "invalid-reference",
+            StreamError::InvalidExternalID => "invalid-external-id",
+            StreamError::InvalidCommentData => "invalid-comment-data",
+            StreamError::InvalidCommentEnd => "invalid-comment-end",
+            StreamError::InvalidCharacterData => "invalid-character-data",
+            StreamError::InvalidNumber(..) => "invalid-number",
+            StreamError::SpaceInEmptyElementEnd(..) => "space-in-empty-element-end",
+            StreamError::LengthLimitExceeded(..) => "length-limit-exceeded",
+            StreamError::ReservedPiTarget(..) => "reserved-pi-target",
+            StreamError::InvalidPubidLiteral(..) => "invalid-pubid-literal",
+            StreamError::MalformedReference(..) => "malformed-reference",
+            StreamError::InvalidEncodingName(..) => "invalid-encoding-name",
+        }
+    }
+
+    /// Returns the error position, if any.
+    pub fn pos(&self) -> Option<TextPos> {
+        match *self {
+            StreamError::NonXmlChar(_, pos, _) => Some(pos),
+            StreamError::InvalidChar(_, _, pos, _) => Some(pos),
+            StreamError::InvalidCharMultiple(_, _, pos, _) => Some(pos),
+            StreamError::InvalidQuote(_, pos, _) => Some(pos),
+            StreamError::InvalidSpace(_, pos, _) => Some(pos),
+            StreamError::InvalidString(_, pos, _) => Some(pos),
+            StreamError::InvalidNumber(pos, _) => Some(pos),
+            StreamError::SpaceInEmptyElementEnd(pos, _) => Some(pos),
+            StreamError::LengthLimitExceeded(pos, _) => Some(pos),
+            StreamError::ReservedPiTarget(pos, _) => Some(pos),
+            StreamError::InvalidPubidLiteral(_, pos, _) => Some(pos),
+            StreamError::MalformedReference(pos, _) => Some(pos),
+            StreamError::InvalidEncodingName(pos, _) => Some(pos),
+            StreamError::UnexpectedEndOfStream
+            | StreamError::InvalidName
+            | StreamError::InvalidReference
+            | StreamError::InvalidExternalID
+            | StreamError::InvalidCommentData
+            | StreamError::InvalidCommentEnd
+            | StreamError::InvalidCharacterData => None,
+        }
+    }
+
+    /// Returns the error byte offset in the original text, if any.
+    pub fn offset(&self) -> Option<usize> {
+        match *self {
+            StreamError::NonXmlChar(_, _, offset) => Some(offset),
+            StreamError::InvalidChar(_, _, _, offset) => Some(offset),
+            StreamError::InvalidCharMultiple(_, _, _, offset) => Some(offset),
+            StreamError::InvalidQuote(_, _, offset) => Some(offset),
+            StreamError::InvalidSpace(_, _, offset) => Some(offset),
+            StreamError::InvalidString(_, _, offset) => Some(offset),
+            StreamError::InvalidNumber(_, offset) => Some(offset),
+            StreamError::SpaceInEmptyElementEnd(_, offset) => Some(offset),
+            StreamError::LengthLimitExceeded(_, offset) => Some(offset),
+            StreamError::ReservedPiTarget(_, offset) => Some(offset),
+            StreamError::InvalidPubidLiteral(_, _, offset) => Some(offset),
+            StreamError::MalformedReference(_, offset) => Some(offset),
+            StreamError::InvalidEncodingName(_, offset) => Some(offset),
+            StreamError::UnexpectedEndOfStream
+            | StreamError::InvalidName
+            | StreamError::InvalidReference
+            | StreamError::InvalidExternalID
+            | StreamError::InvalidCommentData
+            | StreamError::InvalidCommentEnd
+            | StreamError::InvalidCharacterData => None,
+        }
+    }
+
+    /// Calculates the error position in the original text, if any.
+    ///
+    /// Unlike `pos`, works with deferred positions as well.
+    pub fn text_pos(&self, text: &str) -> Option<TextPos> {
+        self.offset()
+            .map(|offset| Stream::from(text).gen_text_pos_from(offset))
+    }
 }
 
 impl fmt::Display for StreamError {
@@ -154,17 +648,17 @@ impl fmt::Display for StreamError {
             StreamError::InvalidName => {
                 write!(f, "invalid name token")
             }
-            StreamError::NonXmlChar(c, pos) => {
+            StreamError::NonXmlChar(c, pos, _) => {
                 write!(f, "a non-XML character {:?} found at {}", c, pos)
             }
-            StreamError::InvalidChar(actual, expected, pos) => {
+            StreamError::InvalidChar(actual, expected, pos, _) => {
                 write!(
                     f,
                     "expected '{}' not '{}' at {}",
                     expected as char, actual as char, pos
                 )
             }
-            StreamError::InvalidCharMultiple(actual, expected, pos) => {
+            StreamError::InvalidCharMultiple(actual, expected, pos, _) => {
                 let mut expected_iter = expected.iter().peekable();
 
                 write!(f, "expected ")?;
@@ -176,13 +670,13 @@ impl fmt::Display for StreamError {
                 }
                 write!(f, " not '{}' at {}", actual as char, pos)
             }
-            StreamError::InvalidQuote(c, pos) => {
+            StreamError::InvalidQuote(c, pos, _) => {
                 write!(f, "expected quote mark not '{}' at {}", c as char, pos)
             }
-            StreamError::InvalidSpace(c, pos) => {
+            StreamError::InvalidSpace(c, pos, _) => {
                 write!(f, "expected space not '{}' at {}", c as char, pos)
             }
-            StreamError::InvalidString(expected, pos) => {
+            StreamError::InvalidString(expected, pos, _) => {
                 write!(f, "expected '{}' at {}", expected, pos)
             }
             StreamError::InvalidReference => {
@@ -200,6 +694,35 @@ impl fmt::Display for StreamError {
             StreamError::InvalidCharacterData => {
                 write!(f, "']]>' is not allowed inside a character data")
             }
+            StreamError::InvalidNumber(pos, _) => {
+                write!(f, "invalid number at {}", pos)
+            }
+            StreamError::SpaceInEmptyElementEnd(pos, _) => {
+                write!(f, "a space between '/' and '>' is not allowed at {}", pos)
+            }
+            StreamError::LengthLimitExceeded(pos, _) => {
+                write!(f, "the length limit is exceeded at {}", pos)
+            }
+            StreamError::ReservedPiTarget(pos, _) => {
+                write!(
+                    f,
+                    "a processing instruction target 'xml' is reserved at {}",
+                    pos
+                )
+            }
+            StreamError::InvalidPubidLiteral(c, pos, _) => {
+                write!(
+                    f,
+                    "a character {:?} is not allowed in a public identifier at {}",
+                    c, pos
+                )
+            }
+            StreamError::MalformedReference(pos, _) => {
+                write!(f, "a malformed reference at {}", pos)
+            }
+            StreamError::InvalidEncodingName(pos, _) => {
+                write!(f, "an invalid encoding name at {}", pos)
+            }
         }
     }
 }
@@ -211,6 +734,100 @@ impl error::Error for StreamError {
     }
 }
 
+/// A tokenizer warning.
+///
+/// Warnings are produced in the lenient mode for spec violations that were accepted.
+/// See [`Tokenizer::warning`](struct.Tokenizer.html#method.warning).
+///
+/// Each warning contains a text position and a byte offset in the original text.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Warning {
+    /// A space between `/` and `>` in an empty element end, like in `<a/ >`.
+    SpaceInEmptyElementEnd(TextPos, usize),
+    /// An attribute without a value, like in `<input disabled>`.
+    MinimizedAttribute(TextPos, usize),
+    /// An attribute value without quotes, like in `<a b=c>`.
+    UnquotedAttributeValue(TextPos, usize),
+    /// A processing instruction target that matches `xml` case-insensitively,
+    /// like in `<?XML?>`.
+    ReservedPiTarget(TextPos, usize),
+    /// A public identifier with a not allowed character, like in `PUBLIC '{id}'`.
+    InvalidPubidLiteral(TextPos, usize),
+    /// A comment with `--` inside or `-` at the end, like in `<!-- a -- b -->`.
+    MalformedComment(TextPos, usize),
+    /// A `]]>` in text, like in `<a>]]></a>`.
+    CdataEndInText(TextPos, usize),
+}
+
+impl Warning {
+    /// Returns a short, stable warning code.
+    pub fn code(&self) -> &'static str {
+        match *self {
+            Warning::SpaceInEmptyElementEnd(..) => "space-in-empty-element-end",
+            Warning::MinimizedAttribute(..) => "minimized-attribute",
+            Warning::UnquotedAttributeValue(..) => "unquoted-attribute-value",
+            Warning::ReservedPiTarget(..) => "reserved-pi-target",
+            Warning::InvalidPubidLiteral(..) => "invalid-pubid-literal",
+            Warning::MalformedComment(..) => "malformed-comment",
+            Warning::CdataEndInText(..) => "cdata-end-in-text",
+        }
+    }
+
+    /// Returns the warning position.
+    pub fn pos(&self) -> TextPos {
+        match *self {
+            Warning::SpaceInEmptyElementEnd(pos, _)
+            | Warning::MinimizedAttribute(pos, _)
+            | Warning::UnquotedAttributeValue(pos, _)
+            | Warning::ReservedPiTarget(pos, _)
+            | Warning::InvalidPubidLiteral(pos, _)
+            | Warning::MalformedComment(pos, _)
+            | Warning::CdataEndInText(pos, _) => pos,
+        }
+    }
+
+    /// Returns the warning byte offset in the original text.
+    pub fn offset(&self) -> usize {
+        match *self {
+            Warning::SpaceInEmptyElementEnd(_, offset)
+            | Warning::MinimizedAttribute(_, offset)
+            | Warning::UnquotedAttributeValue(_, offset)
+            | Warning::ReservedPiTarget(_, offset)
+            | Warning::InvalidPubidLiteral(_, offset)
+            | Warning::MalformedComment(_, offset)
+            | Warning::CdataEndInText(_, offset) => offset,
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Warning::SpaceInEmptyElementEnd(pos, _) => {
+                write!(f, "a space between '/' and '>' at {}", pos)
+            }
+            Warning::MinimizedAttribute(pos, _) => {
+                write!(f, "an attribute without a value at {}", pos)
+            }
+            Warning::UnquotedAttributeValue(pos, _) => {
+                write!(f, "an unquoted attribute value at {}", pos)
+            }
+            Warning::ReservedPiTarget(pos, _) => {
+                write!(f, "a reserved processing instruction target at {}", pos)
+            }
+            Warning::InvalidPubidLiteral(pos, _) => {
+                write!(f, "an invalid public identifier character at {}", pos)
+            }
+            Warning::MalformedComment(pos, _) => {
+                write!(f, "a malformed comment at {}", pos)
+            }
+            Warning::CdataEndInText(pos, _) => {
+                write!(f, "a ']]>' in text at {}", pos)
+            }
+        }
+    }
+}
+
 /// Position in text.
 ///
 /// Position indicates a row/line and a column in the original text. Starting from 1:1.
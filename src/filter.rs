@@ -0,0 +1,88 @@
+use crate::{Error, Token, Tokenizer, XmlByteExt};
+
+/// An iterator over tokens that skips the selected kinds of tokens.
+///
+/// See [`Tokenizer::skip_comments`](struct.Tokenizer.html#method.skip_comments).
+///
+/// # Examples
+///
+/// ```
+/// let text = "<a>\n  <!-- c --><?pi?><b/>\n</a>";
+/// let tokens: Vec<_> = xmlparser::Tokenizer::from(text)
+///     .skip_comments()
+///     .skip_pis()
+///     .significant_whitespace_only()
+///     .map(|t| t.unwrap().span().as_str())
+///     .collect();
+/// assert_eq!(tokens, ["<a", ">", "<b", "/>", "</a>"]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct TokenFilter<'a> {
+    tokenizer: Tokenizer<'a>,
+    comments: bool,
+    pis: bool,
+    whitespaces: bool,
+}
+
+impl<'a> TokenFilter<'a> {
+    pub(crate) fn new(tokenizer: Tokenizer<'a>) -> Self {
+        TokenFilter {
+            tokenizer,
+            comments: false,
+            pis: false,
+            whitespaces: false,
+        }
+    }
+
+    /// Skips comments.
+    pub fn skip_comments(mut self) -> Self {
+        self.comments = true;
+        self
+    }
+
+    /// Skips processing instructions.
+    ///
+    /// The XML declaration is not a processing instruction and is not skipped.
+    pub fn skip_pis(mut self) -> Self {
+        self.pis = true;
+        self
+    }
+
+    /// Skips text tokens that contain only whitespaces.
+    ///
+    /// `xml:space` is not taken into account.
+    pub fn significant_whitespace_only(mut self) -> Self {
+        self.whitespaces = true;
+        self
+    }
+
+    /// Returns the underlying tokenizer.
+    pub fn into_inner(self) -> Tokenizer<'a> {
+        self.tokenizer
+    }
+
+    fn is_skipped(&self, token: &Token) -> bool {
+        match *token {
+            Token::Comment { .. } => self.comments,
+            Token::ProcessingInstruction { .. } => self.pis,
+            Token::Text { text } => {
+                self.whitespaces && text.as_bytes().iter().all(|c| c.is_xml_space())
+            }
+            Token::Whitespaces { .. } => self.whitespaces,
+            _ => false,
+        }
+    }
+}
+
+impl<'a> Iterator for TokenFilter<'a> {
+    type Item = Result<Token<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.tokenizer.next()? {
+                Ok(ref token) if self.is_skipped(token) => {}
+                t => return Some(t),
+            }
+        }
+    }
+}
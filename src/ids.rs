@@ -0,0 +1,132 @@
+use alloc::vec::Vec;
+
+use crate::{StrSpan, Token};
+
+/// An ID attribute.
+///
+/// See [`IdTracker`](struct.IdTracker.html).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct IdAttribute<'a> {
+    /// The element namespace prefix.
+    pub element_prefix: StrSpan<'a>,
+    /// The element local name.
+    pub element: StrSpan<'a>,
+    /// The attribute value.
+    ///
+    /// Not normalized and not unescaped.
+    pub value: StrSpan<'a>,
+    /// The whole attribute span.
+    pub span: StrSpan<'a>,
+    /// Indicates that this is an `xml:id` attribute.
+    pub xml_id: bool,
+}
+
+/// A detector of ID attributes.
+///
+/// Consumes tokens and reports `xml:id` attributes and attributes
+/// declared with the `ID` type in the DTD. Useful for building
+/// an ID lookup index without a DOM.
+///
+/// Only the internal DTD subset and loaded external subsets are taken into account.
+/// IDs are not validated and duplicates are not detected.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::{IdTracker, Tokenizer};
+///
+/// let text = "<!DOCTYPE a [<!ATTLIST b key ID #IMPLIED>]><a><b key='x'/><c xml:id='y'/></a>";
+/// let mut tracker = IdTracker::new();
+/// let ids: Vec<_> = Tokenizer::from(text)
+///     .filter_map(|t| tracker.feed(&t.unwrap()))
+///     .map(|id| (id.element.as_str(), id.value.as_str()))
+///     .collect();
+/// assert_eq!(ids, [("b", "x"), ("c", "y")]);
+/// ```
+#[derive(Clone, Default, Debug)]
+pub struct IdTracker<'a> {
+    // Qualified element and attribute names.
+    declared: Vec<(StrSpan<'a>, StrSpan<'a>)>,
+    element: Option<(StrSpan<'a>, StrSpan<'a>)>,
+}
+
+impl<'a> IdTracker<'a> {
+    /// Creates a new tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the state using the next token.
+    ///
+    /// Returns an ID attribute when `token` is one.
+    ///
+    /// All the tokens must be fed in the tokenizer order.
+    pub fn feed(&mut self, token: &Token<'a>) -> Option<IdAttribute<'a>> {
+        match *token {
+            Token::AttlistDeclaration {
+                name, definitions, ..
+            } => {
+                for def in definitions {
+                    if def.att_type.as_str() == "ID" {
+                        self.declared.push((name, def.name));
+                    }
+                }
+
+                None
+            }
+            Token::ElementStart { prefix, local, .. } => {
+                self.element = Some((prefix, local));
+                None
+            }
+            Token::Attribute {
+                prefix,
+                local,
+                value,
+                span,
+            } => {
+                let (element_prefix, element) = self.element?;
+                let xml_id = prefix.as_str() == "xml" && local.as_str() == "id";
+                if xml_id || self.is_declared(element_prefix, element, prefix, local) {
+                    Some(IdAttribute {
+                        element_prefix,
+                        element,
+                        value,
+                        span,
+                        xml_id,
+                    })
+                } else {
+                    None
+                }
+            }
+            Token::ElementEnd { .. } => {
+                self.element = None;
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn is_declared(
+        &self,
+        element_prefix: StrSpan,
+        element: StrSpan,
+        prefix: StrSpan,
+        local: StrSpan,
+    ) -> bool {
+        self.declared.iter().any(|&(e, a)| {
+            is_qname(e.as_str(), element_prefix.as_str(), element.as_str())
+                && is_qname(a.as_str(), prefix.as_str(), local.as_str())
+        })
+    }
+}
+
+fn is_qname(qname: &str, prefix: &str, local: &str) -> bool {
+    if prefix.is_empty() {
+        qname == local
+    } else {
+        qname.len() == prefix.len() + 1 + local.len()
+            && qname.starts_with(prefix)
+            && qname.ends_with(local)
+            && qname.as_bytes()[prefix.len()] == b':'
+    }
+}
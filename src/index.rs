@@ -0,0 +1,219 @@
+use alloc::vec::Vec;
+
+use crate::Tokenizer;
+
+/// A kind of an [`IndexEntry`].
+///
+/// [`IndexEntry`]: struct.IndexEntry.html
+#[allow(missing_docs)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum IndexKind {
+    /// An XML declaration or a processing instruction.
+    ProcessingInstruction,
+    Comment,
+    Doctype,
+    Cdata,
+    /// An element start tag, including attributes.
+    ElementStart,
+    /// An element close tag.
+    ElementEnd,
+    /// A text inside the root element.
+    Text,
+}
+
+/// An entry of the [`Index`].
+///
+/// [`Index`]: struct.Index.html
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct IndexEntry {
+    /// Entry kind.
+    pub kind: IndexKind,
+    /// An absolute byte offset of the entry start.
+    pub offset: usize,
+}
+
+/// A compact index of the document structure.
+///
+/// Built by a quick byte scanning without any validation,
+/// so an invalid document will produce a meaningless index.
+/// Errors will be detected only when the tokens are realized via `tokenize`.
+///
+/// Useful for huge documents, where only a small part of the tokens is required.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::{Index, IndexKind, Token};
+///
+/// let text = "<root><a>text</a><!-- comment --><b/></root>";
+/// let index = Index::build(text);
+/// assert_eq!(index.entries().len(), 7);
+///
+/// let pos = index.entries().iter().position(|e| e.kind == IndexKind::Comment).unwrap();
+/// match index.tokenize(text, pos).next() {
+///     Some(Ok(Token::Comment { text, .. })) => assert_eq!(text.as_str(), " comment "),
+///     _ => unreachable!(),
+/// }
+/// ```
+#[derive(Clone, Default, Debug)]
+pub struct Index {
+    entries: Vec<IndexEntry>,
+}
+
+impl Index {
+    /// Builds an index for the text.
+    pub fn build(text: &str) -> Self {
+        let bytes = text.as_bytes();
+        let mut entries = Vec::new();
+        let mut depth = 0usize;
+        let mut pos = 0;
+
+        // Skip UTF-8 BOM.
+        if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+            pos = 3;
+        }
+
+        while pos < bytes.len() {
+            if bytes[pos] != b'<' {
+                let start = pos;
+                pos = find_byte(bytes, pos, b'<');
+                if depth > 0 {
+                    entries.push(IndexEntry {
+                        kind: IndexKind::Text,
+                        offset: start,
+                    });
+                }
+
+                continue;
+            }
+
+            let start = pos;
+            let tail = &bytes[pos..];
+            let kind = if tail.starts_with(b"<?") {
+                pos = find_str(bytes, pos + 2, b"?>");
+                IndexKind::ProcessingInstruction
+            } else if tail.starts_with(b"<!--") {
+                pos = find_str(bytes, pos + 4, b"-->");
+                IndexKind::Comment
+            } else if tail.starts_with(b"<![CDATA[") {
+                pos = find_str(bytes, pos, b"]]>");
+                IndexKind::Cdata
+            } else if tail.starts_with(b"<!") {
+                pos = skip_doctype(bytes, pos);
+                IndexKind::Doctype
+            } else if tail.starts_with(b"</") {
+                pos = find_byte(bytes, pos, b'>') + 1;
+                depth = depth.saturating_sub(1);
+                IndexKind::ElementEnd
+            } else {
+                pos = skip_tag(bytes, pos);
+                if !bytes[start..pos].ends_with(b"/>") {
+                    depth += 1;
+                }
+
+                IndexKind::ElementStart
+            };
+
+            entries.push(IndexEntry {
+                kind,
+                offset: start,
+            });
+        }
+
+        Index { entries }
+    }
+
+    /// Returns index entries.
+    pub fn entries(&self) -> &[IndexEntry] {
+        &self.entries
+    }
+
+    /// Returns a tokenizer that starts at the selected entry.
+    ///
+    /// Entries up to the root element start are realized in the document mode,
+    /// so the tokenizer will continue till the document end.
+    /// Otherwise, the tokenizer is in the fragment parsing mode,
+    /// therefore only the content of the root element can be realized.
+    ///
+    /// # Panics
+    ///
+    /// - When `index` is out of bounds.
+    pub fn tokenize<'a>(&self, text: &'a str, index: usize) -> Tokenizer<'a> {
+        let prev = &self.entries[..index];
+        let offset = self.entries[index].offset;
+        if prev.is_empty() {
+            Tokenizer::from(text)
+        } else if prev.iter().all(|e| e.kind != IndexKind::ElementStart) {
+            let after_dtd = prev.iter().any(|e| e.kind == IndexKind::Doctype);
+            Tokenizer::from_prolog(text, offset, after_dtd)
+        } else {
+            Tokenizer::from_fragment(text, offset..text.len())
+        }
+    }
+}
+
+// Returns the position of the byte or the text length.
+fn find_byte(bytes: &[u8], start: usize, c: u8) -> usize {
+    bytes[start..]
+        .iter()
+        .position(|&b| b == c)
+        .map(|p| start + p)
+        .unwrap_or(bytes.len())
+}
+
+// Returns the position right after the string or the text length.
+fn find_str(bytes: &[u8], start: usize, s: &[u8]) -> usize {
+    let mut pos = start;
+    while pos < bytes.len() {
+        if bytes[pos..].starts_with(s) {
+            return pos + s.len();
+        }
+
+        pos += 1;
+    }
+
+    bytes.len()
+}
+
+// Skips a start tag, while ignoring `>` inside attribute values.
+fn skip_tag(bytes: &[u8], start: usize) -> usize {
+    let mut quote = None;
+    for (i, &c) in bytes.iter().enumerate().skip(start) {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == b'"' || c == b'\'' => quote = Some(c),
+            None if c == b'>' => return i + 1,
+            None => {}
+        }
+    }
+
+    bytes.len()
+}
+
+// Skips a DOCTYPE, including the internal subset.
+fn skip_doctype(bytes: &[u8], start: usize) -> usize {
+    let mut quote = None;
+    let mut depth = 0;
+    let mut pos = start;
+    while pos < bytes.len() {
+        let c = bytes[pos];
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if bytes[pos..].starts_with(b"<!--") => {
+                pos = find_str(bytes, pos + 4, b"-->");
+                continue;
+            }
+            None if c == b'"' || c == b'\'' => quote = Some(c),
+            None if c == b'[' => depth += 1,
+            None if c == b']' => depth -= 1,
+            None if c == b'>' && depth <= 0 => return pos + 1,
+            None => {}
+        }
+
+        pos += 1;
+    }
+
+    bytes.len()
+}
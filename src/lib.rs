@@ -33,26 +33,30 @@
 //!   substring in the original document.
 //! - Good error processing. All error types contain the position (line:column)
 //!   where it occurred.
-//! - No heap allocations.
-//! - No dependencies.
-//! - Tiny. ~1400 LOC and ~30KiB in the release build according to
-//!   `cargo-bloat`.
+//! - No heap allocations in the tokenizer. Allocating helpers are available
+//!   only with the `alloc` feature.
+//! - No dependencies by default. The optional `memchr` feature speeds up
+//!   text, comment and CDATA scanning.
+//! - Small core. The tokenizer itself is ~6000 LOC, the rest of the crate
+//!   consists of independent helpers.
 //! - Supports `no_std` builds. To use without the standard library, disable the
-//!   default features.
+//!   default features. The `alloc` feature enables allocating helpers, like owned tokens
+//!   and `TextUnescape::unescape`, on targets with an allocator.
 //!
 //! <br>
 //!
 //! ## Limitations
 //!
-//! - Currently, only ENTITY objects are parsed from the DOCTYPE. All others are
-//!   ignored.
+//! - Parameter entity references inside the DOCTYPE are reported, but not resolved.
+//!   References inside declarations are not supported.
 //! - No tree structure validation. So an XML like
 //!   `<root><child></root></child>` or a string without root element will be
-//!   parsed without errors. You should check for this manually. On the other
-//!   hand `<a/><a/>` will lead to an error.
+//!   parsed without errors. On the other hand `<a/><a/>` will lead to an error.
+//!   Use `validate::WellFormedChecker` to check close tags and a root element.
 //! - Duplicated attributes is not an error. So XML like `<item a="v1" a="v2"/>`
-//!   will be parsed without errors. You should check for this manually.
-//! - UTF-8 only.
+//!   will be parsed without errors. `validate::WellFormedChecker` checks this too.
+//! - UTF-8 only. UTF-16 documents have to be decoded via `DecodedText`
+//!   first, which requires the `utf16` feature.
 //!
 //! <br>
 //!
@@ -69,8 +73,8 @@
 //! Licensed under either of
 //!
 //! - Apache License, Version 2.0 ([LICENSE-APACHE] or
-//!   http://www.apache.org/licenses/LICENSE-2.0)
-//! - MIT license ([LICENSE-MIT] or http://opensource.org/licenses/MIT)
+//!   <http://www.apache.org/licenses/LICENSE-2.0>)
+//! - MIT license ([LICENSE-MIT] or <http://opensource.org/licenses/MIT>)
 //!
 //! at your option.
 //!
@@ -94,6 +98,18 @@
 #[macro_use]
 extern crate std;
 
+// `extern crate alloc` is not available on the minimal supported Rust version,
+// therefore the `std` re-exports are used when possible.
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std as alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::borrow::Cow;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
 macro_rules! matches {
     ($expression:expr, $($pattern:tt)+) => {
         match $expression {
@@ -103,14 +119,88 @@ macro_rules! matches {
     }
 }
 
+mod attlist;
+mod attributes;
+mod builder;
+mod close;
+#[cfg(feature = "utf16")]
+mod decode;
+mod dump;
+#[cfg(feature = "std")]
+mod entity;
 mod error;
+mod filter;
+#[cfg(feature = "alloc")]
+mod ids;
+#[cfg(feature = "alloc")]
+mod index;
+#[cfg(feature = "alloc")]
+mod lines;
+mod locate;
+mod numbers;
+#[cfg(feature = "alloc")]
+mod owned;
+mod pi;
+mod progress;
+mod prolog;
+#[cfg(feature = "alloc")]
+mod push;
+#[cfg(feature = "alloc")]
+mod scope;
+#[cfg(feature = "serde")]
+mod ser;
+mod source;
 mod stream;
 mod strspan;
+mod subset;
+#[cfg(feature = "alloc")]
+mod tags;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod unescape;
+#[cfg(feature = "std")]
+pub mod validate;
+#[cfg(feature = "alloc")]
+mod writer;
 mod xmlchar;
 
+pub use crate::attlist::*;
+pub use crate::attributes::*;
+pub use crate::builder::*;
+pub use crate::close::*;
+#[cfg(feature = "utf16")]
+pub use crate::decode::*;
+pub use crate::dump::*;
+#[cfg(feature = "std")]
+pub use crate::entity::*;
 pub use crate::error::*;
+pub use crate::filter::*;
+#[cfg(feature = "alloc")]
+pub use crate::ids::*;
+#[cfg(feature = "alloc")]
+pub use crate::index::*;
+#[cfg(feature = "alloc")]
+pub use crate::lines::*;
+pub use crate::locate::*;
+pub use crate::numbers::*;
+#[cfg(feature = "alloc")]
+pub use crate::owned::*;
+pub use crate::pi::*;
+pub use crate::progress::*;
+pub use crate::prolog::*;
+#[cfg(feature = "alloc")]
+pub use crate::push::*;
+#[cfg(feature = "alloc")]
+pub use crate::scope::*;
+pub use crate::source::*;
 pub use crate::stream::*;
 pub use crate::strspan::*;
+pub use crate::subset::*;
+#[cfg(feature = "alloc")]
+pub use crate::tags::*;
+pub use crate::unescape::*;
+#[cfg(feature = "alloc")]
+pub use crate::writer::*;
 pub use crate::xmlchar::*;
 
 /// An XML token.
@@ -154,9 +244,12 @@ pub enum Token<'a> {
     ///     ------    - text
     /// ------------- - span
     /// ```
+    ///
+    /// `unterminated` can be set only in the partial mode.
     Comment {
         text: StrSpan<'a>,
         span: StrSpan<'a>,
+        unterminated: bool,
     },
 
     /// DOCTYPE start token.
@@ -203,6 +296,89 @@ pub enum Token<'a> {
         span: StrSpan<'a>,
     },
 
+    /// ELEMENT token.
+    ///
+    /// Can appear only inside the DTD.
+    ///
+    /// ```text
+    /// <!ELEMENT note (to,from)*>
+    ///           ----             - name
+    ///                ----------  - content_spec
+    /// -------------------------- - span
+    /// ```
+    ElementDeclaration {
+        name: StrSpan<'a>,
+        content_spec: ContentSpec<'a>,
+        span: StrSpan<'a>,
+    },
+
+    /// ATTLIST token.
+    ///
+    /// Can appear only inside the DTD.
+    ///
+    /// ```text
+    /// <!ATTLIST img src CDATA #REQUIRED>
+    ///           ---                      - name
+    ///              --------------------  - definitions
+    /// ---------------------------------- - span
+    /// ```
+    AttlistDeclaration {
+        name: StrSpan<'a>,
+        definitions: AttributeDefinitions<'a>,
+        span: StrSpan<'a>,
+    },
+
+    /// NOTATION token.
+    ///
+    /// Can appear only inside the DTD.
+    ///
+    /// ```text
+    /// <!NOTATION gif PUBLIC "gif viewer">
+    ///            ---                      - name
+    ///                -------------------  - external_id
+    /// ----------------------------------- - span
+    /// ```
+    NotationDeclaration {
+        name: StrSpan<'a>,
+        external_id: NotationId<'a>,
+        span: StrSpan<'a>,
+    },
+
+    /// Conditional section start token.
+    ///
+    /// ```text
+    /// <![IGNORE[ ... ]]>
+    /// ----------         - span
+    /// ```
+    ///
+    /// The content of an `IGNORE` section is skipped and the `ConditionalSectionEnd`
+    /// token will follow immediately, unless raw tokens are enabled. The content of an `INCLUDE` section
+    /// is parsed as usual.
+    ConditionalSectionStart { ignore: bool, span: StrSpan<'a> },
+
+    /// Conditional section end token.
+    ///
+    /// ```text
+    /// <![INCLUDE[ ... ]]>
+    ///                 --- - span
+    /// ```
+    ConditionalSectionEnd { span: StrSpan<'a> },
+
+    /// Parameter entity reference token.
+    ///
+    /// ```text
+    /// <!DOCTYPE svg [ %ents; ]>
+    ///                  ----   - name
+    ///                 ------  - span
+    /// ```
+    ///
+    /// Only references between declarations are reported.
+    /// The referenced entity is not resolved.
+    ParameterEntityReference {
+        name: StrSpan<'a>,
+        span: StrSpan<'a>,
+    },
+
     /// DOCTYPE end token.
     ///
     /// ```text
@@ -211,7 +387,13 @@ pub enum Token<'a> {
     /// ]>
     /// -- - span
     /// ```
-    DtdEnd { span: StrSpan<'a> },
+    ///
+    /// `internal_subset` contains the raw text between `[` and `]`,
+    /// including the declarations that were skipped by the tokenizer.
+    DtdEnd {
+        span: StrSpan<'a>,
+        internal_subset: StrSpan<'a>,
+    },
 
     /// Element start token.
     ///
@@ -236,6 +418,8 @@ pub enum Token<'a> {
     ///                -----  - value
     ///       --------------- - span
     /// ```
+    ///
+    /// The `value` is not normalized. Use `Token::normalized_value` for that.
     Attribute {
         prefix: StrSpan<'a>,
         local: StrSpan<'a>,
@@ -271,7 +455,8 @@ pub enum Token<'a> {
     ///
     /// Contains text between elements including whitespaces.
     /// Basically everything between `>` and `<`.
-    /// Except `]]>`, which is not allowed and will lead to an error.
+    /// Except `]]>`, which is not allowed and will lead to an error,
+    /// unless the lenient mode is enabled.
     ///
     /// ```text
     /// <p> text </p>
@@ -279,6 +464,12 @@ pub enum Token<'a> {
     /// ```
     ///
     /// The token span is equal to the `text`.
+    ///
+    /// References are not resolved. Use `TextParts` to split the text by references
+    /// or `TextUnescape` to unescape it.
+    ///
+    /// When whitespace tokens are enabled, whitespace-only text is reported
+    /// via the `Whitespaces` token instead.
     Text { text: StrSpan<'a> },
 
     /// CDATA token.
@@ -288,10 +479,72 @@ pub enum Token<'a> {
     ///             ----        - text
     ///    ----------------     - span
     /// ```
+    ///
+    /// `unterminated` can be set only in the partial mode.
     Cdata {
         text: StrSpan<'a>,
         span: StrSpan<'a>,
+        unterminated: bool,
+    },
+
+    /// Conditional comment start token.
+    ///
+    /// Can appear only inside elements and only when conditional comments are enabled.
+    ///
+    /// ```text
+    /// <!--[if IE 8]>
+    ///         ----    - condition
+    /// -------------- - span
+    /// ```
+    ///
+    /// The downlevel-revealed forms, `<![if IE 8]>` and `<!--[if IE 8]><!-->`,
+    /// are reported via the same token.
+    ConditionalCommentStart {
+        condition: StrSpan<'a>,
+        span: StrSpan<'a>,
     },
+
+    /// Conditional comment end token.
+    ///
+    /// Can appear only inside elements and only when conditional comments are enabled.
+    ///
+    /// ```text
+    /// <![endif]-->
+    /// ------------ - span
+    /// ```
+    ///
+    /// The downlevel-revealed forms, `<![endif]>` and `<!--<![endif]-->`,
+    /// are reported via the same token.
+    ConditionalCommentEnd { span: StrSpan<'a> },
+
+    /// UTF-8 BOM token.
+    ///
+    /// Can appear only at the start of the document and only when the BOM handling
+    /// is set to `BomHandling::Report`.
+    Bom { span: StrSpan<'a> },
+
+    /// Whitespace-only text token.
+    ///
+    /// Can appear only when whitespace tokens are enabled.
+    /// Otherwise, whitespaces are reported via the `Text` token.
+    ///
+    /// ```text
+    /// <p> <b/></p>
+    ///    -         - text
+    /// ```
+    ///
+    /// The token span is equal to the `text`.
+    Whitespaces { text: StrSpan<'a> },
+
+    /// Unparsed content token.
+    ///
+    /// Can appear only when raw tokens are enabled.
+    ///
+    /// ```text
+    /// <![IGNORE[ <!ELEMENT a ANY> ]]>
+    ///           -----------------     - span
+    /// ```
+    Raw { kind: RawKind, span: StrSpan<'a> },
 }
 
 impl<'a> Token<'a> {
@@ -304,15 +557,129 @@ impl<'a> Token<'a> {
             Token::DtdStart { span, .. } => span,
             Token::EmptyDtd { span, .. } => span,
             Token::EntityDeclaration { span, .. } => span,
+            Token::ElementDeclaration { span, .. } => span,
+            Token::AttlistDeclaration { span, .. } => span,
+            Token::NotationDeclaration { span, .. } => span,
+            Token::ConditionalSectionStart { span, .. } => span,
+            Token::ConditionalSectionEnd { span } => span,
+            Token::ParameterEntityReference { span, .. } => span,
             Token::DtdEnd { span, .. } => span,
             Token::ElementStart { span, .. } => span,
             Token::Attribute { span, .. } => span,
             Token::ElementEnd { span, .. } => span,
             Token::Text { text, .. } => text,
             Token::Cdata { span, .. } => span,
+            Token::ConditionalCommentStart { span, .. } => span,
+            Token::ConditionalCommentEnd { span } => span,
+            Token::Bom { span } => span,
+            Token::Whitespaces { text } => text,
+            Token::Raw { span, .. } => span,
         };
         *span
     }
+
+    /// Returns a normalized attribute value.
+    ///
+    /// The value is normalized via `AttrValueNormalizer` on each call.
+    /// The raw `value` span is left as is, so rewriters can still use it.
+    ///
+    /// Borrows the original text when no normalization is required.
+    /// Returns `None` for non-attribute tokens.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{Token, Tokenizer};
+    ///
+    /// let mut p = Tokenizer::from("<a b='x&amp;\ny'/>");
+    /// p.next();
+    /// let token = p.next().unwrap().unwrap();
+    /// if let Token::Attribute { value, .. } = token {
+    ///     assert_eq!(value.as_str(), "x&amp;\ny");
+    /// }
+    /// assert_eq!(token.normalized_value().unwrap(), "x& y");
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn normalized_value(&self) -> Option<Cow<'a, str>> {
+        let value = match self {
+            Token::Attribute { value, .. } => value.as_str(),
+            _ => return None,
+        };
+
+        // Only references and whitespaces, except a space, are affected by the normalization.
+        if value
+            .bytes()
+            .any(|c| matches!(c, b'&' | b'\t' | b'\n' | b'\r'))
+        {
+            Some(Cow::Owned(AttrValueNormalizer::normalize(value)))
+        } else {
+            Some(Cow::Borrowed(value))
+        }
+    }
+
+    /// Returns spans of the attribute parts.
+    ///
+    /// Returns `None` for non-attribute tokens.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut p = xmlparser::Tokenizer::from("<a ns:b = 'c'/>");
+    /// p.next();
+    /// let spans = p.next().unwrap().unwrap().attribute_spans().unwrap();
+    /// assert_eq!(spans.name.as_str(), "ns:b");
+    /// assert_eq!(spans.eq.unwrap().range(), 8..9);
+    /// assert_eq!(spans.quoted_value.as_str(), "'c'");
+    /// ```
+    pub fn attribute_spans(&self) -> Option<AttributeSpans<'a>> {
+        match *self {
+            Token::Attribute {
+                local, value, span, ..
+            } => Some(AttributeSpans::new(local, value, span)),
+            _ => None,
+        }
+    }
+
+    /// Returns the quote character around the token value.
+    ///
+    /// Available for attributes, entity values and XML declarations, where
+    /// the `version` quote is returned. Returns `None` for other tokens
+    /// and for unquoted attribute values in the lenient mode.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut p = xmlparser::Tokenizer::from("<a b='1' c=\"2\"/>");
+    /// assert_eq!(p.next().unwrap().unwrap().quote(), None);
+    /// assert_eq!(p.next().unwrap().unwrap().quote(), Some(b'\''));
+    /// assert_eq!(p.next().unwrap().unwrap().quote(), Some(b'"'));
+    /// ```
+    pub fn quote(&self) -> Option<u8> {
+        let (value, span) = match *self {
+            Token::Attribute { value, span, .. } => (value, span),
+            Token::EntityDeclaration {
+                definition: EntityDefinition::EntityValue(value),
+                span,
+                ..
+            } => (value, span),
+            Token::Declaration { version, span, .. } => (version, span),
+            _ => return None,
+        };
+
+        let idx = value.start().checked_sub(span.start())?.checked_sub(1)?;
+        match span.as_str().as_bytes().get(idx) {
+            Some(&c) if c == b'"' || c == b'\'' => Some(c),
+            _ => None,
+        }
+    }
+
+    /// Returns an owned copy of the token.
+    ///
+    /// Unlike `Clone`, doesn't borrow the original text.
+    #[cfg(feature = "alloc")]
+    pub fn to_owned(&self) -> OwnedToken {
+        OwnedToken::from(*self)
+    }
 }
 
 /// `ElementEnd` token.
@@ -326,6 +693,26 @@ pub enum ElementEnd<'a> {
     Empty,
 }
 
+impl ElementEnd<'_> {
+    /// Checks that this is a close tag with the specified name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut p = xmlparser::Tokenizer::from("<ns:a></ns:a>");
+    /// if let xmlparser::Token::ElementEnd { end, .. } = p.nth(2).unwrap().unwrap() {
+    ///     assert!(end.is_close_of("ns", "a"));
+    ///     assert!(!end.is_close_of("", "a"));
+    /// }
+    /// ```
+    pub fn is_close_of(&self, prefix: &str, local: &str) -> bool {
+        match *self {
+            ElementEnd::Close(p, l) => p.as_str() == prefix && l.as_str() == local,
+            _ => false,
+        }
+    }
+}
+
 /// Representation of the [ExternalID](https://www.w3.org/TR/xml/#NT-ExternalID) value.
 #[allow(missing_docs)]
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
@@ -340,12 +727,83 @@ pub enum ExternalId<'a> {
 pub enum EntityDefinition<'a> {
     EntityValue(StrSpan<'a>),
     ExternalId(ExternalId<'a>),
+    /// An unparsed entity with a notation name, like `SYSTEM 'a.gif' NDATA gif`.
+    Unparsed(ExternalId<'a>, StrSpan<'a>),
+}
+
+/// An action to take when the DTD internal subset exceeds the size limit.
+///
+/// See [`Tokenizer::set_dtd_size_limit`](struct.Tokenizer.html#method.set_dtd_size_limit).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum DtdLimitAction {
+    /// Return the `DtdTooLarge` error.
+    Error,
+    /// Skip the rest of the internal subset without parsing it.
+    Skip,
+}
+
+/// A UTF-8 BOM handling at the start of the document.
+///
+/// See [`Tokenizer::set_bom_handling`](struct.Tokenizer.html#method.set_bom_handling).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum BomHandling {
+    /// Skip the BOM silently.
+    Skip,
+    /// Return the `UnexpectedBom` error.
+    Reject,
+    /// Return the `Bom` token.
+    Report,
+}
+
+// `#[default]` is not available on the minimal supported Rust version.
+#[allow(clippy::derivable_impls)]
+impl Default for BomHandling {
+    fn default() -> Self {
+        BomHandling::Skip
+    }
+}
+
+/// A kind of the `Raw` token content.
+///
+/// See [`Tokenizer::set_raw_tokens`](struct.Tokenizer.html#method.set_raw_tokens).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RawKind {
+    /// The content of an `IGNORE` conditional section.
+    IgnoredSection,
+}
+
+/// Representation of the [NotationDecl](https://www.w3.org/TR/xml/#NT-NotationDecl) identifier.
+///
+/// Unlike `ExternalId`, the system literal is optional for a public identifier.
+#[allow(missing_docs)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum NotationId<'a> {
+    System(StrSpan<'a>),
+    Public(StrSpan<'a>, Option<StrSpan<'a>>),
+}
+
+/// Representation of the [contentspec](https://www.w3.org/TR/xml/#NT-contentspec) value.
+///
+/// Content models are not parsed any further and contain the whole group,
+/// including parentheses and an occurrence indicator.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ContentSpec<'a> {
+    /// `EMPTY`
+    Empty,
+    /// `ANY`
+    Any,
+    /// Mixed content, like `(#PCDATA|a|b)*`.
+    Mixed(StrSpan<'a>),
+    /// Element content, like `(a,(b|c)+)?`.
+    Children(StrSpan<'a>),
 }
 
 type Result<T> = core::result::Result<T, Error>;
 type StreamResult<T> = core::result::Result<T, StreamError>;
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+const BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 enum State {
     Declaration,
     AfterDeclaration,
@@ -357,6 +815,115 @@ enum State {
     End,
 }
 
+impl State {
+    fn expected(self) -> ExpectedToken {
+        match self {
+            State::Declaration | State::AfterDeclaration => ExpectedToken::Prolog,
+            State::Dtd => ExpectedToken::DtdContent,
+            State::AfterDtd => ExpectedToken::RootElement,
+            State::Elements | State::Attributes => ExpectedToken::ElementContent,
+            State::AfterElements | State::End => ExpectedToken::Misc,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Debug)]
+struct Options {
+    lenient: bool,
+    partial: bool,
+    recover: bool,
+    full_tag_spans: bool,
+    #[cfg(feature = "alloc")]
+    track_tags: bool,
+    dtd_limit: Option<(usize, DtdLimitAction)>,
+    max_depth: Option<usize>,
+    max_attribute_value_len: Option<usize>,
+    max_comment_len: Option<usize>,
+    max_text_len: Option<usize>,
+    conditional_comments: bool,
+    bom: BomHandling,
+    multiple_roots: bool,
+    text_chunk_size: Option<usize>,
+    whitespace_tokens: bool,
+    raw_tokens: bool,
+    validate_attribute_references: bool,
+    namespaces: bool,
+}
+
+// A text or CDATA node that is being emitted in chunks.
+// Contains the position of the node text start.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum Chunk {
+    Text(usize),
+    Cdata(usize),
+}
+
+/// An opaque snapshot of the tokenizer's state.
+///
+/// Two tokenizers with equal fingerprints will produce the same tokens
+/// as long as they are parsing the same text with the same options.
+/// Useful for testing that different entry points lead to the same state.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct TokenizerFingerprint {
+    state: State,
+    depth: usize,
+    offset: usize,
+}
+
+/// A saved tokenizer's parsing state.
+///
+/// See [`Tokenizer::checkpoint`](struct.Tokenizer.html#method.checkpoint).
+#[derive(Clone)]
+pub struct Checkpoint<'a> {
+    stream: Stream<'a>,
+    state: State,
+    depth: usize,
+    prolog_retries: usize,
+    dtd_start: usize,
+    tag_start: usize,
+    cond_depth: usize,
+    ignore_section: bool,
+    chunk: Option<Chunk>,
+    warning: Option<Warning>,
+    external_subset: Option<&'a str>,
+    main_stream: Option<Stream<'a>>,
+    dtd_end: Option<Token<'a>>,
+    #[cfg(feature = "alloc")]
+    open_elements: Vec<OpenElement<'a>>,
+}
+
+impl Checkpoint<'_> {
+    /// Returns the checkpoint byte offset in the original text.
+    pub fn offset(&self) -> usize {
+        self.stream.pos()
+    }
+}
+
+/// An error returned when restoring a checkpoint of a different text.
+///
+/// See [`Tokenizer::restore`](struct.Tokenizer.html#method.restore).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct CheckpointMismatch;
+
+impl core::fmt::Display for CheckpointMismatch {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "the checkpoint was created for a different text")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CheckpointMismatch {
+    fn description(&self) -> &str {
+        "a checkpoint mismatch"
+    }
+}
+
+impl core::fmt::Debug for Checkpoint<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "Checkpoint {{ offset: {} }}", self.stream.pos())
+    }
+}
+
 /// Tokenizer for the XML structure.
 #[derive(Clone)]
 pub struct Tokenizer<'a> {
@@ -364,6 +931,21 @@ pub struct Tokenizer<'a> {
     state: State,
     depth: usize,
     fragment_parsing: bool,
+    prolog_retries: usize,
+    dtd_start: usize,
+    tag_start: usize,
+    cond_depth: usize,
+    ignore_section: bool,
+    chunk: Option<Chunk>,
+    warning: Option<Warning>,
+    external_subset: Option<&'a str>,
+    // The document stream while an external subset is being tokenized.
+    main_stream: Option<Stream<'a>>,
+    // The `DtdEnd` token that will be returned after the external subset.
+    dtd_end: Option<Token<'a>>,
+    opt: Options,
+    #[cfg(feature = "alloc")]
+    open_elements: Vec<OpenElement<'a>>,
 }
 
 impl core::fmt::Debug for Tokenizer<'_> {
@@ -375,18 +957,24 @@ impl core::fmt::Debug for Tokenizer<'_> {
 impl<'a> From<&'a str> for Tokenizer<'a> {
     #[inline]
     fn from(text: &'a str) -> Self {
-        let mut stream = Stream::from(text);
-
-        // Skip UTF-8 BOM.
-        if stream.starts_with(&[0xEF, 0xBB, 0xBF]) {
-            stream.advance(3);
-        }
-
         Tokenizer {
-            stream,
+            stream: Stream::from(text),
             state: State::Declaration,
             depth: 0,
             fragment_parsing: false,
+            prolog_retries: 0,
+            dtd_start: 0,
+            tag_start: 0,
+            cond_depth: 0,
+            ignore_section: false,
+            chunk: None,
+            warning: None,
+            external_subset: None,
+            main_stream: None,
+            dtd_end: None,
+            opt: Options::default(),
+            #[cfg(feature = "alloc")]
+            open_elements: Vec::new(),
         }
     }
 }
@@ -394,7 +982,7 @@ impl<'a> From<&'a str> for Tokenizer<'a> {
 macro_rules! map_err_at {
     ($fun:expr, $stream:expr, $err:ident) => {{
         let start = $stream.pos();
-        $fun.map_err(|e| Error::$err(e, $stream.gen_text_pos_from(start)))
+        $fun.map_err(|e| Error::$err(e, $stream.gen_text_pos_from(start), start))
     }};
 }
 
@@ -405,671 +993,2971 @@ impl<'a> Tokenizer<'a> {
     /// But if we have to parse an XML fragment, it will lead to an error.
     /// This method switches the parser to the root element content parsing mode,
     /// so it will treat any data as a content of the root element.
+    ///
+    /// Therefore, a fragment can consist purely of text, start with comments
+    /// or processing instructions and contain multiple elements,
+    /// which is useful for parsing arbitrary snippets, like templates.
+    /// An XML declaration and a DTD are not allowed.
+    ///
+    /// The nesting depth starts at zero and never leaves the element content mode
+    /// when it gets back to zero. Close tags without a matching open tag
+    /// inside the fragment are accepted and do not affect the depth,
+    /// since their open tags may be outside of the fragment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let text = "<!-- header -->Hello, <b>world</b>!</p>";
+    /// let tokens: Vec<_> = xmlparser::Tokenizer::from_fragment(text, 0..text.len())
+    ///     .map(|t| t.unwrap().span().as_str())
+    ///     .collect();
+    /// assert_eq!(tokens, ["<!-- header -->", "Hello, ", "<b", ">", "world", "</b>", "!", "</p>"]);
+    /// ```
     pub fn from_fragment(full_text: &'a str, fragment: core::ops::Range<usize>) -> Self {
         Tokenizer {
             stream: Stream::from_substr(full_text, fragment),
             state: State::Elements,
             depth: 0,
             fragment_parsing: true,
+            prolog_retries: 0,
+            dtd_start: 0,
+            tag_start: 0,
+            cond_depth: 0,
+            ignore_section: false,
+            chunk: None,
+            warning: None,
+            external_subset: None,
+            main_stream: None,
+            dtd_end: None,
+            opt: Options::default(),
+            #[cfg(feature = "alloc")]
+            open_elements: Vec::new(),
         }
     }
 
-    fn parse_next_impl(&mut self) -> Option<Result<Token<'a>>> {
-        let s = &mut self.stream;
+    /// Sets the UTF-8 BOM handling.
+    ///
+    /// By default, a BOM at the start of the document is skipped.
+    /// `BomHandling::Reject` turns it into the `UnexpectedBom` error
+    /// and `BomHandling::Report` returns it as a `Bom` token,
+    /// which is useful for round-tripping.
+    ///
+    /// Regardless of this option, a BOM in place of a markup, like between
+    /// concatenated documents, leads to the `UnexpectedBom` error.
+    /// A BOM inside a text is a valid character and is preserved.
+    ///
+    /// Must be set before the first token is parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{BomHandling, Token, Tokenizer};
+    ///
+    /// let mut p = Tokenizer::from("\u{FEFF}<a/>");
+    /// p.set_bom_handling(BomHandling::Report);
+    /// match p.next().unwrap().unwrap() {
+    ///     Token::Bom { span } => assert_eq!(span.range(), 0..3),
+    ///     _ => unreachable!(),
+    /// }
+    ///
+    /// let mut p = Tokenizer::from("\u{FEFF}<a/>");
+    /// p.set_bom_handling(BomHandling::Reject);
+    /// assert_eq!(p.next().unwrap().unwrap_err().code(), "unexpected-bom");
+    /// ```
+    pub fn set_bom_handling(&mut self, handling: BomHandling) {
+        self.opt.bom = handling;
+    }
 
-        if s.at_end() {
-            return None;
-        }
+    /// Enables recovery from unknown tokens before the root element.
+    ///
+    /// By default, any error stops the parsing. With this option, an `UnknownToken` error
+    /// in the prolog, like a stray text, is still returned, but the tokenizer will skip
+    /// to the next `<` and continue parsing the prolog afterwards.
+    /// Up to `max_retries` errors can be recovered from. `0` disables the recovery.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut p = xmlparser::Tokenizer::from("garbage<root/>");
+    /// p.set_prolog_recovery(1);
+    /// assert!(p.next().unwrap().is_err());
+    /// assert!(p.next().unwrap().is_ok());
+    /// ```
+    pub fn set_prolog_recovery(&mut self, max_retries: usize) {
+        self.prolog_retries = max_retries;
+    }
 
-        let start = s.pos();
+    /// Enables whitespace tokens.
+    ///
+    /// By default, all text is reported via the `Text` token.
+    /// With this option, whitespace-only text is reported via the `Whitespaces` token,
+    /// so consumers that ignore whitespaces do not have to check each text.
+    ///
+    /// With text chunks enabled, each chunk is classified separately.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{Token, Tokenizer};
+    ///
+    /// let mut p = Tokenizer::from("<a>\n  <b/></a>");
+    /// p.set_whitespace_tokens(true);
+    /// match p.nth(2).unwrap().unwrap() {
+    ///     Token::Whitespaces { text } => assert_eq!(text.as_str(), "\n  "),
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn set_whitespace_tokens(&mut self, flag: bool) {
+        self.opt.whitespace_tokens = flag;
+    }
 
-        match self.state {
-            State::Declaration => {
-                self.state = State::AfterDeclaration;
-                if s.starts_with(b"<?xml ") {
-                    Some(Self::parse_declaration(s))
-                } else {
-                    None
-                }
-            }
-            State::AfterDeclaration => {
-                if s.starts_with(b"<!DOCTYPE") {
-                    let t = Self::parse_doctype(s);
-                    match t {
-                        Ok(Token::DtdStart { .. }) => self.state = State::Dtd,
-                        Ok(Token::EmptyDtd { .. }) => self.state = State::AfterDtd,
-                        _ => {}
-                    }
+    /// Enables raw tokens.
+    ///
+    /// By default, the content of an `IGNORE` conditional section is skipped.
+    /// With this option, it is reported via the `Raw` token, so the original
+    /// document can be reconstructed from the tokens' spans without gaps.
+    ///
+    /// Empty content is not reported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{RawKind, Token, Tokenizer};
+    ///
+    /// let mut p = Tokenizer::from("<!DOCTYPE a [<![IGNORE[ <!x> ]]>]><a/>");
+    /// p.set_raw_tokens(true);
+    /// match p.nth(2).unwrap().unwrap() {
+    ///     Token::Raw { kind, span } => {
+    ///         assert_eq!(kind, RawKind::IgnoredSection);
+    ///         assert_eq!(span.as_str(), " <!x> ");
+    ///     }
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn set_raw_tokens(&mut self, flag: bool) {
+        self.opt.raw_tokens = flag;
+    }
 
-                    Some(t)
-                } else if s.starts_with(b"<!--") {
-                    Some(Self::parse_comment(s))
-                } else if s.starts_with(b"<?") {
-                    if s.starts_with(b"<?xml ") {
-                        Some(Err(Error::UnknownToken(s.gen_text_pos())))
-                    } else {
-                        Some(Self::parse_pi(s))
-                    }
-                } else if s.starts_with_space() {
-                    s.skip_spaces();
-                    None
-                } else {
+    /// Tokenizes an external DTD subset inline.
+    ///
+    /// Must be called right after the `DtdStart` or `EmptyDtd` token was returned,
+    /// otherwise does nothing. The external subset is tokenized after the internal one,
+    /// but before the `DtdEnd` token, just like it was a part of the document.
+    /// An `EmptyDtd` token is followed by the external subset tokens directly.
+    ///
+    /// Spans and error positions of the external subset tokens are relative to `text`.
+    /// The DTD size limit is applied to `text` separately.
+    ///
+    /// The crate doesn't perform any IO, so fetching the subset is up to the caller.
+    /// See also [`with_external_subset_loader`](#method.with_external_subset_loader).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{Token, Tokenizer};
+    ///
+    /// let mut p = Tokenizer::from("<!DOCTYPE a SYSTEM 'a.dtd' [<!ENTITY b 'c'>]><a/>");
+    /// p.next(); // DtdStart
+    /// p.set_external_subset("<!ENTITY d 'e'>");
+    /// let names: Vec<_> = p
+    ///     .filter_map(|t| match t.unwrap() {
+    ///         Token::EntityDeclaration { name, .. } => Some(name.as_str()),
+    ///         _ => None,
+    ///     })
+    ///     .collect();
+    /// assert_eq!(names, ["b", "d"]);
+    /// ```
+    pub fn set_external_subset(&mut self, text: &'a str) {
+        if self.main_stream.is_some() {
+            return;
+        }
+
+        match self.state {
+            State::Dtd => self.external_subset = Some(text),
+            State::AfterDtd => self.enter_external_subset(text),
+            _ => {}
+        }
+    }
+
+    /// Splits large text and CDATA nodes into chunks.
+    ///
+    /// By default, each text and CDATA node is returned as a single token.
+    /// With this option, nodes larger than `size` bytes are returned as multiple
+    /// consecutive tokens, each with a text of at most `size` bytes,
+    /// split at char boundaries. Which allows processing huge text nodes
+    /// with a bounded working set.
+    ///
+    /// Only the first CDATA chunk span includes `<![CDATA[`
+    /// and only the last one includes `]]>`.
+    /// Since a node is not parsed ahead, an error inside it is reported
+    /// after the preceding chunks were returned.
+    ///
+    /// The `set_max_text_len` limit is still applied to the whole node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut p = xmlparser::Tokenizer::from("<a>text</a>");
+    /// p.set_text_chunk_size(3);
+    /// let tokens: Vec<_> = p.map(|t| t.unwrap().span().as_str()).collect();
+    /// assert_eq!(tokens, ["<a", ">", "tex", "t", "</a>"]);
+    /// ```
+    pub fn set_text_chunk_size(&mut self, size: usize) {
+        self.opt.text_chunk_size = Some(size);
+    }
+
+    /// Allows multiple root elements.
+    ///
+    /// By default, an element after the root element leads to the `UnknownToken` error.
+    /// With this option, each root element is tokenized as usual,
+    /// which is useful for formats that concatenate XML documents, like logs.
+    /// Only comments, processing instructions and spaces are allowed between root elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut p = xmlparser::Tokenizer::from("<a/>\n<b/>");
+    /// p.set_allow_multiple_roots(true);
+    /// assert_eq!(p.filter(|t| t.is_ok()).count(), 4);
+    /// ```
+    pub fn set_allow_multiple_roots(&mut self, flag: bool) {
+        self.opt.multiple_roots = flag;
+    }
+
+    /// Enables lenient parsing.
+    ///
+    /// By default, the tokenizer follows the XML spec strictly.
+    /// In the lenient mode, some common spec violations are allowed:
+    ///
+    /// - `<` inside attribute values, like in `<a b='<%foo%>'/>`.
+    /// - Missing spaces between attributes, like in `<a b='1'c='2'/>`.
+    /// - HTML-style DOCTYPE, like `<!doctype html>` or `<!DOCTYPE>`.
+    ///   A missing name is reported as an empty span.
+    /// - Case-insensitive `SYSTEM` and `PUBLIC` keywords and missing spaces before
+    ///   the literals, like `<!doctype html public"-//W3C//DTD HTML 4.01//EN">`.
+    ///   A missing system literal is reported as an empty span.
+    /// - Spaces between `/` and `>`, like in `<a/ >`.
+    /// - HTML-style minimized attributes, like `<input disabled>`.
+    ///   The value is reported as an empty span.
+    /// - HTML-style unquoted attribute values, like `<a b=c>`.
+    ///   The value ends at the first space, `>` or `/>`.
+    /// - Processing instruction targets that match `xml` case-insensitively, like `<?XML?>`.
+    /// - Not allowed characters in public identifiers, like `PUBLIC '{id}' 'a.dtd'`.
+    /// - `--` inside comments and comments ending with `-`, like `<!-- a -- b --->`.
+    /// - `]]>` in text, like in `<a>]]></a>`.
+    ///
+    /// Some of the accepted violations are reported via [`warning`](#method.warning).
+    pub fn set_lenient(&mut self, flag: bool) {
+        self.opt.lenient = flag;
+    }
+
+    /// Makes start tag end spans cover the whole start tag.
+    ///
+    /// By default, the `ElementEnd::Open` and `ElementEnd::Empty` spans cover only
+    /// the `>` and `/>` parts of the start tag. With this option, they will start
+    /// at the start tag's `<`, therefore overlapping `ElementStart` and `Attribute` spans.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut p = xmlparser::Tokenizer::from("<a b='1'><c/></a>");
+    /// p.set_full_tag_spans(true);
+    /// let spans: Vec<_> = p.map(|t| t.unwrap().span().as_str()).collect();
+    /// assert_eq!(spans, ["<a", "b='1'", "<a b='1'>", "<c", "<c/>", "</a>"]);
+    /// ```
+    pub fn set_full_tag_spans(&mut self, flag: bool) {
+        self.opt.full_tag_spans = flag;
+    }
+
+    /// Enables HTML-style conditional comments.
+    ///
+    /// By default, `<!--[if IE]>...<![endif]-->` is parsed as a regular comment
+    /// and the downlevel-revealed `<![if IE]>...<![endif]>` leads to an error.
+    /// With this option, conditional comments inside elements are reported via
+    /// `ConditionalCommentStart` and `ConditionalCommentEnd` tokens
+    /// and their content is parsed as a regular element content.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::Token;
+    ///
+    /// let mut p = xmlparser::Tokenizer::from("<p><!--[if IE]><b/><![endif]--></p>");
+    /// p.set_conditional_comments(true);
+    /// match p.nth(2).unwrap().unwrap() {
+    ///     Token::ConditionalCommentStart { condition, .. } => assert_eq!(condition.as_str(), "IE"),
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn set_conditional_comments(&mut self, flag: bool) {
+        self.opt.conditional_comments = flag;
+    }
+
+    /// Enables errors recovery.
+    ///
+    /// By default, the tokenizer stops on the first error.
+    /// In the recovery mode, the error is still returned, but the tokenizer will skip
+    /// to the next `<` and continue parsing afterwards, so multiple errors
+    /// can be reported in one pass.
+    ///
+    /// The recovery is best-effort. A broken start tag is treated as an open one
+    /// and the DOCTYPE parsing resumes at the next `<` or `]`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut p = xmlparser::Tokenizer::from("<root><a b></a><c d/></root>");
+    /// p.set_recover(true);
+    /// assert_eq!(p.filter(|t| t.is_err()).count(), 2);
+    /// ```
+    pub fn set_recover(&mut self, flag: bool) {
+        self.opt.recover = flag;
+    }
+
+    /// Enables partial documents parsing.
+    ///
+    /// By default, an unterminated comment or CDATA at the end of the document
+    /// is an error. In the partial mode, they will be returned as tokens
+    /// with the `unterminated` flag set instead, so the data can be salvaged
+    /// from a truncated document.
+    ///
+    /// Note that a text at the end of the document is always returned as is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{Token, Tokenizer};
+    ///
+    /// let mut p = Tokenizer::from("<root><![CDATA[data]");
+    /// p.set_partial(true);
+    /// match p.nth(2).unwrap().unwrap() {
+    ///     Token::Cdata { text, unterminated, .. } => {
+    ///         assert_eq!(text.as_str(), "data");
+    ///         assert!(unterminated);
+    ///     }
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn set_partial(&mut self, flag: bool) {
+        self.opt.partial = flag;
+    }
+
+    /// Sets the maximum size of the DTD internal subset in bytes.
+    ///
+    /// Once the internal subset exceeds `limit` bytes, the tokenizer will either
+    /// return the `DtdTooLarge` error or skip the rest of the internal subset.
+    /// In the last case, the remaining declarations are not parsed at all and
+    /// the `DtdEnd` token will include the skipped text.
+    ///
+    /// A single declaration is not scanned much past the limit either.
+    /// When it doesn't fit, the limit is applied at its start.
+    ///
+    /// There is no limit by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{DtdLimitAction, Token, Tokenizer};
+    ///
+    /// let text = "<!DOCTYPE a [<!ENTITY b 'c'><!ENTITY d 'e'>]><a/>";
+    /// let mut p = Tokenizer::from(text);
+    /// p.set_dtd_size_limit(10, DtdLimitAction::Skip);
+    /// p.nth(1).unwrap().unwrap(); // <!ENTITY b 'c'>
+    /// match p.next().unwrap().unwrap() {
+    ///     Token::DtdEnd { span, .. } => assert_eq!(span.as_str(), "<!ENTITY d 'e'>]>"),
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn set_dtd_size_limit(&mut self, limit: usize, action: DtdLimitAction) {
+        self.opt.dtd_limit = Some((limit, action));
+    }
+
+    /// Enables strict qualified names validation.
+    ///
+    /// By default, element and attribute names are checked against the XML `Name` rule,
+    /// so a name with a leading colon, like `<:a/>`, is reported with an empty prefix.
+    /// With this option, such names lead to an error, as required by
+    /// [Namespaces in XML](https://www.w3.org/TR/xml-names/#ns-qualnames).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut p = xmlparser::Tokenizer::from("<:a/>");
+    /// p.set_namespaces(true);
+    /// assert_eq!(p.next().unwrap().unwrap_err().to_string(), "invalid element at 1:1 cause invalid name token");
+    /// ```
+    pub fn set_namespaces(&mut self, flag: bool) {
+        self.opt.namespaces = flag;
+    }
+
+    /// Sets the maximum number of nested elements.
+    ///
+    /// An element start that exceeds the limit will lead to the `NestingTooDeep` error.
+    /// Useful for protecting recursive consumers from deeply nested documents.
+    ///
+    /// There is no limit by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut p = xmlparser::Tokenizer::from("<a><b><c/></b></a>");
+    /// p.set_max_depth(2);
+    /// assert_eq!(p.nth(4).unwrap().unwrap_err().code(), "nesting-too-deep");
+    /// ```
+    pub fn set_max_depth(&mut self, depth: usize) {
+        self.opt.max_depth = Some(depth);
+    }
+
+    /// Enables incremental text position tracking.
+    ///
+    /// By default, calculating an error position requires scanning the text
+    /// from the start. With this option, the tokenizer remembers the current line
+    /// after each token, so only the current token has to be scanned.
+    /// Useful when errors are frequent, like in the recovery mode.
+    ///
+    /// Disabled by default, since it requires an additional pass over each token.
+    pub fn set_track_text_pos(&mut self, flag: bool) {
+        self.stream.set_track_text_pos(flag);
+    }
+
+    /// Defers error positions calculation.
+    ///
+    /// By default, each error contains a `TextPos`, which requires scanning the text
+    /// up to the error. With this option, errors contain only byte offsets
+    /// and their positions are set to `0:0`. Use `Error::text_pos`
+    /// to calculate a position later, when it's actually needed.
+    ///
+    /// Warnings are affected as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let text = "<a>\n  <b x/>\n</a>";
+    /// let mut p = xmlparser::Tokenizer::from(text);
+    /// p.set_defer_positions(true);
+    /// let err = p.find_map(|t| t.err()).unwrap();
+    /// assert_eq!(err.pos(), xmlparser::TextPos::new(0, 0));
+    /// assert_eq!(err.offset(), 8);
+    /// assert_eq!(err.text_pos(text), xmlparser::TextPos::new(2, 5));
+    /// ```
+    pub fn set_defer_positions(&mut self, flag: bool) {
+        self.stream.set_defer_text_pos(flag);
+    }
+
+    /// Sets a line index of the tokenizer's text.
+    ///
+    /// Makes error positions cheap to calculate.
+    /// The index must be built from the same text the tokenizer was created from,
+    /// otherwise it is ignored. A copy of the text is not the same text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let text = "<a>\n  <b x/>\n</a>";
+    /// let index = xmlparser::LineIndex::new(text);
+    /// let mut p = xmlparser::Tokenizer::from(text);
+    /// p.set_line_index(&index);
+    /// let err = p.find_map(|t| t.err()).unwrap();
+    /// assert_eq!(err.pos(), xmlparser::TextPos::new(2, 5));
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn set_line_index(&mut self, index: &'a LineIndex<'a>) {
+        self.stream.set_line_index(index);
+    }
+
+    /// Sets the maximum attribute value length in bytes.
+    ///
+    /// A longer value will lead to the `InvalidAttribute` error
+    /// caused by `LengthLimitExceeded`.
+    ///
+    /// There is no limit by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut p = xmlparser::Tokenizer::from("<a b='text'/>");
+    /// p.set_max_attribute_value_len(2);
+    /// let err = p.nth(1).unwrap().unwrap_err();
+    /// assert_eq!(err.stream_error().unwrap().code(), "length-limit-exceeded");
+    /// ```
+    pub fn set_max_attribute_value_len(&mut self, len: usize) {
+        self.opt.max_attribute_value_len = Some(len);
+    }
+
+    /// Enables references validation inside attribute values.
+    ///
+    /// By default, attribute values are not checked for malformed references,
+    /// like a raw `&` or `&#0;`, since most consumers resolve references later anyway.
+    /// With this option, such values will lead to the `InvalidAttribute` error
+    /// caused by `MalformedReference`, which points to the reference start.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut p = xmlparser::Tokenizer::from("<a b='x & y'/>");
+    /// p.set_validate_attribute_references(true);
+    /// let err = p.nth(1).unwrap().unwrap_err();
+    /// assert_eq!(err.stream_error().unwrap().offset(), Some(8));
+    /// ```
+    pub fn set_validate_attribute_references(&mut self, flag: bool) {
+        self.opt.validate_attribute_references = flag;
+    }
+
+    /// Sets the maximum comment length in bytes.
+    ///
+    /// A longer comment will lead to the `InvalidComment` error
+    /// caused by `LengthLimitExceeded`.
+    ///
+    /// There is no limit by default.
+    pub fn set_max_comment_len(&mut self, len: usize) {
+        self.opt.max_comment_len = Some(len);
+    }
+
+    /// Sets the maximum text and CDATA length in bytes.
+    ///
+    /// A longer text will lead to the `InvalidCharData` error and a longer CDATA
+    /// to the `InvalidCdata` error, both caused by `LengthLimitExceeded`.
+    ///
+    /// There is no limit by default.
+    pub fn set_max_text_len(&mut self, len: usize) {
+        self.opt.max_text_len = Some(len);
+    }
+
+    /// Enables tracking of unclosed elements.
+    ///
+    /// When enabled, the tokenizer will remember all the open elements
+    /// and will return the `UnexpectedEndOfDocument` error when the document
+    /// ends before all of them are closed.
+    ///
+    /// Close tags names are not checked.
+    #[cfg(feature = "alloc")]
+    pub fn set_track_tags(&mut self, flag: bool) {
+        self.opt.track_tags = flag;
+    }
+
+    /// Returns a list of currently open elements, from the outermost one.
+    ///
+    /// Always empty when tag tracking is disabled.
+    #[cfg(feature = "alloc")]
+    pub fn open_elements(&self) -> &[OpenElement<'a>] {
+        &self.open_elements
+    }
+
+    /// Returns names of currently open elements, from the outermost one.
+    ///
+    /// Always empty when tag tracking is disabled.
+    #[cfg(feature = "alloc")]
+    pub fn current_path(&self) -> ElementPath<'a, '_> {
+        ElementPath::new(&self.open_elements)
+    }
+
+    /// Returns the current element nesting depth.
+    ///
+    /// An element is counted after its start tag end, like `>`,
+    /// and until its close tag. Unlike `open_elements`, doesn't require tag tracking.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut p = xmlparser::Tokenizer::from("<a><b/></a>");
+    /// p.nth(2).unwrap().unwrap(); // <b
+    /// assert_eq!(p.depth(), 1);
+    /// p.nth(1).unwrap().unwrap(); // </a>
+    /// assert_eq!(p.depth(), 0);
+    /// ```
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns a report about a truncated document.
+    ///
+    /// Returns `None` when the error wasn't caused by a truncated document.
+    /// The list of unclosed elements is available only when tag tracking is enabled.
+    #[cfg(feature = "alloc")]
+    pub fn truncation_report(&self, error: &Error) -> Option<TruncationReport<'a>> {
+        if !error.is_truncated() {
+            return None;
+        }
+
+        let open_elements = self
+            .open_elements
+            .iter()
+            .map(|e| (*e, self.stream.gen_text_pos_from(e.span.start())))
+            .collect();
+
+        Some(TruncationReport {
+            construct: TruncationReport::construct_name(error),
+            pos: error.pos(),
+            open_elements,
+        })
+    }
+
+    #[cfg(feature = "alloc")]
+    fn track_tags(&mut self, t: &Option<Result<Token<'a>>>) {
+        match *t {
+            Some(Ok(Token::ElementStart {
+                prefix,
+                local,
+                span,
+            })) => {
+                self.open_elements.push(OpenElement {
+                    prefix,
+                    local,
+                    span,
+                });
+            }
+            Some(Ok(Token::ElementEnd {
+                end: ElementEnd::Empty,
+                ..
+            }))
+            | Some(Ok(Token::ElementEnd {
+                end: ElementEnd::Close(..),
+                ..
+            })) => {
+                self.open_elements.pop();
+            }
+            _ => {}
+        }
+    }
+
+    fn enter_external_subset(&mut self, text: &'a str) {
+        let stream = self.stream.with_text(text, 0);
+        self.main_stream = Some(core::mem::replace(&mut self.stream, stream));
+        self.chunk = None;
+        self.state = State::Dtd;
+        self.dtd_start = 0;
+    }
+
+    fn leave_external_subset(&mut self) -> Option<Result<Token<'a>>> {
+        let main_stream = self.main_stream?;
+
+        if self.cond_depth > 0 {
+            self.cond_depth = 0;
+            let e = StreamError::UnexpectedEndOfStream;
+            let s = &self.stream;
+            return Some(Err(Error::InvalidDoctype(e, s.gen_text_pos(), s.pos())));
+        }
+
+        self.stream = main_stream;
+        self.main_stream = None;
+        self.chunk = None;
+        self.state = State::AfterDtd;
+        self.dtd_end.take().map(Ok)
+    }
+
+    fn exceed_dtd_limit(&mut self, action: DtdLimitAction) -> Option<Result<Token<'a>>> {
+        let s = &mut self.stream;
+        match action {
+            DtdLimitAction::Error => Some(Err(Error::DtdTooLarge(s.gen_text_pos(), s.pos()))),
+            DtdLimitAction::Skip if self.main_stream.is_some() => {
+                // An external subset has no end marker, so skip it completely.
+                s.jump_to_end();
+                self.cond_depth = 0;
+                self.ignore_section = false;
+                None
+            }
+            DtdLimitAction::Skip => {
+                // We could be inside a conditional section already.
+                let cond_depth = self.cond_depth + self.ignore_section as usize;
+                let t = Self::skip_dtd(s, self.dtd_start, cond_depth);
+                if t.is_ok() {
+                    self.state = State::AfterDtd;
+                    self.cond_depth = 0;
+                    self.ignore_section = false;
+                }
+
+                Some(t)
+            }
+        }
+    }
+
+    // Parses the next DTD token without scanning far past the size limit,
+    // so a single oversized declaration is not scanned to its end.
+    fn parse_dtd_limited(
+        &mut self,
+        limit: usize,
+        action: DtdLimitAction,
+    ) -> Option<Result<Token<'a>>> {
+        // Keywords and terminators are checked via lookahead, which must not fail
+        // because of the stream end while the token is still within the limit.
+        const LOOKAHEAD: usize = 16;
+
+        let stream = self.stream;
+        let limit_pos = self.dtd_start.saturating_add(limit);
+        let mut scan_end = limit_pos.saturating_add(LOOKAHEAD);
+        if scan_end >= stream.end() {
+            return self.parse_dtd();
+        }
+
+        // The stream end must not split a char.
+        let text = stream.span().as_str();
+        while !text.is_char_boundary(scan_end) {
+            scan_end += 1;
+        }
+
+        if scan_end == stream.end() {
+            return self.parse_dtd();
+        }
+
+        let in_subset = self.main_stream.is_some();
+        let cond_depth = self.cond_depth;
+        let ignore_section = self.ignore_section;
+
+        self.stream.set_end(scan_end);
+        let t = self.parse_dtd();
+        match self.main_stream {
+            // The DTD end switched to the external subset.
+            Some(ref mut main_stream) if !in_subset => {
+                main_stream.set_end(stream.end());
+                return t;
+            }
+            _ => self.stream.set_end(stream.end()),
+        }
+
+        let pos = self.stream.pos();
+        let truncated = match t {
+            Some(Err(_)) => pos > limit_pos,
+            _ => pos == scan_end && self.state == State::Dtd,
+        };
+
+        if !truncated {
+            return t;
+        }
+
+        // The token needs more bytes than allowed.
+        self.stream = stream;
+        self.cond_depth = cond_depth;
+        self.ignore_section = ignore_section;
+        self.warning = None;
+        self.exceed_dtd_limit(action)
+    }
+
+    fn parse_dtd(&mut self) -> Option<Result<Token<'a>>> {
+        let s = &mut self.stream;
+
+        let start = s.pos();
+
+        if self.ignore_section {
+            self.ignore_section = false;
+            return match Self::skip_ignore_section(s) {
+                Ok(()) => {
+                    // The section end will be handled as a regular one.
+                    self.cond_depth += 1;
+                    if self.opt.raw_tokens && s.pos() != start {
+                        Some(Ok(Token::Raw {
+                            kind: RawKind::IgnoredSection,
+                            span: s.slice_back(start),
+                        }))
+                    } else {
+                        None
+                    }
+                }
+                Err(e) => Some(Err(e)),
+            };
+        }
+
+        if self.main_stream.is_some()
+            && s.starts_with(b"]")
+            && !(self.cond_depth > 0 && s.starts_with(b"]]>"))
+        {
+            // An external subset cannot end the DTD.
+            return Some(Err(Error::UnknownToken(
+                self.state.expected(),
+                s.gen_text_pos(),
+                s.pos(),
+            )));
+        }
+
+        if s.starts_with(b"<!ENTITY") {
+            Some(Self::parse_entity_decl(s, &self.opt, &mut self.warning))
+        } else if s.starts_with(b"<!--") {
+            Some(Self::parse_comment(s, &self.opt, &mut self.warning))
+        } else if s.starts_with(b"<?") {
+            if Self::starts_with_declaration(s) {
+                Some(Err(Error::MisplacedDeclaration(s.gen_text_pos(), s.pos())))
+            } else {
+                Some(Self::parse_pi(s, &self.opt, &mut self.warning))
+            }
+        } else if s.starts_with(b"<![") {
+            let t = Self::parse_conditional_section_start(s);
+            match t {
+                Ok(Token::ConditionalSectionStart { ignore: true, .. }) => {
+                    self.ignore_section = true;
+                }
+                Ok(_) => self.cond_depth += 1,
+                Err(_) => {}
+            }
+
+            Some(t)
+        } else if self.cond_depth > 0 && s.starts_with(b"]]>") {
+            self.cond_depth -= 1;
+            s.advance(3);
+            Some(Ok(Token::ConditionalSectionEnd {
+                span: s.slice_back(start),
+            }))
+        } else if s.starts_with(b"]") {
+            // DTD ends with ']' S? '>', therefore we have to skip possible spaces.
+            s.advance(1);
+            s.skip_spaces();
+            match s.curr_byte() {
+                Ok(b'>') => {
                     self.state = State::AfterDtd;
+                    s.advance(1);
+                    let t = Token::DtdEnd {
+                        span: s.slice_back(start),
+                        internal_subset: s.span().slice_region(self.dtd_start, start),
+                    };
+
+                    match self.external_subset.take() {
+                        Some(text) => {
+                            // The external subset is processed after the internal one.
+                            self.dtd_end = Some(t);
+                            self.enter_external_subset(text);
+                            None
+                        }
+                        None => Some(Ok(t)),
+                    }
+                }
+                Ok(c) => {
+                    let e = StreamError::InvalidChar(c, b'>', s.gen_text_pos(), s.pos());
+                    Some(Err(Error::InvalidDoctype(
+                        e,
+                        s.gen_text_pos_from(start),
+                        start,
+                    )))
+                }
+                Err(_) => {
+                    let e = StreamError::UnexpectedEndOfStream;
+                    Some(Err(Error::InvalidDoctype(
+                        e,
+                        s.gen_text_pos_from(start),
+                        start,
+                    )))
+                }
+            }
+        } else if s.starts_with_space() {
+            s.skip_spaces();
+            None
+        } else if s.starts_with(b"<!ELEMENT") {
+            Some(Self::parse_element_decl(s))
+        } else if s.starts_with(b"<!ATTLIST") {
+            Some(Self::parse_attlist_decl(s))
+        } else if s.starts_with(b"<!NOTATION") {
+            Some(Self::parse_notation_decl(s, &self.opt, &mut self.warning))
+        } else if s.starts_with(b"%") {
+            Some(Self::parse_pe_reference(s))
+        } else {
+            Some(Err(Error::UnknownToken(
+                self.state.expected(),
+                s.gen_text_pos(),
+                s.pos(),
+            )))
+        }
+    }
+
+    fn parse_next_impl(&mut self) -> Option<Result<Token<'a>>> {
+        if self.stream.at_end() {
+            return self.leave_external_subset();
+        }
+
+        let s = &mut self.stream;
+
+        let start = s.pos();
+
+        match self.state {
+            State::Declaration => {
+                if start == 0 && s.starts_with(BOM) {
+                    s.advance(BOM.len());
+                    match self.opt.bom {
+                        BomHandling::Skip => {}
+                        BomHandling::Reject => {
+                            return Some(Err(Error::UnexpectedBom(s.gen_text_pos_from(0), 0)));
+                        }
+                        BomHandling::Report => {
+                            return Some(Ok(Token::Bom {
+                                span: s.slice_back(0),
+                            }));
+                        }
+                    }
+                }
+
+                self.state = State::AfterDeclaration;
+                if Self::starts_with_declaration(s) {
+                    Some(Self::parse_declaration(s))
+                } else {
                     None
                 }
             }
-            State::Dtd => {
-                if s.starts_with(b"<!ENTITY") {
-                    Some(Self::parse_entity_decl(s))
-                } else if s.starts_with(b"<!--") {
-                    Some(Self::parse_comment(s))
-                } else if s.starts_with(b"<?") {
-                    if s.starts_with(b"<?xml ") {
-                        Some(Err(Error::UnknownToken(s.gen_text_pos())))
-                    } else {
-                        Some(Self::parse_pi(s))
-                    }
-                } else if s.starts_with(b"]") {
-                    // DTD ends with ']' S? '>', therefore we have to skip possible spaces.
-                    s.advance(1);
-                    s.skip_spaces();
-                    match s.curr_byte() {
-                        Ok(b'>') => {
-                            self.state = State::AfterDtd;
-                            s.advance(1);
-                            Some(Ok(Token::DtdEnd {
-                                span: s.slice_back(start),
-                            }))
-                        }
-                        Ok(c) => {
-                            let e = StreamError::InvalidChar(c, b'>', s.gen_text_pos());
-                            Some(Err(Error::InvalidDoctype(e, s.gen_text_pos_from(start))))
-                        }
-                        Err(_) => {
-                            let e = StreamError::UnexpectedEndOfStream;
-                            Some(Err(Error::InvalidDoctype(e, s.gen_text_pos_from(start))))
+            State::AfterDeclaration => {
+                if Self::starts_with_doctype(s, &self.opt) {
+                    let t = Self::parse_doctype(s, &self.opt, &mut self.warning);
+                    match t {
+                        Ok(Token::DtdStart { .. }) => {
+                            self.state = State::Dtd;
+                            self.dtd_start = s.pos();
+                        }
+                        Ok(Token::EmptyDtd { .. }) => self.state = State::AfterDtd,
+                        _ => {}
+                    }
+
+                    Some(t)
+                } else if s.starts_with(b"<!--") {
+                    Some(Self::parse_comment(s, &self.opt, &mut self.warning))
+                } else if s.starts_with(b"<?") {
+                    if Self::starts_with_declaration(s) {
+                        Some(Err(Error::MisplacedDeclaration(s.gen_text_pos(), s.pos())))
+                    } else {
+                        Some(Self::parse_pi(s, &self.opt, &mut self.warning))
+                    }
+                } else if s.starts_with_space() {
+                    s.skip_spaces();
+                    None
+                } else {
+                    self.state = State::AfterDtd;
+                    None
+                }
+            }
+            State::Dtd => {
+                if let Some((limit, action)) = self.opt.dtd_limit {
+                    if s.pos() - self.dtd_start > limit {
+                        return self.exceed_dtd_limit(action);
+                    }
+
+                    return self.parse_dtd_limited(limit, action);
+                }
+
+                self.parse_dtd()
+            }
+            State::AfterDtd => {
+                if s.starts_with(b"<!--") {
+                    Some(Self::parse_comment(s, &self.opt, &mut self.warning))
+                } else if s.starts_with(b"<?") {
+                    if Self::starts_with_declaration(s) {
+                        Some(Err(Error::MisplacedDeclaration(s.gen_text_pos(), s.pos())))
+                    } else {
+                        Some(Self::parse_pi(s, &self.opt, &mut self.warning))
+                    }
+                } else if s.starts_with(b"<!") {
+                    Some(Err(Error::UnknownToken(
+                        self.state.expected(),
+                        s.gen_text_pos(),
+                        s.pos(),
+                    )))
+                } else if s.starts_with(b"<") {
+                    if Self::is_too_deep(&self.opt, self.depth) {
+                        return Some(Err(Error::NestingTooDeep(s.gen_text_pos(), start)));
+                    }
+
+                    self.state = State::Attributes;
+                    self.tag_start = start;
+                    Some(Self::parse_element_start(s, &self.opt))
+                } else if s.starts_with_space() {
+                    s.skip_spaces();
+                    None
+                } else {
+                    Some(Err(Error::UnknownToken(
+                        self.state.expected(),
+                        s.gen_text_pos(),
+                        s.pos(),
+                    )))
+                }
+            }
+            State::Elements => {
+                if let Some(Chunk::Cdata(_)) = self.chunk {
+                    return Some(Self::parse_cdata(s, &self.opt, &mut self.chunk));
+                }
+
+                // Use `match` only here, because only this section is performance-critical.
+                match s.curr_byte() {
+                    Ok(b'<') => match s.next_byte() {
+                        Ok(b'!') => {
+                            if self.opt.conditional_comments && Self::starts_with_cond_comment(s) {
+                                Some(Self::parse_cond_comment(s))
+                            } else if s.starts_with(b"<!--") {
+                                Some(Self::parse_comment(s, &self.opt, &mut self.warning))
+                            } else if s.starts_with(b"<![CDATA[") {
+                                Some(Self::parse_cdata(s, &self.opt, &mut self.chunk))
+                            } else {
+                                Some(Err(Error::UnknownToken(
+                                    self.state.expected(),
+                                    s.gen_text_pos(),
+                                    s.pos(),
+                                )))
+                            }
+                        }
+                        Ok(b'?') => {
+                            if !Self::starts_with_declaration(s) {
+                                Some(Self::parse_pi(s, &self.opt, &mut self.warning))
+                            } else {
+                                Some(Err(Error::MisplacedDeclaration(s.gen_text_pos(), s.pos())))
+                            }
+                        }
+                        Ok(b'/') => {
+                            if self.depth > 0 {
+                                self.depth -= 1;
+                            }
+
+                            if self.depth == 0 && !self.fragment_parsing {
+                                self.state = State::AfterElements;
+                            } else {
+                                self.state = State::Elements;
+                            }
+
+                            Some(Self::parse_close_element(s, &self.opt))
+                        }
+                        Ok(_) => {
+                            if Self::is_too_deep(&self.opt, self.depth) {
+                                return Some(Err(Error::NestingTooDeep(s.gen_text_pos(), start)));
+                            }
+
+                            self.state = State::Attributes;
+                            self.tag_start = start;
+                            Some(Self::parse_element_start(s, &self.opt))
+                        }
+                        Err(_) => Some(Err(Error::UnknownToken(
+                            self.state.expected(),
+                            s.gen_text_pos(),
+                            s.pos(),
+                        ))),
+                    },
+                    Ok(_) => Some(Self::parse_text(
+                        s,
+                        &self.opt,
+                        &mut self.chunk,
+                        &mut self.warning,
+                    )),
+                    Err(_) => Some(Err(Error::UnknownToken(
+                        self.state.expected(),
+                        s.gen_text_pos(),
+                        s.pos(),
+                    ))),
+                }
+            }
+            State::Attributes => {
+                let mut t = Self::parse_attribute(s, &self.opt, &mut self.warning);
+
+                if let Ok(Token::ElementEnd { end, ref mut span }) = t {
+                    if end == ElementEnd::Open {
+                        self.depth += 1;
+                    }
+
+                    if self.opt.full_tag_spans {
+                        *span = s.slice_back(self.tag_start);
+                    }
+
+                    if self.depth == 0 && !self.fragment_parsing {
+                        self.state = State::AfterElements;
+                    } else {
+                        self.state = State::Elements;
+                    }
+                }
+
+                Some(t.map_err(|e| Error::InvalidAttribute(e, s.gen_text_pos_from(start), start)))
+            }
+            State::AfterElements => {
+                if self.opt.multiple_roots && Self::starts_with_element(s) {
+                    self.state = State::Elements;
+                    None
+                } else if s.starts_with(b"<!--") {
+                    Some(Self::parse_comment(s, &self.opt, &mut self.warning))
+                } else if s.starts_with(b"<?") {
+                    if Self::starts_with_declaration(s) {
+                        Some(Err(Error::MisplacedDeclaration(s.gen_text_pos(), s.pos())))
+                    } else {
+                        Some(Self::parse_pi(s, &self.opt, &mut self.warning))
+                    }
+                } else if s.starts_with_space() {
+                    s.skip_spaces();
+                    None
+                } else {
+                    Some(Err(Error::UnknownToken(
+                        self.state.expected(),
+                        s.gen_text_pos(),
+                        s.pos(),
+                    )))
+                }
+            }
+            State::End => None,
+        }
+    }
+
+    fn starts_with_element(s: &Stream) -> bool {
+        s.starts_with(b"<")
+            && match s.next_byte() {
+                Ok(b'!') | Ok(b'?') | Ok(b'/') | Err(_) => false,
+                Ok(_) => true,
+            }
+    }
+
+    // Checks that the stream starts with `<?xml` followed by a space, `?>` or the end of the stream.
+    // Otherwise, it's a processing instruction, like `<?xml-stylesheet`.
+    fn starts_with_declaration(s: &Stream) -> bool {
+        if !s.starts_with(b"<?xml") {
+            return false;
+        }
+
+        let mut s = *s;
+        s.advance(5);
+        s.at_end() || s.starts_with_space() || s.starts_with(b"?>")
+    }
+
+    fn parse_declaration(s: &mut Stream<'a>) -> Result<Token<'a>> {
+        map_err_at!(Self::parse_declaration_impl(s), s, InvalidDeclaration)
+    }
+
+    // XMLDecl ::= '<?xml' VersionInfo EncodingDecl? SDDecl? S? '?>'
+    fn parse_declaration_impl(s: &mut Stream<'a>) -> StreamResult<Token<'a>> {
+        fn consume_spaces(s: &mut Stream) -> StreamResult<()> {
+            if s.starts_with_space() {
+                s.skip_spaces();
+            } else if !s.starts_with(b"?>") && !s.at_end() {
+                return Err(StreamError::InvalidSpace(
+                    s.curr_byte_unchecked(),
+                    s.gen_text_pos(),
+                    s.pos(),
+                ));
+            }
+
+            Ok(())
+        }
+
+        let start = s.pos();
+        s.advance(5);
+
+        let version = Self::parse_version_info(s)?;
+        consume_spaces(s)?;
+
+        let encoding = Self::parse_encoding_decl(s)?;
+        if encoding.is_some() {
+            consume_spaces(s)?;
+        }
+
+        let standalone = Self::parse_standalone(s)?;
+
+        s.skip_spaces();
+        s.skip_string(b"?>")?;
+
+        let span = s.slice_back(start);
+        Ok(Token::Declaration {
+            version,
+            encoding,
+            standalone,
+            span,
+        })
+    }
+
+    // VersionInfo ::= S 'version' Eq ("'" VersionNum "'" | '"' VersionNum '"')
+    // VersionNum  ::= '1.' [0-9]+
+    fn parse_version_info(s: &mut Stream<'a>) -> StreamResult<StrSpan<'a>> {
+        s.skip_spaces();
+        s.skip_string(b"version")?;
+        s.consume_eq()?;
+        let quote = s.consume_quote()?;
+
+        let start = s.pos();
+        s.skip_string(b"1.")?;
+        s.skip_bytes(|_, c| c.is_xml_digit());
+        let ver = s.slice_back(start);
+
+        s.consume_byte(quote)?;
+
+        Ok(ver)
+    }
+
+    // EncodingDecl ::= S 'encoding' Eq ('"' EncName '"' | "'" EncName "'" )
+    // EncName      ::= [A-Za-z] ([A-Za-z0-9._] | '-')*
+    fn parse_encoding_decl(s: &mut Stream<'a>) -> StreamResult<Option<StrSpan<'a>>> {
+        if !s.starts_with(b"encoding") {
+            return Ok(None);
+        }
+
+        s.advance(8);
+        s.consume_eq()?;
+        let quote = s.consume_quote()?;
+        // [A-Za-z] ([A-Za-z0-9._] | '-')*
+        let name = s.consume_bytes(|_, c| {
+            c.is_xml_letter() || c.is_xml_digit() || c == b'.' || c == b'-' || c == b'_'
+        });
+        s.consume_byte(quote)?;
+
+        match name.as_bytes().first() {
+            Some(c) if c.is_xml_letter() => {}
+            _ => {
+                let pos = s.gen_text_pos_from(name.start());
+                return Err(StreamError::InvalidEncodingName(pos, name.start()));
+            }
+        }
+
+        Ok(Some(name))
+    }
+
+    // SDDecl ::= S 'standalone' Eq (("'" ('yes' | 'no') "'") | ('"' ('yes' | 'no') '"'))
+    fn parse_standalone(s: &mut Stream<'a>) -> StreamResult<Option<bool>> {
+        if !s.starts_with(b"standalone") {
+            return Ok(None);
+        }
+
+        s.advance(10);
+        s.consume_eq()?;
+        let quote = s.consume_quote()?;
+
+        let start = s.pos();
+        let value = s.consume_name()?.as_str();
+
+        let flag = match value {
+            "yes" => true,
+            "no" => false,
+            _ => {
+                let pos = s.gen_text_pos_from(start);
+
+                return Err(StreamError::InvalidString("yes', 'no", pos, start));
+            }
+        };
+
+        s.consume_byte(quote)?;
+
+        Ok(Some(flag))
+    }
+
+    fn parse_comment(
+        s: &mut Stream<'a>,
+        opt: &Options,
+        warning: &mut Option<Warning>,
+    ) -> Result<Token<'a>> {
+        let start = s.pos();
+        Self::parse_comment_impl(s, opt, warning)
+            .map_err(|e| Error::InvalidComment(e, s.gen_text_pos_from(start), start))
+    }
+
+    // '<!--' ((Char - '-') | ('-' (Char - '-')))* '-->'
+    fn parse_comment_impl(
+        s: &mut Stream<'a>,
+        opt: &Options,
+        warning: &mut Option<Warning>,
+    ) -> StreamResult<Token<'a>> {
+        let start = s.pos();
+        s.advance(4);
+        let text = s.consume_chars_until(b"-->")?;
+        let (text, unterminated) = Self::parse_terminator(s, text, b"-->", opt)?;
+        Self::check_len(s, text, opt.max_comment_len)?;
+
+        let malformed = match text.as_str().find("--") {
+            Some(idx) => Some((StreamError::InvalidCommentData, text.start() + idx)),
+            None if text.as_str().ends_with('-') => {
+                Some((StreamError::InvalidCommentEnd, text.end() - 1))
+            }
+            None => None,
+        };
+
+        if let Some((e, offset)) = malformed {
+            if !opt.lenient {
+                return Err(e);
+            }
+
+            *warning = Some(Warning::MalformedComment(
+                s.gen_text_pos_from(offset),
+                offset,
+            ));
+        }
+
+        let span = s.slice_back(start);
+
+        Ok(Token::Comment {
+            text,
+            span,
+            unterminated,
+        })
+    }
+
+    fn starts_with_cond_comment(s: &Stream) -> bool {
+        s.starts_with(b"<!--[if")
+            || s.starts_with(b"<![if")
+            || s.starts_with(b"<![endif]")
+            || s.starts_with(b"<!--<![endif]")
+    }
+
+    fn parse_cond_comment(s: &mut Stream<'a>) -> Result<Token<'a>> {
+        map_err_at!(Self::parse_cond_comment_impl(s), s, InvalidComment)
+    }
+
+    // '<!--[if' S Condition ']>' ('<!-->')? | '<![if' S Condition ']>'
+    // ('<!--')? '<![endif]-->' | '<![endif]>'
+    fn parse_cond_comment_impl(s: &mut Stream<'a>) -> StreamResult<Token<'a>> {
+        let start = s.pos();
+
+        if s.starts_with(b"<!--<![endif]") || s.starts_with(b"<![endif]") {
+            let revealed = s.starts_with(b"<![");
+            if !revealed {
+                s.advance(4);
+            }
+
+            s.advance(9);
+            if revealed && s.curr_byte()? == b'>' {
+                s.advance(1);
+            } else {
+                s.skip_string(b"-->")?;
+            }
+
+            let span = s.slice_back(start);
+            return Ok(Token::ConditionalCommentEnd { span });
+        }
+
+        let revealed = s.starts_with(b"<![");
+        s.advance(if revealed { 5 } else { 7 });
+        s.consume_spaces()?;
+        let condition = s.consume_chars_until(b"]>")?;
+        s.skip_string(b"]>")?;
+
+        if !revealed && s.starts_with(b"<!-->") {
+            s.advance(5);
+        }
+
+        let span = s.slice_back(start);
+        Ok(Token::ConditionalCommentStart { condition, span })
+    }
+
+    fn parse_pi(
+        s: &mut Stream<'a>,
+        opt: &Options,
+        warning: &mut Option<Warning>,
+    ) -> Result<Token<'a>> {
+        map_err_at!(Self::parse_pi_impl(s, opt, warning), s, InvalidPI)
+    }
+
+    // PI       ::= '<?' PITarget (S (Char* - (Char* '?>' Char*)))? '?>'
+    // PITarget ::= Name - (('X' | 'x') ('M' | 'm') ('L' | 'l'))
+    fn parse_pi_impl(
+        s: &mut Stream<'a>,
+        opt: &Options,
+        warning: &mut Option<Warning>,
+    ) -> StreamResult<Token<'a>> {
+        let start = s.pos();
+        s.advance(2);
+        let target = s.consume_name()?;
+
+        // PITarget ::= Name - (('X' | 'x') ('M' | 'm') ('L' | 'l'))
+        if target.as_str().eq_ignore_ascii_case("xml") {
+            let pos = s.gen_text_pos_from(target.start());
+            if !opt.lenient {
+                return Err(StreamError::ReservedPiTarget(pos, target.start()));
+            }
+
+            *warning = Some(Warning::ReservedPiTarget(pos, target.start()));
+        }
+        s.skip_spaces();
+        let content = s.consume_chars_until(b"?>")?;
+        let content = if !content.is_empty() {
+            Some(content)
+        } else {
+            None
+        };
+
+        s.skip_string(b"?>")?;
+
+        let span = s.slice_back(start);
+
+        Ok(Token::ProcessingInstruction {
+            target,
+            content,
+            span,
+        })
+    }
+
+    // In the lenient mode, the HTML-style `<!doctype` is allowed too.
+    fn starts_with_doctype(s: &Stream, opt: &Options) -> bool {
+        Self::starts_with_keyword(s, b"<!DOCTYPE", opt)
+    }
+
+    // Keywords are case-insensitive in the lenient mode.
+    fn starts_with_keyword(s: &Stream, keyword: &[u8], opt: &Options) -> bool {
+        s.starts_with(keyword) || (opt.lenient && s.starts_with_ignore_ascii_case(keyword))
+    }
+
+    fn parse_doctype(
+        s: &mut Stream<'a>,
+        opt: &Options,
+        warning: &mut Option<Warning>,
+    ) -> Result<Token<'a>> {
+        map_err_at!(Self::parse_doctype_impl(s, opt, warning), s, InvalidDoctype)
+    }
+
+    // doctypedecl ::= '<!DOCTYPE' S Name (S ExternalID)? S? ('[' intSubset ']' S?)? '>'
+    fn parse_doctype_impl(
+        s: &mut Stream<'a>,
+        opt: &Options,
+        warning: &mut Option<Warning>,
+    ) -> StreamResult<Token<'a>> {
+        let start = s.pos();
+        s.advance(9);
+
+        let name = if opt.lenient && Self::at_doctype_end(s) {
+            // `<!DOCTYPE>` produces an empty name right before the `>`.
+            s.skip_spaces();
+            s.slice_back(s.pos())
+        } else {
+            s.consume_spaces()?;
+            s.consume_name()?
+        };
+        s.skip_spaces();
+
+        let external_id = Self::parse_external_id(s, opt, warning)?;
+        s.skip_spaces();
+
+        let c = s.curr_byte()?;
+        if c != b'[' && c != b'>' {
+            static EXPECTED: &[u8] = b"[>";
+            return Err(StreamError::InvalidCharMultiple(
+                c,
+                EXPECTED,
+                s.gen_text_pos(),
+                s.pos(),
+            ));
+        }
+
+        s.advance(1);
+
+        let span = s.slice_back(start);
+        if c == b'[' {
+            Ok(Token::DtdStart {
+                name,
+                external_id,
+                span,
+            })
+        } else {
+            Ok(Token::EmptyDtd {
+                name,
+                external_id,
+                span,
+            })
+        }
+    }
+
+    // Checks that there is nothing but spaces before `>` or `[`.
+    fn at_doctype_end(s: &Stream) -> bool {
+        let mut s = *s;
+        s.skip_spaces();
+        match s.curr_byte() {
+            Ok(c) => c == b'>' || c == b'[',
+            Err(_) => false,
+        }
+    }
+
+    // ExternalID ::= 'SYSTEM' S SystemLiteral | 'PUBLIC' S PubidLiteral S SystemLiteral
+    fn parse_external_id(
+        s: &mut Stream<'a>,
+        opt: &Options,
+        warning: &mut Option<Warning>,
+    ) -> StreamResult<Option<ExternalId<'a>>> {
+        let is_system = Self::starts_with_keyword(s, b"SYSTEM", opt);
+        let v = if is_system || Self::starts_with_keyword(s, b"PUBLIC", opt) {
+            s.advance(6);
+
+            Self::consume_literal_spaces(s, opt)?;
+            let quote = s.consume_quote()?;
+            let literal1 = s.consume_bytes_until(quote);
+            s.consume_byte(quote)?;
+
+            let v = if is_system {
+                ExternalId::System(literal1)
+            } else {
+                Self::check_pubid_literal(s, literal1, opt, warning)?;
+                Self::consume_literal_spaces(s, opt)?;
+                let literal2 = if opt.lenient && Self::at_doctype_end(s) {
+                    // HTML-style public IDs can omit the system literal.
+                    s.slice_back(s.pos())
+                } else {
+                    let quote = s.consume_quote()?;
+                    let literal = s.consume_bytes_until(quote);
+                    s.consume_byte(quote)?;
+                    literal
+                };
+
+                ExternalId::Public(literal1, literal2)
+            };
+
+            Some(v)
+        } else {
+            None
+        };
+
+        Ok(v)
+    }
+
+    // Spaces before literals are optional in the lenient mode.
+    fn consume_literal_spaces(s: &mut Stream, opt: &Options) -> StreamResult<()> {
+        if opt.lenient {
+            s.skip_spaces();
+            Ok(())
+        } else {
+            s.consume_spaces()
+        }
+    }
+
+    // PubidLiteral ::= '"' PubidChar* '"' | "'" (PubidChar - "'")* "'"
+    // PubidChar    ::= #x20 | #xD | #xA | [a-zA-Z0-9] | [-'()+,./:=?;!*#@$_%]
+    fn check_pubid_literal(
+        s: &Stream<'a>,
+        literal: StrSpan<'a>,
+        opt: &Options,
+        warning: &mut Option<Warning>,
+    ) -> StreamResult<()> {
+        let is_pubid_char = |c: char| {
+            matches!(c,
+                ' ' | '\r' | '\n' | 'a'...'z' | 'A'...'Z' | '0'...'9'
+                | '-' | '\'' | '(' | ')' | '+' | ',' | '.' | '/' | ':' | '=' | '?' | ';' | '!'
+                | '*' | '#' | '@' | '$' | '_' | '%'
+            )
+        };
+
+        let invalid = literal
+            .as_str()
+            .char_indices()
+            .find(|&(_, c)| !is_pubid_char(c));
+        if let Some((idx, c)) = invalid {
+            let offset = literal.start() + idx;
+            let pos = s.gen_text_pos_from(offset);
+            if !opt.lenient {
+                return Err(StreamError::InvalidPubidLiteral(c, pos, offset));
+            }
+
+            *warning = Some(Warning::InvalidPubidLiteral(pos, offset));
+        }
+
+        Ok(())
+    }
+
+    // Skips everything up to the `]` S? `>` sequence without any parsing.
+    // Quoted `]` inside declarations are not handled.
+    fn skip_dtd(s: &mut Stream<'a>, dtd_start: usize, mut cond_depth: usize) -> Result<Token<'a>> {
+        let start = s.pos();
+        // Conditional sections end with `]]>`, which is not a DTD end.
+        loop {
+            s.skip_bytes(|_, c| c != b']' && c != b'<');
+            if s.at_end() {
+                let e = StreamError::UnexpectedEndOfStream;
+                return Err(Error::InvalidDoctype(e, s.gen_text_pos_from(start), start));
+            }
+
+            if s.starts_with(b"<![") {
+                cond_depth += 1;
+                s.advance(3);
+                continue;
+            } else if s.starts_with(b"<") {
+                s.advance(1);
+                continue;
+            } else if cond_depth > 0 && s.starts_with(b"]]>") {
+                cond_depth -= 1;
+                s.advance(3);
+                continue;
+            }
+
+            let subset_end = s.pos();
+            s.advance(1);
+            s.skip_spaces();
+            if s.try_consume_byte(b'>') {
+                return Ok(Token::DtdEnd {
+                    span: s.slice_back(start),
+                    internal_subset: s.span().slice_region(dtd_start, subset_end),
+                });
+            }
+        }
+    }
+
+    fn parse_conditional_section_start(s: &mut Stream<'a>) -> Result<Token<'a>> {
+        map_err_at!(
+            Self::parse_conditional_section_start_impl(s),
+            s,
+            InvalidDoctype
+        )
+    }
+
+    // conditionalSect ::= includeSect | ignoreSect
+    // includeSect     ::= '<![' S? 'INCLUDE' S? '[' extSubsetDecl ']]>'
+    // ignoreSect      ::= '<![' S? 'IGNORE' S? '[' ignoreSectContents* ']]>'
+    fn parse_conditional_section_start_impl(s: &mut Stream<'a>) -> StreamResult<Token<'a>> {
+        let start = s.pos();
+        s.advance(3);
+        s.skip_spaces();
+
+        let ignore = if s.starts_with(b"INCLUDE") {
+            s.advance(7);
+            false
+        } else if s.starts_with(b"IGNORE") {
+            s.advance(6);
+            true
+        } else {
+            // Parameter entity references are not supported.
+            return Err(StreamError::InvalidString(
+                "INCLUDE",
+                s.gen_text_pos(),
+                s.pos(),
+            ));
+        };
+
+        s.skip_spaces();
+        s.consume_byte(b'[')?;
+
+        Ok(Token::ConditionalSectionStart {
+            ignore,
+            span: s.slice_back(start),
+        })
+    }
+
+    // Skips the IGNORE section content, including nested sections,
+    // up to the section end.
+    fn skip_ignore_section(s: &mut Stream<'a>) -> Result<()> {
+        let start = s.pos();
+        let mut depth = 0;
+        while !s.at_end() {
+            if s.starts_with(b"<![") {
+                depth += 1;
+                s.advance(3);
+            } else if s.starts_with(b"]]>") {
+                if depth == 0 {
+                    return Ok(());
+                }
+
+                s.advance(3);
+                depth -= 1;
+            } else {
+                s.advance(1);
+            }
+        }
+
+        let e = StreamError::UnexpectedEndOfStream;
+        Err(Error::InvalidDoctype(e, s.gen_text_pos_from(start), start))
+    }
+
+    fn parse_entity_decl(
+        s: &mut Stream<'a>,
+        opt: &Options,
+        warning: &mut Option<Warning>,
+    ) -> Result<Token<'a>> {
+        map_err_at!(
+            Self::parse_entity_decl_impl(s, opt, warning),
+            s,
+            InvalidEntity
+        )
+    }
+
+    // EntityDecl  ::= GEDecl | PEDecl
+    // GEDecl      ::= '<!ENTITY' S Name S EntityDef S? '>'
+    // PEDecl      ::= '<!ENTITY' S '%' S Name S PEDef S? '>'
+    fn parse_entity_decl_impl(
+        s: &mut Stream<'a>,
+        opt: &Options,
+        warning: &mut Option<Warning>,
+    ) -> StreamResult<Token<'a>> {
+        let start = s.pos();
+        s.advance(8);
+
+        s.consume_spaces()?;
+
+        let is_ge = if s.try_consume_byte(b'%') {
+            s.consume_spaces()?;
+            false
+        } else {
+            true
+        };
+
+        let name = s.consume_name()?;
+        s.consume_spaces()?;
+        let definition = Self::parse_entity_def(s, is_ge, opt, warning)?;
+        s.skip_spaces();
+        s.consume_byte(b'>')?;
+
+        let span = s.slice_back(start);
+
+        Ok(Token::EntityDeclaration {
+            name,
+            definition,
+            span,
+        })
+    }
+
+    // EntityDef   ::= EntityValue | (ExternalID NDataDecl?)
+    // PEDef       ::= EntityValue | ExternalID
+    // EntityValue ::= '"' ([^%&"] | PEReference | Reference)* '"' |  "'" ([^%&']
+    //                             | PEReference | Reference)* "'"
+    // ExternalID  ::= 'SYSTEM' S SystemLiteral | 'PUBLIC' S PubidLiteral S SystemLiteral
+    // NDataDecl   ::= S 'NDATA' S Name
+    fn parse_entity_def(
+        s: &mut Stream<'a>,
+        is_ge: bool,
+        opt: &Options,
+        warning: &mut Option<Warning>,
+    ) -> StreamResult<EntityDefinition<'a>> {
+        let c = s.curr_byte()?;
+        match c {
+            b'"' | b'\'' => {
+                let quote = s.consume_quote()?;
+                let value = s.consume_bytes_until(quote);
+                s.consume_byte(quote)?;
+
+                Ok(EntityDefinition::EntityValue(value))
+            }
+            b'S' | b'P' => {
+                if let Some(id) = Self::parse_external_id(s, opt, warning)? {
+                    if is_ge {
+                        s.skip_spaces();
+                        if s.starts_with(b"NDATA") {
+                            s.advance(5);
+                            s.consume_spaces()?;
+                            let notation = s.consume_name()?;
+                            return Ok(EntityDefinition::Unparsed(id, notation));
                         }
                     }
-                } else if s.starts_with_space() {
-                    s.skip_spaces();
-                    None
-                } else if s.starts_with(b"<!ELEMENT")
-                    || s.starts_with(b"<!ATTLIST")
-                    || s.starts_with(b"<!NOTATION")
-                {
-                    if Self::consume_decl(s).is_err() {
-                        let pos = s.gen_text_pos_from(start);
-                        Some(Err(Error::UnknownToken(pos)))
-                    } else {
-                        None
-                    }
+
+                    Ok(EntityDefinition::ExternalId(id))
                 } else {
-                    Some(Err(Error::UnknownToken(s.gen_text_pos())))
+                    Err(StreamError::InvalidExternalID)
                 }
             }
-            State::AfterDtd => {
-                if s.starts_with(b"<!--") {
-                    Some(Self::parse_comment(s))
-                } else if s.starts_with(b"<?") {
-                    if s.starts_with(b"<?xml ") {
-                        Some(Err(Error::UnknownToken(s.gen_text_pos())))
-                    } else {
-                        Some(Self::parse_pi(s))
-                    }
-                } else if s.starts_with(b"<!") {
-                    Some(Err(Error::UnknownToken(s.gen_text_pos())))
-                } else if s.starts_with(b"<") {
-                    self.state = State::Attributes;
-                    Some(Self::parse_element_start(s))
-                } else if s.starts_with_space() {
-                    s.skip_spaces();
-                    None
-                } else {
-                    Some(Err(Error::UnknownToken(s.gen_text_pos())))
-                }
+            _ => {
+                static EXPECTED: &[u8] = b"\"'SP";
+                let pos = s.gen_text_pos();
+                Err(StreamError::InvalidCharMultiple(c, EXPECTED, pos, s.pos()))
             }
-            State::Elements => {
-                // Use `match` only here, because only this section is performance-critical.
-                match s.curr_byte() {
-                    Ok(b'<') => match s.next_byte() {
-                        Ok(b'!') => {
-                            if s.starts_with(b"<!--") {
-                                Some(Self::parse_comment(s))
-                            } else if s.starts_with(b"<![CDATA[") {
-                                Some(Self::parse_cdata(s))
-                            } else {
-                                Some(Err(Error::UnknownToken(s.gen_text_pos())))
-                            }
-                        }
-                        Ok(b'?') => {
-                            if !s.starts_with(b"<?xml ") {
-                                Some(Self::parse_pi(s))
-                            } else {
-                                Some(Err(Error::UnknownToken(s.gen_text_pos())))
-                            }
-                        }
-                        Ok(b'/') => {
-                            if self.depth > 0 {
-                                self.depth -= 1;
-                            }
+        }
+    }
 
-                            if self.depth == 0 && !self.fragment_parsing {
-                                self.state = State::AfterElements;
-                            } else {
-                                self.state = State::Elements;
-                            }
+    fn parse_element_decl(s: &mut Stream<'a>) -> Result<Token<'a>> {
+        map_err_at!(Self::parse_element_decl_impl(s), s, InvalidElementDecl)
+    }
 
-                            Some(Self::parse_close_element(s))
-                        }
-                        Ok(_) => {
-                            self.state = State::Attributes;
-                            Some(Self::parse_element_start(s))
-                        }
-                        Err(_) => Some(Err(Error::UnknownToken(s.gen_text_pos()))),
-                    },
-                    Ok(_) => Some(Self::parse_text(s)),
-                    Err(_) => Some(Err(Error::UnknownToken(s.gen_text_pos()))),
-                }
-            }
-            State::Attributes => {
-                let t = Self::parse_attribute(s);
+    // elementdecl ::= '<!ELEMENT' S Name S contentspec S? '>'
+    fn parse_element_decl_impl(s: &mut Stream<'a>) -> StreamResult<Token<'a>> {
+        let start = s.pos();
+        s.advance(9);
 
-                if let Ok(Token::ElementEnd { end, .. }) = t {
-                    if end == ElementEnd::Open {
-                        self.depth += 1;
-                    }
+        s.consume_spaces()?;
+        let name = s.consume_name()?;
+        s.consume_spaces()?;
+        let content_spec = Self::parse_content_spec(s)?;
+        s.skip_spaces();
+        s.consume_byte(b'>')?;
 
-                    if self.depth == 0 && !self.fragment_parsing {
-                        self.state = State::AfterElements;
-                    } else {
-                        self.state = State::Elements;
-                    }
-                }
+        Ok(Token::ElementDeclaration {
+            name,
+            content_spec,
+            span: s.slice_back(start),
+        })
+    }
 
-                Some(t.map_err(|e| Error::InvalidAttribute(e, s.gen_text_pos_from(start))))
-            }
-            State::AfterElements => {
-                if s.starts_with(b"<!--") {
-                    Some(Self::parse_comment(s))
-                } else if s.starts_with(b"<?") {
-                    if s.starts_with(b"<?xml ") {
-                        Some(Err(Error::UnknownToken(s.gen_text_pos())))
-                    } else {
-                        Some(Self::parse_pi(s))
-                    }
-                } else if s.starts_with_space() {
-                    s.skip_spaces();
-                    None
-                } else {
-                    Some(Err(Error::UnknownToken(s.gen_text_pos())))
+    fn parse_attlist_decl(s: &mut Stream<'a>) -> Result<Token<'a>> {
+        map_err_at!(Self::parse_attlist_decl_impl(s), s, InvalidAttlistDecl)
+    }
+
+    // AttlistDecl ::= '<!ATTLIST' S Name AttDef* S? '>'
+    fn parse_attlist_decl_impl(s: &mut Stream<'a>) -> StreamResult<Token<'a>> {
+        let start = s.pos();
+        s.advance(9);
+
+        s.consume_spaces()?;
+        let name = s.consume_name()?;
+
+        let definitions_start = s.pos();
+        while attlist::parse_attribute_definition(s)?.is_some() {}
+        let definitions = AttributeDefinitions::new(s.slice_back(definitions_start));
+
+        s.consume_byte(b'>')?;
+
+        Ok(Token::AttlistDeclaration {
+            name,
+            definitions,
+            span: s.slice_back(start),
+        })
+    }
+
+    // contentspec ::= 'EMPTY' | 'ANY' | Mixed | children
+    // Mixed       ::= '(' S? '#PCDATA' (S? '|' S? Name)* S? ')*' | '(' S? '#PCDATA' S? ')'
+    // children    ::= (choice | seq) ('?' | '*' | '+')?
+    fn parse_content_spec(s: &mut Stream<'a>) -> StreamResult<ContentSpec<'a>> {
+        if s.starts_with(b"EMPTY") {
+            s.advance(5);
+            return Ok(ContentSpec::Empty);
+        } else if s.starts_with(b"ANY") {
+            s.advance(3);
+            return Ok(ContentSpec::Any);
+        }
+
+        let start = s.pos();
+        s.consume_byte(b'(')?;
+        s.skip_spaces();
+        let is_mixed = s.starts_with(b"#PCDATA");
+
+        // Groups can be nested, so we have to count parentheses.
+        let mut depth = 1;
+        while depth > 0 {
+            match s.curr_byte()? {
+                b'(' => depth += 1,
+                b')' => depth -= 1,
+                b'>' => {
+                    static EXPECTED: &[u8] = b"()";
+                    return Err(StreamError::InvalidCharMultiple(
+                        b'>',
+                        EXPECTED,
+                        s.gen_text_pos(),
+                        s.pos(),
+                    ));
                 }
+                _ => {}
             }
-            State::End => None,
+
+            s.advance(1);
+        }
+
+        if let Ok(b'?') | Ok(b'*') | Ok(b'+') = s.curr_byte() {
+            s.advance(1);
+        }
+
+        let span = s.slice_back(start);
+        if is_mixed {
+            Ok(ContentSpec::Mixed(span))
+        } else {
+            Ok(ContentSpec::Children(span))
         }
     }
 
-    fn parse_declaration(s: &mut Stream<'a>) -> Result<Token<'a>> {
-        map_err_at!(Self::parse_declaration_impl(s), s, InvalidDeclaration)
+    fn parse_notation_decl(
+        s: &mut Stream<'a>,
+        opt: &Options,
+        warning: &mut Option<Warning>,
+    ) -> Result<Token<'a>> {
+        map_err_at!(
+            Self::parse_notation_decl_impl(s, opt, warning),
+            s,
+            InvalidNotationDecl
+        )
     }
 
-    // XMLDecl ::= '<?xml' VersionInfo EncodingDecl? SDDecl? S? '?>'
-    fn parse_declaration_impl(s: &mut Stream<'a>) -> StreamResult<Token<'a>> {
-        fn consume_spaces(s: &mut Stream) -> StreamResult<()> {
-            if s.starts_with_space() {
-                s.skip_spaces();
-            } else if !s.starts_with(b"?>") && !s.at_end() {
-                return Err(StreamError::InvalidSpace(
-                    s.curr_byte_unchecked(),
-                    s.gen_text_pos(),
-                ));
+    // NotationDecl ::= '<!NOTATION' S Name S (ExternalID | PublicID) S? '>'
+    // PublicID     ::= 'PUBLIC' S PubidLiteral
+    fn parse_notation_decl_impl(
+        s: &mut Stream<'a>,
+        opt: &Options,
+        warning: &mut Option<Warning>,
+    ) -> StreamResult<Token<'a>> {
+        let start = s.pos();
+        s.advance(10);
+
+        s.consume_spaces()?;
+        let name = s.consume_name()?;
+        s.consume_spaces()?;
+
+        fn consume_literal<'a>(s: &mut Stream<'a>) -> StreamResult<StrSpan<'a>> {
+            let quote = s.consume_quote()?;
+            let literal = s.consume_bytes_until(quote);
+            s.consume_byte(quote)?;
+            Ok(literal)
+        }
+
+        let external_id = if s.starts_with(b"SYSTEM") {
+            s.advance(6);
+            s.consume_spaces()?;
+            NotationId::System(consume_literal(s)?)
+        } else if s.starts_with(b"PUBLIC") {
+            s.advance(6);
+            s.consume_spaces()?;
+            let public_id = consume_literal(s)?;
+            Self::check_pubid_literal(s, public_id, opt, warning)?;
+
+            // The system literal is optional, unlike in the ExternalID.
+            let has_space = s.starts_with_space();
+            s.skip_spaces();
+            let system_id = match s.curr_byte()? {
+                b'"' | b'\'' if has_space => Some(consume_literal(s)?),
+                _ => None,
+            };
+
+            NotationId::Public(public_id, system_id)
+        } else {
+            return Err(StreamError::InvalidExternalID);
+        };
+
+        s.skip_spaces();
+        s.consume_byte(b'>')?;
+
+        Ok(Token::NotationDeclaration {
+            name,
+            external_id,
+            span: s.slice_back(start),
+        })
+    }
+
+    fn parse_pe_reference(s: &mut Stream<'a>) -> Result<Token<'a>> {
+        map_err_at!(Self::parse_pe_reference_impl(s), s, InvalidDoctype)
+    }
+
+    // PEReference ::= '%' Name ';'
+    fn parse_pe_reference_impl(s: &mut Stream<'a>) -> StreamResult<Token<'a>> {
+        let start = s.pos();
+        s.advance(1);
+        let name = s.consume_name()?;
+        s.consume_byte(b';')?;
+
+        Ok(Token::ParameterEntityReference {
+            name,
+            span: s.slice_back(start),
+        })
+    }
+
+    fn parse_cdata(
+        s: &mut Stream<'a>,
+        opt: &Options,
+        chunk: &mut Option<Chunk>,
+    ) -> Result<Token<'a>> {
+        map_err_at!(Self::parse_cdata_impl(s, opt, chunk), s, InvalidCdata)
+    }
+
+    // CDSect  ::= CDStart CData CDEnd
+    // CDStart ::= '<![CDATA['
+    // CData   ::= (Char* - (Char* ']]>' Char*))
+    // CDEnd   ::= ']]>'
+    fn parse_cdata_impl(
+        s: &mut Stream<'a>,
+        opt: &Options,
+        chunk: &mut Option<Chunk>,
+    ) -> StreamResult<Token<'a>> {
+        let start = s.pos();
+        let text_start = match *chunk {
+            Some(Chunk::Cdata(text_start)) => text_start,
+            _ => {
+                s.advance(9);
+                s.pos()
             }
+        };
 
-            Ok(())
+        let mut text = Self::consume_chunk(s, b"]]>", opt)?;
+
+        // In the partial mode, the rest can be a truncated terminator,
+        // which must be trimmed instead of being returned as a chunk.
+        if opt.partial && !s.at_end() && s.slice_tail().len() < 3 {
+            s.jump_to_end();
+            text = s.span().slice_region(text.start(), s.pos());
+        }
+
+        Self::check_len(s, s.slice_back(text_start), opt.max_text_len)?;
+
+        if !s.at_end() && !s.starts_with(b"]]>") {
+            *chunk = Some(Chunk::Cdata(text_start));
+            return Ok(Token::Cdata {
+                text,
+                span: s.slice_back(start),
+                unterminated: false,
+            });
+        }
+
+        *chunk = None;
+        let (text, unterminated) = Self::parse_terminator(s, text, b"]]>", opt)?;
+        let span = s.slice_back(start);
+        Ok(Token::Cdata {
+            text,
+            span,
+            unterminated,
+        })
+    }
+
+    // Skips the `terminator` after the `text`.
+    //
+    // In the partial mode, the end of stream is treated as a terminator.
+    // In which case, a truncated terminator, like `--` in a comment,
+    // will be removed from the text.
+    fn parse_terminator(
+        s: &mut Stream<'a>,
+        text: StrSpan<'a>,
+        terminator: &'static [u8],
+        opt: &Options,
+    ) -> StreamResult<(StrSpan<'a>, bool)> {
+        if opt.partial && s.at_end() {
+            let mut len = terminator.len() - 1;
+            while len > 0 && !text.as_bytes().ends_with(&terminator[..len]) {
+                len -= 1;
+            }
+
+            let text = s.span().slice_region(text.start(), text.end() - len);
+            Ok((text, true))
+        } else {
+            s.skip_string(terminator)?;
+            Ok((text, false))
         }
+    }
 
+    fn consume_qname(
+        s: &mut Stream<'a>,
+        opt: &Options,
+    ) -> StreamResult<(StrSpan<'a>, StrSpan<'a>)> {
         let start = s.pos();
-        s.advance(6);
+        let (prefix, local) = s.consume_qname()?;
 
-        let version = Self::parse_version_info(s)?;
-        consume_spaces(s)?;
+        // `:a` is a valid XML name, but not a valid qualified name.
+        if opt.namespaces && prefix.is_empty() && local.start() != start {
+            return Err(StreamError::InvalidName);
+        }
+
+        Ok((prefix, local))
+    }
+
+    fn parse_element_start(s: &mut Stream<'a>, opt: &Options) -> Result<Token<'a>> {
+        map_err_at!(Self::parse_element_start_impl(s, opt), s, InvalidElement)
+    }
+
+    // '<' Name (S Attribute)* S? '>'
+    fn parse_element_start_impl(s: &mut Stream<'a>, opt: &Options) -> StreamResult<Token<'a>> {
+        let start = s.pos();
+        s.advance(1);
+        let (prefix, local) = Self::consume_qname(s, opt)?;
+        let span = s.slice_back(start);
+
+        Ok(Token::ElementStart {
+            prefix,
+            local,
+            span,
+        })
+    }
 
-        let encoding = Self::parse_encoding_decl(s)?;
-        if encoding.is_some() {
-            consume_spaces(s)?;
-        }
+    fn parse_close_element(s: &mut Stream<'a>, opt: &Options) -> Result<Token<'a>> {
+        map_err_at!(Self::parse_close_element_impl(s, opt), s, InvalidElement)
+    }
 
-        let standalone = Self::parse_standalone(s)?;
+    // '</' Name S? '>'
+    fn parse_close_element_impl(s: &mut Stream<'a>, opt: &Options) -> StreamResult<Token<'a>> {
+        let start = s.pos();
+        s.advance(2);
 
+        let (prefix, tag_name) = Self::consume_qname(s, opt)?;
         s.skip_spaces();
-        s.skip_string(b"?>")?;
+        s.consume_byte(b'>')?;
 
         let span = s.slice_back(start);
-        Ok(Token::Declaration {
-            version,
-            encoding,
-            standalone,
+
+        Ok(Token::ElementEnd {
+            end: ElementEnd::Close(prefix, tag_name),
             span,
         })
     }
 
-    // VersionInfo ::= S 'version' Eq ("'" VersionNum "'" | '"' VersionNum '"')
-    // VersionNum  ::= '1.' [0-9]+
-    fn parse_version_info(s: &mut Stream<'a>) -> StreamResult<StrSpan<'a>> {
+    // Name Eq AttValue
+    fn parse_attribute(
+        s: &mut Stream<'a>,
+        opt: &Options,
+        warning: &mut Option<Warning>,
+    ) -> StreamResult<Token<'a>> {
+        let attr_start = s.pos();
+        let has_space = s.starts_with_space();
         s.skip_spaces();
-        s.skip_string(b"version")?;
-        s.consume_eq()?;
-        let quote = s.consume_quote()?;
 
-        let start = s.pos();
-        s.skip_string(b"1.")?;
-        s.skip_bytes(|_, c| c.is_xml_digit());
-        let ver = s.slice_back(start);
+        if let Ok(c) = s.curr_byte() {
+            let start = s.pos();
 
-        s.consume_byte(quote)?;
+            match c {
+                b'/' => {
+                    s.advance(1);
+                    Self::skip_empty_element_end_spaces(s, opt, warning)?;
+                    s.consume_byte(b'>')?;
+                    let span = s.slice_back(start);
+                    return Ok(Token::ElementEnd {
+                        end: ElementEnd::Empty,
+                        span,
+                    });
+                }
+                b'>' => {
+                    s.advance(1);
+                    let span = s.slice_back(start);
+                    return Ok(Token::ElementEnd {
+                        end: ElementEnd::Open,
+                        span,
+                    });
+                }
+                _ => {}
+            }
+        }
 
-        Ok(ver)
-    }
+        if !has_space && !opt.lenient {
+            if !s.at_end() {
+                return Err(StreamError::InvalidSpace(
+                    s.curr_byte_unchecked(),
+                    s.gen_text_pos_from(attr_start),
+                    attr_start,
+                ));
+            } else {
+                return Err(StreamError::UnexpectedEndOfStream);
+            }
+        }
 
-    // EncodingDecl ::= S 'encoding' Eq ('"' EncName '"' | "'" EncName "'" )
-    // EncName      ::= [A-Za-z] ([A-Za-z0-9._] | '-')*
-    fn parse_encoding_decl(s: &mut Stream<'a>) -> StreamResult<Option<StrSpan<'a>>> {
-        if !s.starts_with(b"encoding") {
-            return Ok(None);
+        let start = s.pos();
+
+        let (prefix, local) = Self::consume_qname(s, opt)?;
+
+        if opt.lenient {
+            if let Some(value) = Self::parse_html_attribute_value(s, start, warning) {
+                Self::check_len(s, value, opt.max_attribute_value_len)?;
+                Self::check_attribute_references(s, value, opt)?;
+                let span = s.slice_back(start);
+                return Ok(Token::Attribute {
+                    prefix,
+                    local,
+                    value,
+                    span,
+                });
+            }
         }
 
-        s.advance(8);
         s.consume_eq()?;
         let quote = s.consume_quote()?;
-        // [A-Za-z] ([A-Za-z0-9._] | '-')*
-        // TODO: check that first byte is [A-Za-z]
-        let name = s.consume_bytes(|_, c| {
-            c.is_xml_letter() || c.is_xml_digit() || c == b'.' || c == b'-' || c == b'_'
-        });
+        // The attribute value must not contain the < character.
+        // Unless we are in the lenient mode.
+        let value = if opt.lenient {
+            s.consume_chars_until(&[quote])?
+        } else {
+            s.consume_chars_until_either(quote, b'<')?
+        };
+        Self::check_len(s, value, opt.max_attribute_value_len)?;
+        Self::check_attribute_references(s, value, opt)?;
         s.consume_byte(quote)?;
+        let span = s.slice_back(start);
 
-        Ok(Some(name))
+        Ok(Token::Attribute {
+            prefix,
+            local,
+            value,
+            span,
+        })
     }
 
-    // SDDecl ::= S 'standalone' Eq (("'" ('yes' | 'no') "'") | ('"' ('yes' | 'no') '"'))
-    fn parse_standalone(s: &mut Stream<'a>) -> StreamResult<Option<bool>> {
-        if !s.starts_with(b"standalone") {
-            return Ok(None);
+    // Parses HTML-style minimized and unquoted attribute values,
+    // like `<input disabled>` and `<a b=c>`.
+    //
+    // Returns `None` when the value is quoted.
+    fn parse_html_attribute_value(
+        s: &mut Stream<'a>,
+        start: usize,
+        warning: &mut Option<Warning>,
+    ) -> Option<StrSpan<'a>> {
+        let mut s2 = *s;
+        s2.skip_spaces();
+        if s2.curr_byte().ok() != Some(b'=') {
+            *warning = Some(Warning::MinimizedAttribute(
+                s.gen_text_pos_from(start),
+                start,
+            ));
+            return Some(s.slice_back(s.pos()));
         }
 
-        s.advance(10);
-        s.consume_eq()?;
-        let quote = s.consume_quote()?;
+        s2.advance(1);
+        s2.skip_spaces();
+        match s2.curr_byte() {
+            Ok(b'"') | Ok(b'\'') | Ok(b'>') | Err(_) => return None,
+            Ok(c) if c.is_xml_space() => return None,
+            Ok(_) => {}
+        }
+
+        *warning = Some(Warning::UnquotedAttributeValue(s2.gen_text_pos(), s2.pos()));
+        *s = s2;
+        Some(s.consume_bytes(|s, c| {
+            !c.is_xml_space() && c != b'>' && !(c == b'/' && s.next_byte() == Ok(b'>'))
+        }))
+    }
+
+    fn parse_text(
+        s: &mut Stream<'a>,
+        opt: &Options,
+        chunk: &mut Option<Chunk>,
+        warning: &mut Option<Warning>,
+    ) -> Result<Token<'a>> {
+        map_err_at!(
+            Self::parse_text_impl(s, opt, chunk, warning),
+            s,
+            InvalidCharData
+        )
+    }
 
+    fn parse_text_impl(
+        s: &mut Stream<'a>,
+        opt: &Options,
+        chunk: &mut Option<Chunk>,
+        warning: &mut Option<Warning>,
+    ) -> StreamResult<Token<'a>> {
         let start = s.pos();
-        let value = s.consume_name()?.as_str();
+        let text_start = match *chunk {
+            Some(Chunk::Text(text_start)) => text_start,
+            _ => start,
+        };
 
-        let flag = match value {
-            "yes" => true,
-            "no" => false,
-            _ => {
-                let pos = s.gen_text_pos_from(start);
+        let text = Self::consume_chunk(s, b"<", opt)?;
+        Self::check_len(s, s.slice_back(text_start), opt.max_text_len)?;
 
-                return Err(StreamError::InvalidString("yes', 'no", pos));
+        // According to the spec, `]]>` must not appear inside a Text node.
+        // https://www.w3.org/TR/xml/#syntax
+        //
+        // Search for `>` first, since it's a bit faster than looking for `]]>`.
+        // `]]` can be in the previous chunk.
+        for (position, _) in text.as_str().match_indices('>') {
+            let idx = start + position;
+            if idx >= text_start + 2 && &s.span().as_bytes()[idx - 2..idx] == b"]]" {
+                if !opt.lenient {
+                    return Err(StreamError::InvalidCharacterData);
+                }
+
+                let pos = s.gen_text_pos_from(idx - 2);
+                *warning = Some(Warning::CdataEndInText(pos, idx - 2));
+                break;
             }
+        }
+
+        *chunk = if !s.at_end() && !s.starts_with(b"<") {
+            Some(Chunk::Text(text_start))
+        } else {
+            None
         };
 
-        s.consume_byte(quote)?;
+        if opt.whitespace_tokens && text.as_bytes().iter().all(|c| c.is_xml_space()) {
+            return Ok(Token::Whitespaces { text });
+        }
 
-        Ok(Some(flag))
+        Ok(Token::Text { text })
     }
 
-    fn parse_comment(s: &mut Stream<'a>) -> Result<Token<'a>> {
-        let start = s.pos();
-        Self::parse_comment_impl(s)
-            .map_err(|e| Error::InvalidComment(e, s.gen_text_pos_from(start)))
+    // Consumes chars until `terminator`, but not more than the text chunk size.
+    fn consume_chunk(
+        s: &mut Stream<'a>,
+        terminator: &[u8],
+        opt: &Options,
+    ) -> StreamResult<StrSpan<'a>> {
+        match opt.text_chunk_size {
+            Some(size) => s.consume_chars_until_max(terminator, size),
+            None => s.consume_chars_until(terminator),
+        }
     }
 
-    // '<!--' ((Char - '-') | ('-' (Char - '-')))* '-->'
-    fn parse_comment_impl(s: &mut Stream<'a>) -> StreamResult<Token<'a>> {
-        let start = s.pos();
-        s.advance(4);
-        let text = s.consume_chars(|s, c| !(c == '-' && s.starts_with(b"-->")))?;
-        s.skip_string(b"-->")?;
-
-        if text.as_str().contains("--") {
-            return Err(StreamError::InvalidCommentData);
-        }
+    // Skips everything up to and including the close tag of the current element.
+    fn skip_element_content(s: &mut Stream<'a>) -> Result<()> {
+        let mut depth = 1;
+        loop {
+            s.skip_bytes(|_, c| c != b'<');
+            if s.at_end() {
+                return Err(Error::UnexpectedEndOfDocument(s.gen_text_pos(), s.pos()));
+            }
 
-        if text.as_str().ends_with('-') {
-            return Err(StreamError::InvalidCommentEnd);
-        }
+            let terminator: &[u8] = if s.starts_with(b"<!--") {
+                b"-->"
+            } else if s.starts_with(b"<![CDATA[") {
+                b"]]>"
+            } else if s.starts_with(b"<?") {
+                b"?>"
+            } else if s.starts_with(b"</") {
+                depth -= 1;
+                b">"
+            } else if s.starts_with(b"<!") {
+                b">"
+            } else {
+                if !Self::skip_start_tag(s)? {
+                    depth += 1;
+                }
 
-        let span = s.slice_back(start);
+                continue;
+            };
 
-        Ok(Token::Comment { text, span })
-    }
+            s.skip_bytes(|s, c| !(c == terminator[0] && s.starts_with(terminator)));
+            if s.at_end() {
+                return Err(Error::UnexpectedEndOfDocument(s.gen_text_pos(), s.pos()));
+            }
 
-    fn parse_pi(s: &mut Stream<'a>) -> Result<Token<'a>> {
-        map_err_at!(Self::parse_pi_impl(s), s, InvalidPI)
+            s.advance(terminator.len());
+            if depth == 0 {
+                return Ok(());
+            }
+        }
     }
 
-    // PI       ::= '<?' PITarget (S (Char* - (Char* '?>' Char*)))? '?>'
-    // PITarget ::= Name - (('X' | 'x') ('M' | 'm') ('L' | 'l'))
-    fn parse_pi_impl(s: &mut Stream<'a>) -> StreamResult<Token<'a>> {
-        let start = s.pos();
-        s.advance(2);
-        let target = s.consume_name()?;
-        s.skip_spaces();
-        let content = s.consume_chars(|s, c| !(c == '?' && s.starts_with(b"?>")))?;
-        let content = if !content.is_empty() {
-            Some(content)
-        } else {
-            None
-        };
-
-        s.skip_string(b"?>")?;
+    // Skips a start tag and checks that it's an empty element tag.
+    fn skip_start_tag(s: &mut Stream<'a>) -> Result<bool> {
+        let mut quote = None;
+        let mut prev = 0;
+        while !s.at_end() {
+            let c = s.curr_byte_unchecked();
+            s.advance(1);
+            match quote {
+                Some(q) if c == q => quote = None,
+                Some(_) => {}
+                None => match c {
+                    b'"' | b'\'' => quote = Some(c),
+                    b'>' => return Ok(prev == b'/'),
+                    _ => {}
+                },
+            }
 
-        let span = s.slice_back(start);
+            if !c.is_xml_space() {
+                prev = c;
+            }
+        }
 
-        Ok(Token::ProcessingInstruction {
-            target,
-            content,
-            span,
-        })
+        Err(Error::UnexpectedEndOfDocument(s.gen_text_pos(), s.pos()))
     }
 
-    fn parse_doctype(s: &mut Stream<'a>) -> Result<Token<'a>> {
-        map_err_at!(Self::parse_doctype_impl(s), s, InvalidDoctype)
+    // Checks that the span is not longer than the limit.
+    fn check_len(s: &Stream<'a>, span: StrSpan<'a>, limit: Option<usize>) -> StreamResult<()> {
+        match limit {
+            Some(limit) if span.as_str().len() > limit => Err(StreamError::LengthLimitExceeded(
+                s.gen_text_pos_from(span.start()),
+                span.start(),
+            )),
+            _ => Ok(()),
+        }
     }
 
-    // doctypedecl ::= '<!DOCTYPE' S Name (S ExternalID)? S? ('[' intSubset ']' S?)? '>'
-    fn parse_doctype_impl(s: &mut Stream<'a>) -> StreamResult<Token<'a>> {
-        let start = s.pos();
-        s.advance(9);
-
-        s.consume_spaces()?;
-        let name = s.consume_name()?;
-        s.skip_spaces();
+    // Checks that each `&` in an attribute value starts a well-formed reference.
+    fn check_attribute_references(
+        s: &Stream<'a>,
+        value: StrSpan<'a>,
+        opt: &Options,
+    ) -> StreamResult<()> {
+        if !opt.validate_attribute_references {
+            return Ok(());
+        }
 
-        let external_id = Self::parse_external_id(s)?;
-        s.skip_spaces();
+        let mut vs = Stream::from(value.as_str());
+        loop {
+            vs.skip_bytes(|_, c| c != b'&');
+            if vs.at_end() {
+                return Ok(());
+            }
 
-        let c = s.curr_byte()?;
-        if c != b'[' && c != b'>' {
-            static EXPECTED: &[u8] = b"[>";
-            return Err(StreamError::InvalidCharMultiple(
-                c,
-                EXPECTED,
-                s.gen_text_pos(),
-            ));
+            let pos = vs.pos();
+            if vs.consume_reference().is_err() {
+                let offset = value.start() + pos;
+                return Err(StreamError::MalformedReference(
+                    s.gen_text_pos_from(offset),
+                    offset,
+                ));
+            }
         }
+    }
 
-        s.advance(1);
+    // Checks that an element start at the current depth will exceed the depth limit.
+    fn is_too_deep(opt: &Options, depth: usize) -> bool {
+        match opt.max_depth {
+            Some(max) => depth >= max,
+            None => false,
+        }
+    }
 
-        let span = s.slice_back(start);
-        if c == b'[' {
-            Ok(Token::DtdStart {
-                name,
-                external_id,
-                span,
-            })
-        } else {
-            Ok(Token::EmptyDtd {
-                name,
-                external_id,
-                span,
-            })
+    fn recover(&mut self) {
+        match self.state {
+            State::Dtd => {
+                // Stop at `]` too, so the DTD end will not be skipped.
+                self.stream.advance(1);
+                self.stream.skip_bytes(|_, c| c != b'<' && c != b']');
+                return;
+            }
+            State::Attributes => {
+                // Treat a broken start tag as an open one,
+                // since errors in empty elements are less common.
+                self.depth += 1;
+                self.state = State::Elements;
+            }
+            _ => {}
         }
+
+        self.stream.advance(1);
+        self.stream.skip_bytes(|_, c| c != b'<');
     }
 
-    // ExternalID ::= 'SYSTEM' S SystemLiteral | 'PUBLIC' S PubidLiteral S SystemLiteral
-    fn parse_external_id(s: &mut Stream<'a>) -> StreamResult<Option<ExternalId<'a>>> {
-        let v = if s.starts_with(b"SYSTEM") || s.starts_with(b"PUBLIC") {
-            let start = s.pos();
-            s.advance(6);
-            let id = s.slice_back(start);
+    // Handles `<a/ >`, which is a common typo.
+    fn skip_empty_element_end_spaces(
+        s: &mut Stream<'a>,
+        opt: &Options,
+        warning: &mut Option<Warning>,
+    ) -> StreamResult<()> {
+        if !s.starts_with_space() {
+            return Ok(());
+        }
 
-            s.consume_spaces()?;
-            let quote = s.consume_quote()?;
-            let literal1 = s.consume_bytes(|_, c| c != quote);
-            s.consume_byte(quote)?;
+        let mut s2 = *s;
+        s2.skip_spaces();
+        if s2.curr_byte().ok() != Some(b'>') {
+            // Will be reported as a generic error.
+            return Ok(());
+        }
 
-            let v = if id.as_str() == "SYSTEM" {
-                ExternalId::System(literal1)
-            } else {
-                s.consume_spaces()?;
-                let quote = s.consume_quote()?;
-                let literal2 = s.consume_bytes(|_, c| c != quote);
-                s.consume_byte(quote)?;
+        if !opt.lenient {
+            return Err(StreamError::SpaceInEmptyElementEnd(
+                s.gen_text_pos(),
+                s.pos(),
+            ));
+        }
 
-                ExternalId::Public(literal1, literal2)
-            };
+        *warning = Some(Warning::SpaceInEmptyElementEnd(s.gen_text_pos(), s.pos()));
+        *s = s2;
+        Ok(())
+    }
 
-            Some(v)
-        } else {
-            None
-        };
+    fn can_recover_prolog(&self, e: Error) -> bool {
+        self.prolog_retries > 0
+            && matches!(self.state, State::AfterDeclaration | State::AfterDtd)
+            && matches!(
+                e,
+                Error::UnknownToken(..)
+                    | Error::UnexpectedBom(..)
+                    | Error::MisplacedDeclaration(..)
+            )
+    }
 
-        Ok(v)
+    /// Returns a fingerprint of the current tokenizer's state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let text = "<a/><b/>";
+    /// let mut p1 = xmlparser::Tokenizer::from_fragment(text, 0..text.len());
+    /// p1.next(); // <a
+    /// p1.next(); // />
+    /// let p2 = xmlparser::Tokenizer::from_fragment(text, 4..text.len());
+    /// assert_eq!(p1.fingerprint(), p2.fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> TokenizerFingerprint {
+        TokenizerFingerprint {
+            state: self.state,
+            depth: self.depth,
+            offset: self.stream.pos(),
+        }
     }
 
-    fn parse_entity_decl(s: &mut Stream<'a>) -> Result<Token<'a>> {
-        map_err_at!(Self::parse_entity_decl_impl(s), s, InvalidEntity)
+    /// Saves the current parsing state.
+    ///
+    /// Options are not saved. Useful for speculative parsing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut p = xmlparser::Tokenizer::from("<a><b/></a>");
+    /// p.nth(1);
+    /// let checkpoint = p.checkpoint();
+    /// let peeked: Vec<_> = p.by_ref().take(2).map(|t| t.unwrap().span().as_str()).collect();
+    /// assert_eq!(peeked, ["<b", "/>"]);
+    ///
+    /// p.restore(checkpoint).unwrap();
+    /// assert_eq!(p.next().unwrap().unwrap().span().as_str(), "<b");
+    /// ```
+    pub fn checkpoint(&self) -> Checkpoint<'a> {
+        Checkpoint {
+            stream: self.stream,
+            state: self.state,
+            depth: self.depth,
+            prolog_retries: self.prolog_retries,
+            dtd_start: self.dtd_start,
+            tag_start: self.tag_start,
+            cond_depth: self.cond_depth,
+            ignore_section: self.ignore_section,
+            chunk: self.chunk,
+            warning: self.warning,
+            external_subset: self.external_subset,
+            main_stream: self.main_stream,
+            dtd_end: self.dtd_end,
+            #[cfg(feature = "alloc")]
+            open_elements: self.open_elements.clone(),
+        }
     }
 
-    // EntityDecl  ::= GEDecl | PEDecl
-    // GEDecl      ::= '<!ENTITY' S Name S EntityDef S? '>'
-    // PEDecl      ::= '<!ENTITY' S '%' S Name S PEDef S? '>'
-    fn parse_entity_decl_impl(s: &mut Stream<'a>) -> StreamResult<Token<'a>> {
-        let start = s.pos();
-        s.advance(8);
+    /// Restores the parsing state saved by `checkpoint`.
+    ///
+    /// The checkpoint must be created by a tokenizer of the same text and fragment.
+    ///
+    /// # Errors
+    ///
+    /// - `CheckpointMismatch` when the checkpoint was created for a different text.
+    ///   The tokenizer is not changed in this case.
+    pub fn restore(
+        &mut self,
+        checkpoint: Checkpoint<'a>,
+    ) -> core::result::Result<(), CheckpointMismatch> {
+        let stream = self.main_stream.unwrap_or(self.stream);
+        if !stream.is_same_text(&checkpoint.main_stream.unwrap_or(checkpoint.stream)) {
+            return Err(CheckpointMismatch);
+        }
 
-        s.consume_spaces()?;
+        self.stream = checkpoint.stream;
+        self.state = checkpoint.state;
+        self.depth = checkpoint.depth;
+        self.prolog_retries = checkpoint.prolog_retries;
+        self.dtd_start = checkpoint.dtd_start;
+        self.tag_start = checkpoint.tag_start;
+        self.cond_depth = checkpoint.cond_depth;
+        self.ignore_section = checkpoint.ignore_section;
+        self.chunk = checkpoint.chunk;
+        self.warning = checkpoint.warning;
+        self.external_subset = checkpoint.external_subset;
+        self.main_stream = checkpoint.main_stream;
+        self.dtd_end = checkpoint.dtd_end;
+        #[cfg(feature = "alloc")]
+        {
+            self.open_elements = checkpoint.open_elements;
+        }
 
-        let is_ge = if s.try_consume_byte(b'%') {
-            s.consume_spaces()?;
-            false
-        } else {
-            true
-        };
+        Ok(())
+    }
 
-        let name = s.consume_name()?;
-        s.consume_spaces()?;
-        let definition = Self::parse_entity_def(s, is_ge)?;
-        s.skip_spaces();
-        s.consume_byte(b'>')?;
+    /// Creates a tokenizer that continues parsing an edited text from a checkpoint.
+    ///
+    /// Allows incremental reparsing, which is useful for editors.
+    /// Instead of tokenizing the whole edited text again, save checkpoints
+    /// while parsing, like after each top-level element, and resume
+    /// from the last one that was created before the edited range.
+    ///
+    /// The text before the checkpoint must be unchanged. The checkpoint must be
+    /// created strictly before the edited range start, otherwise the previous token
+    /// could be affected by the edit. Options are copied from this tokenizer.
+    ///
+    /// Returns `None` when the checkpoint offset or an open element span
+    /// is past the end of `text` or not on a char boundary, or when the checkpoint
+    /// was created with an external subset set by `set_external_subset`
+    /// that is not fully tokenized yet. Parse the text from scratch in this case.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut p = xmlparser::Tokenizer::from("<r><a/><b/></r>");
+    /// p.nth(3); // <a/>
+    /// let checkpoint = p.checkpoint();
+    ///
+    /// // Replace `b` with `d`.
+    /// let text = "<r><a/><d/></r>";
+    /// let mut p = p.resume(&checkpoint, text).unwrap();
+    /// assert_eq!(p.next().unwrap().unwrap().span().as_str(), "<d");
+    /// ```
+    pub fn resume<'b>(&self, checkpoint: &Checkpoint<'_>, text: &'b str) -> Option<Tokenizer<'b>> {
+        // The external subset is a separate text, which cannot outlive the current one.
+        if checkpoint.main_stream.is_some() || checkpoint.external_subset.is_some() {
+            return None;
+        }
 
-        let span = s.slice_back(start);
+        let offset = checkpoint.offset();
+        if !text.is_char_boundary(offset) {
+            return None;
+        }
 
-        Ok(Token::EntityDeclaration {
-            name,
-            definition,
-            span,
+        #[cfg(feature = "alloc")]
+        {
+            // Open elements are before the checkpoint, but the text could be edited there too.
+            let is_valid = |span: StrSpan| text.get(span.range()).is_some();
+            let all_valid = checkpoint
+                .open_elements
+                .iter()
+                .all(|e| is_valid(e.prefix) && is_valid(e.local) && is_valid(e.span));
+            if !all_valid {
+                return None;
+            }
+        }
+
+        Some(Tokenizer {
+            stream: self.stream.with_text(text, offset),
+            state: checkpoint.state,
+            depth: checkpoint.depth,
+            fragment_parsing: self.fragment_parsing,
+            prolog_retries: checkpoint.prolog_retries,
+            dtd_start: checkpoint.dtd_start,
+            tag_start: checkpoint.tag_start,
+            cond_depth: checkpoint.cond_depth,
+            ignore_section: checkpoint.ignore_section,
+            chunk: checkpoint.chunk,
+            warning: None,
+            external_subset: None,
+            main_stream: None,
+            dtd_end: None,
+            opt: self.opt,
+            #[cfg(feature = "alloc")]
+            open_elements: checkpoint
+                .open_elements
+                .iter()
+                .map(|e| OpenElement {
+                    prefix: StrSpan::from_substr(text, e.prefix.start(), e.prefix.end()),
+                    local: StrSpan::from_substr(text, e.local.start(), e.local.end()),
+                    span: StrSpan::from_substr(text, e.span.start(), e.span.end()),
+                })
+                .collect(),
         })
     }
 
-    // EntityDef   ::= EntityValue | (ExternalID NDataDecl?)
-    // PEDef       ::= EntityValue | ExternalID
-    // EntityValue ::= '"' ([^%&"] | PEReference | Reference)* '"' |  "'" ([^%&']
-    //                             | PEReference | Reference)* "'"
-    // ExternalID  ::= 'SYSTEM' S SystemLiteral | 'PUBLIC' S PubidLiteral S SystemLiteral
-    // NDataDecl   ::= S 'NDATA' S Name
-    fn parse_entity_def(s: &mut Stream<'a>, is_ge: bool) -> StreamResult<EntityDefinition<'a>> {
-        let c = s.curr_byte()?;
-        match c {
-            b'"' | b'\'' => {
-                let quote = s.consume_quote()?;
-                let value = s.consume_bytes(|_, c| c != quote);
-                s.consume_byte(quote)?;
-
-                Ok(EntityDefinition::EntityValue(value))
-            }
-            b'S' | b'P' => {
-                if let Some(id) = Self::parse_external_id(s)? {
-                    if is_ge {
-                        s.skip_spaces();
-                        if s.starts_with(b"NDATA") {
-                            s.advance(5);
-                            s.consume_spaces()?;
-                            s.skip_name()?;
-                            // TODO: NDataDecl is not supported
-                        }
-                    }
+    /// Creates a tokenizer that will parse the prolog of `text` starting at `pos`.
+    ///
+    /// `pos` must point to a prolog markup start, but not to an XML declaration.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn from_prolog(text: &'a str, pos: usize, after_dtd: bool) -> Self {
+        let mut tokenizer = Tokenizer::from(text);
+        tokenizer.stream.advance(pos);
+        tokenizer.state = if after_dtd {
+            State::AfterDtd
+        } else {
+            State::AfterDeclaration
+        };
+        tokenizer
+    }
 
-                    Ok(EntityDefinition::ExternalId(id))
-                } else {
-                    Err(StreamError::InvalidExternalID)
-                }
-            }
-            _ => {
-                static EXPECTED: &[u8] = b"\"'SP";
-                let pos = s.gen_text_pos();
-                Err(StreamError::InvalidCharMultiple(c, EXPECTED, pos))
-            }
+    /// Returns a copy of the tokenizer that will parse `text` starting at `pos`.
+    ///
+    /// All the options and the parsing state are preserved, except for the open elements,
+    /// the warning and the external subset state, which are reset.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn with_text<'b>(&self, text: &'b str, pos: usize) -> Tokenizer<'b> {
+        Tokenizer {
+            stream: self.stream.with_text(text, pos),
+            state: self.state,
+            depth: self.depth,
+            fragment_parsing: self.fragment_parsing,
+            prolog_retries: self.prolog_retries,
+            dtd_start: self.dtd_start,
+            tag_start: self.tag_start,
+            cond_depth: self.cond_depth,
+            ignore_section: self.ignore_section,
+            chunk: self.chunk,
+            warning: None,
+            external_subset: None,
+            main_stream: None,
+            dtd_end: None,
+            opt: self.opt,
+            open_elements: Vec::new(),
         }
     }
 
-    fn consume_decl(s: &mut Stream) -> StreamResult<()> {
-        s.skip_bytes(|_, c| c != b'>');
-        s.consume_byte(b'>')?;
-        Ok(())
+    /// Returns a copy of the tokenizer's stream.
+    pub fn stream(&self) -> Stream<'a> {
+        self.stream
     }
 
-    fn parse_cdata(s: &mut Stream<'a>) -> Result<Token<'a>> {
-        map_err_at!(Self::parse_cdata_impl(s), s, InvalidCdata)
+    /// Returns a warning produced while parsing the last returned token.
+    ///
+    /// Warnings are produced only in the lenient mode.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut p = xmlparser::Tokenizer::from("<a/ >");
+    /// p.set_lenient(true);
+    /// p.nth(1).unwrap().unwrap();
+    /// assert_eq!(p.warning().unwrap().to_string(), "a space between '/' and '>' at 1:4");
+    /// ```
+    pub fn warning(&self) -> Option<Warning> {
+        self.warning
     }
 
-    // CDSect  ::= CDStart CData CDEnd
-    // CDStart ::= '<![CDATA['
-    // CData   ::= (Char* - (Char* ']]>' Char*))
-    // CDEnd   ::= ']]>'
-    fn parse_cdata_impl(s: &mut Stream<'a>) -> StreamResult<Token<'a>> {
-        let start = s.pos();
-        s.advance(9);
-        let text = s.consume_chars(|s, c| !(c == ']' && s.starts_with(b"]]>")))?;
-        s.skip_string(b"]]>")?;
-        let span = s.slice_back(start);
-        Ok(Token::Cdata { text, span })
+    /// Returns a reference to the tokenizer's stream.
+    pub fn stream_ref(&self) -> &Stream<'a> {
+        &self.stream
     }
 
-    fn parse_element_start(s: &mut Stream<'a>) -> Result<Token<'a>> {
-        map_err_at!(Self::parse_element_start_impl(s), s, InvalidElement)
+    /// Returns the current byte position in the text.
+    ///
+    /// Points right after the last returned token.
+    /// After an error, points to the end of the text, unless the error was recovered.
+    /// Use `Error::offset` to get the error position instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut p = xmlparser::Tokenizer::from("<a b='c'/>");
+    /// p.nth(1).unwrap().unwrap(); // b='c'
+    /// assert_eq!(p.position(), 8);
+    /// ```
+    pub fn position(&self) -> usize {
+        self.stream.pos()
     }
 
-    // '<' Name (S Attribute)* S? '>'
-    fn parse_element_start_impl(s: &mut Stream<'a>) -> StreamResult<Token<'a>> {
-        let start = s.pos();
-        s.advance(1);
-        let (prefix, local) = s.consume_qname()?;
-        let span = s.slice_back(start);
+    /// Returns an iterator that yields tokens along with their source text.
+    ///
+    /// The source text covers the whole token span, including delimiters.
+    /// Useful for pass-through transformers that mostly copy the input.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut iter = xmlparser::Tokenizer::from("<a><!-- c --></a>").with_source();
+    /// assert_eq!(iter.nth(2).unwrap().unwrap().1, "<!-- c -->");
+    /// ```
+    pub fn with_source(self) -> WithSource<'a> {
+        WithSource::new(self)
+    }
 
-        Ok(Token::ElementStart {
-            prefix,
-            local,
-            span,
-        })
+    /// Returns an iterator that skips comments.
+    ///
+    /// See [`TokenFilter`](struct.TokenFilter.html) for other filters.
+    pub fn skip_comments(self) -> TokenFilter<'a> {
+        TokenFilter::new(self).skip_comments()
     }
 
-    fn parse_close_element(s: &mut Stream<'a>) -> Result<Token<'a>> {
-        map_err_at!(Self::parse_close_element_impl(s), s, InvalidElement)
+    /// Returns an iterator that skips processing instructions.
+    ///
+    /// See [`TokenFilter`](struct.TokenFilter.html) for other filters.
+    pub fn skip_pis(self) -> TokenFilter<'a> {
+        TokenFilter::new(self).skip_pis()
     }
 
-    // '</' Name S? '>'
-    fn parse_close_element_impl(s: &mut Stream<'a>) -> StreamResult<Token<'a>> {
-        let start = s.pos();
-        s.advance(2);
+    /// Returns an iterator that skips whitespace-only text tokens.
+    ///
+    /// See [`TokenFilter`](struct.TokenFilter.html) for other filters.
+    pub fn significant_whitespace_only(self) -> TokenFilter<'a> {
+        TokenFilter::new(self).significant_whitespace_only()
+    }
 
-        let (prefix, tag_name) = s.consume_qname()?;
-        s.skip_spaces();
-        s.consume_byte(b'>')?;
+    /// Returns an iterator that tokenizes external DTD subsets inline.
+    ///
+    /// `loader` is called for each `DtdStart` and `EmptyDtd` token with an external ID.
+    /// See [`set_external_subset`](#method.set_external_subset) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{ExternalId, StrSpan, Tokenizer};
+    ///
+    /// let text = "<!DOCTYPE a SYSTEM 'a.dtd'><a/>";
+    /// let loader = |_: StrSpan, id: ExternalId| match id {
+    ///     ExternalId::System(uri) if uri == "a.dtd" => Some("<!ELEMENT a EMPTY>"),
+    ///     _ => None,
+    /// };
+    ///
+    /// let spans: Vec<_> = Tokenizer::from(text)
+    ///     .with_external_subset_loader(loader)
+    ///     .map(|t| t.unwrap().span().as_str())
+    ///     .collect();
+    /// assert_eq!(spans, ["<!DOCTYPE a SYSTEM 'a.dtd'>", "<!ELEMENT a EMPTY>", "<a", "/>"]);
+    /// ```
+    pub fn with_external_subset_loader<L: ExternalSubsetLoader<'a>>(
+        self,
+        loader: L,
+    ) -> WithExternalSubsets<'a, L> {
+        WithExternalSubsets::new(self, loader)
+    }
 
-        let span = s.slice_back(start);
+    /// Returns an iterator that reports the parsing progress.
+    ///
+    /// `callback` is called with the current byte position each time
+    /// the tokenizer has advanced by at least `interval` bytes since the last call,
+    /// and once at the end of the text, unless it was already reported.
+    /// Useful for rendering progress bars for large documents.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let text = "<a><b/><c/></a>";
+    /// let mut progress = Vec::new();
+    /// let count = xmlparser::Tokenizer::from(text)
+    ///     .with_progress(5, |pos| progress.push(pos))
+    ///     .count();
+    /// assert_eq!(count, 7);
+    /// assert_eq!(progress, [5, 11, 15]);
+    /// ```
+    pub fn with_progress<F: FnMut(usize)>(
+        self,
+        interval: usize,
+        callback: F,
+    ) -> WithProgress<'a, F> {
+        WithProgress::new(self, interval, callback)
+    }
 
-        Ok(Token::ElementEnd {
-            end: ElementEnd::Close(prefix, tag_name),
-            span,
-        })
+    /// Returns an iterator over the prolog tokens.
+    ///
+    /// The iterator ends before the root element start,
+    /// so the tokenizer can be used to continue parsing afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{Token, Tokenizer};
+    ///
+    /// let mut p = Tokenizer::from("<?xml-stylesheet href='a.css'?><svg/>");
+    /// assert_eq!(p.prolog().count(), 1);
+    /// match p.next() {
+    ///     Some(Ok(Token::ElementStart { local, .. })) => assert_eq!(local.as_str(), "svg"),
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn prolog(&mut self) -> Prolog<'_, 'a> {
+        Prolog::new(self)
     }
 
-    // Name Eq AttValue
-    fn parse_attribute(s: &mut Stream<'a>) -> StreamResult<Token<'a>> {
-        let attr_start = s.pos();
-        let has_space = s.starts_with_space();
-        s.skip_spaces();
+    /// Returns an iterator over the current start tag attributes.
+    ///
+    /// Must be called right after `ElementStart`. Otherwise, the iterator will be empty.
+    /// The iterator consumes the following `ElementEnd` token as well,
+    /// which is available via `Attributes::element_end` afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{ElementEnd, Tokenizer};
+    ///
+    /// let mut p = Tokenizer::from("<a b='1' c='2'/>");
+    /// p.next();
+    /// let mut attrs = p.attributes();
+    /// let names: Vec<_> = attrs.by_ref().map(|a| a.unwrap().local.as_str()).collect();
+    /// assert_eq!(names, ["b", "c"]);
+    /// assert_eq!(attrs.element_end().unwrap().0, ElementEnd::Empty);
+    /// ```
+    pub fn attributes(&mut self) -> Attributes<'_, 'a> {
+        Attributes::new(self)
+    }
 
-        if let Ok(c) = s.curr_byte() {
-            let start = s.pos();
+    /// Skips the rest of the innermost open element, including its close tag.
+    ///
+    /// When called right after `ElementStart`, the whole element will be skipped.
+    ///
+    /// Unlike regular parsing, the skipped content is not validated.
+    /// Only comments, CDATA, processing instructions and quoted attribute values
+    /// are recognized, so the nested elements are counted correctly.
+    /// Close tag names are not checked.
+    ///
+    /// Does nothing when there are no open elements.
+    ///
+    /// # Errors
+    ///
+    /// - Any error in the remaining attributes of the current element.
+    /// - `UnexpectedEndOfDocument` when the element is not closed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{ElementEnd, Token, Tokenizer};
+    ///
+    /// let mut p = Tokenizer::from("<a><b x='>'><c/><!-- </b> --></b><d/></a>");
+    /// p.nth(2).unwrap().unwrap(); // <b
+    /// p.skip_current_element().unwrap();
+    /// match p.next().unwrap().unwrap() {
+    ///     Token::ElementStart { local, .. } => assert_eq!(local.as_str(), "d"),
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn skip_current_element(&mut self) -> Result<()> {
+        if let Some(Chunk::Cdata(_)) = self.chunk.take() {
+            // The rest of the CDATA content must not be treated as markup.
+            let s = &mut self.stream;
+            s.skip_bytes(|s, c| !(c == b']' && s.starts_with(b"]]>")));
+            if !s.at_end() {
+                s.advance(3);
+            }
+        }
 
-            match c {
-                b'/' => {
-                    s.advance(1);
-                    s.consume_byte(b'>')?;
-                    let span = s.slice_back(start);
-                    return Ok(Token::ElementEnd {
+        if self.state == State::Attributes {
+            // Parse the rest of the start tag as usual.
+            loop {
+                match self.next() {
+                    Some(Ok(Token::ElementEnd {
                         end: ElementEnd::Empty,
-                        span,
-                    });
-                }
-                b'>' => {
-                    s.advance(1);
-                    let span = s.slice_back(start);
-                    return Ok(Token::ElementEnd {
-                        end: ElementEnd::Open,
-                        span,
-                    });
+                        ..
+                    })) => return Ok(()),
+                    Some(Ok(Token::ElementEnd { .. })) => break,
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return Err(e),
+                    None => return Ok(()),
                 }
-                _ => {}
             }
         }
 
-        if !has_space {
-            if !s.at_end() {
-                return Err(StreamError::InvalidSpace(
-                    s.curr_byte_unchecked(),
-                    s.gen_text_pos_from(attr_start),
-                ));
-            } else {
-                return Err(StreamError::UnexpectedEndOfStream);
+        if self.state != State::Elements || self.depth == 0 {
+            return Ok(());
+        }
+
+        if let Err(e) = Self::skip_element_content(&mut self.stream) {
+            self.stream.jump_to_end();
+            self.state = State::End;
+            return Err(e);
+        }
+
+        self.depth -= 1;
+
+        #[cfg(feature = "alloc")]
+        {
+            if self.opt.track_tags {
+                self.open_elements.pop();
             }
         }
 
-        let start = s.pos();
+        if self.depth == 0 && !self.fragment_parsing {
+            self.state = State::AfterElements;
+        }
 
-        let (prefix, local) = s.consume_qname()?;
-        s.consume_eq()?;
-        let quote = s.consume_quote()?;
-        let quote_c = quote as char;
-        // The attribute value must not contain the < character.
-        let value = s.consume_chars(|_, c| c != quote_c && c != '<')?;
-        s.consume_byte(quote)?;
-        let span = s.slice_back(start);
+        Ok(())
+    }
 
-        Ok(Token::Attribute {
-            prefix,
-            local,
-            value,
-            span,
-        })
+    pub(crate) fn in_start_tag(&self) -> bool {
+        self.state == State::Attributes
     }
 
-    fn parse_text(s: &mut Stream<'a>) -> Result<Token<'a>> {
-        map_err_at!(Self::parse_text_impl(s), s, InvalidCharData)
+    #[inline]
+    fn tracks_tags(&self) -> bool {
+        #[cfg(feature = "alloc")]
+        {
+            self.opt.track_tags
+        }
+
+        #[cfg(not(feature = "alloc"))]
+        {
+            false
+        }
     }
 
-    fn parse_text_impl(s: &mut Stream<'a>) -> StreamResult<Token<'a>> {
-        let text = s.consume_chars(|_, c| c != '<')?;
+    // Handles errors and the end of the document.
+    //
+    // Kept out of `next`, so the hot path stays small.
+    #[inline(never)]
+    fn finish_token(&mut self, mut t: Option<Result<Token<'a>>>) -> Option<Result<Token<'a>>> {
+        if let Some(Err(Error::UnknownToken(_, pos, offset))) = t {
+            if offset == self.stream.pos() && self.stream.starts_with(BOM) {
+                t = Some(Err(Error::UnexpectedBom(pos, offset)));
+            }
+        }
 
-        // According to the spec, `]]>` must not appear inside a Text node.
-        // https://www.w3.org/TR/xml/#syntax
-        //
-        // Search for `>` first, since it's a bit faster than looking for `]]>`.
-        if let Some(position) = text.as_str().find('>') {
-            if text.as_str()[position.saturating_sub(2)..].starts_with("]]>") {
-                return Err(StreamError::InvalidCharacterData);
+        #[cfg(feature = "alloc")]
+        {
+            if self.opt.track_tags {
+                if t.is_none() && !self.open_elements.is_empty() && self.state != State::End {
+                    let pos = self.stream.pos();
+                    t = Some(Err(Error::UnexpectedEndOfDocument(
+                        self.stream.gen_text_pos(),
+                        pos,
+                    )));
+                }
+
+                self.track_tags(&t);
             }
         }
 
-        Ok(Token::Text { text })
+        if let Some(Err(e)) = t {
+            self.chunk = None;
+
+            if self.can_recover_prolog(e) {
+                self.prolog_retries -= 1;
+                // Skip at least one byte, since we could be at `<` already.
+                self.stream.advance(1);
+                self.stream.skip_bytes(|_, c| c != b'<');
+            } else if self.opt.recover && !self.stream.at_end() {
+                self.recover();
+            } else {
+                self.stream.jump_to_end();
+                self.state = State::End;
+            }
+        }
+
+        t
     }
 
-    /// Returns a copy of the tokenizer's stream.
-    pub fn stream(&self) -> Stream<'a> {
-        self.stream
+    pub(crate) fn at_prolog_end(&self) -> bool {
+        match self.state {
+            State::Declaration | State::AfterDeclaration | State::AfterDtd => {
+                // Spaces are skipped by the tokenizer, so we have to look past them.
+                let mut s = self.stream;
+                if self.state == State::Declaration
+                    && s.pos() == 0
+                    && self.opt.bom == BomHandling::Skip
+                    && s.starts_with(BOM)
+                {
+                    s.advance(BOM.len());
+                }
+
+                s.skip_spaces();
+                s.starts_with(b"<") && !s.starts_with(b"<!") && !s.starts_with(b"<?")
+            }
+            State::Dtd => false,
+            State::Elements | State::Attributes | State::AfterElements | State::End => true,
+        }
     }
 }
 
@@ -1078,16 +3966,50 @@ impl<'a> Iterator for Tokenizer<'a> {
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
+        self.warning = None;
+
+        // Positions of the current token errors are always after the checkpoint.
+        self.stream.sync_text_pos();
+
         let mut t = None;
-        while !self.stream.at_end() && self.state != State::End && t.is_none() {
+        while (!self.stream.at_end() || self.main_stream.is_some())
+            && self.state != State::End
+            && t.is_none()
+        {
             t = self.parse_next_impl();
         }
 
-        if let Some(Err(_)) = t {
-            self.stream.jump_to_end();
-            self.state = State::End;
+        match t {
+            Some(Ok(_)) if !self.tracks_tags() => t,
+            _ => self.finish_token(t),
         }
-
-        t
     }
 }
+
+/// Returns the number of tokens in the text, including the error token.
+///
+/// The text is parsed with the default options. Intended for fuzzing and crash triage.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(xmlparser::count_tokens("<a b='c'/>"), 3);
+/// assert_eq!(xmlparser::count_tokens("<a b=/>"), 2);
+/// ```
+pub fn count_tokens(text: &str) -> usize {
+    Tokenizer::from(text).count()
+}
+
+/// Returns the first error in the text.
+///
+/// The text is parsed with the default options. Intended for fuzzing and crash triage.
+///
+/// # Examples
+///
+/// ```
+/// assert!(xmlparser::first_error("<a/>").is_none());
+/// assert_eq!(xmlparser::first_error("<a/><b/>").unwrap().offset(), 4);
+/// ```
+pub fn first_error(text: &str) -> Option<Error> {
+    Tokenizer::from(text).find_map(|t| t.err())
+}
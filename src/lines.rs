@@ -0,0 +1,73 @@
+use alloc::vec::Vec;
+
+use crate::TextPos;
+
+/// An index of line starts.
+///
+/// Built once from the text and converts byte offsets into `TextPos`
+/// using a binary search over the lines. Only the current line characters
+/// are counted to find the column.
+///
+/// Can be passed to [`Tokenizer::set_line_index`] to make error positions cheap.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::{LineIndex, TextPos};
+///
+/// let index = LineIndex::new("<a>\n  <b/>\n</a>");
+/// assert_eq!(index.line_count(), 3);
+/// assert_eq!(index.text_pos(6), TextPos::new(2, 3));
+/// assert_eq!(index.text_pos(9999), TextPos::new(3, 5));
+/// ```
+///
+/// [`Tokenizer::set_line_index`]: struct.Tokenizer.html#method.set_line_index
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct LineIndex<'a> {
+    text: &'a str,
+    // A byte offset of each line start. Always starts with 0.
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    /// Builds a new index.
+    pub fn new(text: &'a str) -> Self {
+        let mut line_starts = Vec::new();
+        line_starts.push(0);
+        for (i, c) in text.bytes().enumerate() {
+            if c == b'\n' {
+                line_starts.push(i + 1);
+            }
+        }
+
+        LineIndex { text, line_starts }
+    }
+
+    /// Returns the indexed text.
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Returns the number of lines.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Converts a byte offset into a text position.
+    ///
+    /// Offsets past the end of the text are clamped.
+    ///
+    /// # Panics
+    ///
+    /// - When `offset` is not on a char boundary.
+    pub fn text_pos(&self, offset: usize) -> TextPos {
+        let offset = core::cmp::min(offset, self.text.len());
+        let row = match self.line_starts.binary_search(&offset) {
+            Ok(idx) => idx,
+            Err(idx) => idx - 1,
+        };
+
+        let col = self.text[self.line_starts[row]..offset].chars().count() + 1;
+        TextPos::new(row as u32 + 1, col as u32)
+    }
+}
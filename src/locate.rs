@@ -0,0 +1,148 @@
+use crate::{ElementEnd, Token, Tokenizer};
+
+/// A syntactic context of a byte offset.
+///
+/// See [`locate`](fn.locate.html).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum OffsetContext {
+    /// A start or close tag markup outside of names and attributes,
+    /// like `<`, `=`, spaces, `>` or `/>`.
+    Tag,
+    /// An element name in a start or close tag.
+    ElementName,
+    /// An attribute name.
+    AttributeName,
+    /// An attribute value, including quotes.
+    AttributeValue,
+    /// A text.
+    Text,
+    /// A CDATA section.
+    Cdata,
+    /// A comment.
+    Comment,
+    /// A processing instruction or an XML declaration.
+    ProcessingInstruction,
+    /// A DTD.
+    Dtd,
+    /// Spaces outside of elements, a BOM, a malformed markup or an out of bounds offset.
+    None,
+}
+
+/// Returns the syntactic context of a byte offset in the text.
+///
+/// The text is tokenized only up to the offset. Since the text is likely to be
+/// incomplete while being edited, partial parsing and errors recovery are enabled.
+///
+/// Useful for IDE features, like syntax highlighting and completion.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::{locate, OffsetContext};
+///
+/// let text = "<a b='c'>text<!-- comment --></a>";
+/// assert_eq!(locate(text, 1), OffsetContext::ElementName);
+/// assert_eq!(locate(text, 3), OffsetContext::AttributeName);
+/// assert_eq!(locate(text, 6), OffsetContext::AttributeValue);
+/// assert_eq!(locate(text, 8), OffsetContext::Tag);
+/// assert_eq!(locate(text, 10), OffsetContext::Text);
+/// assert_eq!(locate(text, 20), OffsetContext::Comment);
+/// assert_eq!(locate(text, 31), OffsetContext::ElementName);
+/// ```
+pub fn locate(text: &str, offset: usize) -> OffsetContext {
+    let mut p = Tokenizer::from(text);
+    p.set_partial(true);
+    p.set_recover(true);
+
+    let mut in_tag = false;
+    for token in p {
+        let token = match token {
+            Ok(token) => token,
+            Err(e) => {
+                if e.offset() > offset {
+                    break;
+                }
+
+                in_tag = false;
+                continue;
+            }
+        };
+
+        let span = token.span();
+        if span.start() > offset {
+            break;
+        }
+
+        match token {
+            Token::ElementStart { .. } => in_tag = true,
+            Token::ElementEnd { .. } => in_tag = false,
+            _ => {}
+        }
+
+        if offset < span.end() {
+            return classify(token, offset);
+        }
+    }
+
+    // The offset is between tokens.
+    if in_tag {
+        OffsetContext::Tag
+    } else {
+        OffsetContext::None
+    }
+}
+
+fn classify(token: Token, offset: usize) -> OffsetContext {
+    match token {
+        Token::Declaration { .. } | Token::ProcessingInstruction { .. } => {
+            OffsetContext::ProcessingInstruction
+        }
+        Token::Comment { .. }
+        | Token::ConditionalCommentStart { .. }
+        | Token::ConditionalCommentEnd { .. } => OffsetContext::Comment,
+        Token::DtdStart { .. }
+        | Token::EmptyDtd { .. }
+        | Token::EntityDeclaration { .. }
+        | Token::ElementDeclaration { .. }
+        | Token::AttlistDeclaration { .. }
+        | Token::NotationDeclaration { .. }
+        | Token::ConditionalSectionStart { .. }
+        | Token::ConditionalSectionEnd { .. }
+        | Token::ParameterEntityReference { .. }
+        | Token::DtdEnd { .. }
+        | Token::Raw { .. } => OffsetContext::Dtd,
+        Token::ElementStart { span, .. } => {
+            // Skip `<`.
+            if offset > span.start() {
+                OffsetContext::ElementName
+            } else {
+                OffsetContext::Tag
+            }
+        }
+        Token::Attribute { local, value, .. } => {
+            // Quotes are not a part of the value span.
+            if offset + 1 >= value.start() && offset <= value.end() {
+                OffsetContext::AttributeValue
+            } else if offset < local.end() {
+                OffsetContext::AttributeName
+            } else {
+                OffsetContext::Tag
+            }
+        }
+        Token::ElementEnd {
+            end: ElementEnd::Close(_, local),
+            span,
+        } => {
+            // Skip `</`.
+            if offset >= span.start() + 2 && offset < local.end() {
+                OffsetContext::ElementName
+            } else {
+                OffsetContext::Tag
+            }
+        }
+        Token::ElementEnd { .. } => OffsetContext::Tag,
+        Token::Text { .. } | Token::Whitespaces { .. } => OffsetContext::Text,
+        Token::Cdata { .. } => OffsetContext::Cdata,
+        Token::Bom { .. } => OffsetContext::None,
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{Stream, StreamError, XmlByteExt, XmlStreamExt};
+
+/// An iterator over a list of numbers.
+///
+/// Numbers can be separated by whitespaces and/or commas,
+/// which covers coordinate lists from formats like GPX, KML and SVG.
+/// Parsing is performed directly on the input string without allocations.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::NumberList;
+///
+/// let mut coords = [0.0; 6];
+/// let mut list = NumberList::from("-122.08,37.42,0 -122.09, 37.43,0");
+/// assert_eq!(list.fill(&mut coords), Ok(6));
+/// assert_eq!(coords, [-122.08, 37.42, 0.0, -122.09, 37.43, 0.0]);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct NumberList<'a> {
+    stream: Stream<'a>,
+}
+
+impl<'a> From<&'a str> for NumberList<'a> {
+    #[inline]
+    fn from(text: &'a str) -> Self {
+        NumberList {
+            stream: Stream::from(text),
+        }
+    }
+}
+
+impl<'a> NumberList<'a> {
+    /// Parses numbers into the provided buffer.
+    ///
+    /// Stops when the buffer is full or when there are no more numbers.
+    /// Returns the amount of parsed numbers.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidNumber`
+    pub fn fill(&mut self, out: &mut [f64]) -> Result<usize, StreamError> {
+        let mut count = 0;
+        for slot in out.iter_mut() {
+            match self.next() {
+                Some(n) => *slot = n?,
+                None => break,
+            }
+
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    fn skip_separators(&mut self) {
+        let s = &mut self.stream;
+        s.skip_spaces();
+        if s.try_consume_byte(b',') {
+            s.skip_spaces();
+        }
+    }
+
+    // [+-]? [0-9]* ('.' [0-9]*)? ([eE] [+-]? [0-9]+)?
+    fn parse_number(&mut self) -> Result<f64, StreamError> {
+        let s = &mut self.stream;
+        let start = s.pos();
+
+        if s.curr_byte()? == b'+' || s.curr_byte()? == b'-' {
+            s.advance(1);
+        }
+
+        s.skip_bytes(|_, c| c.is_xml_digit());
+        if s.try_consume_byte(b'.') {
+            s.skip_bytes(|_, c| c.is_xml_digit());
+        }
+
+        if let Ok(c) = s.curr_byte() {
+            if c == b'e' || c == b'E' {
+                // Do not confuse an exponent with a following text, like `1em`.
+                if let Ok(next) = s.next_byte() {
+                    if next.is_xml_digit() || next == b'+' || next == b'-' {
+                        s.advance(2);
+                        s.skip_bytes(|_, c| c.is_xml_digit());
+                    }
+                }
+            }
+        }
+
+        let n = s.slice_back(start).as_str().parse::<f64>();
+        n.map_err(|_| StreamError::InvalidNumber(s.gen_text_pos_from(start), start))
+    }
+}
+
+impl<'a> Iterator for NumberList<'a> {
+    type Item = Result<f64, StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.skip_separators();
+        if self.stream.at_end() {
+            return None;
+        }
+
+        let n = self.parse_number();
+        if n.is_err() {
+            self.stream.jump_to_end();
+        }
+
+        Some(n)
+    }
+}
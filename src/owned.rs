@@ -0,0 +1,517 @@
+use alloc::string::String;
+use core::fmt;
+use core::ops::{Deref, Range};
+
+use crate::{
+    ContentSpec, ElementEnd, EntityDefinition, ExternalId, NotationId, RawKind, StrSpan, Token,
+};
+
+/// An owned version of [`StrSpan`].
+///
+/// [`StrSpan`]: struct.StrSpan.html
+#[derive(Clone, PartialEq, Eq, Hash, Default)]
+pub struct OwnedStrSpan {
+    text: String,
+    start: usize,
+}
+
+impl OwnedStrSpan {
+    /// Returns the start position of the span.
+    #[inline]
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Returns the end position of the span.
+    #[inline]
+    pub fn end(&self) -> usize {
+        self.start + self.text.len()
+    }
+
+    /// Returns the range of the span.
+    #[inline]
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end()
+    }
+
+    /// Returns the span as a string slice
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the span as a `StrSpan`.
+    #[inline]
+    pub fn as_span(&self) -> StrSpan<'_> {
+        StrSpan::from(self.text.as_str()).shift(self.start)
+    }
+}
+
+impl From<StrSpan<'_>> for OwnedStrSpan {
+    fn from(span: StrSpan) -> Self {
+        OwnedStrSpan {
+            text: String::from(span.as_str()),
+            start: span.start(),
+        }
+    }
+}
+
+impl fmt::Debug for OwnedStrSpan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "OwnedStrSpan({:?} {}..{})",
+            self.as_str(),
+            self.start(),
+            self.end()
+        )
+    }
+}
+
+impl fmt::Display for OwnedStrSpan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Deref for OwnedStrSpan {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.text
+    }
+}
+
+/// An owned version of [`Token`].
+///
+/// Available only with the `alloc` feature.
+///
+/// Spans keep their positions in the original text.
+/// Attribute list definitions are stored as a raw span.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::{OwnedToken, Tokenizer};
+///
+/// let tokens: Vec<OwnedToken> = {
+///     let text = String::from("<a b='c'/>");
+///     Tokenizer::from(text.as_str()).map(|t| t.unwrap().to_owned()).collect()
+/// };
+///
+/// match tokens[1] {
+///     OwnedToken::Attribute { ref value, .. } => assert_eq!(value.range(), 6..7),
+///     _ => unreachable!(),
+/// }
+/// ```
+///
+/// [`Token`]: enum.Token.html
+#[allow(missing_docs)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum OwnedToken {
+    Declaration {
+        version: OwnedStrSpan,
+        encoding: Option<OwnedStrSpan>,
+        standalone: Option<bool>,
+        span: OwnedStrSpan,
+    },
+    ProcessingInstruction {
+        target: OwnedStrSpan,
+        content: Option<OwnedStrSpan>,
+        span: OwnedStrSpan,
+    },
+    Comment {
+        text: OwnedStrSpan,
+        span: OwnedStrSpan,
+        unterminated: bool,
+    },
+    DtdStart {
+        name: OwnedStrSpan,
+        external_id: Option<OwnedExternalId>,
+        span: OwnedStrSpan,
+    },
+    EmptyDtd {
+        name: OwnedStrSpan,
+        external_id: Option<OwnedExternalId>,
+        span: OwnedStrSpan,
+    },
+    EntityDeclaration {
+        name: OwnedStrSpan,
+        definition: OwnedEntityDefinition,
+        span: OwnedStrSpan,
+    },
+    ElementDeclaration {
+        name: OwnedStrSpan,
+        content_spec: OwnedContentSpec,
+        span: OwnedStrSpan,
+    },
+    AttlistDeclaration {
+        name: OwnedStrSpan,
+        definitions: OwnedStrSpan,
+        span: OwnedStrSpan,
+    },
+    NotationDeclaration {
+        name: OwnedStrSpan,
+        external_id: OwnedNotationId,
+        span: OwnedStrSpan,
+    },
+    ConditionalSectionStart {
+        ignore: bool,
+        span: OwnedStrSpan,
+    },
+    ConditionalSectionEnd {
+        span: OwnedStrSpan,
+    },
+    ParameterEntityReference {
+        name: OwnedStrSpan,
+        span: OwnedStrSpan,
+    },
+    DtdEnd {
+        span: OwnedStrSpan,
+        internal_subset: OwnedStrSpan,
+    },
+    ElementStart {
+        prefix: OwnedStrSpan,
+        local: OwnedStrSpan,
+        span: OwnedStrSpan,
+    },
+    Attribute {
+        prefix: OwnedStrSpan,
+        local: OwnedStrSpan,
+        value: OwnedStrSpan,
+        span: OwnedStrSpan,
+    },
+    ElementEnd {
+        end: OwnedElementEnd,
+        span: OwnedStrSpan,
+    },
+    Text {
+        text: OwnedStrSpan,
+    },
+    Cdata {
+        text: OwnedStrSpan,
+        span: OwnedStrSpan,
+        unterminated: bool,
+    },
+    ConditionalCommentStart {
+        condition: OwnedStrSpan,
+        span: OwnedStrSpan,
+    },
+    ConditionalCommentEnd {
+        span: OwnedStrSpan,
+    },
+    Bom {
+        span: OwnedStrSpan,
+    },
+    Whitespaces {
+        text: OwnedStrSpan,
+    },
+    Raw {
+        kind: RawKind,
+        span: OwnedStrSpan,
+    },
+}
+
+impl OwnedToken {
+    /// Returns the token span.
+    pub fn span(&self) -> &OwnedStrSpan {
+        match self {
+            OwnedToken::Declaration { span, .. } => span,
+            OwnedToken::ProcessingInstruction { span, .. } => span,
+            OwnedToken::Comment { span, .. } => span,
+            OwnedToken::DtdStart { span, .. } => span,
+            OwnedToken::EmptyDtd { span, .. } => span,
+            OwnedToken::EntityDeclaration { span, .. } => span,
+            OwnedToken::ElementDeclaration { span, .. } => span,
+            OwnedToken::AttlistDeclaration { span, .. } => span,
+            OwnedToken::NotationDeclaration { span, .. } => span,
+            OwnedToken::ConditionalSectionStart { span, .. } => span,
+            OwnedToken::ConditionalSectionEnd { span } => span,
+            OwnedToken::ParameterEntityReference { span, .. } => span,
+            OwnedToken::DtdEnd { span, .. } => span,
+            OwnedToken::ElementStart { span, .. } => span,
+            OwnedToken::Attribute { span, .. } => span,
+            OwnedToken::ElementEnd { span, .. } => span,
+            OwnedToken::Text { text } => text,
+            OwnedToken::Cdata { span, .. } => span,
+            OwnedToken::ConditionalCommentStart { span, .. } => span,
+            OwnedToken::ConditionalCommentEnd { span } => span,
+            OwnedToken::Bom { span } => span,
+            OwnedToken::Whitespaces { text } => text,
+            OwnedToken::Raw { span, .. } => span,
+        }
+    }
+}
+
+impl From<Token<'_>> for OwnedToken {
+    fn from(token: Token) -> Self {
+        let o = OwnedStrSpan::from;
+        match token {
+            Token::Declaration {
+                version,
+                encoding,
+                standalone,
+                span,
+            } => OwnedToken::Declaration {
+                version: o(version),
+                encoding: encoding.map(o),
+                standalone,
+                span: o(span),
+            },
+            Token::ProcessingInstruction {
+                target,
+                content,
+                span,
+            } => OwnedToken::ProcessingInstruction {
+                target: o(target),
+                content: content.map(o),
+                span: o(span),
+            },
+            Token::Comment {
+                text,
+                span,
+                unterminated,
+            } => OwnedToken::Comment {
+                text: o(text),
+                span: o(span),
+                unterminated,
+            },
+            Token::DtdStart {
+                name,
+                external_id,
+                span,
+            } => OwnedToken::DtdStart {
+                name: o(name),
+                external_id: external_id.map(OwnedExternalId::from),
+                span: o(span),
+            },
+            Token::EmptyDtd {
+                name,
+                external_id,
+                span,
+            } => OwnedToken::EmptyDtd {
+                name: o(name),
+                external_id: external_id.map(OwnedExternalId::from),
+                span: o(span),
+            },
+            Token::EntityDeclaration {
+                name,
+                definition,
+                span,
+            } => OwnedToken::EntityDeclaration {
+                name: o(name),
+                definition: definition.into(),
+                span: o(span),
+            },
+            Token::ElementDeclaration {
+                name,
+                content_spec,
+                span,
+            } => OwnedToken::ElementDeclaration {
+                name: o(name),
+                content_spec: content_spec.into(),
+                span: o(span),
+            },
+            Token::AttlistDeclaration {
+                name,
+                definitions,
+                span,
+            } => OwnedToken::AttlistDeclaration {
+                name: o(name),
+                definitions: o(definitions.span()),
+                span: o(span),
+            },
+            Token::NotationDeclaration {
+                name,
+                external_id,
+                span,
+            } => OwnedToken::NotationDeclaration {
+                name: o(name),
+                external_id: external_id.into(),
+                span: o(span),
+            },
+            Token::ConditionalSectionStart { ignore, span } => {
+                OwnedToken::ConditionalSectionStart {
+                    ignore,
+                    span: o(span),
+                }
+            }
+            Token::ConditionalSectionEnd { span } => {
+                OwnedToken::ConditionalSectionEnd { span: o(span) }
+            }
+            Token::ParameterEntityReference { name, span } => {
+                OwnedToken::ParameterEntityReference {
+                    name: o(name),
+                    span: o(span),
+                }
+            }
+            Token::DtdEnd {
+                span,
+                internal_subset,
+            } => OwnedToken::DtdEnd {
+                span: o(span),
+                internal_subset: o(internal_subset),
+            },
+            Token::ElementStart {
+                prefix,
+                local,
+                span,
+            } => OwnedToken::ElementStart {
+                prefix: o(prefix),
+                local: o(local),
+                span: o(span),
+            },
+            Token::Attribute {
+                prefix,
+                local,
+                value,
+                span,
+            } => OwnedToken::Attribute {
+                prefix: o(prefix),
+                local: o(local),
+                value: o(value),
+                span: o(span),
+            },
+            Token::ElementEnd { end, span } => OwnedToken::ElementEnd {
+                end: end.into(),
+                span: o(span),
+            },
+            Token::Text { text } => OwnedToken::Text { text: o(text) },
+            Token::Cdata {
+                text,
+                span,
+                unterminated,
+            } => OwnedToken::Cdata {
+                text: o(text),
+                span: o(span),
+                unterminated,
+            },
+            Token::ConditionalCommentStart { condition, span } => {
+                OwnedToken::ConditionalCommentStart {
+                    condition: o(condition),
+                    span: o(span),
+                }
+            }
+            Token::ConditionalCommentEnd { span } => {
+                OwnedToken::ConditionalCommentEnd { span: o(span) }
+            }
+            Token::Bom { span } => OwnedToken::Bom { span: o(span) },
+            Token::Whitespaces { text } => OwnedToken::Whitespaces { text: o(text) },
+            Token::Raw { kind, span } => OwnedToken::Raw {
+                kind,
+                span: o(span),
+            },
+        }
+    }
+}
+
+/// An owned version of [`ElementEnd`].
+///
+/// [`ElementEnd`]: enum.ElementEnd.html
+#[allow(missing_docs)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum OwnedElementEnd {
+    Open,
+    Close(OwnedStrSpan, OwnedStrSpan),
+    Empty,
+}
+
+impl From<ElementEnd<'_>> for OwnedElementEnd {
+    fn from(end: ElementEnd) -> Self {
+        match end {
+            ElementEnd::Open => OwnedElementEnd::Open,
+            ElementEnd::Close(prefix, local) => OwnedElementEnd::Close(prefix.into(), local.into()),
+            ElementEnd::Empty => OwnedElementEnd::Empty,
+        }
+    }
+}
+
+/// An owned version of [`ExternalId`].
+///
+/// [`ExternalId`]: enum.ExternalId.html
+#[allow(missing_docs)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum OwnedExternalId {
+    System(OwnedStrSpan),
+    Public(OwnedStrSpan, OwnedStrSpan),
+}
+
+impl From<ExternalId<'_>> for OwnedExternalId {
+    fn from(id: ExternalId) -> Self {
+        match id {
+            ExternalId::System(system) => OwnedExternalId::System(system.into()),
+            ExternalId::Public(public, system) => {
+                OwnedExternalId::Public(public.into(), system.into())
+            }
+        }
+    }
+}
+
+/// An owned version of [`EntityDefinition`].
+///
+/// [`EntityDefinition`]: enum.EntityDefinition.html
+#[allow(missing_docs)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum OwnedEntityDefinition {
+    EntityValue(OwnedStrSpan),
+    ExternalId(OwnedExternalId),
+    Unparsed(OwnedExternalId, OwnedStrSpan),
+}
+
+impl From<EntityDefinition<'_>> for OwnedEntityDefinition {
+    fn from(def: EntityDefinition) -> Self {
+        match def {
+            EntityDefinition::EntityValue(value) => {
+                OwnedEntityDefinition::EntityValue(value.into())
+            }
+            EntityDefinition::ExternalId(id) => OwnedEntityDefinition::ExternalId(id.into()),
+            EntityDefinition::Unparsed(id, notation) => {
+                OwnedEntityDefinition::Unparsed(id.into(), notation.into())
+            }
+        }
+    }
+}
+
+/// An owned version of [`NotationId`].
+///
+/// [`NotationId`]: enum.NotationId.html
+#[allow(missing_docs)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum OwnedNotationId {
+    System(OwnedStrSpan),
+    Public(OwnedStrSpan, Option<OwnedStrSpan>),
+}
+
+impl From<NotationId<'_>> for OwnedNotationId {
+    fn from(id: NotationId) -> Self {
+        match id {
+            NotationId::System(system) => OwnedNotationId::System(system.into()),
+            NotationId::Public(public, system) => {
+                OwnedNotationId::Public(public.into(), system.map(OwnedStrSpan::from))
+            }
+        }
+    }
+}
+
+/// An owned version of [`ContentSpec`].
+///
+/// [`ContentSpec`]: enum.ContentSpec.html
+#[allow(missing_docs)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum OwnedContentSpec {
+    Empty,
+    Any,
+    Mixed(OwnedStrSpan),
+    Children(OwnedStrSpan),
+}
+
+impl From<ContentSpec<'_>> for OwnedContentSpec {
+    fn from(spec: ContentSpec) -> Self {
+        match spec {
+            ContentSpec::Empty => OwnedContentSpec::Empty,
+            ContentSpec::Any => OwnedContentSpec::Any,
+            ContentSpec::Mixed(span) => OwnedContentSpec::Mixed(span.into()),
+            ContentSpec::Children(span) => OwnedContentSpec::Children(span.into()),
+        }
+    }
+}
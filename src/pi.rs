@@ -0,0 +1,101 @@
+use crate::{StrSpan, Stream, StreamError, XmlStreamExt};
+
+/// An iterator over processing instruction pseudo-attributes.
+///
+/// Pseudo-attributes follow the same rules as element attributes:
+/// a name, an equality sign and a quoted value, separated by spaces.
+/// Values are not normalized.
+///
+/// Stops after the first error. Error positions are relative to the parsed span.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::{PiAttributes, Token, Tokenizer};
+///
+/// let text = "<?xml-stylesheet type='text/xsl' href=\"a.xsl\"?><a/>";
+/// let token = Tokenizer::from(text).next().unwrap().unwrap();
+/// if let Token::ProcessingInstruction { content: Some(content), .. } = token {
+///     let attrs: Vec<_> = PiAttributes::from(content)
+///         .map(|a| a.map(|(name, value)| (name.as_str(), value.as_str())))
+///         .collect::<Result<_, _>>()
+///         .unwrap();
+///     assert_eq!(attrs, [("type", "text/xsl"), ("href", "a.xsl")]);
+/// }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct PiAttributes<'a> {
+    stream: Stream<'a>,
+    offset: usize,
+    done: bool,
+}
+
+impl<'a> From<StrSpan<'a>> for PiAttributes<'a> {
+    fn from(span: StrSpan<'a>) -> Self {
+        PiAttributes {
+            stream: Stream::from(span),
+            offset: span.start(),
+            done: false,
+        }
+    }
+}
+
+impl<'a> From<&'a str> for PiAttributes<'a> {
+    fn from(text: &'a str) -> Self {
+        PiAttributes::from(StrSpan::from(text))
+    }
+}
+
+impl<'a> Iterator for PiAttributes<'a> {
+    type Item = Result<(StrSpan<'a>, StrSpan<'a>), StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        // Spans are relative to the content span, so we have to shift them.
+        let offset = self.offset;
+        match parse_pseudo_attribute(&mut self.stream) {
+            Ok(Some((name, value))) => Some(Ok((name.shift(offset), value.shift(offset)))),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+// PseudoAtt ::= S? Name Eq AttValue
+//
+// Returns `None` at the end of the stream.
+fn parse_pseudo_attribute<'a>(
+    s: &mut Stream<'a>,
+) -> Result<Option<(StrSpan<'a>, StrSpan<'a>)>, StreamError> {
+    let has_space = s.starts_with_space();
+    s.skip_spaces();
+    if s.at_end() {
+        return Ok(None);
+    }
+
+    // Only the first attribute can be without a leading space.
+    if !has_space && s.pos() != 0 {
+        return Err(StreamError::InvalidSpace(
+            s.curr_byte_unchecked(),
+            s.gen_text_pos(),
+            s.pos(),
+        ));
+    }
+
+    let name = s.consume_name()?;
+    s.consume_eq()?;
+    let quote = s.consume_quote()?;
+    let value = s.consume_chars_until_either(quote, b'<')?;
+    s.consume_byte(quote)?;
+
+    Ok(Some((name, value)))
+}
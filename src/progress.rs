@@ -0,0 +1,63 @@
+use core::fmt;
+
+use crate::{Error, Token, Tokenizer};
+
+/// An iterator over tokens that reports the parsing progress.
+///
+/// See [`Tokenizer::with_progress`](struct.Tokenizer.html#method.with_progress).
+#[derive(Clone)]
+pub struct WithProgress<'a, F> {
+    tokenizer: Tokenizer<'a>,
+    interval: usize,
+    reported: usize,
+    done: bool,
+    callback: F,
+}
+
+impl<'a, F: FnMut(usize)> WithProgress<'a, F> {
+    pub(crate) fn new(tokenizer: Tokenizer<'a>, interval: usize, callback: F) -> Self {
+        let reported = tokenizer.position();
+        WithProgress {
+            tokenizer,
+            interval,
+            reported,
+            done: false,
+            callback,
+        }
+    }
+
+    /// Returns a reference to the underlying tokenizer.
+    pub fn tokenizer(&self) -> &Tokenizer<'a> {
+        &self.tokenizer
+    }
+}
+
+impl<F> fmt::Debug for WithProgress<'_, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "WithProgress {{ interval: {} }}", self.interval)
+    }
+}
+
+impl<'a, F: FnMut(usize)> Iterator for WithProgress<'a, F> {
+    type Item = Result<Token<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let t = self.tokenizer.next();
+        let pos = self.tokenizer.position();
+        if t.is_none() {
+            self.done = true;
+            if pos != self.reported {
+                (self.callback)(pos);
+            }
+        } else if pos - self.reported >= self.interval {
+            self.reported = pos;
+            (self.callback)(pos);
+        }
+
+        t
+    }
+}
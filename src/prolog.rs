@@ -0,0 +1,27 @@
+use crate::{Error, Token, Tokenizer};
+
+/// An iterator over the prolog tokens.
+///
+/// See [`Tokenizer::prolog`](struct.Tokenizer.html#method.prolog).
+#[derive(Debug)]
+pub struct Prolog<'t, 'a> {
+    tokenizer: &'t mut Tokenizer<'a>,
+}
+
+impl<'t, 'a> Prolog<'t, 'a> {
+    pub(crate) fn new(tokenizer: &'t mut Tokenizer<'a>) -> Self {
+        Prolog { tokenizer }
+    }
+}
+
+impl<'t, 'a> Iterator for Prolog<'t, 'a> {
+    type Item = Result<Token<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.tokenizer.at_prolog_end() {
+            return None;
+        }
+
+        self.tokenizer.next()
+    }
+}
@@ -0,0 +1,196 @@
+use core::cmp;
+
+use alloc::string::String;
+
+use crate::{Chunk, Error, State, Token, Tokenizer};
+
+/// A tokenizer for the XML that arrives in chunks.
+///
+/// Unlike `Tokenizer`, doesn't require the whole document to be in memory.
+/// The input is passed via `feed` and tokens are returned as soon as they are complete.
+/// Only the unparsed tail of the input is buffered.
+///
+/// Since the buffer is drained on each `feed`, token spans and error positions are
+/// relative to the current buffer and not to the whole document.
+/// The DTD is kept in the buffer until its end.
+/// Use `offset` to get the absolute offset of the buffer start.
+///
+/// Errors are reported only after `finish`, because an error in the middle of
+/// an unfinished input can be caused by the input being incomplete.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::{PushTokenizer, Token};
+///
+/// let mut p = PushTokenizer::new();
+/// p.feed("<root a='");
+/// match p.next_token() {
+///     Some(Ok(Token::ElementStart { local, .. })) => assert_eq!(local.as_str(), "root"),
+///     _ => unreachable!(),
+/// }
+/// assert!(p.next_token().is_none());
+///
+/// p.feed("b'/>");
+/// match p.next_token() {
+///     Some(Ok(Token::Attribute { value, .. })) => assert_eq!(value.as_str(), "b"),
+///     _ => unreachable!(),
+/// }
+///
+/// p.finish();
+/// while let Some(token) = p.next_token() {
+///     println!("{:?}", token);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct PushTokenizer {
+    buf: String,
+    pos: usize,
+    offset: usize,
+    finished: bool,
+    // A terminator the pending token is waiting for and the buffer position
+    // from which it should be searched.
+    // Allows to avoid rescanning of long texts on each `feed`.
+    wait: Option<(&'static [u8], usize)>,
+    tokenizer: Tokenizer<'static>,
+}
+
+impl Default for PushTokenizer {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PushTokenizer {
+    /// Creates a new push tokenizer.
+    pub fn new() -> Self {
+        PushTokenizer {
+            buf: String::new(),
+            pos: 0,
+            offset: 0,
+            finished: false,
+            wait: None,
+            tokenizer: Tokenizer::from(""),
+        }
+    }
+
+    /// Appends a chunk of text to the input.
+    ///
+    /// All tokens returned previously are invalidated.
+    ///
+    /// Does nothing after `finish`.
+    pub fn feed(&mut self, text: &str) {
+        if self.finished {
+            return;
+        }
+
+        // Remove the already parsed data, but keep the DTD and the current start tag,
+        // since their tokens refer to the start.
+        let len = match self.tokenizer.state {
+            State::Dtd => cmp::min(self.pos, self.tokenizer.dtd_start),
+            State::Attributes => cmp::min(self.pos, self.tokenizer.tag_start),
+            _ => self.pos,
+        };
+
+        self.buf.drain(..len);
+        self.offset += len;
+        self.pos -= len;
+        self.tokenizer.dtd_start = self.tokenizer.dtd_start.saturating_sub(len);
+        self.tokenizer.tag_start = self.tokenizer.tag_start.saturating_sub(len);
+        self.wait = self.wait.map(|(terminator, from)| (terminator, from - len));
+        self.tokenizer.chunk = match self.tokenizer.chunk {
+            Some(Chunk::Text(start)) => Some(Chunk::Text(start.saturating_sub(len))),
+            Some(Chunk::Cdata(start)) => Some(Chunk::Cdata(start.saturating_sub(len))),
+            None => None,
+        };
+
+        self.buf.push_str(text);
+    }
+
+    /// Marks the end of the input.
+    ///
+    /// After this, the remaining tokens and errors will be returned.
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    /// Returns the absolute offset of the current buffer start.
+    ///
+    /// Add it to token spans and error offsets to get offsets in the whole document.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the next complete token.
+    ///
+    /// `None` indicates that more input is required or, after `finish`,
+    /// that the parsing is done.
+    pub fn next_token(&mut self) -> Option<Result<Token<'_>, Error>> {
+        // Skip UTF-8 BOM.
+        if self.offset == 0 && self.pos == 0 && self.buf.starts_with('\u{FEFF}') {
+            self.pos = 3;
+        }
+
+        if let Some((terminator, from)) = self.wait {
+            if !self.finished && !contains(&self.buf.as_bytes()[from..], terminator) {
+                let from = cmp::max(from, self.buf.len().saturating_sub(terminator.len() - 1));
+                self.wait = Some((terminator, from));
+                return None;
+            }
+
+            self.wait = None;
+        }
+
+        let mut tokenizer = self.tokenizer.with_text(&self.buf, self.pos);
+        let token = tokenizer.next();
+
+        let end = tokenizer.stream().pos();
+        let incomplete = match token {
+            // The token might be incomplete, e.g. a text or a name split between chunks.
+            Some(Ok(_)) => end == self.buf.len(),
+            Some(Err(_)) | None => true,
+        };
+
+        if incomplete && !self.finished {
+            self.wait = self.terminator();
+            return None;
+        }
+
+        self.pos = end;
+        self.tokenizer = tokenizer.with_text("", 0);
+        token
+    }
+    // Returns a terminator without which the token at the current position
+    // cannot be complete.
+    fn terminator(&self) -> Option<(&'static [u8], usize)> {
+        // Text chunks are limited in size, so there is nothing to wait for.
+        if self.tokenizer.opt.text_chunk_size.is_some() {
+            return None;
+        }
+
+        let tail = &self.buf.as_bytes()[self.pos..];
+        let (terminator, prefix_len): (&'static [u8], usize) = if tail.starts_with(b"<![CDATA[") {
+            (b"]]>", 9)
+        } else if tail.starts_with(b"<!--") {
+            (b"-->", 4)
+        } else if tail.starts_with(b"<?") {
+            (b"?>", 2)
+        } else if self.tokenizer.state == State::Elements && !tail.starts_with(b"<") {
+            (b"<", 0)
+        } else {
+            return None;
+        };
+
+        // The pending token was already scanned till the buffer end.
+        let from = cmp::max(
+            self.pos + prefix_len,
+            self.buf.len().saturating_sub(terminator.len() - 1),
+        );
+        Some((terminator, from))
+    }
+}
+
+fn contains(bytes: &[u8], s: &[u8]) -> bool {
+    bytes.windows(s.len()).any(|w| w == s)
+}
@@ -0,0 +1,100 @@
+use alloc::vec::Vec;
+
+use crate::{ElementEnd, StrSpan, Token, XmlSpace};
+
+/// A tracker of the inherited `xml:space` and `xml:lang` values.
+///
+/// Consumes tokens and exposes values that are in effect at the current token.
+/// Unknown `xml:space` values are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::{Token, Tokenizer, XmlScope, XmlSpace};
+///
+/// let text = "<a xml:space='preserve' xml:lang='en'><b> 1 </b><c xml:space='default'> 2 </c></a>";
+/// let mut scope = XmlScope::new();
+/// for token in Tokenizer::from(text) {
+///     let token = token.unwrap();
+///     scope.feed(&token);
+///     if let Token::Text { text } = token {
+///         let space = if text.as_str() == " 1 " { XmlSpace::Preserve } else { XmlSpace::Default };
+///         assert_eq!(scope.xml_space(), space);
+///         assert_eq!(scope.xml_lang().unwrap().as_str(), "en");
+///     }
+/// }
+/// ```
+#[derive(Clone, Default, Debug)]
+pub struct XmlScope<'a> {
+    stack: Vec<(XmlSpace, Option<StrSpan<'a>>)>,
+}
+
+impl<'a> XmlScope<'a> {
+    /// Creates a new tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the state using the next token.
+    ///
+    /// All the tokens must be fed in the tokenizer order.
+    pub fn feed(&mut self, token: &Token<'a>) {
+        match *token {
+            Token::ElementStart { .. } => {
+                let current = (self.xml_space(), self.xml_lang());
+                self.stack.push(current);
+            }
+            Token::Attribute {
+                prefix,
+                local,
+                value,
+                ..
+            } if prefix.as_str() == "xml" => {
+                let current = match self.stack.last_mut() {
+                    Some(v) => v,
+                    None => return,
+                };
+
+                match local.as_str() {
+                    "space" => match value.as_str() {
+                        "default" => current.0 = XmlSpace::Default,
+                        "preserve" => current.0 = XmlSpace::Preserve,
+                        _ => {}
+                    },
+                    "lang" => current.1 = Some(value),
+                    _ => {}
+                }
+            }
+            Token::ElementEnd {
+                end: ElementEnd::Empty,
+                ..
+            }
+            | Token::ElementEnd {
+                end: ElementEnd::Close(..),
+                ..
+            } => {
+                self.stack.pop();
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the `xml:space` value in effect.
+    ///
+    /// `XmlSpace::Default` outside of elements.
+    pub fn xml_space(&self) -> XmlSpace {
+        self.stack.last().map(|v| v.0).unwrap_or(XmlSpace::Default)
+    }
+
+    /// Returns the `xml:lang` value in effect.
+    ///
+    /// An empty value means that the language is explicitly unspecified.
+    pub fn xml_lang(&self) -> Option<StrSpan<'a>> {
+        self.stack.last().and_then(|v| v.1)
+    }
+
+    /// Returns the number of open elements.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+}
@@ -0,0 +1,422 @@
+use core::fmt;
+
+use serde::ser::{
+    Serialize, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTupleVariant,
+    Serializer,
+};
+
+use crate::{
+    AttributeDefault, AttributeDefinition, AttributeDefinitions, ContentSpec, ElementEnd,
+    EntityDefinition, Error, ExternalId, NotationId, RawKind, StrSpan, StreamError, TextPos, Token,
+};
+
+// Serializes a value via its `Display` implementation.
+struct AsDisplay<'a, T: fmt::Display>(&'a T);
+
+impl<T: fmt::Display> Serialize for AsDisplay<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self.0)
+    }
+}
+
+impl Serialize for StrSpan<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("StrSpan", 3)?;
+        s.serialize_field("text", self.as_str())?;
+        s.serialize_field("start", &self.start())?;
+        s.serialize_field("end", &self.end())?;
+        s.end()
+    }
+}
+
+impl Serialize for TextPos {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("TextPos", 2)?;
+        s.serialize_field("row", &self.row)?;
+        s.serialize_field("col", &self.col)?;
+        s.end()
+    }
+}
+
+impl Serialize for Error {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Error", 5)?;
+        s.serialize_field("code", self.code())?;
+        s.serialize_field("message", &AsDisplay(self))?;
+        s.serialize_field("pos", &self.pos())?;
+        s.serialize_field("offset", &self.offset())?;
+        s.serialize_field("cause", &self.stream_error())?;
+        s.end()
+    }
+}
+
+impl Serialize for StreamError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("StreamError", 4)?;
+        s.serialize_field("code", self.code())?;
+        s.serialize_field("message", &AsDisplay(self))?;
+        s.serialize_field("pos", &self.pos())?;
+        s.serialize_field("offset", &self.offset())?;
+        s.end()
+    }
+}
+
+impl Serialize for Token<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            Token::Declaration {
+                version,
+                encoding,
+                standalone,
+                span,
+            } => {
+                let mut s = serializer.serialize_struct_variant("Token", 0, "Declaration", 4)?;
+                s.serialize_field("version", &version)?;
+                s.serialize_field("encoding", &encoding)?;
+                s.serialize_field("standalone", &standalone)?;
+                s.serialize_field("span", &span)?;
+                s.end()
+            }
+            Token::ProcessingInstruction {
+                target,
+                content,
+                span,
+            } => {
+                let mut s =
+                    serializer.serialize_struct_variant("Token", 1, "ProcessingInstruction", 3)?;
+                s.serialize_field("target", &target)?;
+                s.serialize_field("content", &content)?;
+                s.serialize_field("span", &span)?;
+                s.end()
+            }
+            Token::Comment {
+                text,
+                span,
+                unterminated,
+            } => {
+                let mut s = serializer.serialize_struct_variant("Token", 2, "Comment", 3)?;
+                s.serialize_field("text", &text)?;
+                s.serialize_field("span", &span)?;
+                s.serialize_field("unterminated", &unterminated)?;
+                s.end()
+            }
+            Token::DtdStart {
+                name,
+                external_id,
+                span,
+            } => {
+                let mut s = serializer.serialize_struct_variant("Token", 3, "DtdStart", 3)?;
+                s.serialize_field("name", &name)?;
+                s.serialize_field("external_id", &external_id)?;
+                s.serialize_field("span", &span)?;
+                s.end()
+            }
+            Token::EmptyDtd {
+                name,
+                external_id,
+                span,
+            } => {
+                let mut s = serializer.serialize_struct_variant("Token", 4, "EmptyDtd", 3)?;
+                s.serialize_field("name", &name)?;
+                s.serialize_field("external_id", &external_id)?;
+                s.serialize_field("span", &span)?;
+                s.end()
+            }
+            Token::EntityDeclaration {
+                name,
+                definition,
+                span,
+            } => {
+                let mut s =
+                    serializer.serialize_struct_variant("Token", 5, "EntityDeclaration", 3)?;
+                s.serialize_field("name", &name)?;
+                s.serialize_field("definition", &definition)?;
+                s.serialize_field("span", &span)?;
+                s.end()
+            }
+            Token::ElementDeclaration {
+                name,
+                content_spec,
+                span,
+            } => {
+                let mut s =
+                    serializer.serialize_struct_variant("Token", 6, "ElementDeclaration", 3)?;
+                s.serialize_field("name", &name)?;
+                s.serialize_field("content_spec", &content_spec)?;
+                s.serialize_field("span", &span)?;
+                s.end()
+            }
+            Token::AttlistDeclaration {
+                name,
+                definitions,
+                span,
+            } => {
+                let mut s =
+                    serializer.serialize_struct_variant("Token", 7, "AttlistDeclaration", 3)?;
+                s.serialize_field("name", &name)?;
+                s.serialize_field("definitions", &definitions)?;
+                s.serialize_field("span", &span)?;
+                s.end()
+            }
+            Token::NotationDeclaration {
+                name,
+                external_id,
+                span,
+            } => {
+                let mut s =
+                    serializer.serialize_struct_variant("Token", 8, "NotationDeclaration", 3)?;
+                s.serialize_field("name", &name)?;
+                s.serialize_field("external_id", &external_id)?;
+                s.serialize_field("span", &span)?;
+                s.end()
+            }
+            Token::ConditionalSectionStart { ignore, span } => {
+                let mut s = serializer.serialize_struct_variant(
+                    "Token",
+                    9,
+                    "ConditionalSectionStart",
+                    2,
+                )?;
+                s.serialize_field("ignore", &ignore)?;
+                s.serialize_field("span", &span)?;
+                s.end()
+            }
+            Token::ConditionalSectionEnd { span } => {
+                let mut s =
+                    serializer.serialize_struct_variant("Token", 10, "ConditionalSectionEnd", 1)?;
+                s.serialize_field("span", &span)?;
+                s.end()
+            }
+            Token::ParameterEntityReference { name, span } => {
+                let mut s = serializer.serialize_struct_variant(
+                    "Token",
+                    11,
+                    "ParameterEntityReference",
+                    2,
+                )?;
+                s.serialize_field("name", &name)?;
+                s.serialize_field("span", &span)?;
+                s.end()
+            }
+            Token::DtdEnd {
+                span,
+                internal_subset,
+            } => {
+                let mut s = serializer.serialize_struct_variant("Token", 12, "DtdEnd", 2)?;
+                s.serialize_field("span", &span)?;
+                s.serialize_field("internal_subset", &internal_subset)?;
+                s.end()
+            }
+            Token::ElementStart {
+                prefix,
+                local,
+                span,
+            } => {
+                let mut s = serializer.serialize_struct_variant("Token", 13, "ElementStart", 3)?;
+                s.serialize_field("prefix", &prefix)?;
+                s.serialize_field("local", &local)?;
+                s.serialize_field("span", &span)?;
+                s.end()
+            }
+            Token::Attribute {
+                prefix,
+                local,
+                value,
+                span,
+            } => {
+                let mut s = serializer.serialize_struct_variant("Token", 14, "Attribute", 4)?;
+                s.serialize_field("prefix", &prefix)?;
+                s.serialize_field("local", &local)?;
+                s.serialize_field("value", &value)?;
+                s.serialize_field("span", &span)?;
+                s.end()
+            }
+            Token::ElementEnd { end, span } => {
+                let mut s = serializer.serialize_struct_variant("Token", 15, "ElementEnd", 2)?;
+                s.serialize_field("end", &end)?;
+                s.serialize_field("span", &span)?;
+                s.end()
+            }
+            Token::Text { text } => {
+                let mut s = serializer.serialize_struct_variant("Token", 16, "Text", 1)?;
+                s.serialize_field("text", &text)?;
+                s.end()
+            }
+            Token::Cdata {
+                text,
+                span,
+                unterminated,
+            } => {
+                let mut s = serializer.serialize_struct_variant("Token", 17, "Cdata", 3)?;
+                s.serialize_field("text", &text)?;
+                s.serialize_field("span", &span)?;
+                s.serialize_field("unterminated", &unterminated)?;
+                s.end()
+            }
+            Token::ConditionalCommentStart { condition, span } => {
+                let mut s = serializer.serialize_struct_variant(
+                    "Token",
+                    18,
+                    "ConditionalCommentStart",
+                    2,
+                )?;
+                s.serialize_field("condition", &condition)?;
+                s.serialize_field("span", &span)?;
+                s.end()
+            }
+            Token::ConditionalCommentEnd { span } => {
+                let mut s =
+                    serializer.serialize_struct_variant("Token", 19, "ConditionalCommentEnd", 1)?;
+                s.serialize_field("span", &span)?;
+                s.end()
+            }
+            Token::Bom { span } => {
+                let mut s = serializer.serialize_struct_variant("Token", 20, "Bom", 1)?;
+                s.serialize_field("span", &span)?;
+                s.end()
+            }
+            Token::Whitespaces { text } => {
+                let mut s = serializer.serialize_struct_variant("Token", 21, "Whitespaces", 1)?;
+                s.serialize_field("text", &text)?;
+                s.end()
+            }
+            Token::Raw { kind, span } => {
+                let mut s = serializer.serialize_struct_variant("Token", 22, "Raw", 2)?;
+                s.serialize_field("kind", &kind)?;
+                s.serialize_field("span", &span)?;
+                s.end()
+            }
+        }
+    }
+}
+
+impl Serialize for ElementEnd<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            ElementEnd::Open => serializer.serialize_unit_variant("ElementEnd", 0, "Open"),
+            ElementEnd::Close(prefix, local) => {
+                let mut s = serializer.serialize_tuple_variant("ElementEnd", 1, "Close", 2)?;
+                s.serialize_field(&prefix)?;
+                s.serialize_field(&local)?;
+                s.end()
+            }
+            ElementEnd::Empty => serializer.serialize_unit_variant("ElementEnd", 2, "Empty"),
+        }
+    }
+}
+
+impl Serialize for RawKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            RawKind::IgnoredSection => {
+                serializer.serialize_unit_variant("RawKind", 0, "IgnoredSection")
+            }
+        }
+    }
+}
+
+impl Serialize for ExternalId<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            ExternalId::System(id) => {
+                serializer.serialize_newtype_variant("ExternalId", 0, "System", &id)
+            }
+            ExternalId::Public(pubid, system) => {
+                let mut s = serializer.serialize_tuple_variant("ExternalId", 1, "Public", 2)?;
+                s.serialize_field(&pubid)?;
+                s.serialize_field(&system)?;
+                s.end()
+            }
+        }
+    }
+}
+
+impl Serialize for EntityDefinition<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            EntityDefinition::EntityValue(value) => {
+                serializer.serialize_newtype_variant("EntityDefinition", 0, "EntityValue", &value)
+            }
+            EntityDefinition::ExternalId(id) => {
+                serializer.serialize_newtype_variant("EntityDefinition", 1, "ExternalId", &id)
+            }
+            EntityDefinition::Unparsed(id, notation) => {
+                let mut s =
+                    serializer.serialize_tuple_variant("EntityDefinition", 2, "Unparsed", 2)?;
+                s.serialize_field(&id)?;
+                s.serialize_field(&notation)?;
+                s.end()
+            }
+        }
+    }
+}
+
+impl Serialize for ContentSpec<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            ContentSpec::Empty => serializer.serialize_unit_variant("ContentSpec", 0, "Empty"),
+            ContentSpec::Any => serializer.serialize_unit_variant("ContentSpec", 1, "Any"),
+            ContentSpec::Mixed(spec) => {
+                serializer.serialize_newtype_variant("ContentSpec", 2, "Mixed", &spec)
+            }
+            ContentSpec::Children(spec) => {
+                serializer.serialize_newtype_variant("ContentSpec", 3, "Children", &spec)
+            }
+        }
+    }
+}
+
+impl Serialize for NotationId<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            NotationId::System(id) => {
+                serializer.serialize_newtype_variant("NotationId", 0, "System", &id)
+            }
+            NotationId::Public(pubid, system) => {
+                let mut s = serializer.serialize_tuple_variant("NotationId", 1, "Public", 2)?;
+                s.serialize_field(&pubid)?;
+                s.serialize_field(&system)?;
+                s.end()
+            }
+        }
+    }
+}
+
+impl Serialize for AttributeDefinitions<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_seq(None)?;
+        for def in self.iter() {
+            s.serialize_element(&def)?;
+        }
+        s.end()
+    }
+}
+
+impl Serialize for AttributeDefinition<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("AttributeDefinition", 3)?;
+        s.serialize_field("name", &self.name)?;
+        s.serialize_field("att_type", &self.att_type)?;
+        s.serialize_field("default", &self.default)?;
+        s.end()
+    }
+}
+
+impl Serialize for AttributeDefault<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            AttributeDefault::Required => {
+                serializer.serialize_unit_variant("AttributeDefault", 0, "Required")
+            }
+            AttributeDefault::Implied => {
+                serializer.serialize_unit_variant("AttributeDefault", 1, "Implied")
+            }
+            AttributeDefault::Fixed(value) => {
+                serializer.serialize_newtype_variant("AttributeDefault", 2, "Fixed", &value)
+            }
+            AttributeDefault::Value(value) => {
+                serializer.serialize_newtype_variant("AttributeDefault", 3, "Value", &value)
+            }
+        }
+    }
+}
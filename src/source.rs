@@ -0,0 +1,91 @@
+use crate::{Error, Token, Tokenizer};
+
+/// An iterator over tokens and their source text.
+///
+/// See [`Tokenizer::with_source`](struct.Tokenizer.html#method.with_source).
+#[derive(Clone, Debug)]
+pub struct WithSource<'a> {
+    tokenizer: Tokenizer<'a>,
+    lossless: bool,
+    // The text of the previous token and its end in it.
+    text: &'a str,
+    prev_end: usize,
+    // The document text and the previous token end in it
+    // while an external subset is being tokenized.
+    saved: Option<(&'a str, usize)>,
+}
+
+impl<'a> WithSource<'a> {
+    pub(crate) fn new(tokenizer: Tokenizer<'a>) -> Self {
+        let stream = tokenizer.stream();
+        let saved = tokenizer.main_stream.map(|s| (s.span().as_str(), s.pos()));
+        WithSource {
+            tokenizer,
+            lossless: false,
+            text: stream.span().as_str(),
+            prev_end: stream.pos(),
+            saved,
+        }
+    }
+
+    /// Includes bytes skipped by the tokenizer into the source text.
+    ///
+    /// By default, the source text covers only the token itself.
+    /// With this option, it starts right after the previous token,
+    /// so whitespaces between attributes and inside the prolog are preserved
+    /// and the concatenation of all source texts reproduces the input.
+    /// The only exception is a whitespace after the root element,
+    /// since it's not followed by any token.
+    /// Source texts of external subset tokens are taken from the subset text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let text = "<a  b='1' />";
+    /// let mut iter = xmlparser::Tokenizer::from(text).with_source();
+    /// iter.set_lossless(true);
+    /// let source: String = iter.map(|t| t.unwrap().1).collect();
+    /// assert_eq!(source, text);
+    /// ```
+    pub fn set_lossless(&mut self, flag: bool) {
+        self.lossless = flag;
+    }
+}
+
+impl<'a> Iterator for WithSource<'a> {
+    type Item = Result<(Token<'a>, &'a str), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = match self.tokenizer.next()? {
+            Ok(token) => token,
+            Err(e) => return Some(Err(e)),
+        };
+
+        // Tokens of an external subset are relative to its own text.
+        let text = self.tokenizer.stream().span().as_str();
+        if !is_same_text(text, self.text) {
+            let prev_end = match self.saved {
+                Some((saved, prev_end)) if is_same_text(text, saved) => prev_end,
+                _ => 0,
+            };
+
+            self.saved = Some((self.text, self.prev_end));
+            self.text = text;
+            self.prev_end = prev_end;
+        }
+
+        let span = token.span();
+        let start = if self.lossless {
+            self.prev_end
+        } else {
+            span.start()
+        };
+        self.prev_end = span.end();
+
+        Some(Ok((token, &text[start..span.end()])))
+    }
+}
+
+fn is_same_text(a: &str, b: &str) -> bool {
+    a.as_ptr() == b.as_ptr() && a.len() == b.len()
+}
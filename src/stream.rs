@@ -1,8 +1,11 @@
 use core::char;
 use core::cmp;
+use core::hash::{Hash, Hasher};
 use core::ops::Range;
 use core::str;
 
+#[cfg(feature = "alloc")]
+use crate::LineIndex;
 use crate::{StrSpan, StreamError, TextPos, XmlByteExt, XmlCharExt};
 
 type Result<T> = ::core::result::Result<T, StreamError>;
@@ -21,12 +24,44 @@ pub enum Reference<'a> {
     Char(char),
 }
 
-/// A streaming XML parsing interface.
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+/// A streaming text scanning interface.
+///
+/// XML-specific methods, like names and references parsing, are provided
+/// by the [`XmlStreamExt`](trait.XmlStreamExt.html) trait.
+#[derive(Clone, Copy, Debug)]
 pub struct Stream<'a> {
     pos: usize,
     end: usize,
     span: StrSpan<'a>,
+    // Text position options and caches. Not a part of the stream identity.
+    line: Option<LineCheckpoint>,
+    defer_text_pos: bool,
+    #[cfg(feature = "alloc")]
+    line_index: Option<&'a LineIndex<'a>>,
+}
+
+impl PartialEq for Stream<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.pos == other.pos && self.end == other.end && self.span == other.span
+    }
+}
+
+impl Eq for Stream<'_> {}
+
+impl Hash for Stream<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.pos.hash(state);
+        self.end.hash(state);
+        self.span.hash(state);
+    }
+}
+
+// A known line at some position. Used by the text position tracking.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+struct LineCheckpoint {
+    pos: usize,
+    row: u32,
+    line_start: usize,
 }
 
 impl<'a> From<&'a str> for Stream<'a> {
@@ -36,6 +71,10 @@ impl<'a> From<&'a str> for Stream<'a> {
             pos: 0,
             end: text.len(),
             span: text.into(),
+            line: None,
+            defer_text_pos: false,
+            #[cfg(feature = "alloc")]
+            line_index: None,
         }
     }
 }
@@ -47,6 +86,10 @@ impl<'a> From<StrSpan<'a>> for Stream<'a> {
             pos: 0,
             end: span.as_str().len(),
             span,
+            line: None,
+            defer_text_pos: false,
+            #[cfg(feature = "alloc")]
+            line_index: None,
         }
     }
 }
@@ -59,6 +102,10 @@ impl<'a> Stream<'a> {
             pos: fragment.start,
             end: fragment.end,
             span: text.into(),
+            line: None,
+            defer_text_pos: false,
+            #[cfg(feature = "alloc")]
+            line_index: None,
         }
     }
 
@@ -74,6 +121,19 @@ impl<'a> Stream<'a> {
         self.pos
     }
 
+    /// Returns the end position.
+    #[inline]
+    pub(crate) fn end(&self) -> usize {
+        self.end
+    }
+
+    // Limits the stream to `end`, which must be within the text.
+    #[inline]
+    pub(crate) fn set_end(&mut self, end: usize) {
+        debug_assert!(self.pos <= end && end <= self.span.as_str().len());
+        self.end = end;
+    }
+
     /// Sets current position equal to the end.
     ///
     /// Used to indicate end of parsing on error.
@@ -172,6 +232,49 @@ impl<'a> Stream<'a> {
         self.span.as_bytes()[self.pos..self.end].starts_with(text)
     }
 
+    /// Checks that the stream starts with a selected text, ignoring ASCII case.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::Stream;
+    ///
+    /// let s = Stream::from("<!doctype html>");
+    /// assert_eq!(s.starts_with_ignore_ascii_case(b"<!DOCTYPE"), true);
+    /// assert_eq!(s.starts_with(b"<!DOCTYPE"), false);
+    /// ```
+    #[inline]
+    pub fn starts_with_ignore_ascii_case(&self, text: &[u8]) -> bool {
+        match self.peek_bytes(text.len()) {
+            Some(bytes) => bytes.eq_ignore_ascii_case(text),
+            None => false,
+        }
+    }
+
+    /// Returns the next `n` bytes without advancing.
+    ///
+    /// Returns `None` when less than `n` bytes are left.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::Stream;
+    ///
+    /// let mut s = Stream::from("text");
+    /// s.advance(1);
+    /// assert_eq!(s.peek_bytes(2), Some(&b"ex"[..]));
+    /// assert_eq!(s.peek_bytes(4), None);
+    /// assert_eq!(s.peek_bytes(std::usize::MAX), None);
+    /// ```
+    #[inline]
+    pub fn peek_bytes(&self, n: usize) -> Option<&'a [u8]> {
+        if n > self.end - self.pos {
+            return None;
+        }
+
+        Some(&self.span.as_str().as_bytes()[self.pos..self.pos + n])
+    }
+
     /// Consumes the current byte if it's equal to the provided byte.
     ///
     /// # Errors
@@ -193,7 +296,12 @@ impl<'a> Stream<'a> {
     pub fn consume_byte(&mut self, c: u8) -> Result<()> {
         let curr = self.curr_byte()?;
         if curr != c {
-            return Err(StreamError::InvalidChar(curr, c, self.gen_text_pos()));
+            return Err(StreamError::InvalidChar(
+                curr,
+                c,
+                self.gen_text_pos(),
+                self.pos(),
+            ));
         }
 
         self.advance(1);
@@ -218,14 +326,20 @@ impl<'a> Stream<'a> {
     /// # Errors
     ///
     /// - `InvalidString`
+    /// - `UnexpectedEndOfStream` when the stream ends with a prefix of `text`.
     pub fn skip_string(&mut self, text: &'static [u8]) -> Result<()> {
         if !self.starts_with(text) {
+            let tail = &self.span.as_bytes()[self.pos..self.end];
+            if text.starts_with(tail) {
+                return Err(StreamError::UnexpectedEndOfStream);
+            }
+
             let pos = self.gen_text_pos();
 
             // Assume that all input `text` are valid UTF-8 strings, so unwrap is safe.
             let expected = str::from_utf8(text).unwrap();
 
-            return Err(StreamError::InvalidString(expected, pos));
+            return Err(StreamError::InvalidString(expected, pos, self.pos));
         }
 
         self.advance(text.len());
@@ -268,6 +382,37 @@ impl<'a> Stream<'a> {
         Ok(self.slice_back(start))
     }
 
+    /// Consumes chars by the predicate and returns them.
+    ///
+    /// Unlike `consume_chars`, doesn't check that chars are allowed in XML,
+    /// so it can be used to scan any text. The result can be empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::Stream;
+    ///
+    /// let mut s = Stream::from("a\u{1}b;c");
+    /// assert_eq!(s.consume_while(|c| c != ';').as_str(), "a\u{1}b");
+    /// assert!(s.starts_with(b";"));
+    /// ```
+    #[inline]
+    pub fn consume_while<F>(&mut self, f: F) -> StrSpan<'a>
+    where
+        F: Fn(char) -> bool,
+    {
+        let start = self.pos;
+        for c in self.chars() {
+            if !f(c) {
+                break;
+            }
+
+            self.advance(c.len_utf8());
+        }
+
+        self.slice_back(start)
+    }
+
     /// Skips chars by the predicate.
     #[inline]
     pub fn skip_chars<F>(&mut self, f: F) -> Result<()>
@@ -276,7 +421,7 @@ impl<'a> Stream<'a> {
     {
         for c in self.chars() {
             if !c.is_xml_char() {
-                return Err(StreamError::NonXmlChar(c, self.gen_text_pos()));
+                return Err(StreamError::NonXmlChar(c, self.gen_text_pos(), self.pos()));
             } else if f(self, c) {
                 self.advance(c.len_utf8());
             } else {
@@ -287,93 +432,211 @@ impl<'a> Stream<'a> {
         Ok(())
     }
 
-    #[inline]
-    pub(crate) fn chars(&self) -> str::Chars<'a> {
-        self.span.as_str()[self.pos..self.end].chars()
-    }
-
-    /// Slices data from `pos` to the current position.
-    #[inline]
-    pub fn slice_back(&self, pos: usize) -> StrSpan<'a> {
-        self.span.slice_region(pos, self.pos)
-    }
+    /// Consumes chars until the selected text or the end of the stream and returns them.
+    ///
+    /// The `text` must start with an ASCII byte. The result can be empty.
+    ///
+    /// # Errors
+    ///
+    /// - `NonXmlChar`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::Stream;
+    ///
+    /// let mut s = Stream::from("text-->");
+    /// assert_eq!(s.consume_chars_until(b"-->").unwrap().as_str(), "text");
+    /// assert!(s.starts_with(b"-->"));
+    /// ```
+    pub fn consume_chars_until(&mut self, text: &[u8]) -> Result<StrSpan<'a>> {
+        debug_assert!(text[0].is_ascii());
 
-    /// Slices data from the current position to the end.
-    #[inline]
-    pub fn slice_tail(&self) -> StrSpan<'a> {
-        self.span.slice_region(self.pos, self.end)
+        let start = self.pos;
+        self.skip_chars_until(text, self.end)?;
+        Ok(self.slice_back(start))
     }
 
-    /// Skips whitespaces.
+    /// Like `consume_chars_until`, but consumes at most `max` bytes.
     ///
-    /// Accepted values: `' ' \n \r \t`.
-    #[inline]
-    pub fn skip_spaces(&mut self) {
-        while !self.at_end() && self.curr_byte_unchecked().is_xml_space() {
-            self.advance(1);
+    /// The result is split at a char boundary. At least one char is consumed,
+    /// unless the stream starts with `text` or is at the end.
+    pub(crate) fn consume_chars_until_max(
+        &mut self,
+        text: &[u8],
+        max: usize,
+    ) -> Result<StrSpan<'a>> {
+        debug_assert!(text[0].is_ascii());
+
+        let start = self.pos;
+        let mut limit = cmp::min(self.end, self.pos.saturating_add(max));
+        let s = self.span.as_str();
+        while !s.is_char_boundary(limit) {
+            limit -= 1;
+        }
+
+        // A `text` that starts before the `limit` will be found,
+        // since only its first byte is checked against the `limit`.
+        self.skip_chars_until(text, limit)?;
+
+        if self.pos == start && !self.at_end() && !self.starts_with(text) {
+            self.check_xml_char_at(self.pos)?;
+            self.advance(self.chars().next().map(char::len_utf8).unwrap_or(0));
         }
+
+        Ok(self.slice_back(start))
     }
 
-    /// Checks if the stream is starts with a space.
-    #[inline]
-    pub fn starts_with_space(&self) -> bool {
-        !self.at_end() && self.curr_byte_unchecked().is_xml_space()
+    // Skips chars until `text` or `limit`, which must be on a char boundary.
+    fn skip_chars_until(&mut self, text: &[u8], limit: usize) -> Result<()> {
+        loop {
+            self.skip_chars_to(text[0], limit)?;
+            if self.pos == limit || self.starts_with(text) {
+                return Ok(());
+            }
+
+            self.advance(1);
+        }
     }
 
-    /// Consumes whitespaces.
-    ///
-    /// Like [`skip_spaces()`], but checks that first char is actually a space.
+    /// Consumes chars until one of the selected ASCII bytes
+    /// or the end of the stream and returns them.
     ///
-    /// [`skip_spaces()`]: #method.skip_spaces
+    /// The result can be empty.
     ///
     /// # Errors
     ///
-    /// - `InvalidSpace`
-    pub fn consume_spaces(&mut self) -> Result<()> {
-        if self.at_end() {
-            return Err(StreamError::UnexpectedEndOfStream);
+    /// - `NonXmlChar`
+    pub fn consume_chars_until_either(&mut self, c1: u8, c2: u8) -> Result<StrSpan<'a>> {
+        debug_assert!(c1.is_ascii() && c2.is_ascii());
+
+        let start = self.pos;
+        let end = self.end;
+        self.skip_chars_to_either(c1, c2, end)?;
+        Ok(self.slice_back(start))
+    }
+
+    /// Consumes bytes until the selected byte or the end of the stream and returns them.
+    ///
+    /// Like `consume_bytes(|_, c| c != byte)`, but faster.
+    /// The result can be empty.
+    pub fn consume_bytes_until(&mut self, c: u8) -> StrSpan<'a> {
+        let start = self.pos;
+        match find_byte(c, &self.span.as_bytes()[self.pos..self.end]) {
+            Some(idx) => self.advance(idx),
+            None => self.pos = self.end,
         }
 
-        if !self.starts_with_space() {
-            return Err(StreamError::InvalidSpace(
-                self.curr_byte_unchecked(),
-                self.gen_text_pos(),
-            ));
+        self.slice_back(start)
+    }
+
+    #[inline]
+    fn skip_chars_to(&mut self, c: u8, limit: usize) -> Result<()> {
+        self.skip_chars_to_either(c, c, limit)
+    }
+
+    // Skips chars until `c1`, `c2` or `limit`, which must be on a char boundary.
+    //
+    // Checks that chars are allowed in XML during the same pass.
+    // Only ASCII control chars and chars starting with 0xEF (U+FFFE and U+FFFF)
+    // have to be decoded, the rest are valid by construction.
+    fn skip_chars_to_either(&mut self, c1: u8, c2: u8, limit: usize) -> Result<()> {
+        // Calling `memchr` has an overhead, so short runs,
+        // like spaces between elements, are still checked byte by byte.
+        let bytes_limit = if cfg!(feature = "memchr") {
+            cmp::min(limit, self.pos + 16)
+        } else {
+            limit
+        };
+
+        let bytes = self.span.as_str().as_bytes();
+        let mut i = self.pos;
+        while i < bytes_limit {
+            let c = bytes[i];
+            if c == c1 || c == c2 {
+                self.pos = i;
+                return Ok(());
+            }
+
+            if BYTE_CLASS[c as usize] & SUSPICIOUS != 0 {
+                self.check_xml_char_at(i)?;
+            }
+
+            i += 1;
+        }
+
+        self.pos = i;
+
+        #[cfg(feature = "memchr")]
+        {
+            if i < limit {
+                return self.skip_long_chars_to_either(c1, c2, limit);
+            }
         }
 
-        self.skip_spaces();
         Ok(())
     }
 
-    /// Consumes an XML character reference if there is one.
-    ///
-    /// On error will reset the position to the original.
-    pub fn try_consume_reference(&mut self) -> Option<Reference<'a>> {
-        let start = self.pos();
+    // Like `skip_chars_to_either`, but finds bytes using `memchr`
+    // and checks each found region right away, while it's still in the cache.
+    #[cfg(feature = "memchr")]
+    fn skip_long_chars_to_either(&mut self, c1: u8, c2: u8, limit: usize) -> Result<()> {
+        let bytes = &self.span.as_str().as_bytes()[..limit];
+        let end = match memchr::memchr2(c1, c2, &bytes[self.pos..]) {
+            Some(idx) => self.pos + idx,
+            None => limit,
+        };
 
-        // Consume reference on a substream.
-        let mut s = *self;
-        match s.consume_reference() {
-            Ok(r) => {
-                // If the current data is a reference than advance the current stream
-                // by number of bytes read by substream.
-                self.advance(s.pos() - start);
-                Some(r)
+        let mut i = self.pos;
+        for chunk in bytes[self.pos..end].chunks(64) {
+            // A branchless check, which compiles into SIMD instructions.
+            let suspicious = chunk.iter().fold(false, |acc, c| {
+                acc | (*c < 0x20 && *c != b'\t' && *c != b'\n' && *c != b'\r') | (*c == 0xEF)
+            });
+
+            if suspicious {
+                for (n, c) in chunk.iter().enumerate() {
+                    if BYTE_CLASS[*c as usize] & SUSPICIOUS != 0 {
+                        self.check_xml_char_at(i + n)?;
+                    }
+                }
             }
-            Err(_) => None,
+
+            i += chunk.len();
         }
+
+        self.pos = end;
+        Ok(())
     }
 
-    /// Consumes an XML reference.
-    ///
-    /// Consumes according to: <https://www.w3.org/TR/xml/#NT-Reference>
-    ///
-    /// # Errors
-    ///
-    /// - `InvalidReference`
-    pub fn consume_reference(&mut self) -> Result<Reference<'a>> {
-        self._consume_reference()
-            .map_err(|_| StreamError::InvalidReference)
+    // Checks a char at `pos`. Moves the stream to it on error.
+    #[cold]
+    #[inline(never)]
+    fn check_xml_char_at(&mut self, pos: usize) -> Result<()> {
+        match self.span.as_str()[pos..].chars().next() {
+            Some(c) if !c.is_xml_char() => {
+                self.pos = pos;
+                Err(StreamError::NonXmlChar(c, self.gen_text_pos(), pos))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn chars(&self) -> str::Chars<'a> {
+        self.span.as_str()[self.pos..self.end].chars()
+    }
+
+    /// Slices data from `pos` to the current position.
+    #[inline]
+    pub fn slice_back(&self, pos: usize) -> StrSpan<'a> {
+        self.span.slice_region(pos, self.pos)
+    }
+
+    /// Slices data from the current position to the end.
+    #[inline]
+    pub fn slice_tail(&self) -> StrSpan<'a> {
+        self.span.slice_region(self.pos, self.end)
     }
 
     #[inline(never)]
@@ -383,14 +646,7 @@ impl<'a> Stream<'a> {
         }
 
         let reference = if self.try_consume_byte(b'#') {
-            let (value, radix) = if self.try_consume_byte(b'x') {
-                let value = self.consume_bytes(|_, c| c.is_xml_hex_digit()).as_str();
-                (value, 16)
-            } else {
-                let value = self.consume_bytes(|_, c| c.is_xml_digit()).as_str();
-                (value, 10)
-            };
-
+            let (value, radix) = self.consume_char_reference_digits();
             let n = u32::from_str_radix(value, radix).map_err(|_| StreamError::InvalidReference)?;
 
             let c = char::from_u32(n).unwrap_or('\u{FFFD}');
@@ -416,166 +672,196 @@ impl<'a> Stream<'a> {
         Ok(reference)
     }
 
-    /// Consumes an XML name and returns it.
+    /// Consumes an XML character reference.
     ///
-    /// Consumes according to: <https://www.w3.org/TR/xml/#NT-Name>
+    /// Unlike `consume_reference()`, returns `None` instead of an error
+    /// when the reference doesn't map to an XML character.
     ///
     /// # Errors
     ///
-    /// - `InvalidName` - if name is empty or starts with an invalid char
-    /// - `UnexpectedEndOfStream`
-    pub fn consume_name(&mut self) -> Result<StrSpan<'a>> {
-        let start = self.pos();
-        self.skip_name()?;
+    /// - `InvalidReference` - when the reference is malformed
+    pub(crate) fn consume_char_reference(&mut self) -> Result<Option<char>> {
+        if !self.starts_with(b"&#") {
+            return Err(StreamError::InvalidReference);
+        }
 
-        let name = self.slice_back(start);
-        if name.is_empty() {
-            return Err(StreamError::InvalidName);
+        self.advance(2);
+        let (value, radix) = self.consume_char_reference_digits();
+        if value.is_empty() {
+            return Err(StreamError::InvalidReference);
         }
 
-        Ok(name)
+        self.consume_byte(b';')?;
+
+        let c = u32::from_str_radix(value, radix)
+            .ok()
+            .and_then(char::from_u32)
+            .filter(|c| c.is_xml_char());
+        Ok(c)
     }
 
-    /// Skips an XML name.
-    ///
-    /// The same as `consume_name()`, but does not return a consumed name.
-    ///
-    /// # Errors
-    ///
-    /// - `InvalidName` - if name is empty or starts with an invalid char
-    pub fn skip_name(&mut self) -> Result<()> {
-        let mut iter = self.chars();
-        if let Some(c) = iter.next() {
-            if c.is_xml_name_start() {
-                self.advance(c.len_utf8());
-            } else {
-                return Err(StreamError::InvalidName);
-            }
+    fn consume_char_reference_digits(&mut self) -> (&'a str, u32) {
+        if self.try_consume_byte(b'x') {
+            let value = self.consume_bytes(|_, c| c.is_xml_hex_digit()).as_str();
+            (value, 16)
+        } else {
+            let value = self.consume_bytes(|_, c| c.is_xml_digit()).as_str();
+            (value, 10)
         }
+    }
 
-        for c in iter {
-            if c.is_xml_name() {
-                self.advance(c.len_utf8());
-            } else {
-                break;
+    // Skips NameChars.
+    #[inline]
+    fn skip_name_chars(&mut self) {
+        loop {
+            // Check for ASCII first for performance reasons.
+            let n = count_bytes(&self.span.as_bytes()[self.pos..self.end], NAME | COLON);
+            self.advance(n);
+
+            // Fallback to Unicode code point.
+            match self.chars().next() {
+                Some(c) if !c.is_ascii() && c.is_xml_name() => self.advance(c.len_utf8()),
+                _ => break,
             }
         }
-
-        Ok(())
     }
 
-    /// Consumes a qualified XML name and returns it.
+    /// Enables incremental text position tracking.
     ///
-    /// Consumes according to: <https://www.w3.org/TR/xml-names/#ns-qualnames>
+    /// When enabled, `gen_text_pos()` will scan the text only from the position
+    /// of the last `sync_text_pos()` call instead of the text start.
     ///
-    /// # Errors
+    /// # Examples
     ///
-    /// - `InvalidName` - if name is empty or starts with an invalid char
-    #[inline(never)]
-    pub fn consume_qname(&mut self) -> Result<(StrSpan<'a>, StrSpan<'a>)> {
-        let start = self.pos();
+    /// ```
+    /// let mut s = xmlparser::Stream::from("a\nb\nc");
+    /// s.set_track_text_pos(true);
+    /// s.advance(4);
+    /// s.sync_text_pos();
+    /// assert_eq!(s.gen_text_pos(), xmlparser::TextPos::new(3, 1));
+    /// ```
+    pub fn set_track_text_pos(&mut self, flag: bool) {
+        if !flag {
+            self.line = None;
+        } else if self.line.is_none() {
+            let text = self.span.as_str();
+            let line_start = text[..self.pos].rfind('\n').map(|i| i + 1).unwrap_or(0);
+            self.line = Some(LineCheckpoint {
+                pos: self.pos,
+                row: Self::calc_curr_row(text, self.pos),
+                line_start,
+            });
+        }
+    }
 
-        let mut splitter = None;
+    // Creates a stream over `text` at `pos` with the same text position options.
+    pub(crate) fn with_text<'b>(&self, text: &'b str, pos: usize) -> Stream<'b> {
+        let mut s = Stream::from(text);
+        s.pos = pos;
+        s.defer_text_pos = self.defer_text_pos;
+        s.set_track_text_pos(self.line.is_some());
+        s
+    }
 
-        while !self.at_end() {
-            // Check for ASCII first for performance reasons.
-            let b = self.curr_byte_unchecked();
-            if b < 128 {
-                if b == b':' {
-                    if splitter.is_none() {
-                        splitter = Some(self.pos());
-                        self.advance(1);
-                    } else {
-                        // Multiple `:` is an error.
-                        return Err(StreamError::InvalidName);
-                    }
-                } else if b.is_xml_name() {
-                    self.advance(1);
-                } else {
-                    break;
-                }
-            } else {
-                // Fallback to Unicode code point.
-                match self.chars().nth(0) {
-                    Some(c) if c.is_xml_name() => {
-                        self.advance(c.len_utf8());
-                    }
-                    _ => break,
-                }
-            }
-        }
-
-        let (prefix, local) = if let Some(splitter) = splitter {
-            let prefix = self.span().slice_region(start, splitter);
-            let local = self.slice_back(splitter + 1);
-            (prefix, local)
-        } else {
-            let local = self.slice_back(start);
-            ("".into(), local)
-        };
+    // Checks that both streams are over the same text and range.
+    pub(crate) fn is_same_text(&self, other: &Stream) -> bool {
+        let a = self.span.as_str();
+        let b = other.span.as_str();
+        a.as_ptr() == b.as_ptr() && a.len() == b.len() && self.end == other.end
+    }
 
-        // Prefix must start with a `NameStartChar`.
-        if let Some(c) = prefix.as_str().chars().nth(0) {
-            if !c.is_xml_name_start() {
-                return Err(StreamError::InvalidName);
+    /// Remembers the line at the current position.
+    ///
+    /// Does nothing when text position tracking is disabled.
+    #[inline]
+    pub fn sync_text_pos(&mut self) {
+        if let Some(line) = self.line {
+            if self.pos > line.pos {
+                self.line = Some(self.line_at(line, self.pos));
             }
         }
+    }
 
-        // Local name must start with a `NameStartChar`.
-        if let Some(c) = local.as_str().chars().nth(0) {
-            if !c.is_xml_name_start() {
-                return Err(StreamError::InvalidName);
+    // Finds the line at `pos` by scanning from the `line` checkpoint.
+    fn line_at(&self, line: LineCheckpoint, pos: usize) -> LineCheckpoint {
+        let mut row = line.row;
+        let mut line_start = line.line_start;
+        let bytes = &self.span.as_bytes()[line.pos..pos];
+        for (i, c) in bytes.iter().enumerate() {
+            if *c == b'\n' {
+                row += 1;
+                line_start = line.pos + i + 1;
             }
-        } else {
-            // If empty - error.
-            return Err(StreamError::InvalidName);
         }
 
-        Ok((prefix, local))
+        LineCheckpoint {
+            pos,
+            row,
+            line_start,
+        }
     }
 
-    /// Consumes `=`.
+    /// Disables the text position calculation.
     ///
-    /// Consumes according to: <https://www.w3.org/TR/xml/#NT-Eq>
+    /// When enabled, `gen_text_pos()` returns `0:0` without scanning the text.
+    /// Takes priority over the text position tracking and a line index.
     ///
-    /// # Errors
+    /// # Examples
     ///
-    /// - `InvalidChar`
-    /// - `UnexpectedEndOfStream`
-    pub fn consume_eq(&mut self) -> Result<()> {
-        self.skip_spaces();
-        self.consume_byte(b'=')?;
-        self.skip_spaces();
-
-        Ok(())
+    /// ```
+    /// let mut s = xmlparser::Stream::from("a\nb");
+    /// s.set_defer_text_pos(true);
+    /// s.advance(2);
+    /// assert_eq!(s.gen_text_pos(), xmlparser::TextPos::new(0, 0));
+    /// ```
+    pub fn set_defer_text_pos(&mut self, flag: bool) {
+        self.defer_text_pos = flag;
     }
 
-    /// Consumes quote.
-    ///
-    /// Consumes `'` or `"` and returns it.
+    /// Sets a line index of the stream's text.
     ///
-    /// # Errors
+    /// Makes `gen_text_pos` cheap. Takes priority over the text position tracking.
     ///
-    /// - `InvalidQuote`
-    /// - `UnexpectedEndOfStream`
-    pub fn consume_quote(&mut self) -> Result<u8> {
-        let c = self.curr_byte()?;
-        if c == b'\'' || c == b'"' {
-            self.advance(1);
-            Ok(c)
+    /// The index is ignored when it was built from a different text.
+    #[cfg(feature = "alloc")]
+    pub fn set_line_index(&mut self, index: &'a LineIndex<'a>) {
+        let a = index.text();
+        let b = self.span.as_str();
+        if a.as_ptr() == b.as_ptr() && a.len() == b.len() {
+            self.line_index = Some(index);
         } else {
-            Err(StreamError::InvalidQuote(c, self.gen_text_pos()))
+            self.line_index = None;
         }
     }
 
     /// Calculates a current absolute position.
     ///
-    /// This operation is very expensive. Use only for errors.
+    /// This operation is very expensive, unless the text position tracking is enabled
+    /// or a line index is set. Use only for errors.
     #[inline(never)]
     pub fn gen_text_pos(&self) -> TextPos {
+        if self.defer_text_pos {
+            return TextPos::new(0, 0);
+        }
+
         let text = self.span.as_str();
         let end = self.pos;
 
+        #[cfg(feature = "alloc")]
+        {
+            if let Some(index) = self.line_index {
+                return index.text_pos(end);
+            }
+        }
+
+        if let Some(line) = self.line {
+            if end >= line.pos {
+                let line = self.line_at(line, end);
+                let col = text[line.line_start..end].chars().count() as u32 + 1;
+                return TextPos::new(line.row, col);
+            }
+        }
+
         let row = Self::calc_curr_row(text, end);
         let col = Self::calc_curr_col(text, end);
         TextPos::new(row, col)
@@ -624,3 +910,298 @@ impl<'a> Stream<'a> {
         col
     }
 }
+
+/// XML-specific methods of [`Stream`].
+///
+/// Generic text scanning methods are implemented by `Stream` itself,
+/// so other markup parsers can reuse it.
+///
+/// [`Stream`]: struct.Stream.html
+pub trait XmlStreamExt<'a> {
+    /// Skips whitespaces.
+    ///
+    /// Accepted values: `' ' \n \r \t`.
+    fn skip_spaces(&mut self);
+
+    /// Checks if the stream is starts with a space.
+    fn starts_with_space(&self) -> bool;
+
+    /// Consumes whitespaces.
+    ///
+    /// Like [`skip_spaces()`], but checks that first char is actually a space.
+    ///
+    /// [`skip_spaces()`]: #method.skip_spaces
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidSpace`
+    fn consume_spaces(&mut self) -> Result<()>;
+
+    /// Consumes an XML character reference if there is one.
+    ///
+    /// On error will reset the position to the original.
+    fn try_consume_reference(&mut self) -> Option<Reference<'a>>;
+
+    /// Consumes an XML reference.
+    ///
+    /// Consumes according to: <https://www.w3.org/TR/xml/#NT-Reference>
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidReference`
+    fn consume_reference(&mut self) -> Result<Reference<'a>>;
+
+    /// Consumes an XML name and returns it.
+    ///
+    /// Consumes according to: <https://www.w3.org/TR/xml/#NT-Name>
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidName` - if name is empty or starts with an invalid char
+    /// - `UnexpectedEndOfStream`
+    fn consume_name(&mut self) -> Result<StrSpan<'a>>;
+
+    /// Skips an XML name.
+    ///
+    /// The same as `consume_name()`, but does not return a consumed name.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidName` - if name is empty or starts with an invalid char
+    fn skip_name(&mut self) -> Result<()>;
+
+    /// Consumes a qualified XML name and returns it.
+    ///
+    /// Consumes according to: <https://www.w3.org/TR/xml-names/#ns-qualnames>
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidName` - if name is empty or starts with an invalid char
+    fn consume_qname(&mut self) -> Result<(StrSpan<'a>, StrSpan<'a>)>;
+
+    /// Consumes `=`.
+    ///
+    /// Consumes according to: <https://www.w3.org/TR/xml/#NT-Eq>
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidChar`
+    /// - `UnexpectedEndOfStream`
+    fn consume_eq(&mut self) -> Result<()>;
+
+    /// Consumes quote.
+    ///
+    /// Consumes `'` or `"` and returns it.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidQuote`
+    /// - `UnexpectedEndOfStream`
+    fn consume_quote(&mut self) -> Result<u8>;
+}
+
+impl<'a> XmlStreamExt<'a> for Stream<'a> {
+    #[inline]
+    fn skip_spaces(&mut self) {
+        let n = count_bytes(&self.span.as_bytes()[self.pos..self.end], SPACE);
+        self.advance(n);
+    }
+
+    #[inline]
+    fn starts_with_space(&self) -> bool {
+        !self.at_end() && self.curr_byte_unchecked().is_xml_space()
+    }
+
+    fn consume_spaces(&mut self) -> Result<()> {
+        if self.at_end() {
+            return Err(StreamError::UnexpectedEndOfStream);
+        }
+
+        if !self.starts_with_space() {
+            return Err(StreamError::InvalidSpace(
+                self.curr_byte_unchecked(),
+                self.gen_text_pos(),
+                self.pos,
+            ));
+        }
+
+        self.skip_spaces();
+        Ok(())
+    }
+
+    fn try_consume_reference(&mut self) -> Option<Reference<'a>> {
+        let start = self.pos();
+
+        // Consume reference on a substream.
+        let mut s = *self;
+        match s.consume_reference() {
+            Ok(r) => {
+                // If the current data is a reference than advance the current stream
+                // by number of bytes read by substream.
+                self.advance(s.pos() - start);
+                Some(r)
+            }
+            Err(_) => None,
+        }
+    }
+
+    fn consume_reference(&mut self) -> Result<Reference<'a>> {
+        self._consume_reference()
+            .map_err(|_| StreamError::InvalidReference)
+    }
+
+    fn consume_name(&mut self) -> Result<StrSpan<'a>> {
+        let start = self.pos();
+        self.skip_name()?;
+
+        let name = self.slice_back(start);
+        if name.is_empty() {
+            return Err(StreamError::InvalidName);
+        }
+
+        Ok(name)
+    }
+
+    fn skip_name(&mut self) -> Result<()> {
+        match self.chars().next() {
+            Some(c) if c.is_xml_name_start() => self.advance(c.len_utf8()),
+            Some(_) => return Err(StreamError::InvalidName),
+            None => return Ok(()),
+        }
+
+        self.skip_name_chars();
+        Ok(())
+    }
+
+    #[inline(never)]
+    fn consume_qname(&mut self) -> Result<(StrSpan<'a>, StrSpan<'a>)> {
+        let start = self.pos();
+
+        let mut splitter = None;
+
+        while !self.at_end() {
+            // Check for ASCII first for performance reasons.
+            let b = self.curr_byte_unchecked();
+            if b < 128 {
+                if b == b':' {
+                    if splitter.is_none() {
+                        splitter = Some(self.pos());
+                        self.advance(1);
+                    } else {
+                        // Multiple `:` is an error.
+                        return Err(StreamError::InvalidName);
+                    }
+                } else if b.is_xml_name() {
+                    self.advance(1);
+                } else {
+                    break;
+                }
+            } else {
+                // Fallback to Unicode code point.
+                match self.chars().nth(0) {
+                    Some(c) if c.is_xml_name() => {
+                        self.advance(c.len_utf8());
+                    }
+                    _ => break,
+                }
+            }
+        }
+
+        let (prefix, local) = if let Some(splitter) = splitter {
+            let prefix = self.span().slice_region(start, splitter);
+            let local = self.slice_back(splitter + 1);
+            (prefix, local)
+        } else {
+            let local = self.slice_back(start);
+            ("".into(), local)
+        };
+
+        // Prefix must start with a `NameStartChar`.
+        if let Some(c) = prefix.as_str().chars().nth(0) {
+            if !c.is_xml_name_start() {
+                return Err(StreamError::InvalidName);
+            }
+        }
+
+        // Local name must start with a `NameStartChar`.
+        if let Some(c) = local.as_str().chars().nth(0) {
+            if !c.is_xml_name_start() {
+                return Err(StreamError::InvalidName);
+            }
+        } else {
+            // If empty - error.
+            return Err(StreamError::InvalidName);
+        }
+
+        Ok((prefix, local))
+    }
+
+    fn consume_eq(&mut self) -> Result<()> {
+        self.skip_spaces();
+        self.consume_byte(b'=')?;
+        self.skip_spaces();
+
+        Ok(())
+    }
+
+    fn consume_quote(&mut self) -> Result<u8> {
+        let c = self.curr_byte()?;
+        if c == b'\'' || c == b'"' {
+            self.advance(1);
+            Ok(c)
+        } else {
+            Err(StreamError::InvalidQuote(
+                c,
+                self.gen_text_pos(),
+                self.pos(),
+            ))
+        }
+    }
+}
+
+// Returns the number of leading bytes with any of the `class` bits set.
+//
+// A lookup table takes about half the time of range checks on its own,
+// though tokenizing a whole document barely changes. Checking multiple bytes
+// at a time is slower, because names and spaces are usually short.
+// See `benches/scan.rs`.
+#[inline]
+fn count_bytes(bytes: &[u8], class: u8) -> usize {
+    let mut i = 0;
+    while i < bytes.len() && BYTE_CLASS[bytes[i] as usize] & class != 0 {
+        i += 1;
+    }
+
+    i
+}
+
+const SPACE: u8 = 1;
+// NameChar, excluding `:` and non-ASCII chars.
+const NAME: u8 = 2;
+const COLON: u8 = 4;
+// Bytes that can start a non-XML char.
+const SUSPICIOUS: u8 = 8;
+
+// ASCII character classes.
+static BYTE_CLASS: [u8; 256] = [
+    8, 8, 8, 8, 8, 8, 8, 8, 8, 1, 1, 8, 8, 1, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8,
+    1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 2, 0, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 4, 0, 0, 0, 0, 0,
+    0, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 0, 0, 0, 0, 2,
+    0, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+#[cfg(feature = "memchr")]
+#[inline]
+fn find_byte(c: u8, bytes: &[u8]) -> Option<usize> {
+    memchr::memchr(c, bytes)
+}
+
+#[cfg(not(feature = "memchr"))]
+#[inline]
+fn find_byte(c: u8, bytes: &[u8]) -> Option<usize> {
+    bytes.iter().position(|b| *b == c)
+}
@@ -1,6 +1,8 @@
 use core::fmt;
 use core::ops::{Deref, Range};
 
+use crate::References;
+
 /// A string slice.
 ///
 /// Like `&str`, but also contains the position in the input XML
@@ -46,7 +48,7 @@ impl PartialEq<StrSpan<'_>> for &str {
 impl<'a> StrSpan<'a> {
     /// Constructs a new `StrSpan` from substring.
     #[inline]
-    pub(crate) fn from_substr(text: &str, start: usize, end: usize) -> StrSpan {
+    pub(crate) fn from_substr(text: &str, start: usize, end: usize) -> StrSpan<'_> {
         debug_assert!(start <= end);
         StrSpan {
             text: &text[start..end],
@@ -83,11 +85,28 @@ impl<'a> StrSpan<'a> {
         self.text
     }
 
+    /// Returns an iterator over references inside the span.
+    ///
+    /// See [`References`](struct.References.html) for details.
+    #[inline]
+    pub fn references(&self) -> References<'a> {
+        References::new(*self)
+    }
+
     /// Returns an underling string region as `StrSpan`.
     #[inline]
     pub(crate) fn slice_region(&self, start: usize, end: usize) -> StrSpan<'a> {
         StrSpan::from_substr(self.text, start, end)
     }
+
+    /// Moves the span start by `offset`.
+    #[inline]
+    pub(crate) fn shift(self, offset: usize) -> StrSpan<'a> {
+        StrSpan {
+            text: self.text,
+            start: self.start + offset,
+        }
+    }
 }
 
 impl fmt::Debug for StrSpan<'_> {
@@ -0,0 +1,81 @@
+use core::fmt;
+
+use crate::{Error, ExternalId, StrSpan, Token, Tokenizer};
+
+/// A provider of external DTD subsets.
+///
+/// The crate doesn't perform any IO by itself, so fetching and decoding
+/// the subset is up to the implementation.
+///
+/// See [`Tokenizer::with_external_subset_loader`](struct.Tokenizer.html#method.with_external_subset_loader).
+pub trait ExternalSubsetLoader<'a> {
+    /// Returns the external subset text of a document type.
+    ///
+    /// `name` is the document type name.
+    ///
+    /// Returning `None` will skip the external subset.
+    fn load(&mut self, name: StrSpan<'a>, id: ExternalId<'a>) -> Option<&'a str>;
+}
+
+impl<'a, F> ExternalSubsetLoader<'a> for F
+where
+    F: FnMut(StrSpan<'a>, ExternalId<'a>) -> Option<&'a str>,
+{
+    #[inline]
+    fn load(&mut self, name: StrSpan<'a>, id: ExternalId<'a>) -> Option<&'a str> {
+        self(name, id)
+    }
+}
+
+/// An iterator over tokens that tokenizes external DTD subsets inline.
+///
+/// See [`Tokenizer::with_external_subset_loader`](struct.Tokenizer.html#method.with_external_subset_loader).
+#[derive(Clone)]
+pub struct WithExternalSubsets<'a, L> {
+    tokenizer: Tokenizer<'a>,
+    loader: L,
+}
+
+impl<'a, L: ExternalSubsetLoader<'a>> WithExternalSubsets<'a, L> {
+    pub(crate) fn new(tokenizer: Tokenizer<'a>, loader: L) -> Self {
+        WithExternalSubsets { tokenizer, loader }
+    }
+
+    /// Returns a reference to the underlying tokenizer.
+    pub fn tokenizer(&self) -> &Tokenizer<'a> {
+        &self.tokenizer
+    }
+}
+
+impl<L> fmt::Debug for WithExternalSubsets<'_, L> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "WithExternalSubsets {{ ... }}")
+    }
+}
+
+impl<'a, L: ExternalSubsetLoader<'a>> Iterator for WithExternalSubsets<'a, L> {
+    type Item = Result<Token<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let t = self.tokenizer.next()?;
+        match t {
+            Ok(Token::DtdStart {
+                name,
+                external_id: Some(id),
+                ..
+            })
+            | Ok(Token::EmptyDtd {
+                name,
+                external_id: Some(id),
+                ..
+            }) => {
+                if let Some(text) = self.loader.load(name, id) {
+                    self.tokenizer.set_external_subset(text);
+                }
+            }
+            _ => {}
+        }
+
+        Some(t)
+    }
+}
@@ -0,0 +1,164 @@
+use alloc::vec::Vec;
+use core::fmt;
+use core::slice;
+
+use crate::{Error, StrSpan, TextPos};
+
+/// An element that was opened, but not closed yet.
+///
+/// See [`Tokenizer::set_track_tags`](struct.Tokenizer.html#method.set_track_tags).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct OpenElement<'a> {
+    /// Element prefix.
+    pub prefix: StrSpan<'a>,
+    /// Element local name.
+    pub local: StrSpan<'a>,
+    /// Element start tag span, like `<ns:elem`.
+    pub span: StrSpan<'a>,
+}
+
+/// An iterator over the open elements names, from the outermost one.
+///
+/// Yields prefix and local name pairs. Displayed as an XPath-like path.
+///
+/// See [`Tokenizer::current_path`](struct.Tokenizer.html#method.current_path).
+///
+/// # Examples
+///
+/// ```
+/// let mut p = xmlparser::Tokenizer::from("<root><ns:a><b/></ns:a></root>");
+/// p.set_track_tags(true);
+/// p.nth(4).unwrap().unwrap(); // <b
+/// assert_eq!(p.current_path().to_string(), "/root/ns:a/b");
+/// assert_eq!(p.current_path().count(), 3);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ElementPath<'a, 'b> {
+    iter: slice::Iter<'b, OpenElement<'a>>,
+}
+
+impl<'a, 'b> ElementPath<'a, 'b> {
+    pub(crate) fn new(elements: &'b [OpenElement<'a>]) -> Self {
+        ElementPath {
+            iter: elements.iter(),
+        }
+    }
+}
+
+impl<'a> Iterator for ElementPath<'a, '_> {
+    type Item = (StrSpan<'a>, StrSpan<'a>);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|e| (e.prefix, e.local))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl fmt::Display for ElementPath<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (prefix, local) in self.clone() {
+            write!(f, "/{}", QName(prefix, local))?;
+        }
+
+        Ok(())
+    }
+}
+
+// Displays a prefix and a local name as `prefix:local`.
+pub(crate) struct QName<'a>(pub StrSpan<'a>, pub StrSpan<'a>);
+
+impl<'a> From<&OpenElement<'a>> for QName<'a> {
+    fn from(e: &OpenElement<'a>) -> Self {
+        QName(e.prefix, e.local)
+    }
+}
+
+impl fmt::Display for QName<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.0.is_empty() {
+            write!(f, "{}:", self.0)?;
+        }
+        write!(f, "{}", self.1)
+    }
+}
+
+/// A report about a truncated document.
+///
+/// See [`Tokenizer::truncation_report`](struct.Tokenizer.html#method.truncation_report).
+///
+/// # Examples
+///
+/// ```
+/// let mut p = xmlparser::Tokenizer::from("<root>\n  <a><!-- text");
+/// p.set_track_tags(true);
+/// let err = p.by_ref().filter_map(|t| t.err()).next().unwrap();
+/// let report = p.truncation_report(&err).unwrap();
+/// assert_eq!(
+///     report.to_string(),
+///     "unexpected end of stream inside a comment at 2:6\n\
+///      unclosed element <root> at 1:1\n\
+///      unclosed element <a> at 2:3"
+/// );
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct TruncationReport<'a> {
+    /// A construct that was truncated, like `a comment`.
+    pub construct: &'static str,
+    /// A truncated construct start.
+    pub pos: TextPos,
+    /// A list of unclosed elements, from the outermost one.
+    ///
+    /// Empty when tag tracking is disabled.
+    pub open_elements: Vec<(OpenElement<'a>, TextPos)>,
+}
+
+impl<'a> TruncationReport<'a> {
+    pub(crate) fn construct_name(error: &Error) -> &'static str {
+        match *error {
+            Error::InvalidDeclaration(..) => "an XML declaration",
+            Error::InvalidComment(..) => "a comment",
+            Error::InvalidPI(..) => "a processing instruction",
+            Error::InvalidDoctype(..) => "a DTD",
+            Error::InvalidEntity(..) => "a DTD entity",
+            Error::InvalidElementDecl(..) => "a DTD element",
+            Error::InvalidAttlistDecl(..) => "a DTD attribute list",
+            Error::InvalidNotationDecl(..) => "a DTD notation",
+            Error::InvalidElement(..) => "an element",
+            Error::InvalidAttribute(..) => "an attribute",
+            Error::InvalidCdata(..) => "a CDATA",
+            Error::InvalidCharData(..) => "a character data",
+            Error::UnknownToken(..)
+            | Error::TooManyEntities(..)
+            | Error::DtdTooLarge(..)
+            | Error::NestingTooDeep(..)
+            | Error::UnexpectedEndOfDocument(..)
+            | Error::UnexpectedBom(..)
+            | Error::MisplacedDeclaration(..) => "an element content",
+        }
+    }
+}
+
+impl fmt::Display for TruncationReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "unexpected end of stream inside {} at {}",
+            self.construct, self.pos
+        )?;
+
+        for (element, pos) in &self.open_elements {
+            write!(f, "\nunclosed element <")?;
+            if !element.prefix.is_empty() {
+                write!(f, "{}:", element.prefix)?;
+            }
+            write!(f, "{}> at {}", element.local, pos)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,659 @@
+//! Helpers for testing code that depends on the tokenizer output.
+//!
+//! Available only with the `testing` feature.
+
+use core::fmt::Write;
+use core::ops::Range;
+use std::format;
+use std::string::{String, ToString};
+use std::vec::Vec;
+
+use crate::{ElementEnd, RawKind, Token, Tokenizer};
+
+/// A builder of an expected token stream.
+///
+/// Tokens are compared by kind and text. Spans are compared only when set via `span`.
+/// On mismatch, both streams are printed with the first mismatched token marked.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::testing::TokenMatcher;
+///
+/// TokenMatcher::new()
+///     .element_start("", "svg")
+///     .span(0..4)
+///     .attribute("", "x", "1")
+///     .element_end_empty()
+///     .assert_matches(xmlparser::Tokenizer::from("<svg x='1'/>"));
+/// ```
+#[derive(Clone, Default, Debug)]
+pub struct TokenMatcher {
+    expected: Vec<(String, Option<Range<usize>>)>,
+}
+
+impl TokenMatcher {
+    /// Creates a new matcher that expects an empty token stream.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expects an XML declaration.
+    pub fn declaration(self, version: &str) -> Self {
+        self.push(format!("Declaration({:?})", version))
+    }
+
+    /// Expects a processing instruction.
+    pub fn pi(self, target: &str, content: Option<&str>) -> Self {
+        self.push(format!("PI({:?}, {:?})", target, content))
+    }
+
+    /// Expects a comment.
+    pub fn comment(self, text: &str) -> Self {
+        self.push(format!("Comment({:?})", text))
+    }
+
+    /// Expects a DOCTYPE start.
+    pub fn dtd_start(self, name: &str) -> Self {
+        self.push(format!("DtdStart({:?})", name))
+    }
+
+    /// Expects an empty DOCTYPE.
+    pub fn empty_dtd(self, name: &str) -> Self {
+        self.push(format!("EmptyDtd({:?})", name))
+    }
+
+    /// Expects an entity declaration.
+    pub fn entity_declaration(self, name: &str) -> Self {
+        self.push(format!("EntityDeclaration({:?})", name))
+    }
+
+    /// Expects an element declaration.
+    pub fn element_declaration(self, name: &str) -> Self {
+        self.push(format!("ElementDeclaration({:?})", name))
+    }
+
+    /// Expects an attribute list declaration.
+    pub fn attlist_declaration(self, name: &str) -> Self {
+        self.push(format!("AttlistDeclaration({:?})", name))
+    }
+
+    /// Expects a notation declaration.
+    pub fn notation_declaration(self, name: &str) -> Self {
+        self.push(format!("NotationDeclaration({:?})", name))
+    }
+
+    /// Expects a conditional section start.
+    pub fn conditional_section_start(self, ignore: bool) -> Self {
+        self.push(format!("ConditionalSectionStart({:?})", ignore))
+    }
+
+    /// Expects a conditional section end.
+    pub fn conditional_section_end(self) -> Self {
+        self.push(String::from("ConditionalSectionEnd"))
+    }
+
+    /// Expects a parameter entity reference.
+    pub fn parameter_entity_reference(self, name: &str) -> Self {
+        self.push(format!("ParameterEntityReference({:?})", name))
+    }
+
+    /// Expects a DOCTYPE end.
+    pub fn dtd_end(self) -> Self {
+        self.push(String::from("DtdEnd"))
+    }
+
+    /// Expects an element start.
+    pub fn element_start(self, prefix: &str, local: &str) -> Self {
+        self.push(format!("ElementStart({:?}, {:?})", prefix, local))
+    }
+
+    /// Expects an attribute.
+    pub fn attribute(self, prefix: &str, local: &str, value: &str) -> Self {
+        self.push(format!("Attribute({:?}, {:?}, {:?})", prefix, local, value))
+    }
+
+    /// Expects an open tag end, aka `>`.
+    pub fn element_end_open(self) -> Self {
+        self.push(String::from("ElementEnd(Open)"))
+    }
+
+    /// Expects a close tag, aka `</name>`.
+    pub fn element_end_close(self, prefix: &str, local: &str) -> Self {
+        self.push(format!("ElementEnd(Close({:?}, {:?}))", prefix, local))
+    }
+
+    /// Expects an empty element end, aka `/>`.
+    pub fn element_end_empty(self) -> Self {
+        self.push(String::from("ElementEnd(Empty)"))
+    }
+
+    /// Expects a text.
+    pub fn text(self, text: &str) -> Self {
+        self.push(format!("Text({:?})", text))
+    }
+
+    /// Expects a CDATA.
+    pub fn cdata(self, text: &str) -> Self {
+        self.push(format!("Cdata({:?})", text))
+    }
+
+    /// Expects a conditional comment start.
+    pub fn conditional_comment_start(self, condition: &str) -> Self {
+        self.push(format!("ConditionalCommentStart({:?})", condition))
+    }
+
+    /// Expects a conditional comment end.
+    pub fn conditional_comment_end(self) -> Self {
+        self.push(String::from("ConditionalCommentEnd"))
+    }
+
+    /// Expects a BOM.
+    pub fn bom(self) -> Self {
+        self.push(String::from("Bom"))
+    }
+
+    /// Expects a whitespace-only text.
+    pub fn whitespaces(self, text: &str) -> Self {
+        self.push(format!("Whitespaces({:?})", text))
+    }
+
+    /// Expects an unparsed content.
+    pub fn raw(self, kind: RawKind, text: &str) -> Self {
+        self.push(format!("Raw({:?}, {:?})", kind, text))
+    }
+
+    /// Expects an error with the specified message.
+    pub fn error(self, message: &str) -> Self {
+        self.push(format!("Error({:?})", message))
+    }
+
+    /// Sets an expected span of the last token.
+    ///
+    /// # Panics
+    ///
+    /// - When no tokens were added yet.
+    pub fn span(mut self, range: Range<usize>) -> Self {
+        let last = self
+            .expected
+            .last_mut()
+            .expect("no token to set a span for");
+        last.1 = Some(range);
+        self
+    }
+
+    /// Checks that the tokenizer output matches the expected tokens.
+    ///
+    /// Returns a report with both token streams on mismatch.
+    pub fn check(&self, tokenizer: Tokenizer) -> Result<(), String> {
+        let actual: Vec<_> = tokenizer.map(describe).collect();
+
+        let mismatch = (0..self.expected.len().max(actual.len())).find(|&i| {
+            match (self.expected.get(i), actual.get(i)) {
+                (Some((e_desc, e_range)), Some((a_desc, a_range))) => {
+                    e_desc != a_desc || (e_range.is_some() && e_range.as_ref() != Some(a_range))
+                }
+                _ => true,
+            }
+        });
+
+        let mismatch = match mismatch {
+            Some(idx) => idx,
+            None => return Ok(()),
+        };
+
+        let mut report = format!("token mismatch at index {}\n\nexpected:\n", mismatch);
+        for (i, (desc, range)) in self.expected.iter().enumerate() {
+            write_line(&mut report, i, i == mismatch, desc, range.as_ref());
+        }
+
+        report.push_str("actual:\n");
+        for (i, (desc, range)) in actual.iter().enumerate() {
+            write_line(&mut report, i, i == mismatch, desc, Some(range));
+        }
+
+        Err(report)
+    }
+
+    /// Asserts that the tokenizer output matches the expected tokens.
+    ///
+    /// # Panics
+    ///
+    /// - On mismatch, with a report from `check`.
+    pub fn assert_matches(&self, tokenizer: Tokenizer) {
+        if let Err(report) = self.check(tokenizer) {
+            panic!("{}", report);
+        }
+    }
+
+    fn push(mut self, desc: String) -> Self {
+        self.expected.push((desc, None));
+        self
+    }
+}
+
+fn write_line(
+    out: &mut String,
+    idx: usize,
+    marked: bool,
+    desc: &str,
+    range: Option<&Range<usize>>,
+) {
+    let marker = if marked { ">" } else { " " };
+    // Writing to a `String` cannot fail.
+    let _ = write!(out, "  {} {}: {}", marker, idx, desc);
+    if let Some(range) = range {
+        let _ = write!(out, " {:?}", range);
+    }
+    out.push('\n');
+}
+
+fn describe(token: Result<Token, crate::Error>) -> (String, Range<usize>) {
+    let token = match token {
+        Ok(token) => token,
+        Err(e) => {
+            let offset = e.offset();
+            return (format!("Error({:?})", e.to_string()), offset..offset);
+        }
+    };
+
+    let desc = match token {
+        Token::Declaration { version, .. } => format!("Declaration({:?})", version.as_str()),
+        Token::ProcessingInstruction {
+            target, content, ..
+        } => format!(
+            "PI({:?}, {:?})",
+            target.as_str(),
+            content.map(|v| v.as_str())
+        ),
+        Token::Comment { text, .. } => format!("Comment({:?})", text.as_str()),
+        Token::DtdStart { name, .. } => format!("DtdStart({:?})", name.as_str()),
+        Token::EmptyDtd { name, .. } => format!("EmptyDtd({:?})", name.as_str()),
+        Token::EntityDeclaration { name, .. } => {
+            format!("EntityDeclaration({:?})", name.as_str())
+        }
+        Token::ElementDeclaration { name, .. } => {
+            format!("ElementDeclaration({:?})", name.as_str())
+        }
+        Token::AttlistDeclaration { name, .. } => {
+            format!("AttlistDeclaration({:?})", name.as_str())
+        }
+        Token::NotationDeclaration { name, .. } => {
+            format!("NotationDeclaration({:?})", name.as_str())
+        }
+        Token::ConditionalSectionStart { ignore, .. } => {
+            format!("ConditionalSectionStart({:?})", ignore)
+        }
+        Token::ConditionalSectionEnd { .. } => String::from("ConditionalSectionEnd"),
+        Token::ParameterEntityReference { name, .. } => {
+            format!("ParameterEntityReference({:?})", name.as_str())
+        }
+        Token::DtdEnd { .. } => String::from("DtdEnd"),
+        Token::ElementStart { prefix, local, .. } => {
+            format!("ElementStart({:?}, {:?})", prefix.as_str(), local.as_str())
+        }
+        Token::Attribute {
+            prefix,
+            local,
+            value,
+            ..
+        } => format!(
+            "Attribute({:?}, {:?}, {:?})",
+            prefix.as_str(),
+            local.as_str(),
+            value.as_str()
+        ),
+        Token::ElementEnd { end, .. } => match end {
+            ElementEnd::Open => String::from("ElementEnd(Open)"),
+            ElementEnd::Close(prefix, local) => format!(
+                "ElementEnd(Close({:?}, {:?}))",
+                prefix.as_str(),
+                local.as_str()
+            ),
+            ElementEnd::Empty => String::from("ElementEnd(Empty)"),
+        },
+        Token::Text { text } => format!("Text({:?})", text.as_str()),
+        Token::Cdata { text, .. } => format!("Cdata({:?})", text.as_str()),
+        Token::ConditionalCommentStart { condition, .. } => {
+            format!("ConditionalCommentStart({:?})", condition.as_str())
+        }
+        Token::ConditionalCommentEnd { .. } => String::from("ConditionalCommentEnd"),
+        Token::Bom { .. } => String::from("Bom"),
+        Token::Whitespaces { text } => format!("Whitespaces({:?})", text.as_str()),
+        Token::Raw { kind, span } => format!("Raw({:?}, {:?})", kind, span.as_str()),
+    };
+
+    (desc, token.span().range())
+}
+
+/// A generated document with its expected token stream.
+///
+/// See [`DocumentGenerator`](struct.DocumentGenerator.html).
+#[derive(Clone, Debug)]
+pub struct GeneratedDocument {
+    /// The document text.
+    pub text: String,
+    /// The expected tokens, including spans.
+    pub tokens: TokenMatcher,
+}
+
+/// A generator of random well-formed documents.
+///
+/// Each document is generated alongside the token stream the tokenizer must produce
+/// with the default options. Useful for property testing.
+///
+/// The generator is deterministic: the same seed always produces the same documents.
+/// Generated documents contain only ASCII and don't exercise the lenient mode.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::testing::DocumentGenerator;
+///
+/// let mut gen = DocumentGenerator::new(42);
+/// for _ in 0..10 {
+///     let doc = gen.generate();
+///     doc.tokens.assert_matches(xmlparser::Tokenizer::from(doc.text.as_str()));
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct DocumentGenerator {
+    state: u64,
+    max_depth: usize,
+    max_children: usize,
+    max_attributes: usize,
+}
+
+impl DocumentGenerator {
+    /// Creates a new generator with the specified seed.
+    pub fn new(seed: u64) -> Self {
+        const MASK: u64 = 0x9E37_79B9_7F4A_7C15;
+
+        DocumentGenerator {
+            // Xorshift state must not be zero.
+            state: match seed ^ MASK {
+                0 => MASK,
+                state => state,
+            },
+            max_depth: 4,
+            max_children: 4,
+            max_attributes: 3,
+        }
+    }
+
+    /// Sets the maximum number of nested elements.
+    ///
+    /// Default: 4
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth.max(1);
+        self
+    }
+
+    /// Sets the maximum number of child nodes per element.
+    ///
+    /// Default: 4
+    pub fn max_children(mut self, count: usize) -> Self {
+        self.max_children = count;
+        self
+    }
+
+    /// Sets the maximum number of attributes per element.
+    ///
+    /// Default: 3
+    pub fn max_attributes(mut self, count: usize) -> Self {
+        self.max_attributes = count;
+        self
+    }
+
+    /// Generates the next document.
+    pub fn generate(&mut self) -> GeneratedDocument {
+        let mut doc = GeneratedDocument {
+            text: String::new(),
+            tokens: TokenMatcher::new(),
+        };
+
+        if self.chance(2) {
+            let version = if self.chance(4) { "1.1" } else { "1.0" };
+            let start = doc.text.len();
+            let _ = write!(doc.text, "<?xml version=\"{}\"?>", version);
+            doc.expect(start, |m| m.declaration(version));
+        }
+
+        self.gen_misc(&mut doc);
+
+        let root = self.gen_qname();
+        if self.chance(3) {
+            self.gen_doctype(&mut doc, &root);
+            self.gen_misc(&mut doc);
+        }
+
+        self.gen_element(&mut doc, Some(&root), 1);
+        self.gen_misc(&mut doc);
+        doc
+    }
+
+    fn gen_misc(&mut self, doc: &mut GeneratedDocument) {
+        for _ in 0..self.below(3) {
+            if self.chance(2) {
+                // Spaces outside the root element are not tokenized.
+                doc.text.push('\n');
+            }
+
+            if self.chance(2) {
+                self.gen_comment(doc);
+            } else {
+                self.gen_pi(doc);
+            }
+        }
+    }
+
+    fn gen_doctype(&mut self, doc: &mut GeneratedDocument, root: &str) {
+        let start = doc.text.len();
+        let _ = write!(doc.text, "<!DOCTYPE {}", root);
+        if self.chance(2) {
+            doc.text.push('>');
+            doc.expect(start, |m| m.empty_dtd(root));
+            return;
+        }
+
+        doc.text.push_str(" [");
+        doc.expect(start, |m| m.dtd_start(root));
+
+        for _ in 0..self.below(3) {
+            doc.text.push('\n');
+            let start = doc.text.len();
+            let name = self.gen_name();
+            if self.chance(2) {
+                let value = self.gen_chars(b"abc xyz", 0, 8);
+                let _ = write!(doc.text, "<!ENTITY {} '{}'>", name, value);
+                doc.expect(start, |m| m.entity_declaration(&name));
+            } else {
+                let _ = write!(doc.text, "<!ELEMENT {} ANY>", name);
+                doc.expect(start, |m| m.element_declaration(&name));
+            }
+        }
+
+        doc.text.push('\n');
+        let start = doc.text.len();
+        doc.text.push_str("]>");
+        doc.expect(start, |m| m.dtd_end());
+    }
+
+    fn gen_element(&mut self, doc: &mut GeneratedDocument, name: Option<&str>, depth: usize) {
+        let name = match name {
+            Some(name) => String::from(name),
+            None => self.gen_qname(),
+        };
+        let (prefix, local) = split_qname(&name);
+
+        let start = doc.text.len();
+        let _ = write!(doc.text, "<{}", name);
+        doc.expect(start, |m| m.element_start(prefix, local));
+
+        let mut attributes: Vec<String> = Vec::new();
+        for _ in 0..self.below(self.max_attributes + 1) {
+            let attr = self.gen_qname();
+            if attr == "xmlns" || attributes.contains(&attr) {
+                continue;
+            }
+
+            let quote = if self.chance(2) { '"' } else { '\'' };
+            let value = self.gen_chars(b"abc XYZ 012-", 0, 8);
+            doc.text.push(' ');
+            let start = doc.text.len();
+            let _ = write!(doc.text, "{}={}{}{}", attr, quote, value, quote);
+            let (attr_prefix, attr_local) = split_qname(&attr);
+            doc.expect(start, |m| m.attribute(attr_prefix, attr_local, &value));
+            attributes.push(attr);
+        }
+
+        let children = if depth < self.max_depth {
+            self.below(self.max_children + 1)
+        } else {
+            0
+        };
+
+        if children == 0 && self.chance(2) {
+            let start = doc.text.len();
+            doc.text.push_str("/>");
+            doc.expect(start, |m| m.element_end_empty());
+            return;
+        }
+
+        let start = doc.text.len();
+        doc.text.push('>');
+        doc.expect(start, |m| m.element_end_open());
+
+        // Adjacent text nodes are merged by the tokenizer.
+        let mut prev_text = false;
+        for _ in 0..children {
+            match self.below(5) {
+                0 | 1 if !prev_text => {
+                    self.gen_text(doc);
+                    prev_text = true;
+                    continue;
+                }
+                0..=2 => self.gen_element(doc, None, depth + 1),
+                3 => self.gen_comment(doc),
+                _ => {
+                    if self.chance(2) {
+                        self.gen_cdata(doc);
+                    } else {
+                        self.gen_pi(doc);
+                    }
+                }
+            }
+
+            prev_text = false;
+        }
+
+        let start = doc.text.len();
+        let _ = write!(doc.text, "</{}>", name);
+        doc.expect(start, |m| m.element_end_close(prefix, local));
+    }
+
+    fn gen_text(&mut self, doc: &mut GeneratedDocument) {
+        let mut text = self.gen_chars(b"abc XYZ 012\n\t'\">", 1, 10);
+        if self.chance(4) {
+            // References are not unescaped by the tokenizer.
+            text.push_str(if self.chance(2) { "&amp;" } else { "&#x20;" });
+        }
+
+        let start = doc.text.len();
+        doc.text.push_str(&text);
+        doc.expect(start, |m| m.text(&text));
+    }
+
+    fn gen_comment(&mut self, doc: &mut GeneratedDocument) {
+        let text = self.gen_chars(b"abc XYZ 012<>&", 0, 10);
+        let start = doc.text.len();
+        let _ = write!(doc.text, "<!--{}-->", text);
+        doc.expect(start, |m| m.comment(&text));
+    }
+
+    fn gen_cdata(&mut self, doc: &mut GeneratedDocument) {
+        let text = self.gen_chars(b"abc XYZ 012<>&", 0, 10);
+        let start = doc.text.len();
+        let _ = write!(doc.text, "<![CDATA[{}]]>", text);
+        doc.expect(start, |m| m.cdata(&text));
+    }
+
+    fn gen_pi(&mut self, doc: &mut GeneratedDocument) {
+        // Targets starting with `xml` are reserved.
+        let target = format!("pi-{}", self.gen_name());
+        // Leading spaces are not a part of the content.
+        let data = format!(
+            "{}{}",
+            self.gen_name(),
+            self.gen_chars(b"abc XYZ 012<>&", 0, 10)
+        );
+        let content = if self.chance(2) {
+            Some(data.as_str())
+        } else {
+            None
+        };
+
+        let start = doc.text.len();
+        match content {
+            Some(content) => {
+                let _ = write!(doc.text, "<?{} {}?>", target, content);
+            }
+            None => {
+                let _ = write!(doc.text, "<?{}?>", target);
+            }
+        }
+        doc.expect(start, |m| m.pi(&target, content));
+    }
+
+    fn gen_qname(&mut self) -> String {
+        let local = self.gen_name();
+        if self.chance(4) {
+            format!("{}:{}", self.gen_name(), local)
+        } else {
+            local
+        }
+    }
+
+    fn gen_name(&mut self) -> String {
+        let mut name = self.gen_chars(b"abcdefghijklmnopqrstuvwxyz", 1, 1);
+        name.push_str(&self.gen_chars(b"abcxyz012-._", 0, 5));
+        name
+    }
+
+    fn gen_chars(&mut self, alphabet: &[u8], min: usize, max: usize) -> String {
+        let len = min + self.below(max - min + 1);
+        (0..len)
+            .map(|_| char::from(alphabet[self.below(alphabet.len())]))
+            .collect()
+    }
+
+    fn chance(&mut self, n: usize) -> bool {
+        self.below(n) == 0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        // Xorshift64*.
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        let v = self.state.wrapping_mul(0x2545_F491_4F6C_DD1D);
+        (v >> 32) as usize % n.max(1)
+    }
+}
+
+impl GeneratedDocument {
+    fn expect<F>(&mut self, start: usize, f: F)
+    where
+        F: FnOnce(TokenMatcher) -> TokenMatcher,
+    {
+        let tokens = core::mem::replace(&mut self.tokens, TokenMatcher::new());
+        self.tokens = f(tokens).span(start..self.text.len());
+    }
+}
+
+fn split_qname(name: &str) -> (&str, &str) {
+    match name.find(':') {
+        Some(idx) => (&name[..idx], &name[idx + 1..]),
+        None => ("", name),
+    }
+}
@@ -0,0 +1,963 @@
+use core::ops::Range;
+
+#[cfg(feature = "alloc")]
+use alloc::borrow::Cow;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use crate::{EntityDefinition, EntityMap};
+use crate::{Reference, StrSpan, Stream, StreamError, XmlByteExt, XmlStreamExt};
+
+/// A whitespace handling mode.
+///
+/// Mirrors the [`xml:space`](https://www.w3.org/TR/xml/#sec-white-space) attribute.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum XmlSpace {
+    /// Consecutive whitespaces are collapsed into a single space.
+    Default,
+    /// Whitespaces are preserved. Only line breaks are normalized.
+    Preserve,
+}
+
+/// A numeric character reference that doesn't map to an XML character handling.
+///
+/// Like `&#0;`, `&#xD800;` or `&#x110000;`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum InvalidCharRef {
+    /// Stops unescaping with an `InvalidReference` error.
+    Error,
+    /// Replaces the reference with U+FFFD REPLACEMENT CHARACTER.
+    Replace,
+    /// Removes the reference.
+    Skip,
+}
+
+/// A custom entities resolver.
+///
+/// Implemented for closures and, with the `std` feature, for `EntityMap`,
+/// which resolves internal entities.
+///
+/// See [`TextUnescape::set_entity_resolver`](struct.TextUnescape.html#method.set_entity_resolver).
+pub trait EntityResolver<'a> {
+    /// Returns a replacement text of the entity.
+    ///
+    /// Returns `None` for unknown entities.
+    fn resolve(&self, name: &str) -> Option<&'a str>;
+}
+
+impl<'a, F: Fn(&str) -> Option<&'a str>> EntityResolver<'a> for F {
+    #[inline]
+    fn resolve(&self, name: &str) -> Option<&'a str> {
+        self(name)
+    }
+}
+
+// A wrapper that allows `TextUnescape` to derive `Debug`.
+#[derive(Clone, Copy)]
+struct Resolver<'a>(&'a dyn EntityResolver<'a>);
+
+impl core::fmt::Debug for Resolver<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str("EntityResolver")
+    }
+}
+
+/// An XML text unescaper.
+///
+/// Resolves character references and predefined entity references,
+/// normalizes line breaks and processes whitespaces according to `XmlSpace`.
+/// Unknown entity references and malformed references are preserved as is,
+/// unless an entity resolver is set.
+///
+/// Yields UTF-8 bytes and doesn't allocate.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::{TextUnescape, XmlSpace};
+///
+/// let bytes: Vec<u8> = TextUnescape::new("&lt;a\n  b&gt;", XmlSpace::Default).collect();
+/// assert_eq!(bytes, b"<a b>");
+/// ```
+#[derive(Clone, Debug)]
+pub struct TextUnescape<'a> {
+    stream: Stream<'a>,
+    xml_space: XmlSpace,
+    invalid_char_ref: InvalidCharRef,
+    buf: [u8; 4],
+    buf_pos: usize,
+    buf_len: usize,
+    resolver: Option<Resolver<'a>>,
+    // The rest of the current entity replacement text.
+    replacement: &'a [u8],
+    error: Option<StreamError>,
+}
+
+impl<'a> TextUnescape<'a> {
+    /// Creates a new `TextUnescape`.
+    pub fn new(text: &'a str, xml_space: XmlSpace) -> Self {
+        TextUnescape {
+            stream: Stream::from(text),
+            xml_space,
+            invalid_char_ref: InvalidCharRef::Replace,
+            buf: [0; 4],
+            buf_pos: 0,
+            buf_len: 0,
+            resolver: None,
+            replacement: &[],
+            error: None,
+        }
+    }
+
+    /// Sets a resolver for entities that are not predefined.
+    ///
+    /// A replacement text is inserted as is, without unescaping
+    /// and whitespaces processing. Unknown entity references are preserved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{TextUnescape, XmlSpace};
+    ///
+    /// let resolver = |name: &str| if name == "nbsp" { Some("\u{A0}") } else { None };
+    /// let mut iter = TextUnescape::new("a&nbsp;&amp;&b;", XmlSpace::Default);
+    /// iter.set_entity_resolver(&resolver);
+    /// assert_eq!(iter.chars().collect::<String>(), "a\u{A0}&&b;");
+    /// ```
+    pub fn set_entity_resolver(&mut self, resolver: &'a dyn EntityResolver<'a>) {
+        self.resolver = Some(Resolver(resolver));
+    }
+
+    /// Sets how invalid numeric character references should be handled.
+    ///
+    /// `InvalidCharRef::Replace` by default.
+    pub fn set_invalid_char_ref(&mut self, value: InvalidCharRef) {
+        self.invalid_char_ref = value;
+    }
+
+    /// Returns an error that stopped the unescaping.
+    ///
+    /// Can be set only when `InvalidCharRef::Error` is used.
+    pub fn error(&self) -> Option<StreamError> {
+        self.error
+    }
+
+    /// Unescapes the text into a `String`.
+    ///
+    /// Invalid numeric character references are replaced with U+FFFD.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{TextUnescape, XmlSpace};
+    ///
+    /// assert_eq!(TextUnescape::unescape("&#1234;&#xD800;", XmlSpace::Preserve), "Ӓ\u{FFFD}");
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn unescape(text: &str, xml_space: XmlSpace) -> String {
+        let bytes: Vec<u8> = TextUnescape::new(text, xml_space).collect();
+        // The output is always a valid UTF-8 string, so unwrap is safe.
+        String::from_utf8(bytes).unwrap()
+    }
+
+    /// Unescapes the text, borrowing it when nothing has to be changed.
+    ///
+    /// Invalid numeric character references are replaced with U+FFFD.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::borrow::Cow;
+    /// use xmlparser::{TextUnescape, XmlSpace};
+    ///
+    /// assert!(match TextUnescape::unescape_cow("a b", XmlSpace::Default) {
+    ///     Cow::Borrowed(_) => true,
+    ///     Cow::Owned(_) => false,
+    /// });
+    /// assert_eq!(TextUnescape::unescape_cow("a  &amp;", XmlSpace::Default), "a &");
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn unescape_cow(text: &str, xml_space: XmlSpace) -> Cow<'_, str> {
+        let bytes = text.as_bytes();
+        let is_unchanged = match xml_space {
+            XmlSpace::Default => {
+                !bytes
+                    .iter()
+                    .any(|c| matches!(*c, b'&' | b'\t' | b'\n' | b'\r'))
+                    && !text.contains("  ")
+            }
+            XmlSpace::Preserve => !bytes.iter().any(|c| *c == b'&' || *c == b'\r'),
+        };
+
+        if is_unchanged {
+            Cow::Borrowed(text)
+        } else {
+            Cow::Owned(Self::unescape(text, xml_space))
+        }
+    }
+
+    /// Unescapes the text into a `String` using the specified invalid references handling.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidReference` - when `InvalidCharRef::Error` is used
+    ///   and an invalid numeric character reference was found
+    #[cfg(feature = "alloc")]
+    pub fn try_unescape(
+        text: &str,
+        xml_space: XmlSpace,
+        invalid_char_ref: InvalidCharRef,
+    ) -> Result<String, StreamError> {
+        let mut iter = TextUnescape::new(text, xml_space);
+        iter.set_invalid_char_ref(invalid_char_ref);
+        let bytes: Vec<u8> = iter.by_ref().collect();
+        if let Some(e) = iter.error() {
+            return Err(e);
+        }
+
+        // The output is always a valid UTF-8 string, so unwrap is safe.
+        Ok(String::from_utf8(bytes).unwrap())
+    }
+
+    /// Converts the iterator into one that yields chars.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{TextUnescape, XmlSpace};
+    ///
+    /// let iter = TextUnescape::new("&#x416;&lt;", XmlSpace::Default);
+    /// assert_eq!(iter.chars().collect::<String>(), "Ж<");
+    /// ```
+    pub fn chars(self) -> TextUnescapeChars<'a> {
+        TextUnescapeChars { iter: self }
+    }
+
+    fn push_char(&mut self, c: char) -> u8 {
+        self.buf_len = c.encode_utf8(&mut self.buf).len();
+        self.buf_pos = 1;
+        self.buf[0]
+    }
+
+    fn next_replacement_byte(&mut self) -> Option<u8> {
+        let (c, rest) = self.replacement.split_first()?;
+        self.replacement = rest;
+        Some(*c)
+    }
+
+    // Returns `None` when the reference is malformed or unknown
+    // and `Some(None)` when the reference should be skipped
+    // or was resolved into a replacement text.
+    fn consume_reference(&mut self) -> Option<Option<char>> {
+        let mut s = self.stream;
+        if s.starts_with(b"&#") {
+            let c = s.consume_char_reference().ok()?;
+            self.stream = s;
+            match c {
+                Some(c) => Some(Some(c)),
+                None => Some(handle_invalid_char_ref(
+                    self.invalid_char_ref,
+                    &mut self.stream,
+                    &mut self.error,
+                )),
+            }
+        } else {
+            match s.consume_reference() {
+                Ok(Reference::Char(c)) => {
+                    self.stream = s;
+                    Some(Some(c))
+                }
+                Ok(Reference::Entity(name)) => {
+                    self.replacement = self.resolver?.0.resolve(name)?.as_bytes();
+                    self.stream = s;
+                    Some(None)
+                }
+                Err(_) => None,
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for TextUnescape<'a> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf_pos < self.buf_len {
+            self.buf_pos += 1;
+            return Some(self.buf[self.buf_pos - 1]);
+        }
+
+        if let Some(c) = self.next_replacement_byte() {
+            return Some(c);
+        }
+
+        while !self.stream.at_end() {
+            let c = self.stream.curr_byte_unchecked();
+            if c == b'&' {
+                match self.consume_reference() {
+                    Some(Some(c)) => return Some(self.push_char(c)),
+                    Some(None) => {
+                        if let Some(c) = self.next_replacement_byte() {
+                            return Some(c);
+                        }
+                    }
+                    None => {
+                        self.stream.advance(1);
+                        return Some(c);
+                    }
+                }
+            } else if c.is_xml_space() && self.xml_space == XmlSpace::Default {
+                self.stream.skip_spaces();
+                return Some(b' ');
+            } else if c == b'\r' {
+                // Line breaks normalization. `\r\n` and `\r` are converted into `\n`.
+                self.stream.advance(1);
+                self.stream.try_consume_byte(b'\n');
+                return Some(b'\n');
+            } else {
+                self.stream.advance(1);
+                return Some(c);
+            }
+        }
+
+        None
+    }
+}
+
+/// A char-oriented `TextUnescape` iterator.
+///
+/// See [`TextUnescape::chars`](struct.TextUnescape.html#method.chars).
+#[derive(Clone, Debug)]
+pub struct TextUnescapeChars<'a> {
+    iter: TextUnescape<'a>,
+}
+
+impl<'a> TextUnescapeChars<'a> {
+    /// Returns an error that stopped the unescaping.
+    ///
+    /// Can be set only when `InvalidCharRef::Error` is used.
+    pub fn error(&self) -> Option<StreamError> {
+        self.iter.error()
+    }
+}
+
+impl<'a> Iterator for TextUnescapeChars<'a> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let first = self.iter.next()?;
+        let len = match first {
+            0x00...0x7F => return Some(first as char),
+            0xC0...0xDF => 2,
+            0xE0...0xEF => 3,
+            _ => 4,
+        };
+
+        // `TextUnescape` always yields a valid UTF-8, so a char cannot be truncated.
+        let mut buf = [first, 0, 0, 0];
+        for b in buf.iter_mut().take(len).skip(1) {
+            *b = self.iter.next()?;
+        }
+
+        core::str::from_utf8(&buf[..len]).ok()?.chars().next()
+    }
+}
+
+/// A part of a text split by references.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum TextPart<'a> {
+    /// A text without references.
+    ///
+    /// Malformed references are treated as a literal text.
+    Literal(StrSpan<'a>),
+    /// A character reference or a predefined entity reference
+    /// with the referenced char and the reference span.
+    CharRef(char, StrSpan<'a>),
+    /// An entity reference with the entity name and the reference span.
+    EntityRef(&'a str, StrSpan<'a>),
+}
+
+/// An iterator over text parts split by references.
+///
+/// Unlike `TextUnescape`, doesn't modify the text and preserves spans,
+/// which are relative to the original document.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::{TextPart, TextParts, Token, Tokenizer};
+///
+/// let mut p = Tokenizer::from("<p>a&lt;&b;</p>");
+/// p.next();
+/// p.next();
+/// if let Some(Ok(Token::Text { text })) = p.next() {
+///     let parts: Vec<_> = TextParts::new(text).collect();
+///     match parts.as_slice() {
+///         [TextPart::Literal(a), TextPart::CharRef('<', _), TextPart::EntityRef("b", b)] => {
+///             assert_eq!(a.as_str(), "a");
+///             assert_eq!(b.range(), 8..11);
+///         }
+///         _ => unreachable!(),
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct TextParts<'a> {
+    stream: Stream<'a>,
+    offset: usize,
+}
+
+impl<'a> TextParts<'a> {
+    /// Creates a new `TextParts` iterator.
+    pub fn new(text: StrSpan<'a>) -> Self {
+        TextParts {
+            stream: Stream::from(text.as_str()),
+            offset: text.start(),
+        }
+    }
+}
+
+impl<'a> Iterator for TextParts<'a> {
+    type Item = TextPart<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.stream.at_end() {
+            return None;
+        }
+
+        let start = self.stream.pos();
+        if let Some(r) = self.stream.try_consume_reference() {
+            let span = self.stream.slice_back(start).shift(self.offset);
+            return Some(match r {
+                Reference::Char(c) => TextPart::CharRef(c, span),
+                Reference::Entity(name) => TextPart::EntityRef(name, span),
+            });
+        }
+
+        // The current byte is either not a reference or a malformed one.
+        self.stream.advance(1);
+        loop {
+            self.stream.skip_bytes(|_, c| c != b'&');
+            if self.stream.at_end() {
+                break;
+            }
+
+            // Check the reference on a copy, so the stream will stay at `&`.
+            let mut s = self.stream;
+            if s.try_consume_reference().is_some() {
+                break;
+            }
+
+            self.stream.advance(1);
+        }
+
+        Some(TextPart::Literal(
+            self.stream.slice_back(start).shift(self.offset),
+        ))
+    }
+}
+
+/// An iterator over references inside a text.
+///
+/// Yields character references, including predefined entity references,
+/// and entity references along with their ranges.
+/// Ranges are relative to the original document. Malformed references are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::{Reference, StrSpan};
+///
+/// let refs: Vec<_> = StrSpan::from("a&lt;b&c;&d").references().collect();
+/// assert_eq!(refs, vec![(1..5, Reference::Char('<')), (6..9, Reference::Entity("c"))]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct References<'a> {
+    stream: Stream<'a>,
+    offset: usize,
+}
+
+impl<'a> References<'a> {
+    /// Creates a new `References` iterator.
+    pub fn new(text: StrSpan<'a>) -> Self {
+        References {
+            stream: Stream::from(text.as_str()),
+            offset: text.start(),
+        }
+    }
+}
+
+impl<'a> Iterator for References<'a> {
+    type Item = (Range<usize>, Reference<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.stream.skip_bytes(|_, c| c != b'&');
+            if self.stream.at_end() {
+                return None;
+            }
+
+            let start = self.stream.pos();
+            if let Some(r) = self.stream.try_consume_reference() {
+                let span = self.stream.slice_back(start).shift(self.offset);
+                return Some((span.range(), r));
+            }
+
+            self.stream.advance(1);
+        }
+    }
+}
+
+/// A part of an entity value split by references.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum EntityValuePart<'a> {
+    /// A text without references.
+    ///
+    /// Malformed references are treated as a literal text.
+    Literal(StrSpan<'a>),
+    /// A character reference with the referenced char and the reference span.
+    CharRef(char, StrSpan<'a>),
+    /// A general entity reference with the entity name and the reference span.
+    ///
+    /// Predefined entities, like `&lt;`, are reported as is,
+    /// since they are not expanded inside entity values.
+    EntityRef(&'a str, StrSpan<'a>),
+    /// A parameter entity reference with the entity name and the reference span.
+    ParameterEntityRef(&'a str, StrSpan<'a>),
+}
+
+/// An iterator over entity value parts split by references.
+///
+/// Splits an `EntityDefinition::EntityValue` according to
+/// the [EntityValue](https://www.w3.org/TR/xml/#NT-EntityValue) grammar.
+/// Spans are relative to the original document.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::{EntityDefinition, EntityValuePart, EntityValueParts, Token, Tokenizer};
+///
+/// let mut p = Tokenizer::from("<!DOCTYPE a [<!ENTITY b 'x&#65;%c;&d;'>]><a/>");
+/// p.next();
+/// if let Some(Ok(Token::EntityDeclaration {
+///     definition: EntityDefinition::EntityValue(value),
+///     ..
+/// })) = p.next()
+/// {
+///     let parts: Vec<_> = EntityValueParts::new(value).collect();
+///     match parts.as_slice() {
+///         [EntityValuePart::Literal(x), EntityValuePart::CharRef('A', _),
+///          EntityValuePart::ParameterEntityRef("c", c), EntityValuePart::EntityRef("d", _)] => {
+///             assert_eq!(x.as_str(), "x");
+///             assert_eq!(c.range(), 31..34);
+///         }
+///         _ => unreachable!(),
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct EntityValueParts<'a> {
+    stream: Stream<'a>,
+    offset: usize,
+}
+
+impl<'a> EntityValueParts<'a> {
+    /// Creates a new `EntityValueParts` iterator.
+    pub fn new(value: StrSpan<'a>) -> Self {
+        EntityValueParts {
+            stream: Stream::from(value.as_str()),
+            offset: value.start(),
+        }
+    }
+
+    // Consumes a reference at the current position, if any.
+    fn try_consume_reference(&mut self) -> Option<EntityValuePart<'a>> {
+        let start = self.stream.pos();
+        let mut s = self.stream;
+        let part = if s.starts_with(b"&#") {
+            let c = match s.consume_reference().ok()? {
+                Reference::Char(c) => c,
+                Reference::Entity(_) => return None,
+            };
+
+            EntityValuePart::CharRef(c, s.slice_back(start).shift(self.offset))
+        } else {
+            let is_pe = match s.curr_byte().ok()? {
+                b'&' => false,
+                b'%' => true,
+                _ => return None,
+            };
+
+            s.advance(1);
+            let name = s.consume_name().ok()?.as_str();
+            s.consume_byte(b';').ok()?;
+            let span = s.slice_back(start).shift(self.offset);
+            if is_pe {
+                EntityValuePart::ParameterEntityRef(name, span)
+            } else {
+                EntityValuePart::EntityRef(name, span)
+            }
+        };
+
+        self.stream = s;
+        Some(part)
+    }
+}
+
+impl<'a> Iterator for EntityValueParts<'a> {
+    type Item = EntityValuePart<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.stream.at_end() {
+            return None;
+        }
+
+        let start = self.stream.pos();
+        if let Some(part) = self.try_consume_reference() {
+            return Some(part);
+        }
+
+        // The current byte is either not a reference or a malformed one.
+        self.stream.advance(1);
+        loop {
+            self.stream.skip_bytes(|_, c| c != b'&' && c != b'%');
+            if self.stream.at_end() {
+                break;
+            }
+
+            // Check the reference on a copy, so the stream will stay at the reference start.
+            if self.clone().try_consume_reference().is_some() {
+                break;
+            }
+
+            self.stream.advance(1);
+        }
+
+        Some(EntityValuePart::Literal(
+            self.stream.slice_back(start).shift(self.offset),
+        ))
+    }
+}
+
+/// An XML attribute value normalizer.
+///
+/// Applies the [attribute-value normalization](https://www.w3.org/TR/xml/#AVNormalize):
+/// resolves character references and entity references
+/// and replaces each whitespace character with a space.
+/// `\r\n` is treated as a single whitespace.
+///
+/// By default, only predefined entity references are resolved.
+/// Unknown entity references and malformed references are preserved as is.
+///
+/// Yields chars and doesn't allocate, unless custom entities are set.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::AttrValueNormalizer;
+///
+/// let value: String = AttrValueNormalizer::new("a\r\n&lt;b&#x9;").collect();
+/// assert_eq!(value, "a <b\t");
+///
+/// let mut iter = AttrValueNormalizer::new("  a \n b ");
+/// iter.set_collapse_spaces(true);
+/// assert_eq!(iter.collect::<String>(), "a b");
+/// ```
+#[derive(Clone, Debug)]
+pub struct AttrValueNormalizer<'a> {
+    stream: Stream<'a>,
+    #[cfg(feature = "std")]
+    entities: Option<&'a EntityMap<'a>>,
+    // Streams and names of the entities that are being expanded.
+    #[cfg(feature = "std")]
+    stack: Vec<(Stream<'a>, &'a str)>,
+    // The outermost reference start, the number of produced bytes
+    // and the number of expanded references.
+    // Used to apply the `EntityMap` size limit.
+    #[cfg(feature = "std")]
+    reference_start: usize,
+    #[cfg(feature = "std")]
+    size: usize,
+    #[cfg(feature = "std")]
+    expanded: usize,
+    collapse_spaces: bool,
+    invalid_char_ref: InvalidCharRef,
+    has_chars: bool,
+    pending_space: bool,
+    pending_char: Option<char>,
+    error: Option<StreamError>,
+}
+
+impl<'a> AttrValueNormalizer<'a> {
+    /// Creates a new `AttrValueNormalizer`.
+    pub fn new(text: &'a str) -> Self {
+        AttrValueNormalizer {
+            stream: Stream::from(text),
+            #[cfg(feature = "std")]
+            entities: None,
+            #[cfg(feature = "std")]
+            stack: Vec::new(),
+            #[cfg(feature = "std")]
+            reference_start: 0,
+            #[cfg(feature = "std")]
+            size: 0,
+            #[cfg(feature = "std")]
+            expanded: 0,
+            collapse_spaces: false,
+            invalid_char_ref: InvalidCharRef::Replace,
+            has_chars: false,
+            pending_space: false,
+            pending_char: None,
+            error: None,
+        }
+    }
+
+    /// Enables spaces collapsing.
+    ///
+    /// Leading and trailing spaces are removed and sequences of spaces are replaced
+    /// by a single space. The spec requires this for all attributes
+    /// that are not declared as `CDATA`.
+    ///
+    /// Disabled by default.
+    pub fn set_collapse_spaces(&mut self, flag: bool) {
+        self.collapse_spaces = flag;
+    }
+
+    /// Sets how invalid numeric character references should be handled.
+    ///
+    /// `InvalidCharRef::Replace` by default.
+    pub fn set_invalid_char_ref(&mut self, value: InvalidCharRef) {
+        self.invalid_char_ref = value;
+    }
+
+    /// Sets entities that should be resolved in addition to the predefined ones.
+    ///
+    /// Only internal entities are resolved. References to external and unparsed entities
+    /// are preserved as is. A recursive entity reference or references nested deeper
+    /// than the `EntityMap` depth limit stop the normalization with an `InvalidReference` error.
+    /// A value larger than the `EntityMap` size limit, as well as more expanded references
+    /// than the size limit, stop the normalization with a `LengthLimitExceeded` error
+    /// pointing to the outermost reference.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{AttrValueNormalizer, EntityMap, Tokenizer};
+    ///
+    /// let mut p = Tokenizer::from("<!DOCTYPE a [<!ENTITY b 'x\ny'>]><a/>");
+    /// let entities = EntityMap::collect(&mut p).unwrap();
+    /// let mut iter = AttrValueNormalizer::new("&b;!");
+    /// iter.set_entities(&entities);
+    /// assert_eq!(iter.collect::<String>(), "x y!");
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn set_entities(&mut self, entities: &'a EntityMap<'a>) {
+        self.entities = Some(entities);
+    }
+
+    /// Returns an error that stopped the normalization.
+    pub fn error(&self) -> Option<StreamError> {
+        self.error
+    }
+
+    /// Normalizes an attribute value into a `String`.
+    ///
+    /// Invalid numeric character references are replaced with U+FFFD.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::AttrValueNormalizer;
+    ///
+    /// assert_eq!(AttrValueNormalizer::normalize("a\tb&#xA;"), "a b\n");
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn normalize(text: &str) -> String {
+        AttrValueNormalizer::new(text).collect()
+    }
+
+    // Returns normalized chars without spaces collapsing.
+    fn next_raw_char(&mut self) -> Option<char> {
+        let c = self.next_unlimited_char()?;
+
+        #[cfg(feature = "std")]
+        {
+            if let Some(entities) = self.entities {
+                self.size += c.len_utf8();
+                if self.size > entities.size_limit() {
+                    self.set_size_limit_error();
+                    return None;
+                }
+            }
+        }
+
+        Some(c)
+    }
+
+    #[cfg(feature = "std")]
+    fn set_size_limit_error(&mut self) {
+        let (root, pos) = match self.stack.first() {
+            Some(&(stream, _)) => (stream, self.reference_start),
+            None => (self.stream, self.stream.pos()),
+        };
+
+        self.error = Some(StreamError::LengthLimitExceeded(
+            root.gen_text_pos_from(pos),
+            pos,
+        ));
+        self.stack.clear();
+        self.stream.jump_to_end();
+    }
+
+    fn next_unlimited_char(&mut self) -> Option<char> {
+        loop {
+            if self.error.is_some() {
+                return None;
+            }
+
+            if self.stream.at_end() {
+                #[cfg(feature = "std")]
+                {
+                    if let Some((stream, _)) = self.stack.pop() {
+                        self.stream = stream;
+                        continue;
+                    }
+                }
+
+                return None;
+            }
+
+            match self.stream.curr_byte_unchecked() {
+                b'&' => match self.consume_reference() {
+                    Some(Some(c)) => return Some(c),
+                    Some(None) => continue,
+                    None => {
+                        self.stream.advance(1);
+                        return Some('&');
+                    }
+                },
+                b'\r' => {
+                    self.stream.advance(1);
+                    self.stream.try_consume_byte(b'\n');
+                    return Some(' ');
+                }
+                b' ' | b'\t' | b'\n' => {
+                    self.stream.advance(1);
+                    return Some(' ');
+                }
+                _ => {
+                    let c = self.stream.chars().next()?;
+                    self.stream.advance(c.len_utf8());
+                    return Some(c);
+                }
+            }
+        }
+    }
+
+    // Returns `None` when the reference is malformed, unknown or external
+    // and `Some(None)` when the reference should be skipped.
+    fn consume_reference(&mut self) -> Option<Option<char>> {
+        let mut s = self.stream;
+        if s.starts_with(b"&#") {
+            let c = s.consume_char_reference().ok()?;
+            self.stream = s;
+            return match c {
+                Some(c) => Some(Some(c)),
+                None => Some(handle_invalid_char_ref(
+                    self.invalid_char_ref,
+                    &mut self.stream,
+                    &mut self.error,
+                )),
+            };
+        }
+
+        match s.consume_reference() {
+            Ok(Reference::Char(c)) => {
+                self.stream = s;
+                Some(Some(c))
+            }
+            #[cfg(feature = "std")]
+            Ok(Reference::Entity(name)) => {
+                let value = match self.entities?.get(name) {
+                    Some(EntityDefinition::EntityValue(value)) => value,
+                    _ => return None,
+                };
+
+                if self.stack.iter().any(|&(_, n)| n == name)
+                    || self.stack.len() == self.entities?.depth_limit()
+                {
+                    self.error = Some(StreamError::InvalidReference);
+                    return Some(None);
+                }
+
+                if self.stack.is_empty() {
+                    self.reference_start = self.stream.pos();
+                }
+
+                // Empty entities do not produce chars, so the amount of work is limited separately.
+                self.expanded += 1;
+                if self.expanded > self.entities?.size_limit() {
+                    self.set_size_limit_error();
+                    return Some(None);
+                }
+
+                self.stack.push((s, name));
+                self.stream = Stream::from(value);
+                Some(None)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Iterator for AttrValueNormalizer<'a> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(c) = self.pending_char.take() {
+            return Some(c);
+        }
+
+        if !self.collapse_spaces {
+            return self.next_raw_char();
+        }
+
+        loop {
+            // Trailing spaces are dropped at the end of the value.
+            let c = self.next_raw_char()?;
+            if c == ' ' {
+                self.pending_space = self.has_chars;
+                continue;
+            }
+
+            self.has_chars = true;
+            if self.pending_space {
+                self.pending_space = false;
+                self.pending_char = Some(c);
+                return Some(' ');
+            }
+
+            return Some(c);
+        }
+    }
+}
+
+// Returns `None` when the reference should be skipped.
+fn handle_invalid_char_ref(
+    mode: InvalidCharRef,
+    stream: &mut Stream,
+    error: &mut Option<StreamError>,
+) -> Option<char> {
+    match mode {
+        InvalidCharRef::Error => {
+            *error = Some(StreamError::InvalidReference);
+            stream.jump_to_end();
+            None
+        }
+        InvalidCharRef::Replace => Some('\u{FFFD}'),
+        InvalidCharRef::Skip => None,
+    }
+}
@@ -0,0 +1,277 @@
+//! A well-formedness checking layer on top of the tokenizer.
+//!
+//! Available only with the `std` feature.
+
+use core::fmt;
+use std::error;
+use std::vec::Vec;
+
+use crate::close::check_close_tag_in;
+use crate::tags::QName;
+use crate::{ElementEnd, Error, MismatchedTag, OpenElement, StrSpan, TextPos, Token, Tokenizer};
+
+/// A well-formedness error.
+///
+/// Each error contains text positions of all the involved tags.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum WellFormedError<'a> {
+    /// A tokenizer error.
+    Syntax(Error),
+
+    /// A close tag name doesn't match the open tag name.
+    MismatchedCloseTag(MismatchedTag<'a>),
+
+    /// A close tag without an open element.
+    ///
+    /// Contains the close tag span, like `</b>`, and its position.
+    UnexpectedCloseTag(StrSpan<'a>, TextPos),
+
+    /// An element that was not closed before the end of the document.
+    ///
+    /// Contains the open element and its position.
+    UnclosedElement(OpenElement<'a>, TextPos),
+
+    /// An element after the root element.
+    ///
+    /// Contains the root element, its position, the second root element and its position.
+    MultipleRootElements(OpenElement<'a>, TextPos, OpenElement<'a>, TextPos),
+
+    /// An attribute that was already set on the same element.
+    ///
+    /// Contains both attribute spans and positions, the first one goes first.
+    DuplicatedAttribute(StrSpan<'a>, TextPos, StrSpan<'a>, TextPos),
+}
+
+impl WellFormedError<'_> {
+    /// Returns the error position.
+    ///
+    /// For errors that involve several tags, this is the position of the last one.
+    pub fn pos(&self) -> TextPos {
+        match *self {
+            WellFormedError::Syntax(ref e) => e.pos(),
+            WellFormedError::MismatchedCloseTag(ref e) => e.close_pos,
+            WellFormedError::UnexpectedCloseTag(_, pos) => pos,
+            WellFormedError::UnclosedElement(_, pos) => pos,
+            WellFormedError::MultipleRootElements(_, _, _, pos) => pos,
+            WellFormedError::DuplicatedAttribute(_, _, _, pos) => pos,
+        }
+    }
+}
+
+impl fmt::Display for WellFormedError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WellFormedError::Syntax(ref e) => write!(f, "{}", e),
+            WellFormedError::MismatchedCloseTag(ref e) => write!(f, "{}", e),
+            WellFormedError::UnexpectedCloseTag(close, pos) => {
+                write!(f, "unexpected close tag {} at {}", close, pos)
+            }
+            WellFormedError::UnclosedElement(ref open, pos) => {
+                write!(f, "unclosed element <{}> at {}", QName::from(open), pos)
+            }
+            WellFormedError::MultipleRootElements(ref root, root_pos, ref elem, pos) => {
+                write!(
+                    f,
+                    "element <{}> at {} is after the root element <{}> at {}",
+                    QName::from(elem),
+                    pos,
+                    QName::from(root),
+                    root_pos
+                )
+            }
+            WellFormedError::DuplicatedAttribute(_, first_pos, attr, pos) => {
+                write!(
+                    f,
+                    "duplicated attribute {} at {}, first set at {}",
+                    attr, pos, first_pos
+                )
+            }
+        }
+    }
+}
+
+impl error::Error for WellFormedError<'_> {
+    fn description(&self) -> &str {
+        "an XML well-formedness error"
+    }
+}
+
+/// A tokenizer adaptor that checks the document well-formedness.
+///
+/// In addition to the tokenizer errors, it reports:
+///
+/// - close tags that don't match the open ones
+/// - elements that were not closed before the end of the document
+/// - multiple root elements, even in the fragment mode
+/// - duplicated attributes
+///
+/// Attribute names are compared as is, without namespace resolution.
+///
+/// Like the tokenizer, it stops after the first error.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::validate::WellFormedChecker;
+///
+/// let mut p = WellFormedChecker::new(xmlparser::Tokenizer::from("<a>\n  <b></a>"));
+/// let err = p.find_map(|t| t.err()).unwrap();
+/// assert_eq!(
+///     err.to_string(),
+///     "close tag </a> at 2:6 doesn't match the open tag <b> at 2:3"
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct WellFormedChecker<'a> {
+    tokenizer: Tokenizer<'a>,
+    open_elements: Vec<OpenElement<'a>>,
+    attributes: Vec<(StrSpan<'a>, StrSpan<'a>, StrSpan<'a>)>,
+    root: Option<OpenElement<'a>>,
+    done: bool,
+}
+
+impl<'a> WellFormedChecker<'a> {
+    /// Creates a new checker.
+    pub fn new(tokenizer: Tokenizer<'a>) -> Self {
+        WellFormedChecker {
+            tokenizer,
+            open_elements: Vec::new(),
+            attributes: Vec::new(),
+            root: None,
+            done: false,
+        }
+    }
+
+    /// Returns the underlying tokenizer.
+    pub fn into_inner(self) -> Tokenizer<'a> {
+        self.tokenizer
+    }
+
+    /// Returns a list of currently open elements, from the outermost one.
+    pub fn open_elements(&self) -> &[OpenElement<'a>] {
+        &self.open_elements
+    }
+
+    fn text_pos(&self, span: StrSpan) -> TextPos {
+        self.tokenizer.stream_ref().gen_text_pos_from(span.start())
+    }
+
+    fn check(&mut self, token: &Token<'a>) -> Option<WellFormedError<'a>> {
+        match *token {
+            Token::ElementStart {
+                prefix,
+                local,
+                span,
+            } => {
+                let elem = OpenElement {
+                    prefix,
+                    local,
+                    span,
+                };
+
+                if self.open_elements.is_empty() {
+                    match self.root {
+                        Some(root) => {
+                            return Some(WellFormedError::MultipleRootElements(
+                                root,
+                                self.text_pos(root.span),
+                                elem,
+                                self.text_pos(span),
+                            ));
+                        }
+                        None => self.root = Some(elem),
+                    }
+                }
+
+                self.open_elements.push(elem);
+                self.attributes.clear();
+            }
+            Token::Attribute {
+                prefix,
+                local,
+                span,
+                ..
+            } => {
+                let first = self
+                    .attributes
+                    .iter()
+                    .find(|(p, l, _)| p.as_str() == prefix.as_str() && l.as_str() == local.as_str())
+                    .map(|&(_, _, span)| span);
+
+                if let Some(first) = first {
+                    return Some(WellFormedError::DuplicatedAttribute(
+                        first,
+                        self.text_pos(first),
+                        span,
+                        self.text_pos(span),
+                    ));
+                }
+
+                self.attributes.push((prefix, local, span));
+            }
+            Token::ElementEnd {
+                end: ElementEnd::Empty,
+                ..
+            } => {
+                self.open_elements.pop();
+            }
+            Token::ElementEnd {
+                end: ElementEnd::Close(..),
+                span,
+            } => match self.open_elements.pop() {
+                Some(open) => {
+                    let start = Token::ElementStart {
+                        prefix: open.prefix,
+                        local: open.local,
+                        span: open.span,
+                    };
+
+                    let s = self.tokenizer.stream_ref();
+                    if let Err(e) = check_close_tag_in(s, start, *token) {
+                        return Some(WellFormedError::MismatchedCloseTag(e));
+                    }
+                }
+                None => {
+                    return Some(WellFormedError::UnexpectedCloseTag(
+                        span,
+                        self.text_pos(span),
+                    ));
+                }
+            },
+            _ => {}
+        }
+
+        None
+    }
+}
+
+impl<'a> Iterator for WellFormedChecker<'a> {
+    type Item = Result<Token<'a>, WellFormedError<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let t = match self.tokenizer.next() {
+            Some(Ok(token)) => match self.check(&token) {
+                Some(e) => Err(e),
+                None => Ok(token),
+            },
+            Some(Err(e)) => Err(WellFormedError::Syntax(e)),
+            None => match self.open_elements.last() {
+                Some(&open) => Err(WellFormedError::UnclosedElement(
+                    open,
+                    self.text_pos(open.span),
+                )),
+                None => return None,
+            },
+        };
+
+        if t.is_err() {
+            self.done = true;
+        }
+
+        Some(t)
+    }
+}
@@ -0,0 +1,299 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{ElementEnd, StrSpan, Token};
+
+/// An indentation used by [`TokenWriter`].
+///
+/// [`TokenWriter`]: struct.TokenWriter.html
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Indent {
+    /// Tokens are written as is.
+    None,
+    /// Indent with the specified amount of spaces.
+    Spaces(u8),
+    /// Indent with tabs.
+    Tabs,
+}
+
+/// An XML writer that consumes tokens.
+///
+/// Text, attribute values and other token parts are already escaped by the tokenizer,
+/// so they are written as is. Use [`write_text_escaped`] and [`write_attribute_escaped`]
+/// to write unescaped strings.
+///
+/// Element tags are normalized: attributes are separated by a single space
+/// and values are quoted with `"`, unless they contain one.
+/// Unterminated comments and CDATA from the partial mode are closed.
+///
+/// Spaces outside of the root element are not tokenized, therefore they are not preserved.
+///
+/// Tokens must be written in the tokenizer order. No validation is performed.
+///
+/// # Pretty-printing
+///
+/// When an indentation is set, whitespace-only text is skipped and each tag starts
+/// on a new line. Once an element contains a non-whitespace text, the rest of its
+/// content is written as is, to preserve mixed content.
+/// Since tokens are not buffered, children written before the first text
+/// are still indented, which adds whitespaces to the element text.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::{Indent, TokenWriter, Tokenizer};
+///
+/// let mut w = TokenWriter::new();
+/// w.set_indent(Indent::Spaces(2));
+/// for token in Tokenizer::from("<a><b x='1'>text</b><c/></a>") {
+///     w.write_token(&token.unwrap());
+/// }
+/// assert_eq!(w.into_string(), "<a>\n  <b x=\"1\">text</b>\n  <c/>\n</a>");
+/// ```
+///
+/// [`write_text_escaped`]: #method.write_text_escaped
+/// [`write_attribute_escaped`]: #method.write_attribute_escaped
+#[derive(Clone, Debug)]
+pub struct TokenWriter {
+    buf: String,
+    indent: Indent,
+    // Whether the current element or its parent contains a non-whitespace text,
+    // one per open element.
+    mixed: Vec<bool>,
+    // Whether the last written token was a start tag end.
+    after_open: bool,
+}
+
+impl Default for TokenWriter {
+    fn default() -> Self {
+        TokenWriter {
+            buf: String::new(),
+            indent: Indent::None,
+            mixed: Vec::new(),
+            after_open: false,
+        }
+    }
+}
+
+impl TokenWriter {
+    /// Creates a new writer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the indentation.
+    ///
+    /// `Indent::None` by default.
+    pub fn set_indent(&mut self, indent: Indent) {
+        self.indent = indent;
+    }
+
+    /// Writes a token.
+    pub fn write_token(&mut self, token: &Token) {
+        match *token {
+            Token::Comment { text, .. } => {
+                self.write_newline();
+                self.push_all(&["<!--", text.as_str(), "-->"]);
+            }
+            Token::ProcessingInstruction {
+                target, content, ..
+            } => {
+                self.write_newline();
+                self.push_all(&["<?", target.as_str()]);
+                if let Some(content) = content {
+                    self.push_all(&[" ", content.as_str()]);
+                }
+                self.buf.push_str("?>");
+            }
+            Token::ElementStart { prefix, local, .. } => {
+                self.write_newline();
+                self.buf.push('<');
+                self.write_qname(prefix, local);
+                // Children of a mixed content element are written as is too.
+                let mixed = self.is_mixed();
+                self.mixed.push(mixed);
+            }
+            Token::Attribute {
+                prefix,
+                local,
+                value,
+                ..
+            } => {
+                let quote = if value.as_str().contains('"') {
+                    "'"
+                } else {
+                    "\""
+                };
+                self.buf.push(' ');
+                self.write_qname(prefix, local);
+                self.push_all(&["=", quote, value.as_str(), quote]);
+            }
+            Token::ElementEnd { end, .. } => match end {
+                ElementEnd::Open => {
+                    self.buf.push('>');
+                    self.after_open = true;
+                    return;
+                }
+                ElementEnd::Empty => {
+                    self.buf.push_str("/>");
+                    self.mixed.pop();
+                }
+                ElementEnd::Close(prefix, local) => {
+                    let mixed = self.mixed.pop().unwrap_or(false);
+                    if !mixed && !self.after_open {
+                        self.write_newline();
+                    }
+
+                    self.buf.push_str("</");
+                    self.write_qname(prefix, local);
+                    self.buf.push('>');
+                }
+            },
+            Token::Text { text } => {
+                if !self.is_pretty() || self.is_mixed() {
+                    self.buf.push_str(text.as_str());
+                } else if !text.as_str().trim().is_empty() {
+                    self.write_text_start();
+                    self.buf.push_str(text.as_str());
+                } else {
+                    // Whitespace-only text is replaced by the indentation.
+                    return;
+                }
+            }
+            Token::Whitespaces { text } => {
+                if self.is_pretty() && !self.is_mixed() {
+                    return;
+                }
+
+                self.buf.push_str(text.as_str());
+            }
+            Token::Cdata { text, .. } => {
+                self.write_text_start();
+                self.push_all(&["<![CDATA[", text.as_str(), "]]>"]);
+            }
+            Token::DtdEnd { .. } => {
+                self.write_newline();
+                self.buf.push_str("]>");
+            }
+            _ => {
+                self.write_newline();
+                self.buf.push_str(token.span().as_str());
+            }
+        }
+
+        self.after_open = false;
+    }
+
+    /// Writes a text, escaping `&`, `<` and `>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut w = xmlparser::TokenWriter::new();
+    /// w.write_text_escaped("a < b & c");
+    /// assert_eq!(w.into_string(), "a &lt; b &amp; c");
+    /// ```
+    pub fn write_text_escaped(&mut self, text: &str) {
+        if self.is_pretty() && !self.is_mixed() && text.trim().is_empty() {
+            return;
+        }
+
+        self.write_text_start();
+        self.write_escaped(text, false);
+        self.after_open = false;
+    }
+
+    /// Writes an attribute, escaping `&`, `<` and `"` in the value.
+    ///
+    /// Must be called between `ElementStart` and `ElementEnd` tokens.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{Token, TokenWriter};
+    ///
+    /// let mut w = TokenWriter::new();
+    /// w.write_token(&Token::ElementStart {
+    ///     prefix: "".into(),
+    ///     local: "a".into(),
+    ///     span: "<a".into(),
+    /// });
+    /// w.write_attribute_escaped("", "b", "\"1 & 2\"");
+    /// assert_eq!(w.into_string(), "<a b=\"&quot;1 &amp; 2&quot;\"");
+    /// ```
+    pub fn write_attribute_escaped(&mut self, prefix: &str, local: &str, value: &str) {
+        self.buf.push(' ');
+        self.write_qname(prefix.into(), local.into());
+        self.buf.push_str("=\"");
+        self.write_escaped(value, true);
+        self.buf.push('"');
+    }
+
+    /// Returns the written text.
+    pub fn as_str(&self) -> &str {
+        &self.buf
+    }
+
+    /// Returns the written text.
+    pub fn into_string(self) -> String {
+        self.buf
+    }
+
+    fn is_pretty(&self) -> bool {
+        self.indent != Indent::None
+    }
+
+    fn is_mixed(&self) -> bool {
+        self.mixed.last() == Some(&true)
+    }
+
+    fn push_all(&mut self, parts: &[&str]) {
+        for part in parts {
+            self.buf.push_str(part);
+        }
+    }
+
+    fn write_qname(&mut self, prefix: StrSpan, local: StrSpan) {
+        if !prefix.is_empty() {
+            self.push_all(&[prefix.as_str(), ":"]);
+        }
+        self.buf.push_str(local.as_str());
+    }
+
+    fn write_text_start(&mut self) {
+        if let Some(mixed) = self.mixed.last_mut() {
+            *mixed = true;
+        }
+    }
+
+    fn write_newline(&mut self) {
+        if !self.is_pretty() || self.buf.is_empty() || self.is_mixed() {
+            return;
+        }
+
+        self.buf.push('\n');
+        for _ in 0..self.mixed.len() {
+            match self.indent {
+                Indent::None => {}
+                Indent::Spaces(n) => {
+                    for _ in 0..n {
+                        self.buf.push(' ');
+                    }
+                }
+                Indent::Tabs => self.buf.push('\t'),
+            }
+        }
+    }
+
+    fn write_escaped(&mut self, text: &str, attribute: bool) {
+        for c in text.chars() {
+            match c {
+                '&' => self.buf.push_str("&amp;"),
+                '<' => self.buf.push_str("&lt;"),
+                '>' if !attribute => self.buf.push_str("&gt;"),
+                '"' if attribute => self.buf.push_str("&quot;"),
+                _ => self.buf.push(c),
+            }
+        }
+    }
+}
@@ -1,5 +1,8 @@
 extern crate xmlparser;
 
+#[cfg(feature = "alloc")]
+use std::borrow::Cow;
+
 use xmlparser::*;
 
 #[test]
@@ -23,9 +26,21 @@ fn text_pos_3() {
     assert_eq!(s.gen_text_pos(), TextPos::new(2, 3));
 }
 
+#[test]
+fn stream_eq_1() {
+    // Text position options are not compared.
+    let mut s1 = Stream::from("a\nb");
+    let mut s2 = s1;
+    s1.set_track_text_pos(true);
+    s2.set_defer_text_pos(true);
+    assert_eq!(s1, s2);
+    s1.advance(1);
+    assert_ne!(s1, s2);
+}
+
 #[test]
 fn token_size() {
-    assert!(::std::mem::size_of::<Token>() <= 196);
+    assert!(::std::mem::size_of::<Token>() <= 128);
 }
 
 #[test]
@@ -42,3 +57,595 @@ fn err_size_1() {
 fn err_size_2() {
     assert!(::std::mem::size_of::<StreamError>() <= 64);
 }
+
+#[test]
+fn fingerprint_1() {
+    let text = "<a><b/><c/></a>";
+    let mut p1 = Tokenizer::from(text);
+    let mut p2 = Tokenizer::from(text);
+    assert_eq!(p1.fingerprint(), p2.fingerprint());
+
+    p1.next();
+    assert_ne!(p1.fingerprint(), p2.fingerprint());
+
+    p2.next();
+    assert_eq!(p1.fingerprint(), p2.fingerprint());
+}
+
+#[test]
+fn fingerprint_2() {
+    let text = "<a><b/><c/></a>";
+    let mut p1 = Tokenizer::from_fragment(text, 3..11);
+    for _ in 0..2 {
+        p1.next();
+    }
+
+    let p2 = Tokenizer::from_fragment(text, 7..11);
+    assert_eq!(p1.fingerprint(), p2.fingerprint());
+}
+
+#[test]
+fn with_source_1() {
+    let text = "<?xml version='1.0'?>\n<!DOCTYPE a>\n<a b = '1'>text<![CDATA[x]]></a>";
+    let sources: Vec<_> = Tokenizer::from(text)
+        .with_source()
+        .map(|t| t.unwrap().1)
+        .collect();
+    assert_eq!(
+        sources,
+        vec![
+            "<?xml version='1.0'?>",
+            "<!DOCTYPE a>",
+            "<a",
+            "b = '1'",
+            ">",
+            "text",
+            "<![CDATA[x]]>",
+            "</a>",
+        ]
+    );
+}
+
+#[test]
+fn with_source_2() {
+    let text = "\n<!-- c -->\n<a  b='1'\n/> ";
+    let mut iter = Tokenizer::from(text).with_source();
+    iter.set_lossless(true);
+    let sources: Vec<_> = iter.map(|t| t.unwrap().1).collect();
+    assert_eq!(sources, vec!["\n<!-- c -->", "\n<a", "  b='1'", "\n/>"]);
+}
+
+#[test]
+fn with_source_3() {
+    let text = "<a><b/></a>";
+    let mut iter = Tokenizer::from_fragment(text, 3..7).with_source();
+    iter.set_lossless(true);
+    let sources: Vec<_> = iter.map(|t| t.unwrap().1).collect();
+    assert_eq!(sources, vec!["<b", "/>"]);
+}
+
+#[test]
+fn with_source_5() {
+    // An external subset has its own text.
+    let text = "<!DOCTYPE a SYSTEM 'a.dtd' [ <!ENTITY b 'c'> ]><a/>";
+    let mut p = Tokenizer::from(text);
+    p.next();
+    p.set_external_subset(" <!ENTITY d 'e'>");
+    let mut iter = p.with_source();
+    iter.set_lossless(true);
+    let sources: Vec<_> = iter.map(|t| t.unwrap().1).collect();
+    assert_eq!(
+        sources,
+        vec![" <!ENTITY b 'c'>", " <!ENTITY d 'e'>", " ]>", "<a", "/>"]
+    );
+}
+
+#[test]
+fn with_source_6() {
+    let text = "<!DOCTYPE a SYSTEM 'a.dtd'><a/>";
+    let mut p = Tokenizer::from(text);
+    p.next();
+    p.set_external_subset("<!ENTITY d 'e'>");
+    let mut iter = p.with_source();
+    iter.set_lossless(true);
+    let sources: Vec<_> = iter.map(|t| t.unwrap().1).collect();
+    assert_eq!(sources, vec!["<!ENTITY d 'e'>", "<a", "/>"]);
+}
+
+#[test]
+fn with_source_4() {
+    let mut iter = Tokenizer::from("<a b></a>").with_source();
+    assert!(iter.next().unwrap().is_ok());
+    assert!(iter.next().unwrap().is_err());
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn with_progress_1() {
+    let text = "<a>text</a>  ";
+    let mut progress = Vec::new();
+    let count = Tokenizer::from(text)
+        .with_progress(0, |pos| progress.push(pos))
+        .count();
+    assert_eq!(count, 4);
+    // Trailing spaces are reported at the end.
+    assert_eq!(progress, vec![2, 3, 7, 11, 13]);
+}
+
+#[test]
+fn with_progress_2() {
+    let text = "<a><b c></a>";
+    let mut progress = Vec::new();
+    {
+        let mut iter = Tokenizer::from(text).with_progress(100, |pos| progress.push(pos));
+        assert_eq!(iter.tokenizer().position(), 0);
+        assert_eq!(iter.by_ref().count(), 4);
+        assert!(iter.next().is_none());
+    }
+    // An error jumps to the end.
+    assert_eq!(progress, vec![12]);
+}
+
+fn filtered(iter: TokenFilter<'_>) -> Vec<&str> {
+    iter.map(|t| t.unwrap().span().as_str()).collect()
+}
+
+#[test]
+fn token_filter_1() {
+    let text = "<!-- a --><?b?><c><!-- d --> <?e?></c>";
+    assert_eq!(
+        filtered(Tokenizer::from(text).skip_comments()),
+        vec!["<?b?>", "<c", ">", " ", "<?e?>", "</c>"]
+    );
+    assert_eq!(
+        filtered(Tokenizer::from(text).skip_pis()),
+        vec!["<!-- a -->", "<c", ">", "<!-- d -->", " ", "</c>"]
+    );
+    assert_eq!(
+        filtered(Tokenizer::from(text).significant_whitespace_only()),
+        vec![
+            "<!-- a -->",
+            "<?b?>",
+            "<c",
+            ">",
+            "<!-- d -->",
+            "<?e?>",
+            "</c>"
+        ]
+    );
+}
+
+#[test]
+fn token_filter_2() {
+    let text = "<a> \t\r\n<b> x </b></a>";
+    assert_eq!(
+        filtered(Tokenizer::from(text).significant_whitespace_only()),
+        vec!["<a", ">", "<b", ">", " x ", "</b>", "</a>"]
+    );
+}
+
+#[test]
+fn token_filter_3() {
+    let mut iter = Tokenizer::from("<a><!-- b --><c d></a>").skip_comments();
+    assert!(iter.nth(1).unwrap().is_ok());
+    assert!(iter.next().unwrap().is_ok()); // <c
+    assert!(iter.next().unwrap().is_err());
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn position_1() {
+    let text = "<a><b/></a>";
+    let mut p = Tokenizer::from(text);
+    let mut positions = Vec::new();
+    while let Some(token) = p.next() {
+        token.unwrap();
+        positions.push(p.position());
+        assert_eq!(p.stream_ref().pos(), p.position());
+    }
+    assert_eq!(positions, vec![2, 3, 5, 7, 11]);
+}
+
+#[test]
+fn position_2() {
+    let text = "<a><b c></a><d/>";
+    let mut p = Tokenizer::from(text);
+    p.set_recover(true);
+    p.nth(2).unwrap().unwrap();
+    assert!(p.next().unwrap().is_err());
+    assert_eq!(p.position(), 8);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn normalized_value_1() {
+    let mut p = Tokenizer::from("<a b='x y' c='&lt;\t&#x20;'/>");
+    assert_eq!(p.next().unwrap().unwrap().normalized_value(), None);
+
+    match p.next().unwrap().unwrap().normalized_value().unwrap() {
+        Cow::Borrowed(value) => assert_eq!(value, "x y"),
+        Cow::Owned(_) => unreachable!(),
+    }
+
+    let token = p.next().unwrap().unwrap();
+    match token {
+        Token::Attribute { value, .. } => assert_eq!(value.as_str(), "&lt;\t&#x20;"),
+        _ => unreachable!(),
+    }
+    assert_eq!(token.normalized_value().unwrap(), "<  ");
+}
+
+#[test]
+fn prolog_1() {
+    let text =
+        "<?xml version='1.0'?>\n<!-- c -->\n<!DOCTYPE a [<!ENTITY b 'c'>]>\n<?pi?>\n<a>text</a>";
+    let mut p = Tokenizer::from(text);
+    assert_eq!(p.prolog().count(), 6);
+    assert_eq!(p.prolog().count(), 0);
+    match p.next().unwrap().unwrap() {
+        Token::ElementStart { local, .. } => assert_eq!(local.as_str(), "a"),
+        _ => unreachable!(),
+    }
+    assert_eq!(p.count(), 3);
+}
+
+#[test]
+fn prolog_2() {
+    let mut p = Tokenizer::from("  <a/>");
+    assert_eq!(p.prolog().count(), 0);
+    assert_eq!(p.count(), 2);
+}
+
+#[test]
+fn prolog_3() {
+    let mut p = Tokenizer::from("<!DOCTYPE a>text");
+    let tokens: Vec<_> = p.prolog().collect();
+    assert_eq!(tokens.len(), 2);
+    assert!(tokens[1].is_err());
+}
+
+#[test]
+fn prolog_4() {
+    let mut p = Tokenizer::from_fragment("<a/><b/>", 0..8);
+    assert_eq!(p.prolog().count(), 0);
+}
+
+#[test]
+fn prolog_5() {
+    // A BOM is skipped.
+    let mut p = Tokenizer::from("\u{FEFF}<a/>");
+    assert_eq!(p.prolog().count(), 0);
+    assert_eq!(p.count(), 2);
+}
+
+#[test]
+fn track_text_pos_1() {
+    let text = "<root>\n  <a b></a>\n  <!-- -- -->\n  <c>\u{414}&#x0;</c>\n  <d e='<'/>\n</root>";
+    let errors = |track| {
+        let mut p = Tokenizer::from(text);
+        p.set_recover(true);
+        p.set_track_text_pos(track);
+        p.filter_map(|t| t.err())
+            .map(|e| (e.pos(), e.stream_error().and_then(|e| e.pos())))
+            .collect::<Vec<_>>()
+    };
+
+    let expected = errors(false);
+    assert_eq!(expected.len(), 3);
+    assert_eq!(errors(true), expected);
+}
+
+#[test]
+fn track_text_pos_2() {
+    let mut s = Stream::from("a\n\u{414}b\nc");
+    s.set_track_text_pos(true);
+    s.advance(4);
+    assert_eq!(s.gen_text_pos(), TextPos::new(2, 2));
+    s.sync_text_pos();
+    assert_eq!(s.gen_text_pos(), TextPos::new(2, 2));
+    assert_eq!(s.gen_text_pos_from(1), TextPos::new(1, 2));
+    assert_eq!(s.gen_text_pos_from(6), TextPos::new(3, 1));
+}
+
+#[test]
+fn checkpoint_1() {
+    let text = "<!DOCTYPE a [<!ENTITY b 'c'>]><a>text</a>";
+    let mut p = Tokenizer::from(text);
+    p.next().unwrap().unwrap();
+    let checkpoint = p.checkpoint();
+    let tokens1: Vec<_> = p.by_ref().map(|t| t.unwrap().span().range()).collect();
+    assert!(p.next().is_none());
+
+    p.restore(checkpoint).unwrap();
+    let tokens2: Vec<_> = p.map(|t| t.unwrap().span().range()).collect();
+    assert_eq!(tokens1, tokens2);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn checkpoint_2() {
+    // Open elements are restored too.
+    let mut p = Tokenizer::from("<a><b></b></a>");
+    p.set_track_tags(true);
+    p.nth(3).unwrap().unwrap();
+    let checkpoint = p.checkpoint();
+    assert_eq!(p.open_elements().len(), 2);
+    p.nth(1).unwrap().unwrap();
+    assert_eq!(p.open_elements().len(), 0);
+
+    p.restore(checkpoint).unwrap();
+    assert_eq!(p.open_elements().len(), 2);
+    assert_eq!(p.count(), 2);
+}
+
+#[test]
+fn checkpoint_3() {
+    // Errors are reproducible after a restore.
+    let mut p = Tokenizer::from("<a><b x></a>");
+    p.nth(2).unwrap().unwrap();
+    let checkpoint = p.checkpoint();
+    let err1 = p.next().unwrap().unwrap_err();
+    assert!(p.next().is_none());
+
+    p.restore(checkpoint).unwrap();
+    let err2 = p.next().unwrap().unwrap_err();
+    assert_eq!(err1, err2);
+}
+
+#[test]
+fn checkpoint_4() {
+    // A checkpoint of another text is rejected.
+    let text = String::from("<a/>");
+    let mut p1 = Tokenizer::from(text.as_str());
+    let mut p2 = Tokenizer::from("<a/>");
+    p2.next().unwrap().unwrap();
+    assert!(p1.restore(p2.checkpoint()).is_err());
+    assert_eq!(p1.position(), 0);
+
+    // Including a different fragment of the same text.
+    let text = "<a><b/></a>";
+    let mut p1 = Tokenizer::from(text);
+    let p2 = Tokenizer::from_fragment(text, 3..7);
+    assert!(p1.restore(p2.checkpoint()).is_err());
+}
+
+#[cfg(feature = "alloc")]
+fn token_ranges(p: Tokenizer) -> Vec<Result<std::ops::Range<usize>, Error>> {
+    p.map(|t| t.map(|t| t.span().range())).collect()
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn resume_1() {
+    let text = "<!DOCTYPE r [<!ENTITY b 'c'>]><r>\n<a x='1'/>\n<b>text</b>\n<c/></r>";
+    let mut p = Tokenizer::from(text);
+    p.set_track_tags(true);
+
+    // Save checkpoints after each tag at depth 1.
+    let mut checkpoints = Vec::new();
+    while let Some(t) = p.next() {
+        if let Ok(Token::ElementEnd { .. }) = t {
+            if p.open_elements().len() == 1 {
+                checkpoints.push(p.checkpoint());
+            }
+        }
+    }
+    assert_eq!(checkpoints.len(), 4);
+
+    // Edit the `b` element text.
+    let edit_start = text.find("text").unwrap();
+    let new_text = text.replace("text", "new <d/> text");
+    let checkpoint = checkpoints
+        .iter()
+        .rev()
+        .find(|c| c.offset() < edit_start)
+        .unwrap();
+    let resumed = p.resume(checkpoint, &new_text).unwrap();
+    assert_eq!(resumed.open_elements().len(), 1);
+
+    let mut full = Tokenizer::from(new_text.as_str());
+    full.set_track_tags(true);
+    let expected: Vec<_> = token_ranges(full)
+        .into_iter()
+        .skip_while(|t| t.as_ref().unwrap().start < checkpoint.offset())
+        .collect();
+    assert_eq!(token_ranges(resumed), expected);
+}
+
+#[test]
+fn resume_2() {
+    // Errors after the checkpoint are reported for the new text.
+    let mut p = Tokenizer::from("<a><b/></a>");
+    p.nth(3).unwrap().unwrap();
+    let checkpoint = p.checkpoint();
+    let mut p = p.resume(&checkpoint, "<a><b/><c x></a>").unwrap();
+    assert_eq!(p.next().unwrap().unwrap().span().as_str(), "<c");
+    assert_eq!(
+        p.next().unwrap().unwrap_err().to_string(),
+        "invalid attribute at 1:10 cause expected '=' not '>' at 1:12"
+    );
+}
+
+#[test]
+fn resume_3() {
+    // The edited text is too short.
+    let mut p = Tokenizer::from("<a><b/></a>");
+    p.nth(3).unwrap().unwrap();
+    let checkpoint = p.checkpoint();
+    assert!(p.resume(&checkpoint, "<a>").is_none());
+    assert!(p.resume(&checkpoint, "<a>\u{444} \u{444}").is_none());
+    assert!(p.resume(&checkpoint, "<a><b/>").is_some());
+}
+
+#[test]
+fn resume_4() {
+    // Checkpoints with an unfinished external subset cannot be resumed.
+    let text = "<!DOCTYPE a SYSTEM 'a.dtd' [<!ENTITY b 'c'>]><a/>";
+    let mut p = Tokenizer::from(text);
+    p.next().unwrap().unwrap();
+    p.set_external_subset("<!ENTITY d 'e'><!ENTITY f 'g'>");
+    let pending = p.checkpoint();
+    p.nth(1).unwrap().unwrap(); // <!ENTITY d 'e'>
+    let inside = p.checkpoint();
+    assert!(p.resume(&pending, text).is_none());
+    assert!(p.resume(&inside, text).is_none());
+
+    p.nth(1).unwrap().unwrap(); // DtdEnd
+    let after = p.checkpoint();
+    let mut p = p.resume(&after, text).unwrap();
+    assert_eq!(p.next().unwrap().unwrap().span().as_str(), "<a");
+}
+
+#[test]
+fn attributes_1() {
+    let mut p = Tokenizer::from("<a x:b='1' c=\"2\">text</a>");
+    p.next().unwrap().unwrap();
+    let mut attrs = p.attributes();
+    let attr = attrs.next().unwrap().unwrap();
+    assert_eq!(attr.prefix.as_str(), "x");
+    assert_eq!(attr.local.as_str(), "b");
+    assert_eq!(attr.value.as_str(), "1");
+    assert_eq!(attr.span.range(), 3..10);
+    assert_eq!(attrs.element_end(), None);
+    assert_eq!(attrs.next().unwrap().unwrap().span.as_str(), "c=\"2\"");
+    assert!(attrs.next().is_none());
+    assert_eq!(attrs.element_end().unwrap().1.range(), 16..17);
+    assert!(attrs.next().is_none());
+
+    match p.next().unwrap().unwrap() {
+        Token::Text { text } => assert_eq!(text.as_str(), "text"),
+        t => panic!("{:?}", t),
+    }
+}
+
+#[test]
+fn attributes_2() {
+    // Not in a start tag.
+    let mut p = Tokenizer::from("<a/>");
+    assert!(p.attributes().next().is_none());
+    assert!(p.next().unwrap().is_ok());
+    assert!(p.attributes().next().is_none());
+    assert!(p.attributes().element_end().is_none());
+}
+
+#[test]
+fn attributes_3() {
+    let mut p = Tokenizer::from("<a b='1' c></a>");
+    p.next().unwrap().unwrap();
+    let mut attrs = p.attributes();
+    assert!(attrs.next().unwrap().is_ok());
+    assert!(attrs.next().unwrap().is_err());
+    assert!(attrs.next().is_none());
+    assert!(attrs.element_end().is_none());
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn line_index_1() {
+    let text = "<a>\n\n  <b>\r\n  привет</b>\n</a>\n";
+    let index = LineIndex::new(text);
+    assert_eq!(index.line_count(), 6);
+    for (offset, _) in text.char_indices() {
+        assert_eq!(
+            index.text_pos(offset),
+            Stream::from(text).gen_text_pos_from(offset)
+        );
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn line_index_2() {
+    let texts = [
+        "<a>\n<b>\n<c x></b></a>",
+        "<a>\nтекст\n<!-- - -- --></a>",
+        "\n\n<a/>\n<b/>",
+    ];
+    for text in texts.iter() {
+        let index = LineIndex::new(text);
+        let mut p = Tokenizer::from(*text);
+        p.set_line_index(&index);
+        let err1 = p.find_map(|t| t.err()).unwrap();
+        let err2 = Tokenizer::from(*text).find_map(|t| t.err()).unwrap();
+        assert_eq!(err1, err2);
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn line_index_3() {
+    // An index of a different text is ignored.
+    let text = "<a>\nтекст\n<b x></a>";
+    let other = "\n\n\n\n\n\n\n\n\n\n\n\n\nтекст\n\n";
+    let index = LineIndex::new(other);
+    let mut p = Tokenizer::from(text);
+    p.set_line_index(&index);
+    let err1 = p.find_map(|t| t.err()).unwrap();
+    let err2 = Tokenizer::from(text).find_map(|t| t.err()).unwrap();
+    assert_eq!(err1, err2);
+}
+
+fn spans(p: Tokenizer<'_>) -> Vec<Result<&str, String>> {
+    p.map(|t| t.map(|t| t.span().as_str()).map_err(|e| e.to_string()))
+        .collect()
+}
+
+#[test]
+fn builder_01() {
+    let text = "<a><b/></a>";
+    let builder = TokenizerBuilder::new();
+    assert_eq!(spans(builder.build(text)), spans(Tokenizer::from(text)));
+}
+
+#[test]
+fn builder_02() {
+    let builder = TokenizerBuilder::new()
+        .fragment(3..12)
+        .full_tag_spans(true)
+        .max_text_len(2);
+    let text = "<a>1<b/>234</a>";
+    assert_eq!(
+        spans(builder.build(text)),
+        vec![
+            Ok("1"),
+            Ok("<b"),
+            Ok("<b/>"),
+            Err(
+                "invalid character data at 1:9 cause the length limit is exceeded at 1:9"
+                    .to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn locate_01() {
+    let text = "<?xml version='1.0'?>\n<!DOCTYPE a []>\n<a x:b = \"c\"><![CDATA[d]]></a >";
+    let contexts: Vec<_> = (0..=text.len()).map(|i| locate(text, i)).collect();
+    let at = |s: &str| contexts[text.find(s).unwrap()];
+    assert_eq!(at("version"), OffsetContext::ProcessingInstruction);
+    assert_eq!(at("\n<!"), OffsetContext::None);
+    assert_eq!(at("DOCTYPE"), OffsetContext::Dtd);
+    assert_eq!(at("<a"), OffsetContext::Tag);
+    assert_eq!(at("a x"), OffsetContext::ElementName);
+    assert_eq!(at(" x:b"), OffsetContext::Tag);
+    assert_eq!(at("x:b"), OffsetContext::AttributeName);
+    assert_eq!(at("= "), OffsetContext::Tag);
+    assert_eq!(at("\"c"), OffsetContext::AttributeValue);
+    assert_eq!(at("c\""), OffsetContext::AttributeValue);
+    assert_eq!(at("><!["), OffsetContext::Tag);
+    assert_eq!(at("d]"), OffsetContext::Cdata);
+    assert_eq!(at("a >"), OffsetContext::ElementName);
+    assert_eq!(at(" >"), OffsetContext::Tag);
+    assert_eq!(contexts[text.len()], OffsetContext::None);
+}
+
+#[test]
+fn locate_02() {
+    // Incomplete text.
+    let text = "<a><!-- x";
+    assert_eq!(locate(text, 8), OffsetContext::Comment);
+    let text = "<a><b c=";
+    assert_eq!(locate(text, 4), OffsetContext::ElementName);
+    assert_eq!(locate(text, 7), OffsetContext::None);
+}
@@ -95,3 +95,118 @@ test!(
         "invalid CDATA at 1:4 cause a non-XML character '\\u{1}' found at 1:13".to_string()
     )
 );
+
+#[test]
+fn partial_01() {
+    assert_eq!(
+        partial_tokens("<a><![CDATA[a]]><![CDATA[b"),
+        vec![("a".to_string(), false), ("b".to_string(), true)]
+    );
+}
+
+#[test]
+fn partial_02() {
+    assert_eq!(
+        partial_tokens("<a><![CDATA[b]"),
+        vec![("b".to_string(), true)]
+    );
+    assert_eq!(
+        partial_tokens("<a><![CDATA[b]]"),
+        vec![("b".to_string(), true)]
+    );
+    assert_eq!(
+        partial_tokens("<a><![CDATA[b]x]"),
+        vec![("b]x".to_string(), true)]
+    );
+}
+
+#[test]
+fn partial_03() {
+    // Disabled by default.
+    let mut p = xml::Tokenizer::from("<a><![CDATA[b");
+    assert!(p.nth(2).unwrap().is_err());
+}
+
+#[test]
+fn partial_chunks_01() {
+    // A truncated terminator must not be returned as a chunk.
+    let texts = [
+        "<a><![CDATA[x]",
+        "<a><![CDATA[x]]",
+        "<a><![CDATA[x]y]]",
+        "<a><![CDATA[xy]]",
+        "<a><![CDATA[xyz",
+    ];
+    for text in texts.iter() {
+        let expected = partial_tokens(text);
+        for size in 1..5 {
+            let mut p = xml::Tokenizer::from(*text);
+            p.set_partial(true);
+            p.set_text_chunk_size(size);
+            let mut data = String::new();
+            let mut unterminated = false;
+            for token in p {
+                if let xml::Token::Cdata {
+                    text,
+                    unterminated: flag,
+                    ..
+                } = token.unwrap()
+                {
+                    data.push_str(text.as_str());
+                    unterminated = flag;
+                }
+            }
+
+            assert_eq!(vec![(data, unterminated)], expected, "{} {}", text, size);
+        }
+    }
+}
+
+#[test]
+fn chunks_01() {
+    let mut p = xml::Tokenizer::from("<p><![CDATA[abcde]]></p>");
+    p.set_text_chunk_size(2);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::ElementStart("", "p", 0..2),
+            Token::ElementEnd(ElementEnd::Open, 2..3),
+            Token::Cdata("ab", 3..14),
+            Token::Cdata("cd", 14..16),
+            Token::Cdata("e", 16..20),
+            Token::ElementEnd(ElementEnd::Close("", "p"), 20..24),
+        ]
+    );
+}
+
+#[test]
+fn chunks_02() {
+    let mut p = xml::Tokenizer::from("<p><![CDATA[ab]]></p>");
+    p.set_text_chunk_size(2);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::ElementStart("", "p", 0..2),
+            Token::ElementEnd(ElementEnd::Open, 2..3),
+            Token::Cdata("ab", 3..17),
+            Token::ElementEnd(ElementEnd::Close("", "p"), 17..21),
+        ]
+    );
+}
+
+#[test]
+fn chunks_03() {
+    let mut p = xml::Tokenizer::from("<p><![CDATA[abc</p>");
+    p.set_text_chunk_size(2);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::ElementStart("", "p", 0..2),
+            Token::ElementEnd(ElementEnd::Open, 2..3),
+            Token::Cdata("ab", 3..14),
+            Token::Cdata("c<", 14..16),
+            Token::Cdata("/p", 16..18),
+            Token::Error("invalid CDATA at 1:19 cause unexpected end of stream".to_string()),
+        ]
+    );
+}
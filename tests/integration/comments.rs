@@ -61,3 +61,175 @@ test_err!(comment_err_32, "<!----->");
 test_err!(comment_err_33, "<!------>");
 test_err!(comment_err_34, "<!-- --->");
 test_err!(comment_err_35, "<!--a--->");
+test_err!(
+    comment_err_36,
+    "<!-- long enough text to span a whole scanning chunk \u{1} -->"
+);
+
+#[test]
+fn partial_01() {
+    assert_eq!(
+        partial_tokens("<!-- a --><!-- b"),
+        vec![(" a ".to_string(), false), (" b".to_string(), true)]
+    );
+}
+
+#[test]
+fn partial_02() {
+    assert_eq!(partial_tokens("<!-- b -"), vec![(" b ".to_string(), true)]);
+    assert_eq!(partial_tokens("<!-- b --"), vec![(" b ".to_string(), true)]);
+    assert_eq!(partial_tokens("<!--"), vec![("".to_string(), true)]);
+}
+
+#[test]
+fn partial_03() {
+    // Errors are still reported.
+    assert_eq!(
+        partial_tokens("<!-- a -- b"),
+        vec![(
+            "invalid comment at 1:1 cause '--' is not allowed in comments".to_string(),
+            false
+        )]
+    );
+}
+
+fn cond_comments(text: &str) -> Vec<Token<'_>> {
+    let mut p = xml::Tokenizer::from(text);
+    p.set_conditional_comments(true);
+    collect_tokens(p)
+}
+
+#[test]
+fn cond_comment_01() {
+    assert_eq!(
+        cond_comments("<p><!--[if IE 8]><b/><![endif]--></p>"),
+        vec![
+            Token::ElementStart("", "p", 0..2),
+            Token::ElementEnd(ElementEnd::Open, 2..3),
+            Token::CondCommentStart("IE 8", 3..17),
+            Token::ElementStart("", "b", 17..19),
+            Token::ElementEnd(ElementEnd::Empty, 19..21),
+            Token::CondCommentEnd(21..33),
+            Token::ElementEnd(ElementEnd::Close("", "p"), 33..37),
+        ]
+    );
+}
+
+#[test]
+fn cond_comment_02() {
+    // Downlevel-revealed.
+    assert_eq!(
+        cond_comments("<p><![if !IE]>text<![endif]></p>"),
+        vec![
+            Token::ElementStart("", "p", 0..2),
+            Token::ElementEnd(ElementEnd::Open, 2..3),
+            Token::CondCommentStart("!IE", 3..14),
+            Token::Text("text", 14..18),
+            Token::CondCommentEnd(18..28),
+            Token::ElementEnd(ElementEnd::Close("", "p"), 28..32),
+        ]
+    );
+}
+
+#[test]
+fn cond_comment_03() {
+    assert_eq!(
+        cond_comments("<p><!--[if !IE]><!-->text<!--<![endif]--></p>"),
+        vec![
+            Token::ElementStart("", "p", 0..2),
+            Token::ElementEnd(ElementEnd::Open, 2..3),
+            Token::CondCommentStart("!IE", 3..21),
+            Token::Text("text", 21..25),
+            Token::CondCommentEnd(25..41),
+            Token::ElementEnd(ElementEnd::Close("", "p"), 41..45),
+        ]
+    );
+}
+
+#[test]
+fn cond_comment_04() {
+    // Disabled by default.
+    assert_eq!(
+        collect_tokens(xml::Tokenizer::from("<p><!--[if IE]>x<![endif]--></p>")),
+        vec![
+            Token::ElementStart("", "p", 0..2),
+            Token::ElementEnd(ElementEnd::Open, 2..3),
+            Token::Comment("[if IE]>x<![endif]", 3..28),
+            Token::ElementEnd(ElementEnd::Close("", "p"), 28..32),
+        ]
+    );
+}
+
+#[test]
+fn cond_comment_05() {
+    // Regular comments are not affected.
+    assert_eq!(
+        cond_comments("<p><!-- [if IE] --></p>")[2],
+        Token::Comment(" [if IE] ", 3..19)
+    );
+}
+
+#[test]
+fn cond_comment_err_01() {
+    assert_eq!(
+        cond_comments("<p><!--[if]></p>")[2],
+        Token::Error("invalid comment at 1:4 cause expected space not ']' at 1:11".to_string())
+    );
+}
+
+#[test]
+fn cond_comment_err_02() {
+    assert_eq!(
+        cond_comments("<p><![endif]</p>")[2],
+        Token::Error("invalid comment at 1:4 cause expected '-->' at 1:13".to_string())
+    );
+}
+
+#[test]
+fn comment_lenient_01() {
+    let mut p = xml::Tokenizer::from("<!-- a -- b --><!-- c ---><a/>");
+    p.set_lenient(true);
+    assert_eq!(
+        to_test_token(p.next().unwrap()),
+        Token::Comment(" a -- b ", 0..15)
+    );
+    assert_eq!(
+        p.warning().unwrap().to_string(),
+        "a malformed comment at 1:8"
+    );
+    assert_eq!(
+        to_test_token(p.next().unwrap()),
+        Token::Comment(" c -", 15..26)
+    );
+    assert_eq!(p.warning().unwrap().offset(), 22);
+    assert_eq!(p.warning().unwrap().code(), "malformed-comment");
+    assert!(p.next().unwrap().is_ok());
+    assert!(p.warning().is_none());
+}
+
+#[test]
+fn comment_text_01() {
+    let text = "<a>\n<!-- a ---></a>";
+    let err = xml::first_error(text).unwrap();
+    assert_eq!(
+        err.stream_error(),
+        Some(xml::StreamError::InvalidCommentEnd)
+    );
+    let comment = err.comment_text(text).unwrap();
+    assert_eq!(comment.as_str(), " a -");
+    assert_eq!(comment.range(), 8..12);
+
+    // Unterminated.
+    let text = "<a><!-- a";
+    assert_eq!(
+        xml::first_error(text)
+            .unwrap()
+            .comment_text(text)
+            .unwrap()
+            .as_str(),
+        " a"
+    );
+
+    let text = "<a><b c/></a>";
+    assert!(xml::first_error(text).unwrap().comment_text(text).is_none());
+}
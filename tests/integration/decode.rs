@@ -0,0 +1,84 @@
+use crate::token::*;
+use xml::{DecodeError, DecodedText, Encoding};
+
+fn utf16(text: &str, be: bool) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for unit in text.encode_utf16() {
+        let b = if be {
+            unit.to_be_bytes()
+        } else {
+            unit.to_le_bytes()
+        };
+        bytes.extend_from_slice(&b);
+    }
+    bytes
+}
+
+#[test]
+fn decode_utf8_01() {
+    let text = DecodedText::decode(b"\xEF\xBB\xBF<a/>").unwrap();
+    assert_eq!(text.encoding(), Encoding::Utf8);
+    assert_eq!(text.as_str(), "\u{FEFF}<a/>");
+    assert_eq!(collect_tokens(text.tokenizer()).len(), 2);
+}
+
+#[test]
+fn decode_utf8_02() {
+    assert_eq!(
+        DecodedText::decode(b"<a>\xFF</a>"),
+        Err(DecodeError::InvalidUtf8(3))
+    );
+}
+
+#[test]
+fn decode_utf16_01() {
+    let bytes = utf16("\u{FEFF}<a>\u{1F600}</a>", false);
+    let text = DecodedText::decode(&bytes).unwrap();
+    assert_eq!(text.encoding(), Encoding::Utf16Le);
+    assert_eq!(
+        collect_tokens(text.tokenizer()),
+        vec![
+            Token::ElementStart("", "a", 3..5),
+            Token::ElementEnd(ElementEnd::Open, 5..6),
+            Token::Text("\u{1F600}", 6..10),
+            Token::ElementEnd(ElementEnd::Close("", "a"), 10..14),
+        ]
+    );
+}
+
+#[test]
+fn decode_utf16_02() {
+    let bytes = utf16("\u{FEFF}<a/>", true);
+    let text = DecodedText::decode(&bytes).unwrap();
+    assert_eq!(text.encoding(), Encoding::Utf16Be);
+    assert_eq!(text.as_str(), "\u{FEFF}<a/>");
+}
+
+#[test]
+fn decode_utf16_03() {
+    // No BOM.
+    let bytes = utf16("<?xml version='1.0' encoding='UTF-16'?><a/>", true);
+    let text = DecodedText::decode(&bytes).unwrap();
+    assert_eq!(text.encoding(), Encoding::Utf16Be);
+    assert_eq!(collect_tokens(text.tokenizer()).len(), 3);
+}
+
+#[test]
+fn decode_utf16_err_01() {
+    let mut bytes = utf16("\u{FEFF}<a/>", false);
+    bytes.extend_from_slice(&[0x00, 0xD8]);
+    assert_eq!(
+        DecodedText::decode(&bytes),
+        Err(DecodeError::InvalidUtf16(10))
+    );
+}
+
+#[test]
+fn decode_utf16_err_02() {
+    let mut bytes = utf16("\u{FEFF}<a/>", false);
+    bytes.push(b'a');
+    assert_eq!(
+        DecodedText::decode(&bytes),
+        Err(DecodeError::InvalidUtf16(10))
+    );
+}
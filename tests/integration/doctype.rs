@@ -121,7 +121,6 @@ test!(
     Token::DtdEnd(186..188)
 );
 
-// TODO: NDATA will be ignored
 test!(
     dtd_entity_05,
     "<!DOCTYPE svg [
@@ -130,13 +129,12 @@ test!(
     Token::DtdStart("svg", None, 0..15),
     Token::EntityDecl(
         "hatch-pic",
-        EntityDefinition::ExternalId(ExternalId::System("../grafix/OpenHatch.gif")),
+        EntityDefinition::Unparsed(ExternalId::System("../grafix/OpenHatch.gif"), "gif"),
         20..83,
     ),
     Token::DtdEnd(84..86)
 );
 
-// TODO: unsupported data will be ignored
 test!(
     dtd_entity_06,
     "<!DOCTYPE svg [
@@ -146,16 +144,26 @@ test!(
     <!ATTLIST img data ENTITY #IMPLIED>
 ]>",
     Token::DtdStart("svg", None, 0..15),
+    Token::ElementDecl("sgml", ContentSpec::Any, 20..39),
     Token::EntityDecl(
         "ns_extend",
         EntityDefinition::EntityValue("http://ns.adobe.com/Extensibility/1.0/"),
         44..104
     ),
+    Token::NotationDecl(
+        "example1SVG-rdf",
+        NotationId::System("example1.svg.rdf"),
+        109..162
+    ),
+    Token::AttlistDecl(
+        "img",
+        vec![("data", "ENTITY", AttributeDefault::Implied)],
+        167..202
+    ),
     Token::DtdEnd(203..205)
 );
 
-// We do not support !ELEMENT DTD token and it will be skipped.
-// Previously, we were calling `Tokenizer::next` after the skip,
+// Previously, we were calling `Tokenizer::next` after skipping an unsupported DTD token,
 // which is recursive and could cause a stack overflow when there are too many sequential
 // unsupported tokens.
 // This tests checks that the current code do not crash with stack overflow.
@@ -172,12 +180,38 @@ fn dtd_entity_07() {
         to_test_token(p.next().unwrap()),
         Token::DtdStart("svg", None, 0..15)
     );
+    for i in 0..500 {
+        let start = 16 + i * 20;
+        assert_eq!(
+            to_test_token(p.next().unwrap()),
+            Token::ElementDecl("sgml", ContentSpec::Any, start..start + 19)
+        );
+    }
     assert_eq!(
         to_test_token(p.next().unwrap()),
         Token::DtdEnd(10016..10018)
     );
 }
 
+test!(
+    dtd_entity_08,
+    "<!DOCTYPE svg [<!ENTITY logo PUBLIC 'pub' 'logo.gif' NDATA\tgif>]>",
+    Token::DtdStart("svg", None, 0..15),
+    Token::EntityDecl(
+        "logo",
+        EntityDefinition::Unparsed(ExternalId::Public("pub", "logo.gif"), "gif"),
+        15..63
+    ),
+    Token::DtdEnd(63..65)
+);
+
+test!(
+    dtd_entity_err_01,
+    "<!DOCTYPE svg [<!ENTITY logo SYSTEM 'logo.gif' NDATA>]>",
+    Token::DtdStart("svg", None, 0..15),
+    Token::Error("invalid DTD entity at 1:16 cause expected space not '>' at 1:53".to_string())
+);
+
 test!(
     dtd_err_01,
     "<!DOCTYPEEG[<!ENTITY%ETT\u{000a}SSSSSSSS<D_IDYT;->\u{000a}<",
@@ -215,3 +249,653 @@ test!(
     Token::DtdStart("s", None, 0..13),
     Token::Error("invalid DTD at 1:14 cause expected '>' not '!' at 1:16".to_string())
 );
+
+#[test]
+fn dtd_limit_01() {
+    let mut p = xml::Tokenizer::from("<!DOCTYPE s [<!ENTITY a 'b'><!ENTITY c 'd'>]><s/>");
+    p.set_dtd_size_limit(10, xml::DtdLimitAction::Skip);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::DtdStart("s", None, 0..13),
+            Token::EntityDecl("a", EntityDefinition::EntityValue("b"), 13..28),
+            Token::DtdEnd(28..45),
+            Token::ElementStart("", "s", 45..47),
+            Token::ElementEnd(ElementEnd::Empty, 47..49),
+        ]
+    );
+}
+
+#[test]
+fn dtd_limit_02() {
+    let mut p = xml::Tokenizer::from("<!DOCTYPE s [<!ENTITY a 'b'><!ENTITY c 'd'>]><s/>");
+    p.set_dtd_size_limit(10, xml::DtdLimitAction::Error);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::DtdStart("s", None, 0..13),
+            Token::EntityDecl("a", EntityDefinition::EntityValue("b"), 13..28),
+            Token::Error("the DTD internal subset is too large at 1:29".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn dtd_limit_03() {
+    let mut p = xml::Tokenizer::from("<!DOCTYPE s [<!ENTITY a 'b'><!ENTITY c 'd'>]><s/>");
+    p.set_dtd_size_limit(100, xml::DtdLimitAction::Error);
+    assert_eq!(collect_tokens(p).len(), 6);
+}
+
+#[test]
+fn dtd_limit_04() {
+    let mut p = xml::Tokenizer::from("<!DOCTYPE s [<!ENTITY a 'b'><!ENTITY c 'd'>");
+    p.set_dtd_size_limit(0, xml::DtdLimitAction::Skip);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::DtdStart("s", None, 0..13),
+            Token::EntityDecl("a", EntityDefinition::EntityValue("b"), 13..28),
+            Token::Error("invalid DTD at 1:29 cause unexpected end of stream".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn dtd_limit_05() {
+    // A single entity value is larger than the limit.
+    let text = format!("<!DOCTYPE s [<!ENTITY a '{}'>]><s/>", "b".repeat(1000));
+    let mut p = xml::Tokenizer::from(text.as_str());
+    p.set_dtd_size_limit(100, xml::DtdLimitAction::Error);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::DtdStart("s", None, 0..13),
+            Token::Error("the DTD internal subset is too large at 1:14".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn dtd_limit_06() {
+    let text = format!("<!DOCTYPE s [<!ENTITY a '{}'>]><s/>", "b".repeat(1000));
+    let mut p = xml::Tokenizer::from(text.as_str());
+    p.set_dtd_size_limit(100, xml::DtdLimitAction::Skip);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::DtdStart("s", None, 0..13),
+            Token::DtdEnd(13..1029),
+            Token::ElementStart("", "s", 1029..1031),
+            Token::ElementEnd(ElementEnd::Empty, 1031..1033),
+        ]
+    );
+}
+
+#[test]
+fn dtd_limit_07() {
+    // An unterminated comment is not scanned to the end of the text.
+    let text = format!("<!DOCTYPE s [<!--{}", " ".repeat(1000));
+    let mut p = xml::Tokenizer::from(text.as_str());
+    p.set_dtd_size_limit(100, xml::DtdLimitAction::Error);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::DtdStart("s", None, 0..13),
+            Token::Error("the DTD internal subset is too large at 1:14".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn dtd_limit_08() {
+    // The scan end must not split a multi-byte char.
+    let text = "<!DOCTYPE r [<!ENTITY e 'éééééééé'>]><r/>";
+    for limit in 0..32 {
+        for &action in &[xml::DtdLimitAction::Error, xml::DtdLimitAction::Skip] {
+            let mut p = xml::Tokenizer::from(text);
+            p.set_dtd_size_limit(limit, action);
+            let tokens = collect_tokens(p);
+            assert_eq!(tokens[0], Token::DtdStart("r", None, 0..13));
+        }
+    }
+
+    let mut p = xml::Tokenizer::from(text);
+    p.set_dtd_size_limit(1, xml::DtdLimitAction::Error);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::DtdStart("r", None, 0..13),
+            Token::Error("the DTD internal subset is too large at 1:14".to_string()),
+        ]
+    );
+
+    let mut p = xml::Tokenizer::from(text);
+    p.set_dtd_size_limit(1, xml::DtdLimitAction::Skip);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::DtdStart("r", None, 0..13),
+            Token::DtdEnd(13..45),
+            Token::ElementStart("", "r", 45..47),
+            Token::ElementEnd(ElementEnd::Empty, 47..49),
+        ]
+    );
+}
+
+test!(
+    element_decl_01,
+    "<!DOCTYPE a [<!ELEMENT a EMPTY><!ELEMENT b ANY >]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::ElementDecl("a", ContentSpec::Empty, 13..31),
+    Token::ElementDecl("b", ContentSpec::Any, 31..48),
+    Token::DtdEnd(48..50)
+);
+
+test!(
+    element_decl_02,
+    "<!DOCTYPE a [<!ELEMENT p (#PCDATA|a|b)*>]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::ElementDecl("p", ContentSpec::Mixed("(#PCDATA|a|b)*"), 13..40),
+    Token::DtdEnd(40..42)
+);
+
+test!(
+    element_decl_03,
+    "<!DOCTYPE a [<!ELEMENT p ( #PCDATA )>]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::ElementDecl("p", ContentSpec::Mixed("( #PCDATA )"), 13..37),
+    Token::DtdEnd(37..39)
+);
+
+test!(
+    element_decl_04,
+    "<!DOCTYPE a [<!ELEMENT spec (front, body, (a|b)+)?>]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::ElementDecl(
+        "spec",
+        ContentSpec::Children("(front, body, (a|b)+)?"),
+        13..51
+    ),
+    Token::DtdEnd(51..53)
+);
+
+test!(
+    element_decl_err_01,
+    "<!DOCTYPE a [<!ELEMENT p (a|b>]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::Error("invalid DTD element at 1:14 cause expected '(', ')' not '>' at 1:30".to_string())
+);
+
+test!(
+    element_decl_err_02,
+    "<!DOCTYPE a [<!ELEMENT p>]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::Error("invalid DTD element at 1:14 cause expected space not '>' at 1:25".to_string())
+);
+
+test!(
+    attlist_decl_01,
+    "<!DOCTYPE a [<!ATTLIST a>]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::AttlistDecl("a", vec![], 13..25),
+    Token::DtdEnd(25..27)
+);
+
+test!(
+    attlist_decl_02,
+    "<!DOCTYPE a [<!ATTLIST a
+    b CDATA #REQUIRED
+    c (x | y) 'x'
+    d NOTATION (n) #FIXED \"n\"
+    e ID #IMPLIED >]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::AttlistDecl(
+        "a",
+        vec![
+            ("b", "CDATA", AttributeDefault::Required),
+            ("c", "(x | y)", AttributeDefault::Value("x")),
+            ("d", "NOTATION (n)", AttributeDefault::Fixed("n")),
+            ("e", "ID", AttributeDefault::Implied),
+        ],
+        13..114
+    ),
+    Token::DtdEnd(114..116)
+);
+
+#[test]
+fn attlist_decl_03() {
+    let text = "<!DOCTYPE a [<!ATTLIST a b CDATA 'c'>]>";
+    match xml::Tokenizer::from(text).nth(1).unwrap().unwrap() {
+        xml::Token::AttlistDeclaration { definitions, .. } => {
+            let def = definitions.iter().next().unwrap();
+            assert_eq!(def.name.range(), 25..26);
+            assert_eq!(def.att_type.range(), 27..32);
+            match def.default {
+                xml::AttributeDefault::Value(v) => assert_eq!(v.range(), 34..35),
+                _ => unreachable!(),
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
+test!(
+    attlist_decl_err_01,
+    "<!DOCTYPE a [<!ATTLIST a b CDATA>]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::Error(
+        "invalid DTD attribute list at 1:14 cause expected space not '>' at 1:33".to_string()
+    )
+);
+
+test!(
+    attlist_decl_err_02,
+    "<!DOCTYPE a [<!ATTLIST a b CDATA #FIXED>]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::Error(
+        "invalid DTD attribute list at 1:14 cause expected space not '>' at 1:40".to_string()
+    )
+);
+
+test!(
+    attlist_decl_err_03,
+    "<!DOCTYPE a [<!ATTLIST a b CDATA 'c'c CDATA 'd'>]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::Error(
+        "invalid DTD attribute list at 1:14 cause expected space not 'c' at 1:37".to_string()
+    )
+);
+
+test!(
+    notation_decl_01,
+    "<!DOCTYPE a [<!NOTATION gif PUBLIC 'gif viewer'>]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::NotationDecl("gif", NotationId::Public("gif viewer", None), 13..48),
+    Token::DtdEnd(48..50)
+);
+
+test!(
+    notation_decl_02,
+    "<!DOCTYPE a [<!NOTATION gif PUBLIC 'gif viewer' \"gif.exe\" >]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::NotationDecl(
+        "gif",
+        NotationId::Public("gif viewer", Some("gif.exe")),
+        13..59
+    ),
+    Token::DtdEnd(59..61)
+);
+
+test!(
+    notation_decl_03,
+    "<!DOCTYPE a [<!NOTATION gif SYSTEM 'gif.exe'>]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::NotationDecl("gif", NotationId::System("gif.exe"), 13..45),
+    Token::DtdEnd(45..47)
+);
+
+test!(
+    notation_decl_err_01,
+    "<!DOCTYPE a [<!NOTATION gif PUBLIC 'a''b'>]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::Error("invalid DTD notation at 1:14 cause expected '>' not ''' at 1:39".to_string())
+);
+
+test!(
+    notation_decl_err_02,
+    "<!DOCTYPE a [<!NOTATION gif>]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::Error("invalid DTD notation at 1:14 cause expected space not '>' at 1:28".to_string())
+);
+
+test!(
+    pubid_literal_err_01,
+    "<!DOCTYPE a PUBLIC 'a\tb' 'a.dtd'>",
+    Token::Error(
+        "invalid DTD at 1:1 cause a character '\\t' is not allowed in a public identifier at 1:22"
+            .to_string()
+    )
+);
+
+test!(
+    pubid_literal_err_02,
+    "<!DOCTYPE a [<!ENTITY b PUBLIC \"it's{}\" 'b.xml'>]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::Error(
+        "invalid DTD entity at 1:14 cause a character '{' is not allowed \
+         in a public identifier at 1:37"
+            .to_string()
+    )
+);
+
+test!(
+    pubid_literal_err_03,
+    "<!DOCTYPE a [<!NOTATION b PUBLIC 'ф'>]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::Error(
+        "invalid DTD notation at 1:14 cause a character 'ф' is not allowed \
+         in a public identifier at 1:35"
+            .to_string()
+    )
+);
+
+#[test]
+fn pubid_literal_lenient_01() {
+    let mut p = xml::Tokenizer::from("<!DOCTYPE a PUBLIC '{id}' 'a.dtd'><a/>");
+    p.set_lenient(true);
+    assert_eq!(
+        to_test_token(p.next().unwrap()),
+        Token::EmptyDtd("a", Some(ExternalId::Public("{id}", "a.dtd")), 0..34)
+    );
+    assert_eq!(p.warning().unwrap().code(), "invalid-pubid-literal");
+    assert_eq!(p.warning().unwrap().offset(), 20);
+}
+
+test!(
+    notation_decl_err_03,
+    "<!DOCTYPE a [<!NOTATION gif DATA 'a'>]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::Error("invalid DTD notation at 1:14 cause invalid ExternalID".to_string())
+);
+
+test!(
+    doctype_lenient_err_01,
+    "<!DOCTYPE>",
+    Token::Error("invalid DTD at 1:1 cause expected space not '>' at 1:10".to_string())
+);
+
+#[test]
+fn doctype_lenient_01() {
+    let mut p = xml::Tokenizer::from("<!doctype html><html/>");
+    p.set_lenient(true);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::EmptyDtd("html", None, 0..15),
+            Token::ElementStart("", "html", 15..20),
+            Token::ElementEnd(ElementEnd::Empty, 20..22),
+        ]
+    );
+}
+
+#[test]
+fn doctype_lenient_02() {
+    let mut p = xml::Tokenizer::from("<!DOCTYPE><a/>");
+    p.set_lenient(true);
+    let tokens: Vec<_> = p.collect();
+    assert_eq!(to_test_token(tokens[0]), Token::EmptyDtd("", None, 0..10));
+    match tokens[0] {
+        Ok(xml::Token::EmptyDtd { name, .. }) => assert_eq!(name.range(), 9..9),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn doctype_lenient_03() {
+    let mut p = xml::Tokenizer::from("<!DocType  [<!ENTITY a 'b'>]><a/>");
+    p.set_lenient(true);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::DtdStart("", None, 0..12),
+            Token::EntityDecl("a", EntityDefinition::EntityValue("b"), 12..27),
+            Token::DtdEnd(27..29),
+            Token::ElementStart("", "a", 29..31),
+            Token::ElementEnd(ElementEnd::Empty, 31..33),
+        ]
+    );
+}
+
+#[test]
+fn internal_subset_01() {
+    let text = "<!DOCTYPE a [\n  <!ENTITY b 'c'>\n  <!-- d -->\n] >";
+    match xml::Tokenizer::from(text).last().unwrap().unwrap() {
+        xml::Token::DtdEnd {
+            internal_subset, ..
+        } => {
+            assert_eq!(
+                internal_subset.as_str(),
+                "\n  <!ENTITY b 'c'>\n  <!-- d -->\n"
+            );
+            assert_eq!(internal_subset.range(), 13..45);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn internal_subset_02() {
+    let text = "<!DOCTYPE a [<!ENTITY b 'c'><!ENTITY d ']'>]><a/>";
+    let mut p = xml::Tokenizer::from(text);
+    p.set_dtd_size_limit(10, xml::DtdLimitAction::Skip);
+    match p.nth(2).unwrap().unwrap() {
+        xml::Token::DtdEnd {
+            internal_subset, ..
+        } => {
+            assert_eq!(internal_subset.as_str(), "<!ENTITY b 'c'><!ENTITY d ']'>");
+        }
+        _ => unreachable!(),
+    }
+}
+
+test!(
+    cond_sect_01,
+    "<!DOCTYPE a [<![INCLUDE[<!ENTITY b 'c'>]]><![ IGNORE [<!ENTITY d 'e'><![INCLUDE[]]>]]>]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::CondSectStart(false, 13..24),
+    Token::EntityDecl("b", EntityDefinition::EntityValue("c"), 24..39),
+    Token::CondSectEnd(39..42),
+    Token::CondSectStart(true, 42..54),
+    Token::CondSectEnd(83..86),
+    Token::DtdEnd(86..88)
+);
+
+test!(
+    cond_sect_02,
+    "<!DOCTYPE a [<![INCLUDE[<![INCLUDE[]]>]]>]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::CondSectStart(false, 13..24),
+    Token::CondSectStart(false, 24..35),
+    Token::CondSectEnd(35..38),
+    Token::CondSectEnd(38..41),
+    Token::DtdEnd(41..43)
+);
+
+test!(
+    cond_sect_err_01,
+    "<!DOCTYPE a [<![%draft;[]]>]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::Error("invalid DTD at 1:14 cause expected 'INCLUDE' at 1:17".to_string())
+);
+
+test!(
+    cond_sect_err_02,
+    "<!DOCTYPE a [<![IGNORE[<!ENTITY d 'e'>]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::CondSectStart(true, 13..23),
+    Token::Error("invalid DTD at 1:24 cause unexpected end of stream".to_string())
+);
+
+test!(
+    cond_sect_err_03,
+    "<!DOCTYPE a []]>]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::Error("invalid DTD at 1:14 cause expected '>' not ']' at 1:15".to_string())
+);
+
+#[test]
+fn raw_tokens_01() {
+    let text = "<!DOCTYPE a [<![ IGNORE [<!ENTITY d 'e'><![INCLUDE[]]>]]><![IGNORE[]]>]>";
+    let mut p = xml::Tokenizer::from(text);
+    p.set_raw_tokens(true);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::DtdStart("a", None, 0..13),
+            Token::CondSectStart(true, 13..25),
+            Token::Raw(
+                xml::RawKind::IgnoredSection,
+                "<!ENTITY d 'e'><![INCLUDE[]]>",
+                25..54
+            ),
+            Token::CondSectEnd(54..57),
+            Token::CondSectStart(true, 57..67),
+            Token::CondSectEnd(67..70),
+            Token::DtdEnd(70..72),
+        ]
+    );
+}
+
+#[test]
+fn raw_tokens_02() {
+    // Spans of all tokens cover the whole internal subset.
+    let text = "<!DOCTYPE a [<![IGNORE[ <!x> ]]>%b;<![INCLUDE[<!ELEMENT a ANY>]]>]>";
+    let mut p = xml::Tokenizer::from(text);
+    p.set_raw_tokens(true);
+    let s: String = p.map(|t| t.unwrap().span().as_str()).collect();
+    assert_eq!(s, text);
+}
+
+#[test]
+fn raw_tokens_03() {
+    let mut p = xml::Tokenizer::from("<!DOCTYPE a [<![IGNORE[<!ENTITY d 'e'>]>");
+    p.set_raw_tokens(true);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::DtdStart("a", None, 0..13),
+            Token::CondSectStart(true, 13..23),
+            Token::Error("invalid DTD at 1:24 cause unexpected end of stream".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn cond_sect_dtd_limit_01() {
+    let text = "<!DOCTYPE a [<![INCLUDE[<!ENTITY b 'c'><!ENTITY d 'e'>]]><!ENTITY f 'g'>]><a/>";
+    let mut p = xml::Tokenizer::from(text);
+    p.set_dtd_size_limit(20, xml::DtdLimitAction::Skip);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::DtdStart("a", None, 0..13),
+            Token::CondSectStart(false, 13..24),
+            Token::EntityDecl("b", EntityDefinition::EntityValue("c"), 24..39),
+            Token::DtdEnd(39..74),
+            Token::ElementStart("", "a", 74..76),
+            Token::ElementEnd(ElementEnd::Empty, 76..78),
+        ]
+    );
+}
+
+test!(
+    pe_reference_01,
+    "<!DOCTYPE a [<!ENTITY % ents SYSTEM 'ents.dtd'> %ents; ]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::EntityDecl(
+        "ents",
+        EntityDefinition::ExternalId(ExternalId::System("ents.dtd")),
+        13..47
+    ),
+    Token::PEReference("ents", 48..54),
+    Token::DtdEnd(55..57)
+);
+
+test!(
+    pe_reference_err_01,
+    "<!DOCTYPE a [%ents ]>",
+    Token::DtdStart("a", None, 0..13),
+    Token::Error("invalid DTD at 1:14 cause expected ';' not ' ' at 1:19".to_string())
+);
+
+#[test]
+fn external_subset_01() {
+    let text = "<!DOCTYPE a SYSTEM 'a.dtd' [<!ENTITY b 'c'>]><a/>";
+    let loader = |name: xml::StrSpan, id: xml::ExternalId| {
+        assert_eq!(name.as_str(), "a");
+        match id {
+            xml::ExternalId::System(uri) if uri == "a.dtd" => Some("<!ELEMENT a EMPTY> %d;"),
+            _ => None,
+        }
+    };
+
+    let tokens: Vec<_> = xml::Tokenizer::from(text)
+        .with_external_subset_loader(loader)
+        .map(to_test_token)
+        .collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::DtdStart("a", Some(ExternalId::System("a.dtd")), 0..28),
+            Token::EntityDecl("b", EntityDefinition::EntityValue("c"), 28..43),
+            Token::ElementDecl("a", ContentSpec::Empty, 0..18),
+            Token::PEReference("d", 19..22),
+            Token::DtdEnd(43..45),
+            Token::ElementStart("", "a", 45..47),
+            Token::ElementEnd(ElementEnd::Empty, 47..49),
+        ]
+    );
+}
+
+#[test]
+fn external_subset_02() {
+    let mut p = xml::Tokenizer::from("<!DOCTYPE a SYSTEM 'a.dtd'><a/>");
+    p.next();
+    p.set_external_subset("<![INCLUDE[<!ENTITY b 'c'>]]>");
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::CondSectStart(false, 0..11),
+            Token::EntityDecl("b", EntityDefinition::EntityValue("c"), 11..26),
+            Token::CondSectEnd(26..29),
+            Token::ElementStart("", "a", 27..29),
+            Token::ElementEnd(ElementEnd::Empty, 29..31),
+        ]
+    );
+}
+
+#[test]
+fn external_subset_03() {
+    // An external subset cannot end the DTD.
+    for subset in &["]>", "<![INCLUDE[", "<!ELEMENT"] {
+        let mut p = xml::Tokenizer::from("<!DOCTYPE a SYSTEM 'a.dtd'><a/>");
+        p.next();
+        p.set_external_subset(subset);
+        assert!(p.any(|t| t.is_err()));
+    }
+}
+
+#[test]
+fn doctype_lenient_04() {
+    let mut p = xml::Tokenizer::from("<!doctype html system'about:legacy-compat'><html/>");
+    p.set_lenient(true);
+    assert_eq!(
+        to_test_token(p.next().unwrap()),
+        Token::EmptyDtd(
+            "html",
+            Some(ExternalId::System("about:legacy-compat")),
+            0..43
+        )
+    );
+}
+
+#[test]
+fn doctype_lenient_05() {
+    let mut p = xml::Tokenizer::from("<!DOCTYPE HTML Public \"-//W3C//DTD HTML 4.01//EN\"><html/>");
+    p.set_lenient(true);
+    assert_eq!(
+        to_test_token(p.next().unwrap()),
+        Token::EmptyDtd(
+            "HTML",
+            Some(ExternalId::Public("-//W3C//DTD HTML 4.01//EN", "")),
+            0..50
+        )
+    );
+}
+
+test!(
+    doctype_lenient_err_02,
+    "<!DOCTYPE html system 'a.dtd'>",
+    Token::Error("invalid DTD at 1:1 cause expected '[', '>' not 's' at 1:16".to_string())
+);
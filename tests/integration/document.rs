@@ -126,3 +126,310 @@ fn parse_fragment_1() {
         _ => panic!(),
     }
 }
+
+fn collect_fragment(text: &str) -> Vec<Token<'_>> {
+    collect_tokens(xml::Tokenizer::from_fragment(text, 0..text.len()))
+}
+
+#[test]
+fn parse_fragment_2() {
+    assert_eq!(
+        collect_fragment("text &amp; text"),
+        vec![Token::Text("text &amp; text", 0..15)]
+    );
+}
+
+#[test]
+fn parse_fragment_3() {
+    assert_eq!(
+        collect_fragment("<!-- c --><?pi?>\ntext<a/>"),
+        vec![
+            Token::Comment(" c ", 0..10),
+            Token::PI("pi", None, 10..16),
+            Token::Text("\ntext", 16..21),
+            Token::ElementStart("", "a", 21..23),
+            Token::ElementEnd(ElementEnd::Empty, 23..25),
+        ]
+    );
+}
+
+#[test]
+fn parse_fragment_4() {
+    assert_eq!(
+        collect_fragment("</a>text<b>"),
+        vec![
+            Token::ElementEnd(ElementEnd::Close("", "a"), 0..4),
+            Token::Text("text", 4..8),
+            Token::ElementStart("", "b", 8..10),
+            Token::ElementEnd(ElementEnd::Open, 10..11),
+        ]
+    );
+}
+
+#[test]
+fn parse_fragment_5() {
+    assert_eq!(
+        collect_fragment("<?xml version='1.0'?><a/>"),
+        vec![Token::Error(
+            "the XML declaration is allowed only at the start of the document at 1:1".to_string()
+        )]
+    );
+}
+
+#[test]
+fn prolog_recovery_01() {
+    let mut p = xml::Tokenizer::from("text<!-- c -->\n<a/>");
+    p.set_prolog_recovery(1);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::Error("unknown token at 1:1".to_string()),
+            Token::Comment(" c ", 4..14),
+            Token::ElementStart("", "a", 15..17),
+            Token::ElementEnd(ElementEnd::Empty, 17..19),
+        ]
+    );
+}
+
+#[test]
+fn prolog_recovery_02() {
+    let mut p = xml::Tokenizer::from("<!DOCTYPE a><!DOCTYPE b><!x><a/>");
+    p.set_prolog_recovery(2);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::EmptyDtd("a", None, 0..12),
+            Token::Error("unknown token at 1:13".to_string()),
+            Token::Error("unknown token at 1:25".to_string()),
+            Token::ElementStart("", "a", 28..30),
+            Token::ElementEnd(ElementEnd::Empty, 30..32),
+        ]
+    );
+}
+
+#[test]
+fn prolog_recovery_limit() {
+    let mut p = xml::Tokenizer::from("a<!x><!y><a/>");
+    p.set_prolog_recovery(1);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::Error("unknown token at 1:1".to_string()),
+            Token::Error("unknown token at 1:2".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn prolog_recovery_after_root() {
+    let mut p = xml::Tokenizer::from("<a/>text<b/>");
+    p.set_prolog_recovery(1);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::ElementStart("", "a", 0..2),
+            Token::ElementEnd(ElementEnd::Empty, 2..4),
+            Token::Error("unknown token at 1:5".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn recover_01() {
+    let mut p = xml::Tokenizer::from("<root><a b></a><c>&#x0;</c></root>");
+    p.set_recover(true);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::ElementStart("", "root", 0..5),
+            Token::ElementEnd(ElementEnd::Open, 5..6),
+            Token::ElementStart("", "a", 6..8),
+            Token::Error("invalid attribute at 1:9 cause expected '=' not '>' at 1:11".to_string()),
+            Token::ElementEnd(ElementEnd::Close("", "a"), 11..15),
+            Token::ElementStart("", "c", 15..17),
+            Token::ElementEnd(ElementEnd::Open, 17..18),
+            Token::Text("&#x0;", 18..23),
+            Token::ElementEnd(ElementEnd::Close("", "c"), 23..27),
+            Token::ElementEnd(ElementEnd::Close("", "root"), 27..34),
+        ]
+    );
+}
+
+#[test]
+fn recover_02() {
+    let mut p = xml::Tokenizer::from("<!DOCTYPE a [<!FOO><!ENTITY b 'c'>]><a><!x>t</a>");
+    p.set_recover(true);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::DtdStart("a", None, 0..13),
+            Token::Error("unknown token at 1:14".to_string()),
+            Token::EntityDecl("b", EntityDefinition::EntityValue("c"), 19..34),
+            Token::DtdEnd(34..36),
+            Token::ElementStart("", "a", 36..38),
+            Token::ElementEnd(ElementEnd::Open, 38..39),
+            // The text is skipped along with the broken token.
+            Token::Error("unknown token at 1:40".to_string()),
+            Token::ElementEnd(ElementEnd::Close("", "a"), 44..48),
+        ]
+    );
+}
+
+#[test]
+fn recover_03() {
+    let mut p = xml::Tokenizer::from("<a><!-- c");
+    p.set_recover(true);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::ElementStart("", "a", 0..2),
+            Token::ElementEnd(ElementEnd::Open, 2..3),
+            Token::Error("invalid comment at 1:4 cause unexpected end of stream".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn max_depth_01() {
+    let mut p = xml::Tokenizer::from("<a><b/><c>t</c></a>");
+    p.set_max_depth(2);
+    assert_eq!(collect_tokens(p).len(), 9);
+}
+
+#[test]
+fn max_depth_02() {
+    let mut p = xml::Tokenizer::from("<a>\n<b><c/></b></a>");
+    p.set_max_depth(2);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::ElementStart("", "a", 0..2),
+            Token::ElementEnd(ElementEnd::Open, 2..3),
+            Token::Text("\n", 3..4),
+            Token::ElementStart("", "b", 4..6),
+            Token::ElementEnd(ElementEnd::Open, 6..7),
+            Token::Error("elements are nested too deep at 2:4".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn max_depth_03() {
+    let mut p = xml::Tokenizer::from("<!-- c --><a/>");
+    p.set_max_depth(0);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::Comment(" c ", 0..10),
+            Token::Error("elements are nested too deep at 1:11".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn bom_01() {
+    let mut p = xml::Tokenizer::from("\u{FEFF}<?xml version='1.0'?><a/>");
+    p.set_bom_handling(xml::BomHandling::Report);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::Bom(0..3),
+            Token::Declaration("1.0", None, None, 3..24),
+            Token::ElementStart("", "a", 24..26),
+            Token::ElementEnd(ElementEnd::Empty, 26..28),
+        ]
+    );
+}
+
+#[test]
+fn bom_02() {
+    let mut p = xml::Tokenizer::from("\u{FEFF}<a/>");
+    p.set_bom_handling(xml::BomHandling::Reject);
+    assert_eq!(
+        collect_tokens(p),
+        vec![Token::Error("unexpected BOM at 1:1".to_string())]
+    );
+}
+
+#[test]
+fn bom_03() {
+    let mut p = xml::Tokenizer::from("<a/>");
+    p.set_bom_handling(xml::BomHandling::Reject);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::ElementStart("", "a", 0..2),
+            Token::ElementEnd(ElementEnd::Empty, 2..4),
+        ]
+    );
+}
+
+test!(
+    bom_04,
+    "<a/>\n\u{FEFF}<b/>",
+    Token::ElementStart("", "a", 0..2),
+    Token::ElementEnd(ElementEnd::Empty, 2..4),
+    Token::Error("unexpected BOM at 2:1".to_string())
+);
+
+test!(
+    bom_05,
+    "\u{FEFF}\u{FEFF}<a/>",
+    Token::Error("unexpected BOM at 1:2".to_string())
+);
+
+// A BOM inside a text is a valid character.
+test!(
+    bom_06,
+    "<a>\u{FEFF}</a>",
+    Token::ElementStart("", "a", 0..2),
+    Token::ElementEnd(ElementEnd::Open, 2..3),
+    Token::Text("\u{FEFF}", 3..6),
+    Token::ElementEnd(ElementEnd::Close("", "a"), 6..10)
+);
+
+#[test]
+fn multiple_roots_01() {
+    let mut p = xml::Tokenizer::from("<a/>\n<!-- c --><b>t</b>");
+    p.set_allow_multiple_roots(true);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::ElementStart("", "a", 0..2),
+            Token::ElementEnd(ElementEnd::Empty, 2..4),
+            Token::Comment(" c ", 5..15),
+            Token::ElementStart("", "b", 15..17),
+            Token::ElementEnd(ElementEnd::Open, 17..18),
+            Token::Text("t", 18..19),
+            Token::ElementEnd(ElementEnd::Close("", "b"), 19..23),
+        ]
+    );
+}
+
+#[test]
+fn multiple_roots_02() {
+    let mut p = xml::Tokenizer::from("<a/>text<b/>");
+    p.set_allow_multiple_roots(true);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::ElementStart("", "a", 0..2),
+            Token::ElementEnd(ElementEnd::Empty, 2..4),
+            Token::Error("unknown token at 1:5".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn multiple_roots_03() {
+    let mut p = xml::Tokenizer::from("<a/></a>");
+    p.set_allow_multiple_roots(true);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::ElementStart("", "a", 0..2),
+            Token::ElementEnd(ElementEnd::Empty, 2..4),
+            Token::Error("unknown token at 1:5".to_string()),
+        ]
+    );
+}
@@ -0,0 +1,18 @@
+#[test]
+fn dump_01() {
+    let mut s = String::new();
+    xml::dump("<?xml version='1.0'?>\n<a>\n\t<!--c-->\n</b>\n<c/>", &mut s).unwrap();
+    assert_eq!(
+        s,
+        "\
+1:1 0..21 Declaration { version: StrSpan(\"1.0\" 15..18), encoding: None, standalone: None, span: StrSpan(\"<?xml version='1.0'?>\" 0..21) }
+2:1 22..24 ElementStart { prefix: StrSpan(\"\" 0..0), local: StrSpan(\"a\" 23..24), span: StrSpan(\"<a\" 22..24) }
+2:3 24..25 ElementEnd { end: Open, span: StrSpan(\">\" 24..25) }
+2:4 25..27 Text { text: StrSpan(\"\\n\\t\" 25..27) }
+3:2 27..35 Comment { text: StrSpan(\"c\" 31..32), span: StrSpan(\"<!--c-->\" 27..35), unterminated: false }
+3:10 35..36 Text { text: StrSpan(\"\\n\" 35..36) }
+4:1 36..40 ElementEnd { end: Close(StrSpan(\"\" 0..0), StrSpan(\"b\" 38..39)), span: StrSpan(\"</b>\" 36..40) }
+error: unknown token at 5:1
+"
+    );
+}
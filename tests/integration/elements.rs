@@ -300,3 +300,361 @@ test!(
     Token::Attribute("", "a", "v", 3..8),
     Token::Error("invalid attribute at 1:9 cause expected space not 'b' at 1:9".to_string())
 );
+
+#[test]
+fn lenient_01() {
+    let mut p = xml::Tokenizer::from("<c a='<%foo%>' b=\"<\"/>");
+    p.set_lenient(true);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::ElementStart("", "c", 0..2),
+            Token::Attribute("", "a", "<%foo%>", 3..14),
+            Token::Attribute("", "b", "<", 15..20),
+            Token::ElementEnd(ElementEnd::Empty, 20..22),
+        ]
+    );
+}
+
+#[test]
+fn lenient_02() {
+    let mut p = xml::Tokenizer::from("<c a='v'b='v'/>");
+    p.set_lenient(true);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::ElementStart("", "c", 0..2),
+            Token::Attribute("", "a", "v", 3..8),
+            Token::Attribute("", "b", "v", 8..13),
+            Token::ElementEnd(ElementEnd::Empty, 13..15),
+        ]
+    );
+}
+
+#[test]
+fn lenient_03() {
+    let mut p = xml::Tokenizer::from("<c a='v' b=v/>");
+    p.set_lenient(true);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::ElementStart("", "c", 0..2),
+            Token::Attribute("", "a", "v", 3..8),
+            Token::Attribute("", "b", "v", 9..12),
+            Token::ElementEnd(ElementEnd::Empty, 12..14),
+        ]
+    );
+}
+
+#[test]
+fn full_tag_spans_01() {
+    let mut p = xml::Tokenizer::from("<ns:a b='1' >\n<c/></ns:a>");
+    p.set_full_tag_spans(true);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::ElementStart("ns", "a", 0..5),
+            Token::Attribute("", "b", "1", 6..11),
+            Token::ElementEnd(ElementEnd::Open, 0..13),
+            Token::Text("\n", 13..14),
+            Token::ElementStart("", "c", 14..16),
+            Token::ElementEnd(ElementEnd::Empty, 14..18),
+            Token::ElementEnd(ElementEnd::Close("ns", "a"), 18..25),
+        ]
+    );
+}
+
+#[test]
+fn full_tag_spans_02() {
+    let mut p = xml::Tokenizer::from_fragment("<a><b x='y'/></a>", 3..13);
+    p.set_full_tag_spans(true);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::ElementStart("", "b", 3..5),
+            Token::Attribute("", "x", "y", 6..11),
+            Token::ElementEnd(ElementEnd::Empty, 3..13),
+        ]
+    );
+}
+
+test!(
+    element_end_space_err_01,
+    "<a b='1'/ >",
+    Token::ElementStart("", "a", 0..2),
+    Token::Attribute("", "b", "1", 3..8),
+    Token::Error(
+        "invalid attribute at 1:9 cause a space between '/' and '>' is not allowed at 1:10"
+            .to_string()
+    )
+);
+
+test!(
+    element_end_space_err_02,
+    "<a/ b>",
+    Token::ElementStart("", "a", 0..2),
+    Token::Error("invalid attribute at 1:3 cause expected '>' not ' ' at 1:4".to_string())
+);
+
+#[test]
+fn html_attributes_01() {
+    let mut p = xml::Tokenizer::from("<input disabled type=text\nvalue = a/b/>");
+    p.set_lenient(true);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::ElementStart("", "input", 0..6),
+            Token::Attribute("", "disabled", "", 7..15),
+            Token::Attribute("", "type", "text", 16..25),
+            Token::Attribute("", "value", "a/b", 26..37),
+            Token::ElementEnd(ElementEnd::Empty, 37..39),
+        ]
+    );
+}
+
+#[test]
+fn html_attributes_02() {
+    let mut p = xml::Tokenizer::from("<a b c=d e='f'>");
+    p.set_lenient(true);
+    p.next().unwrap().unwrap();
+    p.next().unwrap().unwrap();
+    assert_eq!(
+        p.warning().unwrap().to_string(),
+        "an attribute without a value at 1:4"
+    );
+    assert_eq!(
+        to_test_token(p.next().unwrap()),
+        Token::Attribute("", "c", "d", 5..8)
+    );
+    assert_eq!(p.warning().unwrap().code(), "unquoted-attribute-value");
+    assert_eq!(p.warning().unwrap().offset(), 7);
+    p.next().unwrap().unwrap();
+    assert_eq!(p.warning(), None);
+}
+
+#[test]
+fn html_attributes_03() {
+    // Not allowed in the strict mode.
+    let mut p = xml::Tokenizer::from("<input disabled>");
+    p.next().unwrap().unwrap();
+    assert!(p.next().unwrap().is_err());
+}
+
+#[test]
+fn element_end_space_lenient_01() {
+    let mut p = xml::Tokenizer::from("<a b='1'/ \n>");
+    p.set_lenient(true);
+    assert_eq!(
+        to_test_token(p.next().unwrap()),
+        Token::ElementStart("", "a", 0..2)
+    );
+    assert_eq!(p.warning(), None);
+    assert_eq!(
+        to_test_token(p.next().unwrap()),
+        Token::Attribute("", "b", "1", 3..8)
+    );
+    assert_eq!(
+        to_test_token(p.next().unwrap()),
+        Token::ElementEnd(ElementEnd::Empty, 8..12)
+    );
+    let warning = p.warning().unwrap();
+    assert_eq!(warning.code(), "space-in-empty-element-end");
+    assert_eq!(warning.offset(), 9);
+    assert!(p.next().is_none());
+    assert_eq!(p.warning(), None);
+}
+
+#[test]
+fn skip_current_element_01() {
+    let text = "<root><a b='>'><b/><![CDATA[</a>]]><?pi </a>?><c x=\"</a>\">t</c></a><d/></root>";
+    let mut p = xml::Tokenizer::from(text);
+    p.nth(2).unwrap().unwrap(); // <a
+    p.skip_current_element().unwrap();
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::ElementStart("", "d", 67..69),
+            Token::ElementEnd(ElementEnd::Empty, 69..71),
+            Token::ElementEnd(ElementEnd::Close("", "root"), 71..78),
+        ]
+    );
+}
+
+#[test]
+fn skip_current_element_02() {
+    let mut p = xml::Tokenizer::from("<root><a/>t</root><!-- c -->");
+    p.nth(2).unwrap().unwrap(); // <a
+    p.skip_current_element().unwrap();
+    assert_eq!(to_test_token(p.next().unwrap()), Token::Text("t", 10..11));
+
+    // Skips the rest of the root element.
+    p.skip_current_element().unwrap();
+    assert_eq!(collect_tokens(p), vec![Token::Comment(" c ", 18..28)]);
+}
+
+#[test]
+fn skip_current_element_03() {
+    let mut p = xml::Tokenizer::from("<root><a><b></a>");
+    p.nth(2).unwrap().unwrap(); // <a
+    let err = p.skip_current_element().unwrap_err();
+    assert_eq!(err.to_string(), "unexpected end of document at 1:17");
+    assert!(p.next().is_none());
+}
+
+#[test]
+fn skip_current_element_04() {
+    let mut p = xml::Tokenizer::from("<a/>");
+    p.skip_current_element().unwrap();
+    assert_eq!(collect_tokens(p).len(), 2);
+}
+
+#[test]
+fn skip_current_element_05() {
+    // Skip in the middle of a chunked CDATA.
+    let text = "<r><a><![CDATA[abc<b>ghij]]></a><b>xyz</b></r>";
+    let mut p = xml::Tokenizer::from(text);
+    p.set_text_chunk_size(3);
+    assert_eq!(to_test_token(p.nth(4).unwrap()), Token::Cdata("abc", 6..18));
+    p.skip_current_element().unwrap();
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::ElementStart("", "b", 32..34),
+            Token::ElementEnd(ElementEnd::Open, 34..35),
+            Token::Text("xyz", 35..38),
+            Token::ElementEnd(ElementEnd::Close("", "b"), 38..42),
+            Token::ElementEnd(ElementEnd::Close("", "r"), 42..46),
+        ]
+    );
+}
+
+#[test]
+fn skip_current_element_06() {
+    // Skip in the middle of a chunked text.
+    let text = "<r><a>abcdefghij</a><b/></r>";
+    let mut p = xml::Tokenizer::from(text);
+    p.set_text_chunk_size(3);
+    assert_eq!(to_test_token(p.nth(4).unwrap()), Token::Text("abc", 6..9));
+    p.skip_current_element().unwrap();
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::ElementStart("", "b", 20..22),
+            Token::ElementEnd(ElementEnd::Empty, 22..24),
+            Token::ElementEnd(ElementEnd::Close("", "r"), 24..28),
+        ]
+    );
+}
+
+#[test]
+fn attribute_references_01() {
+    let mut p = xml::Tokenizer::from("<a b='&amp;&#x20;&c;' d=\"&lt\"/>");
+    p.set_validate_attribute_references(true);
+    assert_eq!(
+        to_test_token(p.nth(1).unwrap()),
+        Token::Attribute("", "b", "&amp;&#x20;&c;", 3..21)
+    );
+    assert_eq!(
+        to_test_token(p.next().unwrap()),
+        Token::Error("invalid attribute at 1:22 cause a malformed reference at 1:26".to_string())
+    );
+}
+
+#[test]
+fn attribute_references_02() {
+    for text in &["<a b='&'/>", "<a b='&#0;'/>", "<a b='& amp;'/>"] {
+        let mut p = xml::Tokenizer::from(*text);
+        p.set_validate_attribute_references(true);
+        let err = p.nth(1).unwrap().unwrap_err();
+        assert_eq!(err.stream_error().unwrap().code(), "malformed-reference");
+        assert_eq!(err.stream_error().unwrap().offset(), Some(6));
+
+        // Not validated by default.
+        assert!(xml::Tokenizer::from(*text).all(|t| t.is_ok()));
+    }
+}
+
+#[test]
+fn namespaces_01() {
+    for text in &["<:a/>", "<a :b='c'/>", "<a></:a>"] {
+        let mut p = xml::Tokenizer::from(*text);
+        p.set_namespaces(true);
+        let err = p.find_map(|t| t.err()).unwrap();
+        assert_eq!(err.stream_error(), Some(xml::StreamError::InvalidName));
+    }
+}
+
+#[test]
+fn namespaces_02() {
+    let mut p = xml::Tokenizer::from("<a:b c:d='e' f='g'></a:b>");
+    p.set_namespaces(true);
+    assert!(p.all(|t| t.is_ok()));
+}
+
+fn attribute_spans(text: &str, lenient: bool) -> Vec<(&str, Option<usize>, &str)> {
+    let mut p = xml::Tokenizer::from(text);
+    p.set_lenient(lenient);
+    p.filter_map(|t| t.unwrap().attribute_spans())
+        .map(|s| {
+            (
+                s.name.as_str(),
+                s.eq.map(|s| s.start()),
+                s.quoted_value.as_str(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn attribute_spans_01() {
+    assert_eq!(
+        attribute_spans("<a b=\"1\" c\n=\n'' d:e='\"'/>", false),
+        vec![
+            ("b", Some(4), "\"1\""),
+            ("c", Some(11), "''"),
+            ("d:e", Some(19), "'\"'")
+        ]
+    );
+}
+
+#[test]
+fn attribute_spans_02() {
+    assert_eq!(
+        attribute_spans("<a b c=d>", true),
+        vec![("b", None, ""), ("c", Some(6), "d")]
+    );
+}
+
+#[test]
+fn check_close_tag_01() {
+    let text = "<r><a:b></a:b><c></d ></r>";
+    let t: Vec<_> = xml::Tokenizer::from(text).map(|t| t.unwrap()).collect();
+    assert!(xml::check_close_tag(text, t[2], t[4]).is_ok());
+    // Not a close tag.
+    assert!(xml::check_close_tag(text, t[2], t[3]).is_ok());
+
+    let err = xml::check_close_tag(text, t[5], t[7]).unwrap_err();
+    assert_eq!(err.open.range(), 14..16);
+    assert_eq!(err.close.as_str(), "</d >");
+    assert_eq!(err.close_pos, xml::TextPos::new(1, 18));
+}
+
+#[test]
+fn quote_01() {
+    let text = "<!DOCTYPE a [<!ENTITY b \"c\">]><a d = 'e' f=\"'\"/>";
+    let quotes: Vec<_> = xml::Tokenizer::from(text)
+        .map(|t| t.unwrap().quote())
+        .collect();
+    assert_eq!(
+        quotes,
+        vec![None, Some(b'"'), None, None, Some(b'\''), Some(b'"'), None]
+    );
+}
+
+#[test]
+fn quote_02() {
+    let mut p = xml::Tokenizer::from("<?xml version=\"1.0\"?><a b c=d/>");
+    p.set_lenient(true);
+    let quotes: Vec<_> = p.map(|t| t.unwrap().quote()).collect();
+    assert_eq!(quotes, vec![Some(b'"'), None, None, None, None]);
+}
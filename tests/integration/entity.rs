@@ -0,0 +1,228 @@
+use xml::{
+    EntityDefinition, EntityMap, EntityMapOptions, ExpansionError, ExternalId, Token, Tokenizer,
+};
+
+#[test]
+fn collect_01() {
+    let text = "\
+<!DOCTYPE svg [
+    <!ENTITY a 'text'>
+    <!ENTITY b SYSTEM 'b.xml'>
+]>
+<svg>&a;</svg>";
+
+    let mut p = Tokenizer::from(text);
+    let entities = EntityMap::collect(&mut p).unwrap();
+    assert_eq!(entities.len(), 2);
+    assert_eq!(entities.value("a").unwrap().as_str(), "text");
+    assert_eq!(entities.value("b"), None);
+    match entities.get("b") {
+        Some(EntityDefinition::ExternalId(ExternalId::System(v))) => {
+            assert_eq!(v.as_str(), "b.xml")
+        }
+        _ => panic!(),
+    }
+
+    match p.next().unwrap().unwrap() {
+        Token::ElementStart { local, .. } => assert_eq!(local.as_str(), "svg"),
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn collect_without_dtd() {
+    let text = "<?xml version='1.0'?><!-- comment --><svg/>";
+
+    let mut p = Tokenizer::from(text);
+    let entities = EntityMap::collect(&mut p).unwrap();
+    assert!(entities.is_empty());
+
+    match p.next().unwrap().unwrap() {
+        Token::ElementStart { local, .. } => assert_eq!(local.as_str(), "svg"),
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn collect_without_prolog() {
+    for text in &["<svg/>", "\u{FEFF}<svg/>", "\u{FEFF} <svg/>"] {
+        let mut p = Tokenizer::from(*text);
+        assert!(EntityMap::collect(&mut p).unwrap().is_empty());
+
+        match p.next().unwrap().unwrap() {
+            Token::ElementStart { local, .. } => assert_eq!(local.as_str(), "svg"),
+            _ => panic!(),
+        }
+    }
+}
+
+#[test]
+fn collect_first_wins() {
+    let text = "<!DOCTYPE svg [<!ENTITY a 'first'><!ENTITY a 'second'>]><svg/>";
+
+    let mut p = Tokenizer::from(text);
+    let entities = EntityMap::collect(&mut p).unwrap();
+    assert_eq!(entities.len(), 1);
+    assert_eq!(entities.value("a").unwrap().as_str(), "first");
+}
+
+#[test]
+fn collect_limit() {
+    let text = "<!DOCTYPE svg [\n<!ENTITY a 'a'>\n<!ENTITY b 'b'>\n]><svg/>";
+
+    let mut p = Tokenizer::from(text);
+    let err = EntityMap::collect_with_limit(&mut p, 1).unwrap_err();
+    assert_eq!(err.to_string(), "too many entities at 3:10");
+}
+
+#[test]
+fn collect_error() {
+    let text = "<!DOCTYPE svg [<!ENTITY a>]><svg/>";
+
+    let mut p = Tokenizer::from(text);
+    assert!(EntityMap::collect(&mut p).is_err());
+    assert!(p.next().is_none());
+}
+
+fn entities(dtd: &str) -> EntityMap<'_> {
+    let mut p = Tokenizer::from(dtd);
+    EntityMap::collect(&mut p).unwrap()
+}
+
+#[test]
+fn expand_01() {
+    let map = entities("<!DOCTYPE a [<!ENTITY b 'x&c;y'><!ENTITY c '&#60;&d;'>]>");
+    assert_eq!(map.expand("&b;&lt;&e;&").unwrap(), "x&#60;&d;y&lt;&e;&");
+}
+
+#[test]
+fn expand_recursive() {
+    let map = entities("<!DOCTYPE a [<!ENTITY b '&c;'><!ENTITY c '&b;'>]>");
+    assert_eq!(map.expand("a &b;"), Err(ExpansionError::RecursiveEntity(2)));
+}
+
+#[test]
+fn expand_depth_limit() {
+    let mut map = entities("<!DOCTYPE a [<!ENTITY b '&c;'><!ENTITY c '&d;'><!ENTITY d 'd'>]>");
+    assert_eq!(map.expand("&b;").unwrap(), "d");
+    map.set_depth_limit(2);
+    assert_eq!(
+        map.expand("&b;"),
+        Err(ExpansionError::DepthLimitExceeded(0))
+    );
+}
+
+#[test]
+fn expand_billion_laughs() {
+    let text = "\
+<!DOCTYPE lolz [
+    <!ENTITY lol 'lol'>
+    <!ENTITY lol1 '&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;'>
+    <!ENTITY lol2 '&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;'>
+    <!ENTITY lol3 '&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;'>
+    <!ENTITY lol4 '&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;'>
+    <!ENTITY lol5 '&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;'>
+    <!ENTITY lol6 '&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;'>
+    <!ENTITY lol7 '&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;'>
+    <!ENTITY lol8 '&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;'>
+    <!ENTITY lol9 '&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;'>
+]>";
+    let mut map = entities(text);
+    assert_eq!(map.expand("&lol9;"), Err(ExpansionError::SizeLimitExceeded));
+    map.set_size_limit(30);
+    assert_eq!(map.expand("&lol1;").unwrap().len(), 30);
+    assert_eq!(
+        map.expand("&lol1;!"),
+        Err(ExpansionError::SizeLimitExceeded)
+    );
+}
+
+#[test]
+fn expand_empty_laughs() {
+    let mut text = String::from("<!DOCTYPE a [<!ENTITY e0 ''>");
+    for i in 1..9 {
+        let refs: String = (0..10).map(|_| format!("&e{};", i - 1)).collect();
+        text.push_str(&format!("<!ENTITY e{} '{}'>", i, refs));
+    }
+    text.push_str("]>");
+
+    let mut map = entities(&text);
+    assert_eq!(map.expand("&e8;"), Err(ExpansionError::SizeLimitExceeded));
+    map.set_size_limit(11);
+    assert_eq!(map.expand("&e1;").unwrap(), "");
+    assert_eq!(
+        map.expand("&e1;&e0;"),
+        Err(ExpansionError::SizeLimitExceeded)
+    );
+}
+
+fn collect_duplicates(text: &str, limit: usize) -> EntityMap<'_> {
+    let opt = EntityMapOptions::new().limit(limit).duplicates(true);
+    EntityMap::collect_with_options(&mut Tokenizer::from(text), opt).unwrap()
+}
+
+#[test]
+fn duplicates_01() {
+    let text = "<!DOCTYPE a [\n<!ENTITY b 'c'>\n<!ENTITY d 'e'>\n<!ENTITY b 'f'>\n]><a/>";
+    let entities = collect_duplicates(text, 16);
+    assert_eq!(entities.len(), 2);
+    assert_eq!(entities.value("b").unwrap().as_str(), "c");
+    let dups = entities.duplicates();
+    assert_eq!(dups.len(), 1);
+    assert!(!dups[0].parameter);
+    assert_eq!(dups[0].first_pos, xml::TextPos::new(2, 10));
+    assert_eq!(dups[0].duplicate.as_str(), "b");
+    assert_eq!(dups[0].duplicate_pos, xml::TextPos::new(4, 10));
+}
+
+#[test]
+fn duplicates_02() {
+    // Duplicates are bounded by the entities limit.
+    let text = "<!DOCTYPE a [<!ENTITY b 'c'><!ENTITY b 'd'><!ENTITY b 'e'>]><a/>";
+    assert_eq!(collect_duplicates(text, 1).duplicates().len(), 1);
+}
+
+#[test]
+fn duplicates_03() {
+    // Disabled by default.
+    let text = "<!DOCTYPE a [<!ENTITY b 'c'><!ENTITY b 'd'>]><a/>";
+    let entities = EntityMap::collect(&mut Tokenizer::from(text)).unwrap();
+    assert!(entities.duplicates().is_empty());
+}
+
+#[test]
+fn duplicates_04() {
+    // Parameter and general entities are checked separately.
+    let text = "<!DOCTYPE a [<!ENTITY % b 'c'><!ENTITY b 'd'><!ENTITY % b 'e'>]><a/>";
+    let entities = collect_duplicates(text, 16);
+    assert_eq!(entities.value("b").unwrap().as_str(), "d");
+    let dups = entities.duplicates();
+    assert_eq!(dups.len(), 1);
+    assert!(dups[0].parameter);
+    assert_eq!(dups[0].first.start(), 24);
+    assert_eq!(dups[0].duplicate.start(), 56);
+}
+
+#[test]
+fn parameter_entities_01() {
+    // Parameter and general entities have separate namespaces.
+    let text = "<!DOCTYPE a [<!ENTITY % b 'pe'><!ENTITY b 'ge'>]><a>&b;</a>";
+    let mut p = Tokenizer::from(text);
+    let entities = EntityMap::collect(&mut p).unwrap();
+    assert_eq!(entities.len(), 1);
+    assert_eq!(entities.value("b").unwrap().as_str(), "ge");
+    assert_eq!(entities.expand("&b;").unwrap(), "ge");
+    assert!(entities.duplicates().is_empty());
+}
+
+#[test]
+fn parameter_entities_02() {
+    // Any spaces before `%`.
+    let text = "<!DOCTYPE a [<!ENTITY\n%\tb 'c'><!ENTITY % b 'd'><!ENTITY b 'e'>]><a/>";
+    let entities = collect_duplicates(text, 16);
+    assert_eq!(entities.value("b").unwrap().as_str(), "e");
+    let dups = entities.duplicates();
+    assert_eq!(dups.len(), 1);
+    assert!(dups[0].parameter);
+    assert_eq!(dups[0].duplicate.start(), 41);
+}
@@ -0,0 +1,236 @@
+use xml::{Error, ExpectedToken, Tokenizer};
+
+fn first_error(text: &str) -> Error {
+    Tokenizer::from(text)
+        .filter_map(|t| t.err())
+        .next()
+        .unwrap()
+}
+
+#[test]
+fn code_01() {
+    let err = first_error("<a><!-- a--b --></a>");
+    assert_eq!(err.code(), "invalid-comment");
+    assert_eq!(err.stream_error().unwrap().code(), "invalid-comment-data");
+}
+
+#[test]
+fn code_02() {
+    let err = first_error("<a/><b/>");
+    assert_eq!(err.code(), "unknown-token");
+    assert_eq!(err.stream_error(), None);
+}
+
+#[cfg(feature = "stable-messages")]
+mod stable {
+    use super::first_error;
+
+    macro_rules! test {
+        ($name:ident, $text:expr, $message:expr) => {
+            #[test]
+            fn $name() {
+                assert_eq!(first_error($text).stable_message().to_string(), $message);
+            }
+        };
+    }
+
+    test!(stable_01, "<a/><b/>", "unknown-token at 1:5");
+    test!(
+        stable_02,
+        "<a b='<'/>",
+        "invalid-attribute at 1:3 caused by invalid-char at 1:7"
+    );
+    test!(
+        stable_03,
+        "<!-- a--b -->",
+        "invalid-comment at 1:1 caused by invalid-comment-data"
+    );
+    test!(
+        stable_04,
+        "<?xml version='2.0'?>",
+        "invalid-declaration at 1:1 caused by invalid-string at 1:16"
+    );
+}
+
+fn render(text: &str, width: usize) -> String {
+    let mut s = String::new();
+    first_error(text)
+        .render_snippet(text, &mut s, width)
+        .unwrap();
+    s
+}
+
+#[test]
+fn snippet_01() {
+    assert_eq!(render("<a/><b/>", 80), "<a/><b/>\n    ^\n");
+}
+
+#[test]
+fn snippet_02() {
+    assert_eq!(
+        render("<a>\r\n\t<b c='<'/>\r\n</a>", 80),
+        " <b c='<'/>\n   ^\n"
+    );
+}
+
+#[test]
+fn snippet_03() {
+    assert_eq!(render("<a>0123456789<b c/></a>", 8), "89<b c/>\n    ^\n");
+}
+
+#[test]
+fn snippet_04() {
+    assert_eq!(render("<a c/>0123456789", 4), "<a c\n  ^\n");
+}
+
+#[test]
+fn snippet_05() {
+    assert_eq!(render("<!-- ф -->ф", 80), "<!-- ф -->ф\n          ^\n");
+}
+
+#[test]
+fn offset_01() {
+    let err = first_error("<a>\n  <b c='<'/></a>");
+    assert_eq!(err.offset(), 8);
+    assert_eq!(err.stream_error().unwrap().offset(), Some(12));
+}
+
+#[test]
+fn offset_02() {
+    // Offsets are in bytes, not in characters.
+    let err = first_error("<a>текст<b c></a>");
+    assert_eq!(err.pos(), xml::TextPos::new(1, 11));
+    assert_eq!(err.offset(), 15);
+}
+
+#[test]
+fn offset_03() {
+    let text = "<root><a/><b c></root>";
+    let err = Tokenizer::from_fragment(text, 6..15)
+        .filter_map(|t| t.err())
+        .next()
+        .unwrap();
+    assert_eq!(err.offset(), 12);
+}
+
+#[test]
+fn offset_04() {
+    let err = first_error("<a><!-- a--b --></a>");
+    assert_eq!(err.stream_error().unwrap().offset(), None);
+}
+
+#[test]
+fn length_limit_01() {
+    let text = "<a b='12'><!--12-->12<![CDATA[12]]></a>";
+    let mut p = Tokenizer::from(text);
+    p.set_max_attribute_value_len(2);
+    p.set_max_comment_len(2);
+    p.set_max_text_len(2);
+    assert!(p.all(|t| t.is_ok()));
+}
+
+#[test]
+fn length_limit_02() {
+    let mut p = Tokenizer::from("<a b='123'/>");
+    p.set_max_attribute_value_len(2);
+    let err = p.nth(1).unwrap().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid attribute at 1:3 cause the length limit is exceeded at 1:7"
+    );
+}
+
+#[test]
+fn length_limit_03() {
+    let mut p = Tokenizer::from("<a><!--123--></a>");
+    p.set_max_comment_len(2);
+    let err = p.nth(2).unwrap().unwrap_err();
+    assert_eq!(err.code(), "invalid-comment");
+    assert_eq!(err.stream_error().unwrap().code(), "length-limit-exceeded");
+}
+
+#[test]
+fn length_limit_04() {
+    for text in &["<a>123</a>", "<a><![CDATA[123]]></a>"] {
+        let mut p = Tokenizer::from(*text);
+        p.set_max_text_len(2);
+        let err = p.nth(2).unwrap().unwrap_err();
+        assert_eq!(err.stream_error().unwrap().code(), "length-limit-exceeded");
+        assert_eq!(
+            err.stream_error().unwrap().offset(),
+            Some(text.find('1').unwrap())
+        );
+    }
+}
+
+#[test]
+fn expected_01() {
+    assert_eq!(
+        first_error("<!DOCTYPE a [ x ]>").expected(),
+        Some(ExpectedToken::DtdContent)
+    );
+    assert_eq!(
+        first_error("<!DOCTYPE a>text").expected(),
+        Some(ExpectedToken::RootElement)
+    );
+    assert_eq!(
+        first_error("<a><!x></a>").expected(),
+        Some(ExpectedToken::ElementContent)
+    );
+    assert_eq!(
+        first_error("<a/><b/>").expected(),
+        Some(ExpectedToken::Misc)
+    );
+}
+
+#[test]
+fn expected_02() {
+    let err = first_error("<a b=''c=''/>");
+    assert_eq!(err.expected(), None);
+
+    let err = first_error("<?xml version='1.0'?><?xml version='1.0'?>");
+    assert_eq!(err.code(), "misplaced-declaration");
+    assert_eq!(err.expected(), None);
+
+    let err = first_error("<a><!x></a>");
+    assert_eq!(
+        err.expected().unwrap().to_string(),
+        "expected an element, a text, a comment, a CDATA or a processing instruction"
+    );
+}
+
+#[test]
+fn defer_positions_01() {
+    let text = "<a>\n  <b c='<'/></a>";
+    let mut p = Tokenizer::from(text);
+    p.set_defer_positions(true);
+    let err = p.find_map(|t| t.err()).unwrap();
+    assert_eq!(err.pos(), xml::TextPos::new(0, 0));
+    assert_eq!(err.text_pos(text), xml::TextPos::new(2, 5));
+
+    let e = err.stream_error().unwrap();
+    assert_eq!(e.pos(), Some(xml::TextPos::new(0, 0)));
+    assert_eq!(e.text_pos(text), Some(xml::TextPos::new(2, 9)));
+}
+
+#[test]
+fn defer_positions_02() {
+    // Positions are the same as without deferring.
+    for text in &["<a>\n<b c></a>", "<a/>\nx", "<!-- ф --><?xml?>"] {
+        let mut p = Tokenizer::from(*text);
+        p.set_defer_positions(true);
+        let err = p.find_map(|t| t.err()).unwrap();
+        assert_eq!(err.text_pos(text), first_error(text).pos());
+    }
+}
+
+#[test]
+fn defer_positions_03() {
+    let text = "<a>\n  <b c='<'/></a>";
+    let mut p = Tokenizer::from(text);
+    p.set_defer_positions(true);
+    let err = p.find_map(|t| t.err()).unwrap();
+    let mut s = String::new();
+    err.render_snippet(text, &mut s, 80).unwrap();
+    assert_eq!(s, "  <b c='<'/></a>\n    ^\n");
+}
@@ -0,0 +1,38 @@
+use xml::{IdTracker, Tokenizer};
+
+fn collect(text: &str) -> Vec<(&str, &str, bool)> {
+    let mut tracker = IdTracker::new();
+    Tokenizer::from(text)
+        .filter_map(|t| tracker.feed(&t.unwrap()))
+        .map(|id| (id.element.as_str(), id.value.as_str(), id.xml_id))
+        .collect()
+}
+
+#[test]
+fn ids_01() {
+    let text = "<a xml:id='a1'><b id='b1'/><c xml:id='c1' id='c2'/></a>";
+    assert_eq!(collect(text), vec![("a", "a1", true), ("c", "c1", true)]);
+}
+
+#[test]
+fn ids_02() {
+    let text = "<!DOCTYPE a [
+        <!ATTLIST b id ID #IMPLIED name CDATA #IMPLIED>
+        <!ATTLIST ns:c ns:key ID #REQUIRED>
+    ]>
+    <a id='a1'><b id='b1' name='n'/><ns:c key='c1' ns:key='c2'/><c ns:key='c3'/></a>";
+    assert_eq!(collect(text), vec![("b", "b1", false), ("c", "c2", false)]);
+}
+
+#[test]
+fn ids_03() {
+    let text = "<!DOCTYPE a [<!ATTLIST a id ID #IMPLIED>]><a id='x'/>";
+    let mut tracker = IdTracker::new();
+    let id = Tokenizer::from(text)
+        .filter_map(|t| tracker.feed(&t.unwrap()))
+        .next()
+        .unwrap();
+    assert_eq!(id.span.as_str(), "id='x'");
+    assert_eq!(id.span.range(), 45..51);
+    assert_eq!(id.element_prefix.as_str(), "");
+}
@@ -0,0 +1,110 @@
+use xml::{Index, IndexKind, Tokenizer};
+
+use crate::token::*;
+
+fn kinds(text: &str) -> Vec<(IndexKind, usize)> {
+    Index::build(text)
+        .entries()
+        .iter()
+        .map(|e| (e.kind, e.offset))
+        .collect()
+}
+
+#[test]
+fn build_01() {
+    let text = "<?xml version='1.0'?>
+<!DOCTYPE a [<!ENTITY b '>'>]>
+<a x='>'>t<![CDATA[<>]]><b/><?pi?></a>
+";
+    assert_eq!(
+        kinds(text),
+        vec![
+            (IndexKind::ProcessingInstruction, 0),
+            (IndexKind::Doctype, 22),
+            (IndexKind::ElementStart, 53),
+            (IndexKind::Text, 62),
+            (IndexKind::Cdata, 63),
+            (IndexKind::ElementStart, 77),
+            (IndexKind::ProcessingInstruction, 81),
+            (IndexKind::ElementEnd, 87),
+        ]
+    );
+}
+
+#[test]
+fn build_02() {
+    assert_eq!(
+        kinds("<a><!-- <b> --></a>"),
+        vec![
+            (IndexKind::ElementStart, 0),
+            (IndexKind::Comment, 3),
+            (IndexKind::ElementEnd, 15),
+        ]
+    );
+}
+
+#[test]
+fn build_03() {
+    // Must not panic on invalid input.
+    kinds("<");
+    kinds("<a");
+    kinds("<!DOCTYPE [");
+    kinds("<a><!--");
+}
+
+#[test]
+fn build_04() {
+    // `<?>` is not a complete PI.
+    assert_eq!(
+        kinds("<?><a/>?><b/>"),
+        vec![
+            (IndexKind::ProcessingInstruction, 0),
+            (IndexKind::ElementStart, 9)
+        ]
+    );
+}
+
+#[test]
+fn tokenize_01() {
+    let text = "<root><a x='1'>text</a><b/></root>";
+    let index = Index::build(text);
+    let tokens: Vec<_> = index.tokenize(text, 1).take(4).map(to_test_token).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::ElementStart("", "a", 6..8),
+            Token::Attribute("", "x", "1", 9..14),
+            Token::ElementEnd(ElementEnd::Open, 14..15),
+            Token::Text("text", 15..19),
+        ]
+    );
+}
+
+#[test]
+fn tokenize_02() {
+    // The index must agree with the tokenizer.
+    let text = "<root><a x='>'>text</a><!--c--><![CDATA[d]]><b/></root>";
+    let index = Index::build(text);
+    for (i, entry) in index.entries().iter().enumerate() {
+        let token = index.tokenize(text, i).next().unwrap().unwrap();
+        assert_eq!(token.span().start(), entry.offset);
+    }
+
+    assert_eq!(
+        Tokenizer::from(text).count(),
+        index.entries().len() + 4 // an attribute, two `>` and one `/>`
+    );
+}
+
+#[test]
+fn tokenize_03() {
+    // Prolog entries can be realized too.
+    let text =
+        "<?xml version='1.0'?>\n<!--c-->\n<?pi?>\n<!DOCTYPE a [<!ENTITY b 'c'>]>\n<!--d--><a/>";
+    let index = Index::build(text);
+    assert_eq!(index.entries().len(), 6);
+    for (i, entry) in index.entries().iter().enumerate() {
+        let tokens: Result<Vec<_>, _> = index.tokenize(text, i).collect();
+        assert_eq!(tokens.unwrap()[0].span().start(), entry.offset);
+    }
+}
@@ -6,8 +6,34 @@ mod token;
 mod api;
 mod cdata;
 mod comments;
+#[cfg(feature = "utf16")]
+mod decode;
 mod doctype;
 mod document;
+mod dump;
 mod elements;
+#[cfg(feature = "std")]
+mod entity;
+mod errors;
+#[cfg(feature = "alloc")]
+mod ids;
+#[cfg(feature = "alloc")]
+mod index;
+mod numbers;
+#[cfg(feature = "alloc")]
+mod owned;
 mod pi;
+#[cfg(feature = "alloc")]
+mod push;
+#[cfg(feature = "alloc")]
+mod scope;
+#[cfg(feature = "alloc")]
+mod tags;
+#[cfg(feature = "testing")]
+mod testing;
 mod text;
+mod unescape;
+#[cfg(feature = "std")]
+mod validate;
+#[cfg(feature = "alloc")]
+mod writer;
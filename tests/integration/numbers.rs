@@ -0,0 +1,48 @@
+use xml::{NumberList, StreamError, TextPos};
+
+macro_rules! test {
+    ($name:ident, $text:expr, $result:expr) => {
+        #[test]
+        fn $name() {
+            let list: Result<Vec<f64>, StreamError> = NumberList::from($text).collect();
+            assert_eq!(list, $result);
+        }
+    };
+}
+
+test!(numbers_01, "", Ok(vec![]));
+test!(numbers_02, " \n, ", Ok(vec![]));
+test!(numbers_03, "1 2.5 -3", Ok(vec![1.0, 2.5, -3.0]));
+test!(numbers_04, "1,2 , 3,\n4", Ok(vec![1.0, 2.0, 3.0, 4.0]));
+test!(
+    numbers_05,
+    "1e2 1E-2 +.5 5.",
+    Ok(vec![100.0, 0.01, 0.5, 5.0])
+);
+test!(numbers_06, "1-2", Ok(vec![1.0, -2.0]));
+test!(
+    numbers_07,
+    "1 x",
+    Err(StreamError::InvalidNumber(TextPos::new(1, 3), 2))
+);
+test!(
+    numbers_08,
+    "1,,2",
+    Err(StreamError::InvalidNumber(TextPos::new(1, 3), 2))
+);
+test!(
+    numbers_09,
+    "1em",
+    Err(StreamError::InvalidNumber(TextPos::new(1, 2), 1))
+);
+
+#[test]
+fn fill_01() {
+    let mut buf = [0.0; 2];
+    let mut list = NumberList::from("1 2 3");
+    assert_eq!(list.fill(&mut buf), Ok(2));
+    assert_eq!(buf, [1.0, 2.0]);
+    assert_eq!(list.fill(&mut buf), Ok(1));
+    assert_eq!(buf[0], 3.0);
+    assert_eq!(list.fill(&mut buf), Ok(0));
+}
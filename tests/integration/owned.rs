@@ -0,0 +1,59 @@
+use xml::{OwnedElementEnd, OwnedExternalId, OwnedToken, Tokenizer};
+
+fn owned(text: &str) -> Vec<OwnedToken> {
+    Tokenizer::from(text)
+        .map(|t| t.unwrap().to_owned())
+        .collect()
+}
+
+#[test]
+fn owned_token_01() {
+    let tokens = owned("<!DOCTYPE a SYSTEM 'b'><a:b c='d'>text</a:b>");
+
+    match tokens[0] {
+        OwnedToken::EmptyDtd {
+            ref name,
+            external_id: Some(OwnedExternalId::System(ref id)),
+            ..
+        } => {
+            assert_eq!(name.as_str(), "a");
+            assert_eq!(id.as_str(), "b");
+        }
+        ref t => panic!("{:?}", t),
+    }
+
+    match tokens[5] {
+        OwnedToken::ElementEnd {
+            end: OwnedElementEnd::Close(ref prefix, ref local),
+            ref span,
+        } => {
+            assert_eq!(prefix.as_str(), "a");
+            assert_eq!(local.as_str(), "b");
+            assert_eq!(span.range(), 38..44);
+        }
+        ref t => panic!("{:?}", t),
+    }
+}
+
+#[test]
+fn owned_token_02() {
+    // Spans are preserved.
+    let text = "<a b='c'>text<![CDATA[x]]><!--y--></a>";
+    let tokens = owned(text);
+    let spans: Vec<_> = Tokenizer::from(text)
+        .map(|t| t.unwrap().span().range())
+        .collect();
+    let owned_spans: Vec<_> = tokens.iter().map(|t| t.span().range()).collect();
+    assert_eq!(spans, owned_spans);
+    assert_eq!(tokens[3].span().as_span().range(), 9..13);
+}
+
+#[test]
+fn owned_token_03() {
+    let tokens = owned("<a>text</a>");
+    let handle = std::thread::spawn(move || match tokens[2] {
+        OwnedToken::Text { ref text } => text.to_string(),
+        _ => String::new(),
+    });
+    assert_eq!(handle.join().unwrap(), "text");
+}
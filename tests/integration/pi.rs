@@ -124,7 +124,9 @@ test!(
 test!(
     declaration_err_07,
     "\u{000a}<?xml\u{000a}&jg'];",
-    Token::Error("invalid processing instruction at 2:1 cause expected '?>' at 3:7".to_string())
+    Token::Error(
+        "the XML declaration is allowed only at the start of the document at 2:1".to_string()
+    )
 );
 
 test!(
@@ -139,11 +141,19 @@ test!(
     Token::Error("invalid XML declaration at 1:1 cause expected 'version' at 2:2".to_string())
 );
 
+test!(
+    declaration_11,
+    "<?xml\nversion='1.0'\t?>",
+    Token::Declaration("1.0", None, None, 0..22)
+);
+
 // XML declaration allowed only at the start of the document.
 test!(
     declaration_err_10,
     " <?xml version='1.0'?>",
-    Token::Error("unknown token at 1:2".to_string())
+    Token::Error(
+        "the XML declaration is allowed only at the start of the document at 1:2".to_string()
+    )
 );
 
 // XML declaration allowed only at the start of the document.
@@ -151,7 +161,9 @@ test!(
     declaration_err_11,
     "<!-- comment --><?xml version='1.0'?>",
     Token::Comment(" comment ", 0..16),
-    Token::Error("unknown token at 1:17".to_string())
+    Token::Error(
+        "the XML declaration is allowed only at the start of the document at 1:17".to_string()
+    )
 );
 
 // Duplicate.
@@ -159,7 +171,9 @@ test!(
     declaration_err_12,
     "<?xml version='1.0'?><?xml version='1.0'?>",
     Token::Declaration("1.0", None, None, 0..21),
-    Token::Error("unknown token at 1:22".to_string())
+    Token::Error(
+        "the XML declaration is allowed only at the start of the document at 1:22".to_string()
+    )
 );
 
 test!(
@@ -186,5 +200,127 @@ test!(
 test!(
     declaration_err_16,
     "<?xml version='1.0'",
-    Token::Error("invalid XML declaration at 1:1 cause expected '?>' at 1:20".to_string())
+    Token::Error("invalid XML declaration at 1:1 cause unexpected end of stream".to_string())
+);
+
+test!(
+    declaration_err_17,
+    "<?xml?>",
+    Token::Error("invalid XML declaration at 1:1 cause expected 'version' at 1:6".to_string())
+);
+
+test!(
+    declaration_err_18,
+    "<a><?xml\tversion='1.0'?></a>",
+    Token::ElementStart("", "a", 0..2),
+    Token::ElementEnd(ElementEnd::Open, 2..3),
+    Token::Error(
+        "the XML declaration is allowed only at the start of the document at 1:4".to_string()
+    )
+);
+
+type PiAttr<'a> = Result<(&'a str, &'a str, std::ops::Range<usize>), String>;
+
+fn pi_attrs(text: &str) -> Vec<PiAttr<'_>> {
+    let content = match xml::Tokenizer::from(text).next().unwrap().unwrap() {
+        xml::Token::ProcessingInstruction {
+            content: Some(content),
+            ..
+        } => content,
+        t => panic!("{:?}", t),
+    };
+
+    xml::PiAttributes::from(content)
+        .map(|a| match a {
+            Ok((name, value)) => Ok((name.as_str(), value.as_str(), value.range())),
+            Err(e) => Err(e.to_string()),
+        })
+        .collect()
+}
+
+#[test]
+fn pi_attributes_01() {
+    assert_eq!(
+        pi_attrs("<?xml-stylesheet type=\"text/xsl\"\n href = 'a.xsl' ?>"),
+        vec![
+            Ok(("type", "text/xsl", 23..31)),
+            Ok(("href", "a.xsl", 42..47)),
+        ]
+    );
+}
+
+#[test]
+fn pi_attributes_02() {
+    assert_eq!(
+        pi_attrs("<?pi a='1'b='2'?>"),
+        vec![
+            Ok(("a", "1", 8..9)),
+            Err("expected space not 'b' at 1:6".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn pi_attributes_03() {
+    assert_eq!(
+        pi_attrs("<?pi a='<' b='2'?>"),
+        vec![Err("expected ''' not '<' at 1:4".to_string())]
+    );
+    assert_eq!(
+        pi_attrs("<?pi some text?>"),
+        vec![Err("expected '=' not 't' at 1:6".to_string())]
+    );
+}
+
+#[test]
+fn reserved_target_01() {
+    for text in &["<?XML?><a/>", "<?xMl x?><a/>", "<a><?Xml?></a>"] {
+        let err = xml::Tokenizer::from(*text).find_map(|t| t.err()).unwrap();
+        assert_eq!(err.stream_error().unwrap().code(), "reserved-pi-target");
+    }
+}
+
+#[test]
+fn reserved_target_02() {
+    let mut p = xml::Tokenizer::from("<?XML x?><a/>");
+    p.set_lenient(true);
+    assert_eq!(
+        to_test_token(p.next().unwrap()),
+        Token::PI("XML", Some("x"), 0..9)
+    );
+    assert_eq!(
+        p.warning().unwrap().to_string(),
+        "a reserved processing instruction target at 1:3"
+    );
+}
+
+test!(
+    reserved_target_03,
+    "<?xml-stylesheet?><?xmlx?><a/>",
+    Token::PI("xml-stylesheet", None, 0..18),
+    Token::PI("xmlx", None, 18..26),
+    Token::ElementStart("", "a", 26..28),
+    Token::ElementEnd(ElementEnd::Empty, 28..30)
+);
+
+#[test]
+fn pi_attributes_04() {
+    assert!(xml::PiAttributes::from("").next().is_none());
+    assert_eq!(xml::PiAttributes::from(" a=\"\" ").count(), 1);
+}
+
+test!(
+    declaration_err_19,
+    "<?xml version='1.0' encoding='-utf8'?>",
+    Token::Error(
+        "invalid XML declaration at 1:1 cause an invalid encoding name at 1:31".to_string()
+    )
+);
+
+test!(
+    declaration_err_20,
+    "<?xml version='1.0' encoding=''?>",
+    Token::Error(
+        "invalid XML declaration at 1:1 cause an invalid encoding name at 1:31".to_string()
+    )
 );
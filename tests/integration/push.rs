@@ -0,0 +1,163 @@
+use xml::{PushTokenizer, Tokenizer};
+
+// Returns token spans, since ranges are relative to the buffer.
+fn push_tokens(text: &str, chunk_len: usize) -> Vec<String> {
+    let mut p = PushTokenizer::new();
+    let mut tokens = Vec::new();
+
+    let chars: Vec<char> = text.chars().collect();
+    for chunk in chars.chunks(chunk_len) {
+        let chunk: String = chunk.iter().collect();
+        p.feed(&chunk);
+        while let Some(token) = p.next_token() {
+            tokens.push(token.unwrap().span().to_string());
+        }
+    }
+
+    p.finish();
+    while let Some(token) = p.next_token() {
+        match token {
+            Ok(token) => tokens.push(token.span().to_string()),
+            Err(e) => tokens.push(format!("{} at {}", e.code(), p.offset() + e.offset())),
+        }
+    }
+
+    tokens
+}
+
+fn tokens(text: &str) -> Vec<String> {
+    Tokenizer::from(text)
+        .map(|t| match t {
+            Ok(token) => token.span().to_string(),
+            Err(e) => format!("{} at {}", e.code(), e.offset()),
+        })
+        .collect()
+}
+
+#[test]
+fn chunks_01() {
+    let text = "\u{FEFF}<?xml version='1.0'?>
+<!DOCTYPE svg [
+    <!ENTITY ns 'http://www.w3.org/2000/svg'>
+]>
+<!-- comment -->
+<svg xmlns='&ns;' a=\"b\">
+    text &amp; текст
+    <![CDATA[data]]>
+    <?pi data?>
+    <g/>
+</svg>
+";
+
+    let expected = tokens(text);
+    for len in 1..text.len() {
+        assert_eq!(push_tokens(text, len), expected, "chunk length: {}", len);
+    }
+}
+
+#[test]
+fn error_01() {
+    let text = "<a><b c></a>";
+    let expected = tokens(text);
+    for len in 1..text.len() {
+        assert_eq!(push_tokens(text, len), expected, "chunk length: {}", len);
+    }
+}
+
+#[test]
+fn error_02() {
+    let mut p = PushTokenizer::new();
+    p.feed("<a><b c");
+    assert!(p.next_token().unwrap().is_ok());
+    assert!(p.next_token().unwrap().is_ok());
+    assert!(p.next_token().unwrap().is_ok());
+    assert!(p.next_token().is_none());
+
+    p.finish();
+    assert!(p.next_token().unwrap().is_err());
+    assert!(p.next_token().is_none());
+}
+
+#[test]
+fn offset_01() {
+    let mut p = PushTokenizer::new();
+    p.feed("<a>text");
+    assert!(p.next_token().is_some());
+    assert!(p.next_token().is_some());
+    assert!(p.next_token().is_none());
+
+    p.feed("</a>");
+    assert_eq!(p.offset(), 3);
+    let token = p.next_token().unwrap().unwrap();
+    assert_eq!(token.span().as_str(), "text");
+    assert_eq!(token.span().start(), 0);
+}
+
+#[test]
+fn dtd_01() {
+    let text = "<!DOCTYPE a [<!ENTITY b 'c'><!ENTITY d 'e'>]><a/>";
+    let expected = Tokenizer::from(text)
+        .find_map(|t| match t {
+            Ok(xml::Token::DtdEnd {
+                internal_subset, ..
+            }) => Some(internal_subset.as_str()),
+            _ => None,
+        })
+        .unwrap();
+
+    for split in 1..text.len() {
+        let mut p = PushTokenizer::new();
+        let mut subset = None;
+        for chunk in &[&text[..split], &text[split..]] {
+            p.feed(chunk);
+            while let Some(token) = p.next_token() {
+                if let Ok(xml::Token::DtdEnd {
+                    internal_subset, ..
+                }) = token
+                {
+                    subset = Some(internal_subset.to_string());
+                }
+            }
+        }
+
+        assert_eq!(subset.as_deref(), Some(expected), "split: {}", split);
+    }
+}
+
+#[test]
+fn finish_01() {
+    let mut p = PushTokenizer::new();
+    p.feed("<a/>");
+    p.finish();
+    p.feed("<b/>");
+    assert_eq!(p.next_token().unwrap().unwrap().span().as_str(), "<a");
+    assert_eq!(p.next_token().unwrap().unwrap().span().as_str(), "/>");
+    assert!(p.next_token().is_none());
+}
+
+#[test]
+fn large_text_01() {
+    // Must not rescan the pending text on each chunk.
+    let text = format!(
+        "<a>{}<![CDATA[{}]]><!--{}--></a>",
+        "x".repeat(1 << 20),
+        "y".repeat(1 << 20),
+        "z".repeat(1 << 20)
+    );
+
+    let mut p = PushTokenizer::new();
+    let mut lens = Vec::new();
+    for chunk in text.as_bytes().chunks(1024) {
+        p.feed(std::str::from_utf8(chunk).unwrap());
+        while let Some(token) = p.next_token() {
+            lens.push(token.unwrap().span().len());
+        }
+    }
+
+    p.finish();
+    while let Some(token) = p.next_token() {
+        lens.push(token.unwrap().span().len());
+    }
+
+    assert_eq!(lens, vec![2, 1, 1 << 20, (1 << 20) + 12, (1 << 20) + 7, 4]);
+}
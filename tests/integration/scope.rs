@@ -0,0 +1,69 @@
+use xml::{Token, Tokenizer, XmlScope, XmlSpace};
+
+fn collect(text: &str) -> Vec<(String, XmlSpace, Option<String>)> {
+    let mut scope = XmlScope::new();
+    let mut v = Vec::new();
+    for token in Tokenizer::from(text) {
+        let token = token.unwrap();
+        scope.feed(&token);
+        if let Token::Text { text } = token {
+            v.push((
+                text.as_str().to_string(),
+                scope.xml_space(),
+                scope.xml_lang().map(|s| s.as_str().to_string()),
+            ));
+        }
+    }
+    v
+}
+
+#[test]
+fn xml_scope_01() {
+    let text = "<a xml:space='preserve'>1<b>2</b>3<c xml:space='default'>4</c>5</a>";
+    let v: Vec<_> = collect(text).into_iter().map(|v| v.1).collect();
+    assert_eq!(
+        v,
+        vec![
+            XmlSpace::Preserve,
+            XmlSpace::Preserve,
+            XmlSpace::Preserve,
+            XmlSpace::Default,
+            XmlSpace::Preserve,
+        ]
+    );
+}
+
+#[test]
+fn xml_scope_02() {
+    let text = "<a xml:lang='en'>1<b xml:lang='de'/>2<c xml:lang=''>3</c></a>";
+    let v: Vec<_> = collect(text).into_iter().map(|v| v.2).collect();
+    assert_eq!(
+        v,
+        vec![
+            Some("en".to_string()),
+            Some("en".to_string()),
+            Some(String::new())
+        ]
+    );
+}
+
+#[test]
+fn xml_scope_03() {
+    // Unknown values and other prefixes are ignored.
+    let text = "<a xml:space='preserve'><b xml:space='yes' x:space='default'>1</b></a>";
+    assert_eq!(collect(text)[0].1, XmlSpace::Preserve);
+}
+
+#[test]
+fn xml_scope_04() {
+    let mut scope = XmlScope::new();
+    assert_eq!(scope.xml_space(), XmlSpace::Default);
+    assert_eq!(scope.xml_lang(), None);
+
+    for token in Tokenizer::from("<a xml:space='preserve'><b/></a>") {
+        scope.feed(&token.unwrap());
+    }
+
+    assert_eq!(scope.depth(), 0);
+    assert_eq!(scope.xml_space(), XmlSpace::Default);
+}
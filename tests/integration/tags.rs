@@ -0,0 +1,146 @@
+use xml::{Error, Tokenizer};
+
+use crate::token::*;
+
+fn tracking(text: &str) -> Tokenizer<'_> {
+    let mut p = Tokenizer::from(text);
+    p.set_track_tags(true);
+    p
+}
+
+fn report(text: &str) -> String {
+    let mut p = tracking(text);
+    let err = p.by_ref().filter_map(|t| t.err()).next().unwrap();
+    p.truncation_report(&err).unwrap().to_string()
+}
+
+#[test]
+fn open_elements_01() {
+    let mut p = tracking("<a><b:c><d/></b:c></a>");
+    p.nth(3).unwrap().unwrap();
+    let names: Vec<_> = p.open_elements().iter().map(|e| e.local.as_str()).collect();
+    assert_eq!(names, vec!["a", "c"]);
+
+    assert!(p.all(|t| t.is_ok()));
+}
+
+#[test]
+fn open_elements_02() {
+    // Disabled by default.
+    let mut p = Tokenizer::from("<a><b>");
+    p.nth(2).unwrap().unwrap();
+    assert!(p.open_elements().is_empty());
+    assert!(p.next().unwrap().is_ok());
+    assert!(p.next().is_none());
+}
+
+#[test]
+fn unclosed_01() {
+    assert_eq!(
+        collect_tokens(tracking("<a><b>text")),
+        vec![
+            Token::ElementStart("", "a", 0..2),
+            Token::ElementEnd(ElementEnd::Open, 2..3),
+            Token::ElementStart("", "b", 3..5),
+            Token::ElementEnd(ElementEnd::Open, 5..6),
+            Token::Text("text", 6..10),
+            Token::Error("unexpected end of document at 1:11".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn unclosed_02() {
+    assert_eq!(
+        collect_tokens(tracking("<a b='c'")),
+        vec![
+            Token::ElementStart("", "a", 0..2),
+            Token::Attribute("", "b", "c", 3..8),
+            Token::Error("unexpected end of document at 1:9".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn report_01() {
+    assert_eq!(
+        report("<root>\n  <ns:a><![CDATA[text"),
+        "unexpected end of stream inside a CDATA at 2:9\n\
+         unclosed element <root> at 1:1\n\
+         unclosed element <ns:a> at 2:3"
+    );
+}
+
+#[test]
+fn report_02() {
+    assert_eq!(
+        report("<root><a b='c"),
+        "unexpected end of stream inside an attribute at 1:9\n\
+         unclosed element <root> at 1:1\n\
+         unclosed element <a> at 1:7"
+    );
+}
+
+#[test]
+fn report_03() {
+    assert_eq!(
+        report("<root><a/>"),
+        "unexpected end of stream inside an element content at 1:11\n\
+         unclosed element <root> at 1:1"
+    );
+}
+
+#[test]
+fn report_04() {
+    let mut p = Tokenizer::from("<root><!-- text");
+    let err = p.by_ref().filter_map(|t| t.err()).next().unwrap();
+    let report = p.truncation_report(&err).unwrap();
+    assert_eq!(report.construct, "a comment");
+    assert!(report.open_elements.is_empty());
+}
+
+#[test]
+fn report_05() {
+    let mut p = tracking("<root><a b></root>");
+    let err: Error = p.by_ref().filter_map(|t| t.err()).next().unwrap();
+    assert!(!err.is_truncated());
+    assert!(p.truncation_report(&err).is_none());
+}
+
+#[test]
+fn path_01() {
+    let mut p = tracking("<root><a:b><c>text</c></a:b></root>");
+    let mut paths = Vec::new();
+    let mut depths = Vec::new();
+    while let Some(t) = p.next() {
+        t.unwrap();
+        paths.push(p.current_path().to_string());
+        depths.push(p.depth());
+    }
+
+    assert_eq!(
+        paths,
+        vec![
+            "/root",
+            "/root",
+            "/root/a:b",
+            "/root/a:b",
+            "/root/a:b/c",
+            "/root/a:b/c",
+            "/root/a:b/c",
+            "/root/a:b",
+            "/root",
+            "",
+        ]
+    );
+    assert_eq!(depths, vec![0, 1, 1, 2, 2, 3, 3, 2, 1, 0]);
+}
+
+#[test]
+fn path_02() {
+    // Always empty without tag tracking.
+    let mut p = Tokenizer::from("<root><a>");
+    p.nth(3).unwrap().unwrap();
+    assert_eq!(p.current_path().count(), 0);
+    assert_eq!(p.depth(), 2);
+}
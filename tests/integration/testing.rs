@@ -0,0 +1,86 @@
+use xml::testing::{DocumentGenerator, TokenMatcher};
+use xml::Tokenizer;
+
+#[test]
+fn matcher_01() {
+    TokenMatcher::new()
+        .dtd_start("a")
+        .entity_declaration("b")
+        .dtd_end()
+        .element_start("ns", "a")
+        .span(30..35)
+        .element_end_open()
+        .text("text")
+        .cdata("data")
+        .comment("c")
+        .element_end_close("ns", "a")
+        .assert_matches(Tokenizer::from(
+            "<!DOCTYPE a [<!ENTITY b 'c'>]><ns:a>text<![CDATA[data]]><!--c--></ns:a>",
+        ));
+}
+
+#[test]
+fn matcher_02() {
+    TokenMatcher::new()
+        .element_start("", "a")
+        .error("invalid attribute at 1:3 cause expected '=' not '/' at 1:5")
+        .span(2..2)
+        .assert_matches(Tokenizer::from("<a b/>"));
+}
+
+#[test]
+fn matcher_03() {
+    let report = TokenMatcher::new()
+        .element_start("", "a")
+        .attribute("", "b", "2")
+        .check(Tokenizer::from("<a b='1'/>"))
+        .unwrap_err();
+    assert_eq!(
+        report,
+        "token mismatch at index 1
+
+expected:
+    0: ElementStart(\"\", \"a\")
+  > 1: Attribute(\"\", \"b\", \"2\")
+actual:
+    0: ElementStart(\"\", \"a\") 0..2
+  > 1: Attribute(\"\", \"b\", \"1\") 3..8
+    2: ElementEnd(Empty) 8..10
+"
+    );
+}
+
+#[test]
+fn matcher_04() {
+    let result = TokenMatcher::new()
+        .element_start("", "a")
+        .span(0..3)
+        .check(Tokenizer::from("<a/>"));
+    assert!(result.unwrap_err().starts_with("token mismatch at index 0"));
+}
+
+#[test]
+fn generator_01() {
+    let mut gen = DocumentGenerator::new(0);
+    for _ in 0..500 {
+        let doc = gen.generate();
+        if let Err(report) = doc.tokens.check(Tokenizer::from(doc.text.as_str())) {
+            panic!("{}\n{}", doc.text, report);
+        }
+    }
+}
+
+#[test]
+fn generator_02() {
+    let mut a = DocumentGenerator::new(7).max_depth(2);
+    let mut b = DocumentGenerator::new(7).max_depth(2);
+    assert_eq!(a.generate().text, b.generate().text);
+}
+
+#[test]
+fn generator_03() {
+    // A seed that would produce a zero state.
+    let mut gen = DocumentGenerator::new(0x9E37_79B9_7F4A_7C15);
+    let docs: Vec<_> = (0..4).map(|_| gen.generate().text).collect();
+    assert!(docs.iter().any(|doc| *doc != docs[0]));
+}
@@ -75,6 +75,18 @@ test!(
     )
 );
 
+// Not only the first `>` is checked.
+test!(
+    text_err_04,
+    "<p>a > b ]]></p>",
+    Token::ElementStart("", "p", 0..2),
+    Token::ElementEnd(ElementEnd::Open, 2..3),
+    Token::Error(
+        "invalid character data at 1:4 cause ']]>' is not allowed inside a character data"
+            .to_string()
+    )
+);
+
 test!(
     text_err_02,
     "<p>\u{0c}</p>",
@@ -84,3 +96,165 @@ test!(
         "invalid character data at 1:4 cause a non-XML character '\\u{c}' found at 1:4".to_string()
     )
 );
+
+test!(
+    text_err_03,
+    "<p>€></p>",
+    Token::ElementStart("", "p", 0..2),
+    Token::ElementEnd(ElementEnd::Open, 2..3),
+    Token::Text("€>", 3..7),
+    Token::ElementEnd(ElementEnd::Close("", "p"), 7..11)
+);
+
+// Non-XML characters are found past the first bytes of a long text too.
+test!(
+    text_err_05,
+    "<p>long enough text to span a whole scanning chunk \u{FFFE}</p>",
+    Token::ElementStart("", "p", 0..2),
+    Token::ElementEnd(ElementEnd::Open, 2..3),
+    Token::Error(
+        "invalid character data at 1:4 cause a non-XML character '\\u{fffe}' found at 1:52"
+            .to_string()
+    )
+);
+
+test!(
+    text_08,
+    "<p>long enough text to span a whole scanning chunk 欄\u{FEFF}</p>",
+    Token::ElementStart("", "p", 0..2),
+    Token::ElementEnd(ElementEnd::Open, 2..3),
+    Token::Text(
+        "long enough text to span a whole scanning chunk 欄\u{FEFF}",
+        3..57
+    ),
+    Token::ElementEnd(ElementEnd::Close("", "p"), 57..61)
+);
+
+fn chunked_tokens(text: &str, size: usize) -> Vec<Token<'_>> {
+    let mut p = xml::Tokenizer::from(text);
+    p.set_text_chunk_size(size);
+    collect_tokens(p)
+}
+
+#[test]
+fn text_chunks_01() {
+    assert_eq!(
+        chunked_tokens("<p>abcde</p>", 2),
+        vec![
+            Token::ElementStart("", "p", 0..2),
+            Token::ElementEnd(ElementEnd::Open, 2..3),
+            Token::Text("ab", 3..5),
+            Token::Text("cd", 5..7),
+            Token::Text("e", 7..8),
+            Token::ElementEnd(ElementEnd::Close("", "p"), 8..12),
+        ]
+    );
+}
+
+#[test]
+fn text_chunks_02() {
+    // Split at char boundaries, but at least one char per chunk.
+    assert_eq!(
+        chunked_tokens("<p>aяb</p>", 2),
+        vec![
+            Token::ElementStart("", "p", 0..2),
+            Token::ElementEnd(ElementEnd::Open, 2..3),
+            Token::Text("a", 3..4),
+            Token::Text("я", 4..6),
+            Token::Text("b", 6..7),
+            Token::ElementEnd(ElementEnd::Close("", "p"), 7..11),
+        ]
+    );
+    assert_eq!(
+        chunked_tokens("<p>€</p>", 1),
+        vec![
+            Token::ElementStart("", "p", 0..2),
+            Token::ElementEnd(ElementEnd::Open, 2..3),
+            Token::Text("€", 3..6),
+            Token::ElementEnd(ElementEnd::Close("", "p"), 6..10),
+        ]
+    );
+}
+
+#[test]
+fn text_chunks_03() {
+    // `]]>` split between chunks.
+    assert_eq!(
+        chunked_tokens("<p>a]]></p>", 2),
+        vec![
+            Token::ElementStart("", "p", 0..2),
+            Token::ElementEnd(ElementEnd::Open, 2..3),
+            Token::Text("a]", 3..5),
+            Token::Error(
+                "invalid character data at 1:6 cause ']]>' is not allowed inside a character data"
+                    .to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn text_chunks_04() {
+    // The length limit is applied to the whole node.
+    let mut p = xml::Tokenizer::from("<p>abcde</p>");
+    p.set_text_chunk_size(2);
+    p.set_max_text_len(4);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::ElementStart("", "p", 0..2),
+            Token::ElementEnd(ElementEnd::Open, 2..3),
+            Token::Text("ab", 3..5),
+            Token::Text("cd", 5..7),
+            Token::Error(
+                "invalid character data at 1:8 cause the length limit is exceeded at 1:4"
+                    .to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn whitespaces_01() {
+    let mut p = xml::Tokenizer::from("<p>\n\t<b> x </b>\r\n</p>");
+    p.set_whitespace_tokens(true);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::ElementStart("", "p", 0..2),
+            Token::ElementEnd(ElementEnd::Open, 2..3),
+            Token::Whitespaces("\n\t", 3..5),
+            Token::ElementStart("", "b", 5..7),
+            Token::ElementEnd(ElementEnd::Open, 7..8),
+            Token::Text(" x ", 8..11),
+            Token::ElementEnd(ElementEnd::Close("", "b"), 11..15),
+            Token::Whitespaces("\r\n", 15..17),
+            Token::ElementEnd(ElementEnd::Close("", "p"), 17..21),
+        ]
+    );
+}
+
+#[test]
+fn whitespaces_02() {
+    // Non-breaking space is not an XML whitespace.
+    let mut p = xml::Tokenizer::from("<p>\u{A0}</p>");
+    p.set_whitespace_tokens(true);
+    assert_eq!(
+        collect_tokens(p),
+        vec![
+            Token::ElementStart("", "p", 0..2),
+            Token::ElementEnd(ElementEnd::Open, 2..3),
+            Token::Text("\u{A0}", 3..5),
+            Token::ElementEnd(ElementEnd::Close("", "p"), 5..9),
+        ]
+    );
+}
+
+#[test]
+fn cdata_end_lenient_01() {
+    let mut p = xml::Tokenizer::from("<p>a]]>b</p>");
+    p.set_lenient(true);
+    assert_eq!(to_test_token(p.nth(2).unwrap()), Token::Text("a]]>b", 3..8));
+    assert_eq!(p.warning().unwrap().to_string(), "a ']]>' in text at 1:5");
+    assert_eq!(p.warning().unwrap().offset(), 4);
+}
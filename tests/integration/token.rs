@@ -8,12 +8,27 @@ pub enum Token<'a> {
     DtdStart(&'a str, Option<ExternalId<'a>>, Range),
     EmptyDtd(&'a str, Option<ExternalId<'a>>, Range),
     EntityDecl(&'a str, EntityDefinition<'a>, Range),
+    ElementDecl(&'a str, ContentSpec<'a>, Range),
+    AttlistDecl(
+        &'a str,
+        Vec<(&'a str, &'a str, AttributeDefault<'a>)>,
+        Range,
+    ),
+    NotationDecl(&'a str, NotationId<'a>, Range),
+    CondSectStart(bool, Range),
+    CondSectEnd(Range),
+    PEReference(&'a str, Range),
     DtdEnd(Range),
     ElementStart(&'a str, &'a str, Range),
     Attribute(&'a str, &'a str, &'a str, Range),
     ElementEnd(ElementEnd<'a>, Range),
     Text(&'a str, Range),
     Cdata(&'a str, Range),
+    CondCommentStart(&'a str, Range),
+    CondCommentEnd(Range),
+    Bom(Range),
+    Whitespaces(&'a str, Range),
+    Raw(xml::RawKind, &'a str, Range),
     Error(String),
 }
 
@@ -34,6 +49,29 @@ pub enum ExternalId<'a> {
 pub enum EntityDefinition<'a> {
     EntityValue(&'a str),
     ExternalId(ExternalId<'a>),
+    Unparsed(ExternalId<'a>, &'a str),
+}
+
+#[derive(PartialEq, Debug)]
+pub enum ContentSpec<'a> {
+    Empty,
+    Any,
+    Mixed(&'a str),
+    Children(&'a str),
+}
+
+#[derive(PartialEq, Debug)]
+pub enum NotationId<'a> {
+    System(&'a str),
+    Public(&'a str, Option<&'a str>),
+}
+
+#[derive(PartialEq, Debug)]
+pub enum AttributeDefault<'a> {
+    Required,
+    Implied,
+    Fixed(&'a str),
+    Value(&'a str),
 }
 
 #[macro_export]
@@ -70,7 +108,7 @@ pub fn to_test_token(token: Result<xml::Token, xml::Error>) -> Token {
             content,
             span,
         }) => Token::PI(target.as_str(), content.map(|v| v.as_str()), span.range()),
-        Ok(xml::Token::Comment { text, span }) => Token::Comment(text.as_str(), span.range()),
+        Ok(xml::Token::Comment { text, span, .. }) => Token::Comment(text.as_str(), span.range()),
         Ok(xml::Token::DtdStart {
             name,
             external_id,
@@ -102,10 +140,71 @@ pub fn to_test_token(token: Result<xml::Token, xml::Error>) -> Token {
                 xml::EntityDefinition::ExternalId(id) => {
                     EntityDefinition::ExternalId(to_test_external_id(id))
                 }
+                xml::EntityDefinition::Unparsed(id, notation) => {
+                    EntityDefinition::Unparsed(to_test_external_id(id), notation.as_str())
+                }
+            },
+            span.range(),
+        ),
+        Ok(xml::Token::ElementDeclaration {
+            name,
+            content_spec,
+            span,
+        }) => Token::ElementDecl(
+            name.as_str(),
+            match content_spec {
+                xml::ContentSpec::Empty => ContentSpec::Empty,
+                xml::ContentSpec::Any => ContentSpec::Any,
+                xml::ContentSpec::Mixed(spec) => ContentSpec::Mixed(spec.as_str()),
+                xml::ContentSpec::Children(spec) => ContentSpec::Children(spec.as_str()),
+            },
+            span.range(),
+        ),
+        Ok(xml::Token::AttlistDeclaration {
+            name,
+            definitions,
+            span,
+        }) => Token::AttlistDecl(
+            name.as_str(),
+            definitions
+                .iter()
+                .map(|def| {
+                    (
+                        def.name.as_str(),
+                        def.att_type.as_str(),
+                        match def.default {
+                            xml::AttributeDefault::Required => AttributeDefault::Required,
+                            xml::AttributeDefault::Implied => AttributeDefault::Implied,
+                            xml::AttributeDefault::Fixed(v) => AttributeDefault::Fixed(v.as_str()),
+                            xml::AttributeDefault::Value(v) => AttributeDefault::Value(v.as_str()),
+                        },
+                    )
+                })
+                .collect(),
+            span.range(),
+        ),
+        Ok(xml::Token::NotationDeclaration {
+            name,
+            external_id,
+            span,
+        }) => Token::NotationDecl(
+            name.as_str(),
+            match external_id {
+                xml::NotationId::System(id) => NotationId::System(id.as_str()),
+                xml::NotationId::Public(id, system) => {
+                    NotationId::Public(id.as_str(), system.map(|v| v.as_str()))
+                }
             },
             span.range(),
         ),
-        Ok(xml::Token::DtdEnd { span }) => Token::DtdEnd(span.range()),
+        Ok(xml::Token::ConditionalSectionStart { ignore, span }) => {
+            Token::CondSectStart(ignore, span.range())
+        }
+        Ok(xml::Token::ConditionalSectionEnd { span }) => Token::CondSectEnd(span.range()),
+        Ok(xml::Token::ParameterEntityReference { name, span }) => {
+            Token::PEReference(name.as_str(), span.range())
+        }
+        Ok(xml::Token::DtdEnd { span, .. }) => Token::DtdEnd(span.range()),
         Ok(xml::Token::ElementStart {
             prefix,
             local,
@@ -133,11 +232,40 @@ pub fn to_test_token(token: Result<xml::Token, xml::Error>) -> Token {
             span.range(),
         ),
         Ok(xml::Token::Text { text }) => Token::Text(text.as_str(), text.range()),
-        Ok(xml::Token::Cdata { text, span }) => Token::Cdata(text.as_str(), span.range()),
+        Ok(xml::Token::Cdata { text, span, .. }) => Token::Cdata(text.as_str(), span.range()),
+        Ok(xml::Token::ConditionalCommentStart { condition, span }) => {
+            Token::CondCommentStart(condition.as_str(), span.range())
+        }
+        Ok(xml::Token::ConditionalCommentEnd { span }) => Token::CondCommentEnd(span.range()),
+        Ok(xml::Token::Bom { span }) => Token::Bom(span.range()),
+        Ok(xml::Token::Whitespaces { text }) => Token::Whitespaces(text.as_str(), text.range()),
+        Ok(xml::Token::Raw { kind, span }) => Token::Raw(kind, span.as_str(), span.range()),
         Err(ref e) => Token::Error(e.to_string()),
     }
 }
 
+pub fn collect_tokens(p: xml::Tokenizer) -> Vec<Token> {
+    p.map(to_test_token).collect()
+}
+
+// Returns texts of comments and CDATA along with the unterminated flag
+// for a tokenizer in the partial mode.
+pub fn partial_tokens(text: &str) -> Vec<(String, bool)> {
+    let mut p = xml::Tokenizer::from(text);
+    p.set_partial(true);
+    p.filter_map(|t| match t {
+        Ok(xml::Token::Comment {
+            text, unterminated, ..
+        })
+        | Ok(xml::Token::Cdata {
+            text, unterminated, ..
+        }) => Some((text.to_string(), unterminated)),
+        Ok(_) => None,
+        Err(e) => Some((e.to_string(), false)),
+    })
+    .collect()
+}
+
 fn to_test_external_id(id: xml::ExternalId) -> ExternalId {
     match id {
         xml::ExternalId::System(name) => ExternalId::System(name.as_str()),
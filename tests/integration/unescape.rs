@@ -0,0 +1,392 @@
+#[cfg(feature = "alloc")]
+use std::borrow::Cow;
+
+use xml::{
+    AttrValueNormalizer, EntityDefinition, EntityValuePart, EntityValueParts, InvalidCharRef,
+    StreamError, TextPart, TextParts, TextUnescape, Token, Tokenizer, XmlSpace,
+};
+#[cfg(feature = "std")]
+use xml::{EntityMap, EntityResolver};
+
+macro_rules! test {
+    ($name:ident, $text:expr, $xml_space:expr, $result:expr) => {
+        #[cfg(feature = "alloc")]
+        #[test]
+        fn $name() {
+            assert_eq!(TextUnescape::unescape($text, $xml_space), $result);
+        }
+    };
+}
+
+test!(unescape_01, "text", XmlSpace::Default, "text");
+test!(
+    unescape_02,
+    "&lt;&gt;&amp;&apos;&quot;",
+    XmlSpace::Default,
+    "<>&'\""
+);
+test!(unescape_03, "&#x20;&#65;&#x42;", XmlSpace::Default, " AB");
+test!(unescape_04, "&#x1F600;", XmlSpace::Default, "\u{1F600}");
+test!(unescape_05, "&unknown;", XmlSpace::Default, "&unknown;");
+test!(
+    unescape_06,
+    "& &#; &#x; &lt",
+    XmlSpace::Default,
+    "& &#; &#x; &lt"
+);
+test!(unescape_07, " a \t\n\r b ", XmlSpace::Default, " a b ");
+test!(
+    unescape_08,
+    " a \t\n\r b ",
+    XmlSpace::Preserve,
+    " a \t\n\n b "
+);
+test!(unescape_09, "a\r\nb\rc", XmlSpace::Preserve, "a\nb\nc");
+test!(unescape_10, "&#x20;&#x20;", XmlSpace::Default, "  ");
+test!(unescape_11, "&#xD800;", XmlSpace::Default, "\u{FFFD}");
+test!(unescape_12, "&#1;", XmlSpace::Default, "\u{FFFD}");
+test!(unescape_13, "&#99999999999;", XmlSpace::Default, "\u{FFFD}");
+
+#[cfg(feature = "alloc")]
+#[test]
+fn invalid_char_ref_skip() {
+    let s = TextUnescape::try_unescape("a&#xD800;b", XmlSpace::Default, InvalidCharRef::Skip);
+    assert_eq!(s.unwrap(), "ab");
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn invalid_char_ref_error() {
+    let s = TextUnescape::try_unescape("a&#xD800;b", XmlSpace::Default, InvalidCharRef::Error);
+    assert_eq!(s, Err(StreamError::InvalidReference));
+}
+
+#[test]
+fn invalid_char_ref_error_iter() {
+    let mut iter = TextUnescape::new("a&#x110000;b", XmlSpace::Default);
+    iter.set_invalid_char_ref(InvalidCharRef::Error);
+    assert_eq!(iter.next(), Some(b'a'));
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.error(), Some(StreamError::InvalidReference));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn unescape_cow_borrowed() {
+    for &(text, xml_space) in &[
+        ("", XmlSpace::Default),
+        ("a b", XmlSpace::Default),
+        ("a  b\t\n", XmlSpace::Preserve),
+    ] {
+        match TextUnescape::unescape_cow(text, xml_space) {
+            Cow::Borrowed(s) => assert_eq!(s, text),
+            Cow::Owned(_) => panic!("{:?} should be borrowed", text),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn unescape_cow_owned() {
+    assert_eq!(TextUnescape::unescape_cow("a  b", XmlSpace::Default), "a b");
+    assert_eq!(TextUnescape::unescape_cow("a\nb", XmlSpace::Default), "a b");
+    assert_eq!(
+        TextUnescape::unescape_cow("a\r\nb", XmlSpace::Preserve),
+        "a\nb"
+    );
+    assert_eq!(TextUnescape::unescape_cow("&lt;", XmlSpace::Preserve), "<");
+}
+
+fn normalize_collapsed(text: &str) -> String {
+    let mut iter = AttrValueNormalizer::new(text);
+    iter.set_collapse_spaces(true);
+    iter.collect()
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn normalize_01() {
+    assert_eq!(AttrValueNormalizer::normalize(" a\t\r\nb\r "), " a  b  ");
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn normalize_02() {
+    assert_eq!(
+        AttrValueNormalizer::normalize("&lt;&#x20;&#xA;&#9;&unknown;&amp"),
+        "< \n\t&unknown;&amp"
+    );
+}
+
+#[test]
+fn normalize_03() {
+    assert_eq!(normalize_collapsed("  a \n\t b  c "), "a b c");
+}
+
+#[test]
+fn normalize_04() {
+    assert_eq!(normalize_collapsed("&#x20;a&#x20;&#xA;b"), "a \nb");
+}
+
+#[test]
+fn normalize_05() {
+    assert_eq!(normalize_collapsed("   "), "");
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn normalize_entities_01() {
+    let text = "<!DOCTYPE a [
+    <!ENTITY b 'x&c;'>
+    <!ENTITY c '\ty&amp;'>
+    <!ENTITY d SYSTEM 'd.xml'>
+]><a/>";
+    let mut p = xml::Tokenizer::from(text);
+    let entities = EntityMap::collect(&mut p).unwrap();
+    let mut iter = AttrValueNormalizer::new("&b;-&d;");
+    iter.set_entities(&entities);
+    assert_eq!(iter.collect::<String>(), "x y&-&d;");
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn normalize_entities_02() {
+    let text = "<!DOCTYPE a [<!ENTITY b 'x&c;'><!ENTITY c '&b;'>]><a/>";
+    let mut p = xml::Tokenizer::from(text);
+    let entities = EntityMap::collect(&mut p).unwrap();
+    let mut iter = AttrValueNormalizer::new("a&b;b");
+    iter.set_entities(&entities);
+    assert_eq!(iter.by_ref().collect::<String>(), "ax");
+    assert_eq!(iter.error(), Some(StreamError::InvalidReference));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn normalize_entities_03() {
+    let mut text = String::from("<!DOCTYPE a [<!ENTITY l0 'lol'>");
+    for i in 1..8 {
+        let refs: String = (0..10).map(|_| format!("&l{};", i - 1)).collect();
+        text.push_str(&format!("<!ENTITY l{} '{}'>", i, refs));
+    }
+    text.push_str("]><a/>");
+
+    let mut p = xml::Tokenizer::from(text.as_str());
+    let entities = EntityMap::collect(&mut p).unwrap();
+    let mut iter = AttrValueNormalizer::new("a &l7;");
+    iter.set_entities(&entities);
+    assert_eq!(iter.by_ref().count(), 1024 * 1024);
+    assert_eq!(
+        iter.error(),
+        Some(StreamError::LengthLimitExceeded(xml::TextPos::new(1, 3), 2))
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn normalize_entities_04() {
+    let text = "<!DOCTYPE a [<!ENTITY b '&c;'><!ENTITY c '&d;'><!ENTITY d 'x'>]><a/>";
+    let mut p = xml::Tokenizer::from(text);
+    let mut entities = EntityMap::collect(&mut p).unwrap();
+    entities.set_depth_limit(2);
+    let mut iter = AttrValueNormalizer::new("&c;&b;");
+    iter.set_entities(&entities);
+    assert_eq!(iter.by_ref().collect::<String>(), "x");
+    assert_eq!(iter.error(), Some(StreamError::InvalidReference));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn normalize_entities_05() {
+    let mut text = String::from("<!DOCTYPE a [<!ENTITY e0 ''>");
+    for i in 1..9 {
+        let refs: String = (0..10).map(|_| format!("&e{};", i - 1)).collect();
+        text.push_str(&format!("<!ENTITY e{} '{}'>", i, refs));
+    }
+    text.push_str("]><a/>");
+
+    let mut p = xml::Tokenizer::from(text.as_str());
+    let entities = EntityMap::collect(&mut p).unwrap();
+    let mut iter = AttrValueNormalizer::new("a &e8;");
+    iter.set_entities(&entities);
+    assert_eq!(iter.by_ref().collect::<String>(), "a ");
+    assert_eq!(
+        iter.error(),
+        Some(StreamError::LengthLimitExceeded(xml::TextPos::new(1, 3), 2))
+    );
+}
+
+#[test]
+fn normalize_invalid_char_ref_01() {
+    let mut iter = AttrValueNormalizer::new("a&#0;b");
+    iter.set_invalid_char_ref(InvalidCharRef::Skip);
+    assert_eq!(iter.collect::<String>(), "ab");
+}
+
+fn text_parts(text: &str) -> Vec<(&str, &str, ::std::ops::Range<usize>)> {
+    let mut p = Tokenizer::from(text);
+    let text = p
+        .find_map(|t| match t {
+            Ok(Token::Text { text }) => Some(text),
+            _ => None,
+        })
+        .unwrap();
+
+    TextParts::new(text)
+        .map(|part| match part {
+            TextPart::Literal(span) => ("literal", span.as_str(), span.range()),
+            TextPart::CharRef(_, span) => ("char", span.as_str(), span.range()),
+            TextPart::EntityRef(name, span) => ("entity", name, span.range()),
+        })
+        .collect()
+}
+
+#[test]
+fn text_parts_01() {
+    assert_eq!(
+        text_parts("<p>a &amp; b&#x20;&c;</p>"),
+        vec![
+            ("literal", "a ", 3..5),
+            ("char", "&amp;", 5..10),
+            ("literal", " b", 10..12),
+            ("char", "&#x20;", 12..18),
+            ("entity", "c", 18..21),
+        ]
+    );
+}
+
+#[test]
+fn text_parts_02() {
+    assert_eq!(
+        text_parts("<p>& &#; &amp</p>"),
+        vec![("literal", "& &#; &amp", 3..13)]
+    );
+}
+
+#[test]
+fn text_parts_03() {
+    assert_eq!(
+        text_parts("<p>&lt;&#0;Ӓ</p>"),
+        vec![("char", "&lt;", 3..7), ("literal", "&#0;Ӓ", 7..13)]
+    );
+}
+
+fn entity_value_parts(text: &str) -> Vec<(&str, &str, ::std::ops::Range<usize>)> {
+    let mut p = Tokenizer::from(text);
+    let value = p
+        .find_map(|t| match t {
+            Ok(Token::EntityDeclaration {
+                definition: EntityDefinition::EntityValue(value),
+                ..
+            }) => Some(value),
+            _ => None,
+        })
+        .unwrap();
+
+    EntityValueParts::new(value)
+        .map(|part| match part {
+            EntityValuePart::Literal(span) => ("literal", span.as_str(), span.range()),
+            EntityValuePart::CharRef(_, span) => ("char", span.as_str(), span.range()),
+            EntityValuePart::EntityRef(name, span) => ("entity", name, span.range()),
+            EntityValuePart::ParameterEntityRef(name, span) => ("pe", name, span.range()),
+        })
+        .collect()
+}
+
+#[test]
+fn entity_value_parts_01() {
+    assert_eq!(
+        entity_value_parts("<!DOCTYPE a [<!ENTITY b 'a&lt;%c;&#x20;b&d;'>]>"),
+        vec![
+            ("literal", "a", 25..26),
+            ("entity", "lt", 26..30),
+            ("pe", "c", 30..33),
+            ("char", "&#x20;", 33..39),
+            ("literal", "b", 39..40),
+            ("entity", "d", 40..43),
+        ]
+    );
+}
+
+#[test]
+fn entity_value_parts_02() {
+    assert_eq!(
+        entity_value_parts("<!DOCTYPE a [<!ENTITY b '% %; &#0; &c'>]>"),
+        vec![("literal", "% %; &#0; &c", 25..37)]
+    );
+}
+
+#[test]
+fn entity_value_parts_03() {
+    assert!(entity_value_parts("<!DOCTYPE a [<!ENTITY b ''>]>").is_empty());
+}
+
+#[test]
+fn entity_resolver_01() {
+    let resolver = |name: &str| match name {
+        "a" => Some("x  &amp;\r\n"),
+        "b" => Some(""),
+        _ => None,
+    };
+
+    let mut iter = TextUnescape::new("&a;&b;&#0;&c;&lt;", XmlSpace::Default);
+    iter.set_invalid_char_ref(InvalidCharRef::Skip);
+    iter.set_entity_resolver(&resolver);
+    assert_eq!(iter.chars().collect::<String>(), "x  &amp;\r\n&c;<");
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn entity_resolver_02() {
+    let mut p = Tokenizer::from("<!DOCTYPE a [<!ENTITY b 'text'><!ENTITY c SYSTEM 'c.xml'>]><a/>");
+    let entities = EntityMap::collect(&mut p).unwrap();
+    assert_eq!(entities.resolve("b"), Some("text"));
+    assert_eq!(entities.resolve("c"), None);
+
+    let mut iter = TextUnescape::new("&b; &c;", XmlSpace::Preserve);
+    iter.set_entity_resolver(&entities);
+    assert_eq!(iter.chars().collect::<String>(), "text &c;");
+}
+
+#[test]
+fn references_01() {
+    let mut p = Tokenizer::from("<a b='&#x20;&amp &c;'>x&#0;&d;&lt;</a>");
+    let value = match p.nth(1).unwrap().unwrap() {
+        Token::Attribute { value, .. } => value,
+        _ => unreachable!(),
+    };
+    assert_eq!(
+        value.references().collect::<Vec<_>>(),
+        vec![
+            (6..12, xml::Reference::Char(' ')),
+            (17..20, xml::Reference::Entity("c")),
+        ]
+    );
+
+    let text = match p.nth(1).unwrap().unwrap() {
+        Token::Text { text } => text,
+        _ => unreachable!(),
+    };
+    assert_eq!(
+        text.references().collect::<Vec<_>>(),
+        vec![
+            (27..30, xml::Reference::Entity("d")),
+            (30..34, xml::Reference::Char('<')),
+        ]
+    );
+}
+
+#[test]
+fn unescape_chars_01() {
+    let text = "a\u{416}&#x1F600;&amp;\u{FFFD}";
+    let chars: Vec<char> = TextUnescape::new(text, XmlSpace::Default).chars().collect();
+    assert_eq!(chars, vec!['a', '\u{416}', '\u{1F600}', '&', '\u{FFFD}']);
+}
+
+#[test]
+fn unescape_chars_02() {
+    let mut iter = TextUnescape::new("a&#0;b", XmlSpace::Default);
+    iter.set_invalid_char_ref(InvalidCharRef::Error);
+    let mut iter = iter.chars();
+    assert_eq!(iter.by_ref().collect::<String>(), "a");
+    assert_eq!(iter.error(), Some(StreamError::InvalidReference));
+}
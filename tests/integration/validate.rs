@@ -0,0 +1,106 @@
+use xml::validate::{WellFormedChecker, WellFormedError};
+use xml::Tokenizer;
+
+fn check(text: &str) -> Option<WellFormedError<'_>> {
+    WellFormedChecker::new(Tokenizer::from(text)).find_map(|t| t.err())
+}
+
+fn check_fragment(text: &str) -> Option<WellFormedError<'_>> {
+    WellFormedChecker::new(Tokenizer::from_fragment(text, 0..text.len())).find_map(|t| t.err())
+}
+
+#[test]
+fn well_formed_01() {
+    let text = "<?xml version='1.0'?><!-- c --><a x='1' y='2'><b:c x='1' b:x='2'/>text</a>";
+    let p = WellFormedChecker::new(Tokenizer::from(text));
+    assert_eq!(p.filter(|t| t.is_ok()).count(), 12);
+    assert!(check(text).is_none());
+}
+
+#[test]
+fn well_formed_02() {
+    let mut p = WellFormedChecker::new(Tokenizer::from("<a><b:c>"));
+    p.nth(2).unwrap().unwrap();
+    let names: Vec<_> = p.open_elements().iter().map(|e| e.local.as_str()).collect();
+    assert_eq!(names, vec!["a", "c"]);
+}
+
+#[test]
+fn mismatched_close_tag_01() {
+    match check("<root>\n  <a></b>\n</root>") {
+        Some(WellFormedError::MismatchedCloseTag(e)) => {
+            assert_eq!(e.open.as_str(), "<a");
+            assert_eq!(e.open_pos, xml::TextPos::new(2, 3));
+            assert_eq!(e.close.as_str(), "</b>");
+            assert_eq!(e.close_pos, xml::TextPos::new(2, 6));
+        }
+        e => panic!("{:?}", e),
+    }
+}
+
+#[test]
+fn mismatched_close_tag_02() {
+    assert_eq!(
+        check("<a:b></b>").unwrap().to_string(),
+        "close tag </b> at 1:6 doesn't match the open tag <a:b> at 1:1"
+    );
+}
+
+#[test]
+fn unexpected_close_tag_01() {
+    assert_eq!(
+        check_fragment("<a/></b>").unwrap().to_string(),
+        "unexpected close tag </b> at 1:5"
+    );
+}
+
+#[test]
+fn unclosed_element_01() {
+    assert_eq!(
+        check("<a>\n<b>text").unwrap().to_string(),
+        "unclosed element <b> at 2:1"
+    );
+}
+
+#[test]
+fn multiple_roots_01() {
+    assert_eq!(
+        check_fragment("<a/>\n<b/>").unwrap().to_string(),
+        "element <b> at 2:1 is after the root element <a> at 1:1"
+    );
+}
+
+#[test]
+fn multiple_roots_02() {
+    // Reported by the tokenizer in the document mode.
+    match check("<a/><b/>") {
+        Some(WellFormedError::Syntax(_)) => {}
+        e => panic!("{:?}", e),
+    }
+}
+
+#[test]
+fn duplicated_attribute_01() {
+    assert_eq!(
+        check("<a x='1' y='2' x='3'/>").unwrap().to_string(),
+        "duplicated attribute x='3' at 1:16, first set at 1:4"
+    );
+}
+
+#[test]
+fn duplicated_attribute_02() {
+    // Names are compared without namespace resolution.
+    assert!(check("<a x='1' b:x='2'><b x='3'/></a>").is_none());
+}
+
+#[test]
+fn syntax_error_01() {
+    let mut p = WellFormedChecker::new(Tokenizer::from("<a></a x>"));
+    assert!(p.next().unwrap().is_ok());
+    assert!(p.next().unwrap().is_ok());
+    match p.next() {
+        Some(Err(WellFormedError::Syntax(_))) => {}
+        t => panic!("{:?}", t),
+    }
+    assert!(p.next().is_none());
+}
@@ -0,0 +1,96 @@
+use xml::{Indent, TokenWriter, Tokenizer};
+
+fn write(text: &str, indent: Indent) -> String {
+    let mut w = TokenWriter::new();
+    w.set_indent(indent);
+    for token in Tokenizer::from(text) {
+        w.write_token(&token.unwrap());
+    }
+    w.into_string()
+}
+
+#[test]
+fn round_trip_01() {
+    let text = "<?xml version=\"1.0\"?><!DOCTYPE a [<!ENTITY b \"c\">]>\
+                <!-- c --><a:b x:y=\"&amp;\" z='\"'><?pi data?>text &b;<![CDATA[<>]]><c/></a:b>";
+    assert_eq!(write(text, Indent::None), text);
+}
+
+#[test]
+fn round_trip_02() {
+    // Element tags are normalized.
+    assert_eq!(
+        write("<a  b = '1'\n c=\"2\" ></a >", Indent::None),
+        "<a b=\"1\" c=\"2\"></a>"
+    );
+}
+
+#[test]
+fn pretty_01() {
+    assert_eq!(
+        write(
+            "<?xml version='1.0'?><!--c--><a>\n<b><c/></b>\n  <d></d></a>",
+            Indent::Spaces(2)
+        ),
+        "<?xml version='1.0'?>\n<!--c-->\n<a>\n  <b>\n    <c/>\n  </b>\n  <d></d>\n</a>"
+    );
+}
+
+#[test]
+fn pretty_02() {
+    // Mixed content is preserved.
+    assert_eq!(
+        write("<a><p>Some <b>bold</b> text</p><c/></a>", Indent::Tabs),
+        "<a>\n\t<p>Some <b>bold</b> text</p>\n\t<c/>\n</a>"
+    );
+}
+
+#[test]
+fn pretty_03() {
+    // Whitespaces inside mixed content are preserved.
+    assert_eq!(
+        write(
+            "<r><a>x <b>y</b> <c><d/> z</c>
+</a></r>",
+            Indent::Spaces(1)
+        ),
+        "<r>\n <a>x <b>y</b> <c><d/> z</c>\n</a>\n</r>"
+    );
+}
+
+#[test]
+fn pretty_04() {
+    // Children before the first text are indented.
+    assert_eq!(
+        write("<a><b/>text</a>", Indent::Spaces(2)),
+        "<a>\n  <b/>text</a>"
+    );
+}
+
+#[test]
+fn partial_01() {
+    let mut p = Tokenizer::from("<a><!-- text");
+    p.set_partial(true);
+    let mut w = TokenWriter::new();
+    for token in p {
+        w.write_token(&token.unwrap());
+    }
+    assert_eq!(w.as_str(), "<a><!-- text-->");
+}
+
+#[test]
+fn escaped_01() {
+    let mut w = TokenWriter::new();
+    for token in Tokenizer::from("<a b='1'>") {
+        let token = token.unwrap();
+        if let xml::Token::ElementEnd { .. } = token {
+            w.write_attribute_escaped("x", "c", "<'\">");
+        }
+        w.write_token(&token);
+    }
+    w.write_text_escaped("1 < 2 && 3 > 2");
+    assert_eq!(
+        w.into_string(),
+        "<a b=\"1\" x:c=\"&lt;'&quot;>\">1 &lt; 2 &amp;&amp; 3 &gt; 2"
+    );
+}